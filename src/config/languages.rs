@@ -1,10 +1,11 @@
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum SupportedLanguage {
     #[serde(rename = "ru")]
+    #[default]
     Russian,
     #[serde(rename = "uk")]
     Ukrainian,
@@ -157,6 +158,56 @@ impl SupportedLanguage {
         }
     }
 
+    /// Название языка на нём самом — для пользователей, у которых интерфейс Telegram не на русском.
+    pub fn endonym(&self) -> &'static str {
+        match self {
+            Self::Russian => "Русский",
+            Self::Ukrainian => "Українська",
+            Self::English => "English",
+            Self::German => "Deutsch",
+            Self::French => "Français",
+            Self::Spanish => "Español",
+            Self::Italian => "Italiano",
+            Self::Portuguese => "Português",
+            Self::Polish => "Polski",
+            Self::Japanese => "日本語",
+            Self::Chinese => "中文",
+            Self::Korean => "한국어",
+            Self::Arabic => "العربية",
+            Self::Hebrew => "עברית",
+            Self::Turkish => "Türkçe",
+            Self::Dutch => "Nederlands",
+            Self::Swedish => "Svenska",
+            Self::Norwegian => "Norsk",
+            Self::Danish => "Dansk",
+            Self::Finnish => "Suomi",
+            Self::Czech => "Čeština",
+            Self::Bulgarian => "Български",
+            Self::Croatian => "Hrvatski",
+            Self::Serbian => "Српски",
+            Self::Slovak => "Slovenčina",
+            Self::Slovenian => "Slovenščina",
+            Self::Hungarian => "Magyar",
+            Self::Romanian => "Română",
+            Self::Greek => "Ελληνικά",
+            Self::Latvian => "Latviešu",
+            Self::Lithuanian => "Lietuvių",
+            Self::Estonian => "Eesti",
+            Self::Catalan => "Català",
+            Self::Basque => "Euskara",
+            Self::Galician => "Galego",
+        }
+    }
+
+    /// Название языка для интерфейса пользователя: русское прилагательное
+    /// для русской локали (или если локаль неизвестна), иначе самоназвание.
+    pub fn name_for_locale(&self, locale: Option<&str>) -> &'static str {
+        match locale {
+            Some(code) if !code.to_lowercase().starts_with("ru") => self.endonym(),
+            _ => self.display_name(),
+        }
+    }
+
     pub fn flag_emoji(&self) -> &'static str {
         match self {
             Self::Russian => "🇷🇺",
@@ -197,6 +248,12 @@ impl SupportedLanguage {
         }
     }
 
+    /// Разделяются ли слова пробелами. В японском и китайском пробелов нет,
+    /// поэтому текст обрезается по знакам препинания или границе символа.
+    pub fn uses_word_spaces(&self) -> bool {
+        !matches!(self, Self::Japanese | Self::Chinese)
+    }
+
     pub fn from_code(code: &str) -> Option<Self> {
         match code.to_lowercase().as_str() {
             "ru" => Some(Self::Russian),
@@ -288,6 +345,123 @@ impl SupportedLanguage {
             Self::Galician,
         ]
     }
+
+    /// Популярные языки по порядку — для фильтрации и сортировки без копирования среза.
+    pub fn iter_popular() -> impl Iterator<Item = SupportedLanguage> + Clone {
+        Self::popular_languages().iter().copied()
+    }
+
+    /// Все поддерживаемые языки по порядку.
+    pub fn iter_all() -> impl Iterator<Item = SupportedLanguage> + Clone {
+        Self::all_languages().iter().copied()
+    }
+
+    /// Языки из списка кодов в заданном порядке. Неизвестные коды пропускаются
+    /// с предупреждением в логе, повторы — молча.
+    pub fn from_codes(codes: &[&str]) -> Vec<SupportedLanguage> {
+        let mut languages = Vec::new();
+        for code in codes.iter().map(|code| code.trim()) {
+            match Self::from_code(code) {
+                Some(language) if !languages.contains(&language) => languages.push(language),
+                Some(_) => {}
+                None => tracing::warn!("⚠️ Неизвестный код языка '{}' пропущен", code),
+            }
+        }
+        languages
+    }
+}
+
+/// Письменность, по которой угадывается язык запроса.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Script {
+    Latin,
+    Cyrillic,
+    Greek,
+    Arabic,
+    Hebrew,
+    Hangul,
+    Kana,
+    Han,
+}
+
+impl Script {
+    fn of(c: char) -> Option<Self> {
+        match c {
+            'a'..='z' | 'A'..='Z' | '\u{00C0}'..='\u{024F}' => Some(Self::Latin),
+            '\u{0400}'..='\u{04FF}' => Some(Self::Cyrillic),
+            '\u{0370}'..='\u{03FF}' => Some(Self::Greek),
+            '\u{0590}'..='\u{05FF}' => Some(Self::Hebrew),
+            '\u{0600}'..='\u{06FF}' => Some(Self::Arabic),
+            '\u{1100}'..='\u{11FF}' | '\u{AC00}'..='\u{D7AF}' => Some(Self::Hangul),
+            '\u{3040}'..='\u{30FF}' => Some(Self::Kana),
+            '\u{4E00}'..='\u{9FFF}' => Some(Self::Han),
+            _ => None,
+        }
+    }
+}
+
+impl SupportedLanguage {
+    /// Основная письменность раздела. Японский записывается и каной, и
+    /// иероглифами, поэтому для него важна кана.
+    pub fn script(&self) -> Script {
+        match self {
+            Self::Russian | Self::Ukrainian | Self::Bulgarian | Self::Serbian => Script::Cyrillic,
+            Self::Greek => Script::Greek,
+            Self::Arabic => Script::Arabic,
+            Self::Hebrew => Script::Hebrew,
+            Self::Korean => Script::Hangul,
+            Self::Japanese => Script::Kana,
+            Self::Chinese => Script::Han,
+            _ => Script::Latin,
+        }
+    }
+}
+
+/// Угадывает язык запроса по письменности: кириллица — русский (или
+/// украинский при буквах «іїєґ»), латиница — английский, иероглифы без каны —
+/// китайский. `None`, если в запросе нет букв известной письменности.
+pub fn detect_language(query: &str) -> Option<SupportedLanguage> {
+    let scripts: Vec<Script> = query.chars().filter_map(Script::of).collect();
+
+    let language = if scripts.contains(&Script::Kana) {
+        SupportedLanguage::Japanese
+    } else {
+        // Письменность большинства букв; при равенстве — первая встреченная
+        let script = scripts.iter().copied().max_by_key(|script| {
+            (
+                scripts.iter().filter(|s| *s == script).count(),
+                std::cmp::Reverse(scripts.iter().position(|s| s == script)),
+            )
+        })?;
+
+        match script {
+            Script::Cyrillic
+                if query
+                    .chars()
+                    .any(|c| matches!(c.to_lowercase().next(), Some('і' | 'ї' | 'є' | 'ґ'))) =>
+            {
+                SupportedLanguage::Ukrainian
+            }
+            Script::Cyrillic => SupportedLanguage::Russian,
+            Script::Latin => SupportedLanguage::English,
+            Script::Greek => SupportedLanguage::Greek,
+            Script::Arabic => SupportedLanguage::Arabic,
+            Script::Hebrew => SupportedLanguage::Hebrew,
+            Script::Hangul => SupportedLanguage::Korean,
+            Script::Kana => SupportedLanguage::Japanese,
+            Script::Han => SupportedLanguage::Chinese,
+        }
+    };
+
+    Some(language)
+}
+
+/// Язык запроса, если письменность указывает на него однозначно (кириллица,
+/// греческое письмо, кана). Латиницей и иероглифами пишут на нескольких
+/// популярных языках, поэтому для них — `None`.
+pub fn detect_unambiguous_language(query: &str) -> Option<SupportedLanguage> {
+    detect_language(query)
+        .filter(|language| !matches!(language.script(), Script::Latin | Script::Han))
 }
 
 impl fmt::Display for SupportedLanguage {
@@ -296,23 +470,198 @@ impl fmt::Display for SupportedLanguage {
     }
 }
 
-impl Default for SupportedLanguage {
-    fn default() -> Self {
-        Self::Russian
+/// Откуда взят язык запроса.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LanguageSource {
+    /// Префикс `en:`.
+    Explicit,
+    /// Угадан по письменности запроса, см. [`detect_language`].
+    Detected,
+    /// Префикса нет — язык по умолчанию.
+    Default,
+    /// Префикс похож на код языка (`xx:`), но такого раздела нет. Язык — по
+    /// умолчанию, текст — весь запрос вместе с префиксом.
+    InvalidPrefix,
+}
+
+/// Запрос, разобранный на язык раздела и текст для поиска.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedQuery {
+    pub language: SupportedLanguage,
+    pub text: String,
+    pub language_source: LanguageSource,
+}
+
+impl ParsedQuery {
+    /// Отделяет префикс языка (`en:`) от запроса. Без префикса — `default_language`.
+    pub fn parse(query: &str, default_language: SupportedLanguage) -> Self {
+        if let Some(colon_pos) = query.find(':') {
+            if colon_pos > 0 && colon_pos < 5 {
+                let lang_code = &query[..colon_pos];
+
+                if let Some(language) = SupportedLanguage::from_code(lang_code) {
+                    return Self {
+                        language,
+                        text: query[colon_pos + 1..].trim().to_string(),
+                        language_source: LanguageSource::Explicit,
+                    };
+                }
+
+                // Двухбуквенный префикс — почти наверняка опечатка в коде
+                // языка; длинные (`cat:`, `toc:`) — служебные префиксы
+                if lang_code.len() == 2 && lang_code.chars().all(|c| c.is_ascii_alphabetic()) {
+                    return Self {
+                        language: default_language,
+                        text: query.to_string(),
+                        language_source: LanguageSource::InvalidPrefix,
+                    };
+                }
+            }
+        }
+
+        Self {
+            language: default_language,
+            text: query.to_string(),
+            language_source: LanguageSource::Default,
+        }
+    }
+
+    /// Как [`ParsedQuery::parse`], но запрос без префикса в другой
+    /// письменности, чем у `default_language`, ищется на угаданном языке.
+    pub fn parse_detecting(query: &str, default_language: SupportedLanguage) -> Self {
+        let parsed = Self::parse(query, default_language);
+        if parsed.language_source != LanguageSource::Default {
+            return parsed;
+        }
+
+        match detect_language(query) {
+            Some(detected) if detected.script() != default_language.script() => Self {
+                language: detected,
+                language_source: LanguageSource::Detected,
+                ..parsed
+            },
+            _ => parsed,
+        }
+    }
+
+    pub fn into_parts(self) -> (SupportedLanguage, String) {
+        (self.language, self.text)
     }
 }
 
+/// Отделяет префикс языка (`en:`) от запроса. Без префикса — язык по
+/// умолчанию, [`SupportedLanguage::default`].
 pub fn parse_query_with_language(query: &str) -> (SupportedLanguage, String) {
-    if let Some(colon_pos) = query.find(':') {
-        if colon_pos > 0 && colon_pos < 5 {
-            let lang_code = &query[..colon_pos];
-            let search_query = query[colon_pos + 1..].trim().to_string();
+    parse_query_with_default_language(query, SupportedLanguage::default())
+}
 
-            if let Some(language) = SupportedLanguage::from_code(lang_code) {
-                return (language, search_query);
-            }
+/// Вариант [`parse_query_with_language`] с настроенным языком по умолчанию
+/// (`DEFAULT_LANGUAGE`) для запросов без префикса. Подробности разбора —
+/// в [`ParsedQuery`].
+pub fn parse_query_with_default_language(
+    query: &str,
+    default_language: SupportedLanguage,
+) -> (SupportedLanguage, String) {
+    ParsedQuery::parse(query, default_language).into_parts()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parsed_query_language_source() {
+        let parse = |query| ParsedQuery::parse_detecting(query, SupportedLanguage::Russian);
+
+        let explicit = parse("en: Albert Einstein");
+        assert_eq!(explicit.language, SupportedLanguage::English);
+        assert_eq!(explicit.text, "Albert Einstein");
+        assert_eq!(explicit.language_source, LanguageSource::Explicit);
+
+        let detected = parse("Albert Einstein");
+        assert_eq!(detected.language, SupportedLanguage::English);
+        assert_eq!(detected.text, "Albert Einstein");
+        assert_eq!(detected.language_source, LanguageSource::Detected);
+
+        let default = parse("Пушкин");
+        assert_eq!(default.language, SupportedLanguage::Russian);
+        assert_eq!(default.language_source, LanguageSource::Default);
+        assert_eq!(
+            ParsedQuery::parse("Albert Einstein", SupportedLanguage::Russian).language_source,
+            LanguageSource::Default
+        );
+
+        let invalid = parse("xx:Пушкин");
+        assert_eq!(invalid.language, SupportedLanguage::Russian);
+        assert_eq!(invalid.text, "xx:Пушкин");
+        assert_eq!(invalid.language_source, LanguageSource::InvalidPrefix);
+        // Служебные префиксы кодом языка не считаются
+        assert_eq!(parse("toc:Пушкин").language_source, LanguageSource::Default);
+
+        assert_eq!(
+            parse_query_with_default_language("de:Berlin", SupportedLanguage::Russian),
+            (SupportedLanguage::German, "Berlin".to_string())
+        );
+    }
+
+    #[test]
+    fn test_from_codes_skips_invalid_and_duplicate_codes() {
+        assert_eq!(
+            SupportedLanguage::from_codes(&["en", " RU ", "xx", "", "en", "ja"]),
+            vec![
+                SupportedLanguage::English,
+                SupportedLanguage::Russian,
+                SupportedLanguage::Japanese
+            ]
+        );
+        assert!(SupportedLanguage::from_codes(&["klingon"]).is_empty());
+
+        let cyrillic: Vec<_> = SupportedLanguage::iter_popular()
+            .filter(|language| language.script() == Script::Cyrillic)
+            .collect();
+        assert_eq!(
+            cyrillic,
+            vec![SupportedLanguage::Russian, SupportedLanguage::Ukrainian]
+        );
+        assert_eq!(
+            SupportedLanguage::iter_all().count(),
+            SupportedLanguage::all_languages().len()
+        );
+    }
+
+    #[test]
+    fn test_endonym_defined_for_all_languages() {
+        for language in SupportedLanguage::all_languages() {
+            assert!(!language.endonym().is_empty(), "{language}");
         }
+        assert_eq!(SupportedLanguage::German.endonym(), "Deutsch");
+        assert_eq!(SupportedLanguage::Japanese.endonym(), "日本語");
+    }
+
+    #[test]
+    fn test_name_for_locale() {
+        let language = SupportedLanguage::French;
+        assert_eq!(language.name_for_locale(None), "французской");
+        assert_eq!(language.name_for_locale(Some("ru")), "французской");
+        assert_eq!(language.name_for_locale(Some("ru-RU")), "французской");
+        assert_eq!(language.name_for_locale(Some("en")), "Français");
     }
 
-    (SupportedLanguage::default(), query.to_string())
+    #[test]
+    fn test_detect_language_by_script() {
+        assert_eq!(detect_language("Пушкин"), Some(SupportedLanguage::Russian));
+        assert_eq!(detect_language("Київ"), Some(SupportedLanguage::Ukrainian));
+        assert_eq!(
+            detect_language("Einstein"),
+            Some(SupportedLanguage::English)
+        );
+        assert_eq!(
+            detect_language("東京タワー"),
+            Some(SupportedLanguage::Japanese)
+        );
+        assert_eq!(detect_language("北京"), Some(SupportedLanguage::Chinese));
+        assert_eq!(detect_language("서울"), Some(SupportedLanguage::Korean));
+        assert_eq!(detect_language("Αθήνα"), Some(SupportedLanguage::Greek));
+        assert_eq!(detect_language("1799"), None);
+    }
 }
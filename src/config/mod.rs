@@ -1,12 +1,57 @@
 use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::time::Duration;
 
 pub mod languages;
+pub mod projects;
+
+use languages::SupportedLanguage;
+
+/// Имя и версия бота, которые всегда присутствуют в User-Agent.
+pub const BOT_USER_AGENT_PRODUCT: &str =
+    concat!("WikipediaArticlesBot/", env!("CARGO_PKG_VERSION"));
+
+/// Профили ранжирования CirrusSearch (`srqiprofile`), которые можно задать в
+/// `SEARCH_PROFILE`.
+pub const SEARCH_PROFILES: &[&str] = &[
+    "classic",
+    "classic_noboostlinks",
+    "empty",
+    "engine_autoselect",
+    "popular_inclinks",
+    "popular_inclinks_pv",
+    "wsum_inclinks",
+    "wsum_inclinks_pv",
+];
+
+/// Допустимые значения `srwhat`. `title` поддерживают не все вики: CirrusSearch
+/// отвечает на него ошибкой.
+pub const SEARCH_WHAT_VALUES: &[&str] = &["text", "nearmatch", "title"];
+
+/// Наибольший `srlimit`/`gsrlimit`, который принимает поиск MediaWiki.
+pub const MAX_SEARCH_RESULTS_LIMIT: usize = 500;
+
+/// CirrusSearch отклоняет запросы длиннее 300 символов.
+pub const MAX_QUERY_LENGTH_LIMIT: usize = 300;
+
+/// Наибольший `exsentences`, который принимает TextExtracts.
+pub const MAX_EXTRACT_SENTENCES: u8 = 10;
+
+/// Telegram принимает не больше 50 результатов в ответе на inline-запрос.
+pub const TELEGRAM_MAX_INLINE_RESULTS: usize = 50;
+
+/// Больше кнопок в ряд Telegram-клиенты показывают нечитаемо узкими.
+pub const TELEGRAM_MAX_KEYBOARD_COLUMNS: usize = 8;
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct AppConfig {
     pub telegram: TelegramConfig,
     pub wikipedia: WikipediaConfig,
+    #[serde(default)]
+    pub wikidata: WikidataConfig,
+    #[serde(default)]
+    pub retry: RetryConfig,
     pub cache: CacheConfig,
     pub logging: LoggingConfig,
 }
@@ -16,6 +61,243 @@ pub struct TelegramConfig {
     pub bot_token: String,
     #[serde(default = "default_request_timeout")]
     pub request_timeout_secs: u64,
+
+    #[serde(default)]
+    pub show_category_buttons: bool,
+
+    #[serde(default = "default_send_top_result_as_photo")]
+    pub send_top_result_as_photo: bool,
+
+    /// Отвечать в inline-режиме фото с карточкой статьи в подписи вместо
+    /// текстового результата, если у статьи есть крупное изображение.
+    #[serde(default)]
+    pub inline_photo_results: bool,
+
+    /// Добавлять к inline-результатам URL-кнопку со ссылкой на статью.
+    #[serde(default)]
+    pub show_open_button: bool,
+
+    /// Отправлять из inline-режима вместо текста статьи короткую ссылку с
+    /// превью (и Instant View, если Telegram его поддерживает для статьи).
+    #[serde(default)]
+    pub use_article_url_preview: bool,
+
+    /// Начинать сообщение статьи невидимой ссылкой на её изображение, чтобы
+    /// Telegram показал его в превью ссылки.
+    #[serde(default)]
+    pub embed_article_image: bool,
+
+    /// Показывать первым inline-результатом определение из Викисловаря, если
+    /// запрос состоит из одного слова.
+    #[serde(default)]
+    pub show_definitions: bool,
+
+    /// Показывать в описании inline-результата, когда статью правили последний
+    /// раз. Время есть не у всех результатов — без него строка не добавляется.
+    #[serde(default)]
+    pub show_last_edited: bool,
+
+    /// Сколько результатов показывать в inline-режиме, независимо от
+    /// `max_search_results` (сколько статей запрашивать для ранжирования).
+    #[serde(default = "default_max_inline_results")]
+    pub max_inline_results: usize,
+
+    /// JSONL-файл для анонимных событий inline-запросов; без него аналитика выключена.
+    #[serde(default)]
+    pub analytics_path: Option<String>,
+
+    /// Сохранять в событиях аналитики текст запроса.
+    #[serde(default)]
+    pub analytics_include_query: bool,
+
+    /// Чат, куда пересылаются отзывы пользователей (`/feedback`). Не задан —
+    /// команда отвечает, что отзывы не принимаются.
+    #[serde(default)]
+    pub feedback_chat_id: Option<i64>,
+
+    /// Коды языков для клавиатуры выбора языка, в порядке показа. Пусто —
+    /// `SupportedLanguage::popular_languages()`.
+    #[serde(default)]
+    pub keyboard_languages: Vec<String>,
+
+    /// Сколько кнопок в ряду клавиатуры выбора языка.
+    #[serde(default = "default_keyboard_columns")]
+    pub keyboard_columns: usize,
+
+    /// Окно в секундах, за которое `/trending` считает популярные запросы.
+    /// Не задано — счётчик не ведётся и команда выключена.
+    #[serde(default)]
+    pub trending_window_secs: Option<u64>,
+
+    /// Сколько запросов показывать в `/trending`.
+    #[serde(default = "default_trending_max_items")]
+    pub trending_max_items: usize,
+
+    /// Запросы, встретившиеся реже, в `/trending` не попадают: редкие
+    /// запросы могут выдать конкретного пользователя.
+    #[serde(default = "default_trending_min_count")]
+    pub trending_min_count: u32,
+
+    /// Сколько последних запросов пользователя показывать в пустом
+    /// inline-запросе для повтора. 0 — история не ведётся.
+    #[serde(default = "default_search_history_size")]
+    pub search_history_size: usize,
+
+    /// Что показывать в inline-режиме на запрос из одного префикса (`en:`,
+    /// `wikt:`), когда искать ещё нечего.
+    #[serde(default)]
+    pub prefix_only_query: PrefixOnlyQuery,
+
+    /// Вид inline-результатов по умолчанию; пользователь может выбрать свой
+    /// командой `/style`.
+    #[serde(default)]
+    pub result_style: ResultStyle,
+
+    /// Оформление карточки статьи.
+    #[serde(default)]
+    pub branding: Branding,
+}
+
+/// Ответ на inline-запрос, в котором после префикса языка или проекта
+/// ничего нет.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrefixOnlyQuery {
+    /// Подсказка ввести запрос для выбранного языка.
+    #[default]
+    Prompt,
+    /// Клавиатура выбора языка, как в пустом запросе.
+    LanguageKeyboard,
+}
+
+impl std::str::FromStr for PrefixOnlyQuery {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "prompt" => Ok(Self::Prompt),
+            "language_keyboard" | "keyboard" => Ok(Self::LanguageKeyboard),
+            other => Err(format!(
+                "unknown prefix-only query behavior '{other}', expected prompt or language_keyboard"
+            )),
+        }
+    }
+}
+
+/// Вид сообщения, которое отправляет inline-результат.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResultStyle {
+    /// Заголовок, одна строка описания и кнопка со ссылкой.
+    Compact,
+    /// Карточка с началом статьи.
+    #[default]
+    Detailed,
+}
+
+impl std::str::FromStr for ResultStyle {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "compact" => Ok(Self::Compact),
+            "detailed" => Ok(Self::Detailed),
+            other => Err(format!(
+                "unknown result style '{other}', expected compact or detailed"
+            )),
+        }
+    }
+}
+
+/// Источник данных Wikidata, которым дополняются найденные статьи.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnrichmentSource {
+    /// Краткое описание сущности на языке раздела.
+    Descriptions,
+    /// Тип сущности по «экземпляр» (P31).
+    InstanceOf,
+    /// Координаты (P625), если их нет в Wikipedia.
+    Coordinates,
+}
+
+impl std::str::FromStr for EnrichmentSource {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "descriptions" => Ok(Self::Descriptions),
+            "instance_of" => Ok(Self::InstanceOf),
+            "coordinates" => Ok(Self::Coordinates),
+            other => Err(format!(
+                "unknown enrichment source '{other}', expected descriptions, instance_of or coordinates"
+            )),
+        }
+    }
+}
+
+/// Оформление карточки статьи в сообщениях: эмодзи заголовка, подзаголовок
+/// из Wikidata, подпись ссылки и разделитель между частями. Пустой эмодзи убирается вместе с пробелом.
+/// Значения — обычный текст, экранируются при форматировании.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Branding {
+    #[serde(default = "default_branding_title_emoji")]
+    pub title_emoji: String,
+
+    #[serde(default = "default_branding_link_emoji")]
+    pub link_emoji: String,
+
+    #[serde(default = "default_branding_link_label")]
+    pub link_label: String,
+
+    #[serde(default = "default_branding_separator")]
+    pub separator: String,
+
+    /// Показывать краткое описание Wikidata курсивом под заголовком, даже
+    /// если под ним идёт текст статьи.
+    #[serde(default)]
+    pub show_wikidata_subtitle: bool,
+}
+
+impl Default for Branding {
+    fn default() -> Self {
+        Self {
+            title_emoji: default_branding_title_emoji(),
+            link_emoji: default_branding_link_emoji(),
+            link_label: default_branding_link_label(),
+            separator: default_branding_separator(),
+            show_wikidata_subtitle: false,
+        }
+    }
+}
+
+impl TelegramConfig {
+    /// Языки клавиатуры выбора языка. Неизвестные коды и повторы пропускаются
+    /// (при загрузке из окружения коды уже проверены); если не осталось ни
+    /// одного языка — набор по умолчанию.
+    pub fn selection_languages(&self) -> Vec<SupportedLanguage> {
+        let codes: Vec<&str> = self.keyboard_languages.iter().map(String::as_str).collect();
+        let languages = SupportedLanguage::from_codes(&codes);
+
+        if languages.is_empty() {
+            SupportedLanguage::popular_languages().to_vec()
+        } else {
+            languages
+        }
+    }
+
+    /// Кнопок в ряду клавиатуры выбора языка, от 1 до
+    /// [`TELEGRAM_MAX_KEYBOARD_COLUMNS`].
+    pub fn keyboard_row_width(&self) -> usize {
+        self.keyboard_columns
+            .clamp(1, TELEGRAM_MAX_KEYBOARD_COLUMNS)
+    }
+
+    /// Лимит inline-результатов с учётом ограничения Telegram.
+    pub fn inline_results_limit(&self) -> usize {
+        self.max_inline_results
+            .clamp(1, TELEGRAM_MAX_INLINE_RESULTS)
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -34,6 +316,297 @@ pub struct WikipediaConfig {
 
     #[serde(default = "default_user_agent")]
     pub user_agent: String,
+
+    #[serde(default = "default_max_query_length")]
+    pub max_query_length: usize,
+
+    #[serde(default)]
+    pub sanitize_queries: bool,
+
+    /// Базовый адрес зеркала Wikipedia вместо `https://{lang}.wikipedia.org`.
+    #[serde(default)]
+    pub api_base: Option<String>,
+
+    /// Для запросов без префикса языка искать сразу во всех популярных разделах.
+    #[serde(default)]
+    pub auto_language: bool,
+
+    /// Повторять пустой латинский поиск в ru/uk разделах в кириллической транслитерации.
+    #[serde(default = "default_transliterate_latin")]
+    pub transliterate_latin: bool,
+
+    /// Убирать из начала extract скобки с произношением и датами жизни.
+    #[serde(default)]
+    pub trim_parentheticals: bool,
+
+    #[serde(default)]
+    pub ranking: RankingWeights,
+
+    /// Каталог с фикстурами: при заданном значении поиск отвечает заготовленными
+    /// статьями без обращения к сети (см. `FixtureStore`).
+    #[serde(default)]
+    pub fixtures_dir: Option<PathBuf>,
+
+    /// Категории, статьи из которых не показываются (без учёта регистра и
+    /// префикса «Категория:»). Пусто — фильтр выключен.
+    #[serde(default)]
+    pub blocked_categories: Vec<String>,
+
+    /// Регулярные выражения (без учёта регистра) для заголовков скрываемых статей.
+    #[serde(default)]
+    pub blocked_title_patterns: Vec<String>,
+
+    /// Профиль ранжирования поиска (`srqiprofile`) из [`SEARCH_PROFILES`].
+    /// Не задан — профиль по умолчанию на стороне API.
+    #[serde(default)]
+    pub search_profile: Option<String>,
+
+    /// Где искать запрос (`srwhat`) из [`SEARCH_WHAT_VALUES`]. Если по
+    /// `nearmatch` ничего не нашлось, поиск повторяется по тексту. Не задано —
+    /// значение по умолчанию на стороне API.
+    #[serde(default)]
+    pub search_what: Option<String>,
+
+    /// Синонимы запросов: запрос, целиком совпавший с ключом (без учёта
+    /// регистра), заменяется значением перед поиском («covid» → «COVID-19»).
+    #[serde(default)]
+    pub query_synonyms: HashMap<String, String>,
+
+    /// Разделы, в которых по очереди повторяется поиск, если в запрошенном
+    /// ничего не нашлось. Пусто — без резервных разделов.
+    #[serde(default)]
+    pub fallback_languages: Vec<String>,
+
+    /// Раздел для запросов без префикса языка (`en:`, `de:`…).
+    #[serde(default = "default_language_code")]
+    pub default_language: String,
+
+    /// Популярные запросы, результаты которых загружаются в кэш в фоне
+    /// вскоре после запуска. Префикс языка поддерживается (`en:Moscow`).
+    #[serde(default)]
+    pub warm_queries: Vec<String>,
+
+    /// Сколько миллисекунд ждать ответа на поиск, которого нет в кэше. Если
+    /// не дождались, отдаём закэшированный результат более короткого запроса,
+    /// которым начинается текущий, а запрос дозаполняет кэш в фоне. Не задано
+    /// или подходящего результата нет — ждём ответ как обычно.
+    #[serde(default)]
+    pub cached_fallback_deadline_ms: Option<u64>,
+
+    /// Сколько простаивающих соединений держать на один хост. Не задано — по умолчанию reqwest.
+    #[serde(default)]
+    pub pool_max_idle_per_host: Option<usize>,
+
+    /// Через сколько секунд закрывать простаивающее соединение. Не задано — по умолчанию reqwest.
+    #[serde(default)]
+    pub pool_idle_timeout_secs: Option<u64>,
+
+    /// Сразу говорить по HTTP/2 без согласования. Включать только если сервер
+    /// (или зеркало из `api_base`) заведомо поддерживает HTTP/2.
+    #[serde(default)]
+    pub http2_prior_knowledge: bool,
+
+    /// Какой источник описания показывать в списке результатов первым.
+    #[serde(default)]
+    pub description_source: DescriptionSource,
+
+    /// В каком виде запрашивать текст статьи: простым текстом
+    /// (`explaintext`) или HTML для карточек в `ParseMode::Html`.
+    #[serde(default)]
+    pub extract_format: ExtractFormat,
+
+    /// Сколько предложений текста статьи запрашивать (`exsentences`, 1–10)
+    /// вместо обрезки по числу символов (`exchars`).
+    #[serde(default)]
+    pub extract_sentences: Option<u8>,
+}
+
+impl WikipediaConfig {
+    /// Раздел для запросов без префикса языка. Код проверяется в
+    /// [`AppConfig::validate`], поэтому запасной русский нужен только для
+    /// конфигураций, собранных в обход проверки.
+    pub fn default_language(&self) -> SupportedLanguage {
+        SupportedLanguage::from_code(&self.default_language).unwrap_or_default()
+    }
+
+    /// User-Agent для запросов к Wikimedia: настроенная строка, к которой при
+    /// необходимости добавляется версия бота.
+    pub fn user_agent_header(&self) -> String {
+        if self.user_agent.contains("WikipediaArticlesBot/") {
+            self.user_agent.clone()
+        } else {
+            format!("{} {}", self.user_agent.trim(), BOT_USER_AGENT_PRODUCT)
+        }
+    }
+
+    /// Используется ли User-Agent по умолчанию, без контактов оператора.
+    pub fn has_default_user_agent(&self) -> bool {
+        self.user_agent == default_user_agent()
+    }
+}
+
+/// Порядок источников краткого описания статьи. Остальные источники
+/// остаются запасными в прежнем порядке, последним всегда идёт snippet поиска.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DescriptionSource {
+    /// Начало текста статьи (`extracts`).
+    ExtractFirst,
+    /// Описание элемента Wikidata.
+    WikidataFirst,
+    /// Описание из `pageprops` (`wikibase-shortdesc`).
+    #[default]
+    ShortDescFirst,
+}
+
+impl std::str::FromStr for DescriptionSource {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "extract_first" | "extract" => Ok(Self::ExtractFirst),
+            "wikidata_first" | "wikidata" => Ok(Self::WikidataFirst),
+            "short_desc_first" | "short_desc" => Ok(Self::ShortDescFirst),
+            other => Err(format!(
+                "unknown description source '{other}', expected one of \
+                 extract_first, wikidata_first, short_desc_first"
+            )),
+        }
+    }
+}
+
+/// Формат текста статьи (`prop=extracts`). С `Html` простой текст для
+/// MarkdownV2 и описаний получается из того же HTML, а HTML-карточки
+/// сохраняют разрешённое Telegram форматирование.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExtractFormat {
+    #[default]
+    Plain,
+    Html,
+}
+
+impl ExtractFormat {
+    /// Разметка карточек статей: форматирование HTML-текста сохраняется
+    /// только в `ParseMode::Html`.
+    pub fn parse_mode(self) -> teloxide::types::ParseMode {
+        match self {
+            Self::Plain => teloxide::types::ParseMode::MarkdownV2,
+            Self::Html => teloxide::types::ParseMode::Html,
+        }
+    }
+}
+
+impl std::str::FromStr for ExtractFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "plain" | "text" => Ok(Self::Plain),
+            "html" => Ok(Self::Html),
+            other => Err(format!(
+                "unknown extract format '{other}', expected plain or html"
+            )),
+        }
+    }
+}
+
+/// Настройки обогащения через Wikidata. Это дополнительный путь, поэтому у
+/// него свои таймаут и кэш, независимые от Wikipedia.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WikidataConfig {
+    #[serde(default = "default_request_timeout")]
+    pub request_timeout_secs: u64,
+
+    #[serde(default = "default_cache_ttl_secs")]
+    pub cache_ttl_secs: u64,
+
+    /// Ёмкость каждого из кэшей Wikidata (описания и утверждения).
+    #[serde(default = "default_cache_capacity")]
+    pub cache_capacity: u64,
+
+    #[serde(default = "default_enable_wikidata")]
+    pub enabled: bool,
+
+    /// Сколько пачек `wbgetentities` (по 50 идентификаторов) выполнять
+    /// одновременно — общее ограничение для всех поисков.
+    #[serde(default = "default_wikidata_max_concurrency")]
+    pub max_concurrency: usize,
+
+    /// Какие данные Wikidata добавлять к результатам и в каком порядке.
+    #[serde(default = "default_wikidata_enrichers")]
+    pub enrichers: Vec<EnrichmentSource>,
+
+    /// Базовый адрес Wikibase API вместо `https://www.wikidata.org`.
+    #[serde(default)]
+    pub api_base: Option<String>,
+}
+
+impl Default for WikidataConfig {
+    fn default() -> Self {
+        Self {
+            request_timeout_secs: default_request_timeout(),
+            cache_ttl_secs: default_cache_ttl_secs(),
+            cache_capacity: default_cache_capacity(),
+            enabled: default_enable_wikidata(),
+            max_concurrency: default_wikidata_max_concurrency(),
+            enrichers: default_wikidata_enrichers(),
+            api_base: None,
+        }
+    }
+}
+
+/// Повторы запросов к Wikipedia и Wikidata при временных сбоях: таймаутах,
+/// ошибках соединения, 5xx и 429 с коротким `Retry-After`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RetryConfig {
+    /// Сколько всего попыток делать, включая первую; 1 — без повторов.
+    #[serde(default = "default_retry_max_attempts")]
+    pub max_attempts: u32,
+
+    /// Пауза перед первым повтором; дальше удваивается.
+    #[serde(default = "default_retry_base_delay_ms")]
+    pub base_delay_ms: u64,
+
+    /// Верхняя граница паузы. Если `Retry-After` просит ждать дольше,
+    /// запрос не повторяется.
+    #[serde(default = "default_retry_max_delay_ms")]
+    pub max_delay_ms: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_retry_max_attempts(),
+            base_delay_ms: default_retry_base_delay_ms(),
+            max_delay_ms: default_retry_max_delay_ms(),
+        }
+    }
+}
+
+/// Веса клиентского переранжирования результатов поиска.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RankingWeights {
+    /// На сколько позиций (максимум) может подняться статья, заголовок которой
+    /// совпадает с запросом. Небольшое значение оставляет переранжирование
+    /// тай-брейком и не перебивает сильный сигнал релевантности; 0 — выключено.
+    #[serde(default = "default_title_similarity_weight")]
+    pub title_similarity: f64,
+
+    /// Прибавка к оценке статьи за класс качества (избранная получает её
+    /// целиком, заготовка — ноль). Оценки есть в английской Википедии; 0 —
+    /// не запрашивать их вовсе.
+    #[serde(default = "default_quality_weight")]
+    pub quality: f64,
+}
+
+impl Default for RankingWeights {
+    fn default() -> Self {
+        Self {
+            title_similarity: default_title_similarity_weight(),
+            quality: default_quality_weight(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -46,6 +619,24 @@ pub struct CacheConfig {
 
     #[serde(default = "default_enable_cache")]
     pub enabled: bool,
+
+    /// Ёмкость кэша поиска; по умолчанию `max_capacity`.
+    #[serde(default)]
+    pub search_capacity: Option<u64>,
+
+    /// Ёмкость кэша batch-информации; по умолчанию `max_capacity / 2`.
+    #[serde(default)]
+    pub batch_capacity: Option<u64>,
+
+    /// Ёмкость кэша unified-запросов; по умолчанию `max_capacity / 4`.
+    #[serde(default)]
+    pub unified_capacity: Option<u64>,
+
+    /// «Мягкий» TTL результатов поиска: более старый результат всё ещё
+    /// отдаётся сразу, но обновляется в фоне. После `ttl_secs` результат
+    /// удаляется и поиск снова ждёт ответа API. Не задано — фонового обновления нет.
+    #[serde(default)]
+    pub soft_ttl_secs: Option<u64>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -70,37 +661,252 @@ pub enum LogFormat {
 
 impl AppConfig {
     pub fn from_env() -> Result<Self, crate::errors::WikiError> {
-        let bot_token = std::env::var("TELOXIDE_TOKEN")
-            .or_else(|_| std::env::var("BOT_TOKEN"))
-            .map_err(|_| {
+        Self::from_vars(&|name| std::env::var(name).ok())
+    }
+
+    /// То же, что [`Self::from_env`], но значения переменных берутся из
+    /// `vars`, а не из окружения процесса.
+    pub fn from_vars(vars: Vars) -> Result<Self, crate::errors::WikiError> {
+        let bot_token = vars("TELOXIDE_TOKEN")
+            .or_else(|| vars("BOT_TOKEN"))
+            .ok_or_else(|| {
                 crate::errors::WikiError::config(
                     "TELOXIDE_TOKEN or BOT_TOKEN environment variable not set",
                 )
             })?;
 
-        Ok(AppConfig {
+        let config = AppConfig {
             telegram: TelegramConfig {
                 bot_token,
                 request_timeout_secs: default_request_timeout(),
+                show_category_buttons: env_or(vars, "SHOW_CATEGORY_BUTTONS", false),
+                show_open_button: env_or(vars, "SHOW_OPEN_BUTTON", false),
+                use_article_url_preview: env_or(vars, "USE_ARTICLE_URL_PREVIEW", false),
+                embed_article_image: env_or(vars, "EMBED_ARTICLE_IMAGE", false),
+                show_definitions: env_or(vars, "SHOW_DEFINITIONS", false),
+                show_last_edited: env_or(vars, "SHOW_LAST_EDITED", false),
+                send_top_result_as_photo: env_or(
+                    vars,
+                    "SEND_TOP_RESULT_AS_PHOTO",
+                    default_send_top_result_as_photo(),
+                ),
+                inline_photo_results: env_or(vars, "INLINE_PHOTO_RESULTS", false),
+                max_inline_results: env_or(
+                    vars,
+                    "MAX_INLINE_RESULTS",
+                    default_max_inline_results(),
+                ),
+                analytics_path: env_opt(vars, "ANALYTICS_PATH"),
+                analytics_include_query: env_or(vars, "ANALYTICS_INCLUDE_QUERY", false),
+                feedback_chat_id: env_opt(vars, "FEEDBACK_CHAT_ID"),
+                keyboard_languages: env_languages(vars, "KEYBOARD_LANGUAGES")?,
+                keyboard_columns: env_or(vars, "KEYBOARD_COLUMNS", default_keyboard_columns()),
+                trending_window_secs: env_opt(vars, "TRENDING_WINDOW_SECS"),
+                trending_max_items: env_or(
+                    vars,
+                    "TRENDING_MAX_ITEMS",
+                    default_trending_max_items(),
+                ),
+                trending_min_count: env_or(
+                    vars,
+                    "TRENDING_MIN_COUNT",
+                    default_trending_min_count(),
+                ),
+                search_history_size: env_or(
+                    vars,
+                    "SEARCH_HISTORY_SIZE",
+                    default_search_history_size(),
+                ),
+                prefix_only_query: env_choice(vars, "PREFIX_ONLY_QUERY")?,
+                result_style: env_choice(vars, "RESULT_STYLE")?,
+                branding: Branding {
+                    title_emoji: env_or(
+                        vars,
+                        "BRANDING_TITLE_EMOJI",
+                        default_branding_title_emoji(),
+                    ),
+                    link_emoji: env_or(vars, "BRANDING_LINK_EMOJI", default_branding_link_emoji()),
+                    link_label: env_or(vars, "BRANDING_LINK_LABEL", default_branding_link_label()),
+                    // В переменной окружения перевод строки записывается как `\n`
+                    separator: env_opt::<String>(vars, "BRANDING_SEPARATOR")
+                        .map(|separator| separator.replace("\\n", "\n"))
+                        .unwrap_or_else(default_branding_separator),
+                    show_wikidata_subtitle: env_or(vars, "SHOW_WIKIDATA_SUBTITLE", false),
+                },
             },
             wikipedia: WikipediaConfig {
                 request_timeout_secs: default_request_timeout(),
                 max_search_results: default_max_results(),
                 max_description_length: default_max_description_length(),
                 max_content_length: default_max_content_length(),
-                user_agent: default_user_agent(),
+                user_agent: env_opt::<String>(vars, "WIKI_USER_AGENT")
+                    .filter(|user_agent| !user_agent.is_empty())
+                    .unwrap_or_else(default_user_agent),
+                max_query_length: env_or(vars, "MAX_QUERY_LENGTH", default_max_query_length()),
+                sanitize_queries: env_or(vars, "SANITIZE_QUERIES", false),
+                api_base: env_api_base(vars, "WIKIPEDIA_API_BASE")?,
+                auto_language: env_or(vars, "AUTO_LANGUAGE", false),
+                transliterate_latin: env_or(
+                    vars,
+                    "TRANSLITERATE_LATIN",
+                    default_transliterate_latin(),
+                ),
+                trim_parentheticals: env_or(vars, "TRIM_PARENTHETICALS", false),
+                ranking: RankingWeights {
+                    title_similarity: env_or(
+                        vars,
+                        "RANKING_TITLE_SIMILARITY_WEIGHT",
+                        default_title_similarity_weight(),
+                    ),
+                    quality: env_or(vars, "RANKING_QUALITY_WEIGHT", default_quality_weight()),
+                },
+                fixtures_dir: env_opt(vars, "WIKI_FIXTURES_DIR"),
+                blocked_categories: env_list(vars, "BLOCKED_CATEGORIES"),
+                blocked_title_patterns: env_patterns(vars, "BLOCKED_TITLE_PATTERNS")?,
+                search_profile: env_search_profile(vars, "SEARCH_PROFILE")?,
+                search_what: env_one_of(vars, "SEARCH_WHAT", SEARCH_WHAT_VALUES, "search mode")?,
+                query_synonyms: env_map(vars, "QUERY_SYNONYMS")?,
+                fallback_languages: env_languages(vars, "FALLBACK_LANGUAGES")?,
+                default_language: env_or(vars, "DEFAULT_LANGUAGE", default_language_code()),
+                warm_queries: env_list(vars, "WARM_QUERIES"),
+                cached_fallback_deadline_ms: env_opt(vars, "CACHED_FALLBACK_DEADLINE_MS"),
+                pool_max_idle_per_host: env_opt(vars, "HTTP_POOL_MAX_IDLE_PER_HOST"),
+                pool_idle_timeout_secs: env_opt(vars, "HTTP_POOL_IDLE_TIMEOUT_SECS"),
+                http2_prior_knowledge: env_or(vars, "HTTP2_PRIOR_KNOWLEDGE", false),
+                description_source: env_choice(vars, "DESCRIPTION_SOURCE")?,
+                extract_format: env_choice(vars, "EXTRACT_FORMAT")?,
+                extract_sentences: env_opt(vars, "EXTRACT_SENTENCES"),
+            },
+            wikidata: WikidataConfig {
+                request_timeout_secs: env_or(
+                    vars,
+                    "WIKIDATA_REQUEST_TIMEOUT_SECS",
+                    default_request_timeout(),
+                ),
+                cache_ttl_secs: env_or(vars, "WIKIDATA_CACHE_TTL_SECS", default_cache_ttl_secs()),
+                cache_capacity: env_or(vars, "WIKIDATA_CACHE_CAPACITY", default_cache_capacity()),
+                enabled: env_or(vars, "ENABLE_WIKIDATA", default_enable_wikidata()),
+                max_concurrency: env_or(
+                    vars,
+                    "WIKIDATA_MAX_CONCURRENCY",
+                    default_wikidata_max_concurrency(),
+                ),
+                enrichers: env_choices(vars, "WIKIDATA_ENRICHERS", default_wikidata_enrichers())?,
+                api_base: env_api_base(vars, "WIKIDATA_API_BASE")?,
+            },
+            retry: RetryConfig {
+                max_attempts: env_or(
+                    vars,
+                    "HTTP_RETRY_MAX_ATTEMPTS",
+                    default_retry_max_attempts(),
+                ),
+                base_delay_ms: env_or(
+                    vars,
+                    "HTTP_RETRY_BASE_DELAY_MS",
+                    default_retry_base_delay_ms(),
+                ),
+                max_delay_ms: env_or(
+                    vars,
+                    "HTTP_RETRY_MAX_DELAY_MS",
+                    default_retry_max_delay_ms(),
+                ),
             },
             cache: CacheConfig {
                 max_capacity: default_cache_capacity(),
                 ttl_secs: default_cache_ttl_secs(),
                 enabled: default_enable_cache(),
+                search_capacity: env_opt(vars, "SEARCH_CACHE_CAPACITY"),
+                batch_capacity: env_opt(vars, "BATCH_CACHE_CAPACITY"),
+                unified_capacity: env_opt(vars, "UNIFIED_CACHE_CAPACITY"),
+                soft_ttl_secs: env_opt(vars, "CACHE_SOFT_TTL_SECS"),
             },
             logging: LoggingConfig {
-                level: std::env::var("RUST_LOG").unwrap_or_else(|_| default_log_level()),
+                level: vars("RUST_LOG").unwrap_or_else(default_log_level),
                 format: default_log_format(),
                 console: default_enable_console(),
             },
-        })
+        };
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Проверяет ограничения, которые иначе всплыли бы только во время работы
+    /// (например, Wikipedia отклонила бы `srlimit=99999`). Ошибка перечисляет
+    /// все нарушения сразу.
+    pub fn validate(&self) -> Result<(), crate::errors::WikiError> {
+        let mut violations = Vec::new();
+
+        if self.telegram.bot_token.trim().is_empty() {
+            violations.push("bot token is empty".to_string());
+        }
+
+        let max_results = self.wikipedia.max_search_results;
+        if !(1..=MAX_SEARCH_RESULTS_LIMIT).contains(&max_results) {
+            violations.push(format!(
+                "wikipedia.max_search_results must be between 1 and \
+                 {MAX_SEARCH_RESULTS_LIMIT}, got {max_results}"
+            ));
+        }
+
+        if SupportedLanguage::from_code(&self.wikipedia.default_language).is_none() {
+            violations.push(format!(
+                "DEFAULT_LANGUAGE: unsupported language code '{}'",
+                self.wikipedia.default_language
+            ));
+        }
+
+        if let Some(sentences) = self.wikipedia.extract_sentences {
+            if !(1..=MAX_EXTRACT_SENTENCES).contains(&sentences) {
+                violations.push(format!(
+                    "EXTRACT_SENTENCES must be between 1 and {MAX_EXTRACT_SENTENCES}, \
+                     got {sentences}"
+                ));
+            }
+        }
+
+        let max_query_length = self.wikipedia.max_query_length;
+        if !(1..=MAX_QUERY_LENGTH_LIMIT).contains(&max_query_length) {
+            violations.push(format!(
+                "MAX_QUERY_LENGTH must be between 1 and {MAX_QUERY_LENGTH_LIMIT}, \
+                 got {max_query_length}"
+            ));
+        }
+
+        for (name, timeout) in [
+            (
+                "wikipedia.request_timeout_secs",
+                self.wikipedia.request_timeout_secs,
+            ),
+            (
+                "WIKIDATA_REQUEST_TIMEOUT_SECS",
+                self.wikidata.request_timeout_secs,
+            ),
+        ] {
+            if timeout == 0 {
+                violations.push(format!("{name} must be greater than 0"));
+            }
+        }
+
+        if self.retry.max_delay_ms < self.retry.base_delay_ms {
+            violations.push(format!(
+                "HTTP_RETRY_MAX_DELAY_MS ({}) must not be less than HTTP_RETRY_BASE_DELAY_MS ({})",
+                self.retry.max_delay_ms, self.retry.base_delay_ms
+            ));
+        }
+
+        if let Err(e) = tracing_subscriber::EnvFilter::try_new(&self.logging.level) {
+            violations.push(format!("invalid log level '{}': {e}", self.logging.level));
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(crate::errors::WikiError::config(format!(
+                "invalid configuration: {}",
+                violations.join("; ")
+            )))
+        }
     }
 
     pub fn http_timeout(&self) -> Duration {
@@ -110,11 +916,251 @@ impl AppConfig {
     pub fn cache_ttl(&self) -> Duration {
         Duration::from_secs(self.cache.ttl_secs)
     }
+
+    pub fn wikidata_timeout(&self) -> Duration {
+        Duration::from_secs(self.wikidata.request_timeout_secs)
+    }
+
+    pub fn wikidata_cache_ttl(&self) -> Duration {
+        Duration::from_secs(self.wikidata.cache_ttl_secs)
+    }
+
+    pub fn search_cache_capacity(&self) -> u64 {
+        self.cache
+            .search_capacity
+            .unwrap_or(self.cache.max_capacity)
+    }
+
+    pub fn batch_cache_capacity(&self) -> u64 {
+        self.cache
+            .batch_capacity
+            .unwrap_or(self.cache.max_capacity / 2)
+    }
+
+    /// Мягкий TTL, если он меньше жёсткого; иначе фоновое обновление не имеет смысла.
+    pub fn cache_soft_ttl(&self) -> Option<Duration> {
+        self.cache
+            .soft_ttl_secs
+            .filter(|&soft_ttl| soft_ttl < self.cache.ttl_secs)
+            .map(Duration::from_secs)
+    }
+
+    pub fn unified_cache_capacity(&self) -> u64 {
+        self.cache
+            .unified_capacity
+            .unwrap_or(self.cache.max_capacity / 4)
+    }
+}
+
+/// Значение переменной конфигурации по имени; `None`, если она не задана.
+pub type Vars<'a> = &'a dyn Fn(&str) -> Option<String>;
+
+/// Читает и парсит переменную окружения, возвращая `default` если она не задана или некорректна.
+fn env_or<T: std::str::FromStr>(vars: Vars, name: &str, default: T) -> T {
+    env_opt(vars, name).unwrap_or(default)
+}
+
+fn env_opt<T: std::str::FromStr>(vars: Vars, name: &str) -> Option<T> {
+    vars(name).and_then(|value| value.trim().parse().ok())
+}
+
+/// Читает список значений, разделённых `;`.
+fn env_list(vars: Vars, name: &str) -> Vec<String> {
+    vars(name)
+        .map(|value| {
+            value
+                .split(';')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Читает список регулярных выражений, разделённых `;`, и проверяет, что они компилируются.
+fn env_patterns(vars: Vars, name: &str) -> Result<Vec<String>, crate::errors::WikiError> {
+    let patterns = env_list(vars, name);
+
+    for pattern in &patterns {
+        regex::Regex::new(pattern).map_err(|e| {
+            crate::errors::WikiError::config(format!("{name}: invalid pattern '{pattern}': {e}"))
+        })?;
+    }
+
+    Ok(patterns)
+}
+
+/// Читает необязательный профиль ранжирования и проверяет, что он известен.
+fn env_search_profile(vars: Vars, name: &str) -> Result<Option<String>, crate::errors::WikiError> {
+    env_one_of(vars, name, SEARCH_PROFILES, "search profile")
+}
+
+/// Читает необязательное значение из списка `allowed`; `kind` — что это за
+/// значение, для сообщения об ошибке.
+fn env_one_of(
+    vars: Vars,
+    name: &str,
+    allowed: &[&str],
+    kind: &str,
+) -> Result<Option<String>, crate::errors::WikiError> {
+    let Some(value) = env_opt::<String>(vars, name).filter(|value| !value.is_empty()) else {
+        return Ok(None);
+    };
+
+    if !allowed.contains(&value.as_str()) {
+        return Err(crate::errors::WikiError::config(format!(
+            "{name}: unknown {kind} '{value}', expected one of {}",
+            allowed.join(", ")
+        )));
+    }
+
+    Ok(Some(value))
+}
+
+/// Читает значение перечисления (порядок описаний, формат текста);
+/// пусто — значение по умолчанию.
+fn env_choice<T>(vars: Vars, name: &str) -> Result<T, crate::errors::WikiError>
+where
+    T: std::str::FromStr<Err = String> + Default,
+{
+    match env_opt::<String>(vars, name).filter(|value| !value.is_empty()) {
+        Some(value) => value
+            .parse()
+            .map_err(|e| crate::errors::WikiError::config(format!("{name}: {e}"))),
+        None => Ok(T::default()),
+    }
+}
+
+/// Читает список значений перечисления, разделённых `;`. Переменная не
+/// задана — `default`; задана пустой — пустой список.
+fn env_choices<T>(
+    vars: Vars,
+    name: &str,
+    default: Vec<T>,
+) -> Result<Vec<T>, crate::errors::WikiError>
+where
+    T: std::str::FromStr<Err = String>,
+{
+    if vars(name).is_none() {
+        return Ok(default);
+    }
+
+    env_list(vars, name)
+        .iter()
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|e| crate::errors::WikiError::config(format!("{name}: {e}")))
+        })
+        .collect()
+}
+
+/// Читает пары `ключ=значение`, разделённые `;`.
+fn env_map(vars: Vars, name: &str) -> Result<HashMap<String, String>, crate::errors::WikiError> {
+    env_list(vars, name)
+        .into_iter()
+        .map(|entry| match entry.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() && !value.trim().is_empty() => {
+                Ok((key.trim().to_string(), value.trim().to_string()))
+            }
+            _ => Err(crate::errors::WikiError::config(format!(
+                "{name}: expected 'key=value', got '{entry}'"
+            ))),
+        })
+        .collect()
+}
+
+/// Читает список кодов языков, разделённых `;`, и проверяет, что все они поддерживаются.
+fn env_languages(vars: Vars, name: &str) -> Result<Vec<String>, crate::errors::WikiError> {
+    let codes = env_list(vars, name);
+
+    for code in &codes {
+        if SupportedLanguage::from_code(code).is_none() {
+            return Err(crate::errors::WikiError::config(format!(
+                "{name}: unsupported language code '{code}'"
+            )));
+        }
+    }
+
+    Ok(codes)
+}
+
+/// Читает необязательный базовый адрес API и проверяет, что это корректный http(s) URL.
+/// Плейсхолдер `{lang}` допускается в любом месте адреса.
+fn env_api_base(vars: Vars, name: &str) -> Result<Option<String>, crate::errors::WikiError> {
+    let Some(value) = vars(name).filter(|v| !v.trim().is_empty()) else {
+        return Ok(None);
+    };
+
+    validate_api_base(&value).map_err(|reason| {
+        crate::errors::WikiError::config(format!("{name}='{value}' is invalid: {reason}"))
+    })?;
+
+    Ok(Some(value.trim().to_string()))
+}
+
+fn validate_api_base(value: &str) -> Result<(), String> {
+    let url = url::Url::parse(
+        &value
+            .trim()
+            .replace("{lang}", "en")
+            .replace("{project}", "wikipedia.org"),
+    )
+    .map_err(|e| e.to_string())?;
+
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("unsupported scheme '{}'", url.scheme()));
+    }
+    if url.query().is_some() || url.fragment().is_some() {
+        return Err("base URL must not contain a query or fragment".to_string());
+    }
+
+    Ok(())
 }
 
 fn default_request_timeout() -> u64 {
     30
 }
+fn default_send_top_result_as_photo() -> bool {
+    true
+}
+fn default_title_similarity_weight() -> f64 {
+    1.5
+}
+fn default_quality_weight() -> f64 {
+    20.0
+}
+fn default_max_inline_results() -> usize {
+    TELEGRAM_MAX_INLINE_RESULTS
+}
+fn default_keyboard_columns() -> usize {
+    2
+}
+fn default_trending_max_items() -> usize {
+    10
+}
+fn default_search_history_size() -> usize {
+    5
+}
+fn default_trending_min_count() -> u32 {
+    3
+}
+fn default_branding_title_emoji() -> String {
+    "📖".to_string()
+}
+fn default_branding_link_emoji() -> String {
+    "🔗".to_string()
+}
+fn default_branding_link_label() -> String {
+    "Читать полностью".to_string()
+}
+fn default_branding_separator() -> String {
+    "\n\n".to_string()
+}
+fn default_language_code() -> String {
+    SupportedLanguage::default().code().to_string()
+}
 fn default_max_results() -> usize {
     50
 }
@@ -124,12 +1170,40 @@ fn default_max_description_length() -> usize {
 fn default_max_content_length() -> usize {
     300
 }
+fn default_max_query_length() -> usize {
+    300
+}
+fn default_enable_wikidata() -> bool {
+    true
+}
+fn default_wikidata_max_concurrency() -> usize {
+    4
+}
+fn default_wikidata_enrichers() -> Vec<EnrichmentSource> {
+    vec![
+        EnrichmentSource::Descriptions,
+        EnrichmentSource::InstanceOf,
+        EnrichmentSource::Coordinates,
+    ]
+}
+fn default_transliterate_latin() -> bool {
+    true
+}
 fn default_cache_capacity() -> u64 {
     1000
 }
 fn default_cache_ttl_secs() -> u64 {
     300
 }
+fn default_retry_max_attempts() -> u32 {
+    3
+}
+fn default_retry_base_delay_ms() -> u64 {
+    250
+}
+fn default_retry_max_delay_ms() -> u64 {
+    2000
+}
 fn default_enable_cache() -> bool {
     true
 }
@@ -143,6 +1217,180 @@ fn default_enable_console() -> bool {
     true
 }
 fn default_user_agent() -> String {
-    "WikipediaArticlesBot/1.1.0 (https://github.com/Newmcpe/wiki-article-finder-telegram)"
-        .to_string()
+    format!("{BOT_USER_AGENT_PRODUCT} (https://github.com/Newmcpe/wiki-article-finder-telegram)")
+}
+
+#[cfg(test)]
+impl AppConfig {
+    /// Конфигурация по умолчанию с тестовым токеном. Окружение процесса не
+    /// читается, поэтому параллельные тесты не влияют друг на друга.
+    pub fn for_tests() -> Self {
+        Self::from_vars(&|name| (name == "BOT_TOKEN").then(|| "test_token_123".to_string()))
+            .expect("default test configuration is valid")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_user_agent_header() {
+        let mut config = AppConfig::for_tests().wikipedia;
+        config.user_agent = default_user_agent();
+        assert!(config.has_default_user_agent());
+        assert_eq!(config.user_agent_header(), default_user_agent());
+
+        config.user_agent = "MyWikiBot (https://example.org; ops@example.org)".to_string();
+        assert!(!config.has_default_user_agent());
+        assert_eq!(
+            config.user_agent_header(),
+            format!("MyWikiBot (https://example.org; ops@example.org) {BOT_USER_AGENT_PRODUCT}")
+        );
+    }
+
+    #[test]
+    fn test_validate_api_base() {
+        assert!(validate_api_base("https://{lang}.wikipedia.org").is_ok());
+        assert!(validate_api_base("https://{lang}.{project}").is_ok());
+        assert!(validate_api_base("http://127.0.0.1:8080").is_ok());
+        assert!(validate_api_base("https://mirror.example/{lang}/").is_ok());
+
+        assert!(validate_api_base("wikipedia.org").is_err());
+        assert!(validate_api_base("ftp://mirror.example").is_err());
+        assert!(validate_api_base("https://mirror.example/?x=1").is_err());
+    }
+
+    #[test]
+    fn test_selection_languages() {
+        let mut config = AppConfig::for_tests().telegram;
+
+        config.keyboard_languages = Vec::new();
+        assert_eq!(
+            config.selection_languages(),
+            SupportedLanguage::popular_languages()
+        );
+
+        config.keyboard_languages = ["de", "xx", "EN", "de"].map(String::from).to_vec();
+        assert_eq!(
+            config.selection_languages(),
+            vec![SupportedLanguage::German, SupportedLanguage::English]
+        );
+
+        config.keyboard_languages = vec!["klingon".to_string()];
+        assert_eq!(
+            config.selection_languages(),
+            SupportedLanguage::popular_languages()
+        );
+    }
+
+    #[test]
+    fn test_wikidata_defaults_match_previous_shared_settings() {
+        let wikidata = WikidataConfig::default();
+        assert_eq!(wikidata.request_timeout_secs, default_request_timeout());
+        assert_eq!(wikidata.cache_ttl_secs, default_cache_ttl_secs());
+        assert_eq!(wikidata.cache_capacity, default_cache_capacity());
+        assert!(wikidata.enabled);
+    }
+
+    /// Переменные конфигурации из списка пар вместо окружения процесса.
+    fn fixed_vars<'a>(pairs: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| {
+            pairs
+                .iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        }
+    }
+
+    #[test]
+    fn test_env_search_profile() {
+        let vars = fixed_vars(&[
+            ("PROFILE_OK", "popular_inclinks"),
+            ("PROFILE_BAD", "most_popular"),
+            ("SEARCH_WHAT", "fuzzy"),
+        ]);
+        assert_eq!(
+            env_search_profile(&vars, "PROFILE_OK").unwrap(),
+            Some("popular_inclinks".to_string())
+        );
+        assert!(env_search_profile(&vars, "PROFILE_BAD").is_err());
+        assert_eq!(env_search_profile(&vars, "PROFILE_UNSET").unwrap(), None);
+        assert!(env_one_of(&vars, "SEARCH_WHAT", SEARCH_WHAT_VALUES, "search mode").is_err());
+    }
+
+    #[test]
+    fn test_env_languages_rejects_unknown_code() {
+        let vars = fixed_vars(&[("LANGUAGES_OK", "de; en"), ("LANGUAGES_BAD", "de;xx")]);
+        assert_eq!(
+            env_languages(&vars, "LANGUAGES_OK").unwrap(),
+            vec!["de".to_string(), "en".to_string()]
+        );
+        assert!(env_languages(&vars, "LANGUAGES_BAD").is_err());
+    }
+
+    #[test]
+    fn test_from_vars_reads_only_given_values() {
+        let config = AppConfig::from_vars(&fixed_vars(&[
+            ("BOT_TOKEN", "token"),
+            ("SHOW_OPEN_BUTTON", "true"),
+            ("KEYBOARD_LANGUAGES", "de;en"),
+        ]))
+        .unwrap();
+        assert_eq!(config.telegram.bot_token, "token");
+        assert!(config.telegram.show_open_button);
+        assert_eq!(config.telegram.keyboard_languages, vec!["de", "en"]);
+
+        assert!(AppConfig::from_vars(&fixed_vars(&[])).is_err());
+        assert!(AppConfig::from_vars(&fixed_vars(&[
+            ("BOT_TOKEN", "token"),
+            ("KEYBOARD_LANGUAGES", "xx"),
+        ]))
+        .is_err());
+    }
+
+    #[test]
+    fn test_validate_reports_each_violation() {
+        let config = AppConfig::for_tests();
+        assert!(config.validate().is_ok());
+
+        let mut invalid = config.clone();
+        invalid.wikipedia.max_search_results = 99_999;
+        let error = invalid.validate().unwrap_err().to_string();
+        assert!(
+            error.contains("wikipedia.max_search_results must be between 1 and 500, got 99999"),
+            "{error}"
+        );
+
+        let mut invalid = config.clone();
+        invalid.telegram.bot_token = "  ".to_string();
+        invalid.wikidata.request_timeout_secs = 0;
+        invalid.logging.level = "wiki=loud".to_string();
+        let error = invalid.validate().unwrap_err().to_string();
+        assert!(error.contains("bot token is empty"), "{error}");
+        assert!(
+            error.contains("WIKIDATA_REQUEST_TIMEOUT_SECS must be greater than 0"),
+            "{error}"
+        );
+        assert!(error.contains("invalid log level 'wiki=loud'"), "{error}");
+
+        let mut invalid = config;
+        invalid.wikipedia.max_query_length = 1000;
+        invalid.retry.base_delay_ms = 500;
+        invalid.retry.max_delay_ms = 100;
+        invalid.wikipedia.default_language = "xx".to_string();
+        let error = invalid.validate().unwrap_err().to_string();
+        assert!(
+            error.contains("MAX_QUERY_LENGTH must be between 1 and 300"),
+            "{error}"
+        );
+        assert!(
+            error.contains("HTTP_RETRY_MAX_DELAY_MS (100) must not be less than"),
+            "{error}"
+        );
+        assert!(
+            error.contains("DEFAULT_LANGUAGE: unsupported language code 'xx'"),
+            "{error}"
+        );
+    }
 }
@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Проект Викимедиа с MediaWiki API, в котором выполняется поиск.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WikiProject {
+    #[default]
+    Wikipedia,
+    Wiktionary,
+    Wikivoyage,
+}
+
+impl WikiProject {
+    /// Домен проекта без языкового поддомена: `{lang}.{domain}`.
+    pub fn domain(&self) -> &'static str {
+        match self {
+            Self::Wikipedia => "wikipedia.org",
+            Self::Wiktionary => "wiktionary.org",
+            Self::Wikivoyage => "wikivoyage.org",
+        }
+    }
+
+    /// Префикс запроса, направляющий поиск в проект (`wikt:en:word`).
+    /// Пока маршрутизируется только Викисловарь.
+    pub fn from_prefix(prefix: &str) -> Option<Self> {
+        match prefix.to_lowercase().as_str() {
+            "wikt" => Some(Self::Wiktionary),
+            _ => None,
+        }
+    }
+
+    /// Значение `prop` для запросов extract и метаданных страниц. В Викисловаре
+    /// нет PageImages и координат, поэтому их не запрашиваем.
+    pub fn page_props(&self) -> &'static str {
+        match self {
+            Self::Wikipedia | Self::Wikivoyage => {
+                "extracts|pageimages|pageprops|coordinates|categories"
+            }
+            Self::Wiktionary => "extracts|pageprops|categories",
+        }
+    }
+}
+
+impl fmt::Display for WikiProject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.domain())
+    }
+}
+
+/// Отделяет префикс проекта (`wikt:`) от запроса. Без префикса — Wikipedia.
+pub fn split_project_prefix(query: &str) -> (WikiProject, &str) {
+    if let Some((prefix, rest)) = query.split_once(':') {
+        if let Some(project) = WikiProject::from_prefix(prefix) {
+            return (project, rest.trim_start());
+        }
+    }
+
+    (WikiProject::default(), query)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_project_prefix() {
+        assert_eq!(
+            split_project_prefix("wikt:en:word"),
+            (WikiProject::Wiktionary, "en:word")
+        );
+        assert_eq!(
+            split_project_prefix("WIKT: слово"),
+            (WikiProject::Wiktionary, "слово")
+        );
+        assert_eq!(
+            split_project_prefix("en:Albert Einstein"),
+            (WikiProject::Wikipedia, "en:Albert Einstein")
+        );
+        assert_eq!(
+            split_project_prefix("Пушкин"),
+            (WikiProject::Wikipedia, "Пушкин")
+        );
+    }
+}
@@ -1,13 +1,25 @@
+use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
 
+use crate::utils::time::russian_plural_form;
+
 #[derive(Debug, Error)]
 pub enum WikiError {
     #[error("Сетевая ошибка: {0}")]
-    Network(#[from] reqwest::Error),
+    Network(#[source] reqwest::Error),
+
+    #[error("Не удалось подключиться к серверу: {0}")]
+    Connect(#[source] reqwest::Error),
 
     #[error("Ошибка парсинга JSON: {0}")]
     Parse(#[from] serde_json::Error),
 
+    /// JSON оборвался на середине: соединение закрылось после заголовков.
+    /// В отличие от `Parse`, это сбой сети, а не другой формат ответа.
+    #[error("Ответ API оборвался: {0}")]
+    TruncatedResponse(#[source] serde_json::Error),
+
     #[error("Ошибка парсинга URL: {0}")]
     UrlParse(#[from] url::ParseError),
 
@@ -20,6 +32,11 @@ pub enum WikiError {
     #[error("Превышено время ожидания запроса")]
     Timeout,
 
+    /// API ответил 429 Too Many Requests. `retry_after` — значение заголовка
+    /// `Retry-After`, если сервер его прислал.
+    #[error("Превышен лимит запросов к API")]
+    RateLimited { retry_after: Option<Duration> },
+
     #[error("Ответ API содержит неожиданную структуру")]
     UnexpectedApiResponse,
 
@@ -51,6 +68,80 @@ impl WikiError {
             message: message.into(),
         }
     }
+
+    /// Достаёт ошибку из `Arc`, в котором её отдаёт `moka` всем ждавшим одного
+    /// запроса. Если ошибку держит кто-то ещё, возвращается копия; варианты с
+    /// неклонируемым источником копируются как `Internal` с тем же текстом.
+    pub fn from_shared(error: Arc<WikiError>) -> Self {
+        Arc::try_unwrap(error).unwrap_or_else(|error| match error.as_ref() {
+            Self::NoResults { query } => Self::NoResults {
+                query: query.clone(),
+            },
+            Self::InvalidLanguage { code } => Self::InvalidLanguage { code: code.clone() },
+            Self::Timeout => Self::Timeout,
+            Self::RateLimited { retry_after } => Self::RateLimited {
+                retry_after: *retry_after,
+            },
+            Self::UnexpectedApiResponse => Self::UnexpectedApiResponse,
+            Self::Cache { message } => Self::cache(message.clone()),
+            Self::Config { message } => Self::config(message.clone()),
+            other => Self::internal(other.to_string()),
+        })
+    }
+
+    /// Ошибка разбора JSON-ответа: конец данных посреди документа — оборванное
+    /// тело (`TruncatedResponse`), остальное — несовпадение схемы (`Parse`).
+    pub fn from_json(error: serde_json::Error) -> Self {
+        if error.is_eof() {
+            Self::TruncatedResponse(error)
+        } else {
+            Self::Parse(error)
+        }
+    }
+
+    /// Ошибка для ответа API с неуспешным статусом. 429 превращается в
+    /// `RateLimited` с задержкой из `Retry-After`, остальное — в `Network`.
+    pub fn from_response(response: reqwest::Response) -> Self {
+        if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(parse_retry_after);
+            return Self::RateLimited { retry_after };
+        }
+
+        match response.error_for_status() {
+            Err(error) => Self::Network(error),
+            Ok(response) => Self::internal(format!("Unexpected HTTP status {}", response.status())),
+        }
+    }
+}
+
+/// `Retry-After` бывает числом секунд или HTTP-датой (RFC 7231).
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let seconds = (date.with_timezone(&chrono::Utc) - chrono::Utc::now()).num_seconds();
+    Some(Duration::from_secs(seconds.max(0) as u64))
+}
+
+/// Таймауты и ошибки соединения выделяются в отдельные варианты, чтобы
+/// пользователь видел понятную причину, а не общую сетевую ошибку.
+impl From<reqwest::Error> for WikiError {
+    fn from(error: reqwest::Error) -> Self {
+        if error.is_timeout() {
+            Self::Timeout
+        } else if error.is_connect() {
+            Self::Connect(error)
+        } else {
+            Self::Network(error)
+        }
+    }
 }
 
 pub type WikiResult<T> = Result<T, WikiError>;
@@ -63,13 +154,29 @@ impl UserFriendlyError for WikiError {
     fn user_message(&self) -> String {
         match self {
             WikiError::Network(_) => "🔌 Проблемы с подключением. Попробуйте позже.".to_string(),
+            WikiError::Connect(_) => {
+                "🔌 Wikipedia сейчас недоступна. Попробуйте позже.".to_string()
+            }
             WikiError::Parse(_) => "⚠️ Ошибка обработки данных от Wikipedia.".to_string(),
+            WikiError::TruncatedResponse(_) => {
+                "🔌 Ответ от Wikipedia пришёл не полностью. Попробуйте ещё раз.".to_string()
+            }
             WikiError::UrlParse(_) => "🔗 Неверный формат ссылки.".to_string(),
             WikiError::NoResults { query } => {
                 format!("🔍 По запросу \"{query}\" ничего не найдено.")
             }
             WikiError::InvalidLanguage { code } => format!("🌍 Язык '{code}' не поддерживается."),
             WikiError::Timeout => "⏱️ Превышено время ожидания. Попробуйте позже.".to_string(),
+            WikiError::RateLimited {
+                retry_after: Some(retry_after),
+            } => {
+                let seconds = retry_after.as_secs().max(1);
+                let unit = ["секунду", "секунды", "секунд"][russian_plural_form(seconds as i64)];
+                format!("⏳ Слишком много запросов к Wikipedia, попробуйте через {seconds} {unit}.")
+            }
+            WikiError::RateLimited { retry_after: None } => {
+                "⏳ Слишком много запросов к Wikipedia, попробуйте чуть позже.".to_string()
+            }
             WikiError::UnexpectedApiResponse => {
                 "📡 Неожиданный ответ от Wikipedia API.".to_string()
             }
@@ -81,3 +188,49 @@ impl UserFriendlyError for WikiError {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limited_user_message() {
+        let error = WikiError::RateLimited {
+            retry_after: Some(Duration::from_secs(30)),
+        };
+        assert_eq!(
+            error.user_message(),
+            "⏳ Слишком много запросов к Wikipedia, попробуйте через 30 секунд."
+        );
+
+        let error = WikiError::RateLimited {
+            retry_after: Some(Duration::from_secs(1)),
+        };
+        assert!(error.user_message().ends_with("через 1 секунду."));
+    }
+
+    #[test]
+    fn test_from_json_separates_truncation_from_schema_mismatch() {
+        let truncated = serde_json::from_str::<serde_json::Value>(r#"{"query": {"pa"#).unwrap_err();
+        assert!(matches!(
+            WikiError::from_json(truncated),
+            WikiError::TruncatedResponse(_)
+        ));
+
+        let mismatch = serde_json::from_str::<Vec<u32>>(r#"{"query": {}}"#).unwrap_err();
+        assert!(matches!(
+            WikiError::from_json(mismatch),
+            WikiError::Parse(_)
+        ));
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        assert_eq!(parse_retry_after(" 7 "), Some(Duration::from_secs(7)));
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+}
@@ -0,0 +1,142 @@
+use regex::{Regex, RegexBuilder};
+
+use crate::config::WikipediaConfig;
+use crate::models::EnrichedArticle;
+use crate::utils::strip_namespace_prefix;
+
+/// Необязательный фильтр содержимого: скрывает статьи из заблокированных
+/// категорий и статьи, заголовок которых совпадает с одним из шаблонов.
+/// По умолчанию пуст и пропускает всё.
+#[derive(Debug, Clone, Default)]
+pub struct ContentFilter {
+    blocked_categories: Vec<String>,
+    blocked_title_patterns: Vec<Regex>,
+}
+
+impl ContentFilter {
+    /// Строит фильтр из конфигурации. Некорректные шаблоны отбрасываются с
+    /// предупреждением (при загрузке из окружения они уже проверены).
+    pub fn from_config(config: &WikipediaConfig) -> Self {
+        let language = config.default_language();
+        let blocked_categories = config
+            .blocked_categories
+            .iter()
+            .map(|category| strip_namespace_prefix(category.trim(), language).to_lowercase())
+            .filter(|category| !category.is_empty())
+            .collect();
+
+        let blocked_title_patterns = config
+            .blocked_title_patterns
+            .iter()
+            .filter_map(|pattern| {
+                RegexBuilder::new(pattern)
+                    .case_insensitive(true)
+                    .build()
+                    .map_err(|e| {
+                        tracing::warn!("⚠️ Некорректный шаблон заголовка '{}': {}", pattern, e);
+                    })
+                    .ok()
+            })
+            .collect();
+
+        Self {
+            blocked_categories,
+            blocked_title_patterns,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocked_categories.is_empty() && self.blocked_title_patterns.is_empty()
+    }
+
+    pub fn allows(&self, article: &EnrichedArticle) -> bool {
+        let title = &article.basic_info.title;
+        if self
+            .blocked_title_patterns
+            .iter()
+            .any(|pattern| pattern.is_match(title))
+        {
+            return false;
+        }
+
+        !article.topic_categories().iter().any(|category| {
+            let category = category.to_lowercase();
+            self.blocked_categories.contains(&category)
+        })
+    }
+
+    /// Убирает из списка статьи, не прошедшие фильтр.
+    pub fn apply(&self, articles: Vec<EnrichedArticle>) -> Vec<EnrichedArticle> {
+        if self.is_empty() {
+            return articles;
+        }
+
+        let before = articles.len();
+        let articles: Vec<EnrichedArticle> = articles
+            .into_iter()
+            .filter(|article| self.allows(article))
+            .collect();
+
+        if articles.len() < before {
+            tracing::info!(
+                "🚫 Фильтр содержимого скрыл {} статей",
+                before - articles.len()
+            );
+        }
+
+        articles
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use crate::models::ArticleBatchInfo;
+    use crate::test_support;
+
+    fn article(title: &str, categories: &[&str]) -> EnrichedArticle {
+        test_support::article_with(
+            title,
+            ArticleBatchInfo {
+                categories: categories.iter().map(|c| c.to_string()).collect(),
+                ..Default::default()
+            },
+        )
+    }
+
+    fn filter(categories: &[&str], patterns: &[&str]) -> ContentFilter {
+        let mut config = AppConfig::for_tests().wikipedia;
+        config.blocked_categories = categories.iter().map(|c| c.to_string()).collect();
+        config.blocked_title_patterns = patterns.iter().map(|p| p.to_string()).collect();
+        ContentFilter::from_config(&config)
+    }
+
+    #[test]
+    fn test_blocked_category_is_excluded() {
+        let filter = filter(&["Категория:Азартные игры"], &[]);
+        let articles = vec![
+            article("Покер", &["Карточные игры", "азартные игры"]),
+            article("Шахматы", &["Настольные игры"]),
+        ];
+
+        let filtered = filter.apply(articles);
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].basic_info.title, "Шахматы");
+    }
+
+    #[test]
+    fn test_blocked_title_pattern_is_excluded() {
+        let filter = filter(&[], &["^казино"]);
+        assert!(!filter.allows(&article("Казино Рояль", &[])));
+        assert!(filter.allows(&article("Монте-Карло", &[])));
+    }
+
+    #[test]
+    fn test_empty_filter_passes_everything() {
+        let filter = filter(&[], &[]);
+        assert!(filter.is_empty());
+        let filtered = filter.apply(vec![article("Покер", &["Азартные игры"])]);
+        assert_eq!(filtered.len(), 1);
+    }
+}
@@ -0,0 +1,417 @@
+use async_trait::async_trait;
+use futures::future::join_all;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+
+use crate::config::languages::SupportedLanguage;
+use crate::config::EnrichmentSource;
+use crate::errors::WikiResult;
+use crate::models::{EnrichedArticle, EntityKind};
+use crate::services::{WikidataApi, WikidataService};
+
+/// Изменения, которые провайдер вносит в статьи, когда его данные загружены.
+pub type Enrichment = Box<dyn FnOnce(&mut [EnrichedArticle]) + Send>;
+
+/// Источник дополнительных данных для найденных статей. Ошибки источника не
+/// должны ломать поиск — провайдер просто оставляет статьи как есть.
+#[async_trait]
+pub trait EnrichmentProvider: Send + Sync {
+    /// Короткое имя для логов.
+    fn name(&self) -> &'static str;
+
+    /// Загружает данные для статей, не меняя их, и возвращает, как их
+    /// применить. `fetch` всех провайдеров выполняются одновременно, и каждый
+    /// делает свои запросы один раз — в том числе когда источник недоступен.
+    async fn fetch(&self, articles: &[EnrichedArticle], language: SupportedLanguage) -> Enrichment;
+}
+
+/// Загруженные, но ещё не применённые данные провайдеров.
+#[must_use]
+pub struct PendingEnrichment {
+    enrichments: Vec<(&'static str, Enrichment)>,
+}
+
+impl PendingEnrichment {
+    /// Применяет данные в порядке провайдеров. Статьи сопоставляются по
+    /// идентификатору Wikidata, так что после `fetch` их можно переставить.
+    pub fn apply(self, articles: &mut [EnrichedArticle]) {
+        for (name, enrichment) in self.enrichments {
+            tracing::debug!("🧩 Обогащение результатов: {}", name);
+            enrichment(articles);
+        }
+    }
+}
+
+/// Список провайдеров, которые поиск применяет к результатам.
+#[derive(Clone, Default)]
+pub struct EnrichmentPipeline {
+    providers: Vec<Arc<dyn EnrichmentProvider>>,
+}
+
+impl EnrichmentPipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Провайдеры Wikidata из `WIKIDATA_ENRICHERS` в заданном порядке. Пустой
+    /// список, если Wikidata выключена.
+    pub fn from_wikidata(wikidata: Arc<WikidataService>) -> Self {
+        if !wikidata.is_enabled() {
+            return Self::new();
+        }
+
+        wikidata
+            .enrichers()
+            .iter()
+            .fold(Self::new(), |pipeline, source| match source {
+                EnrichmentSource::Descriptions => {
+                    pipeline.with_provider(WikidataDescriptionProvider::new(Arc::clone(&wikidata)))
+                }
+                EnrichmentSource::InstanceOf => {
+                    pipeline.with_provider(WikidataInstanceOfProvider::new(Arc::clone(&wikidata)))
+                }
+                EnrichmentSource::Coordinates => {
+                    pipeline.with_provider(WikidataCoordinatesProvider::new(Arc::clone(&wikidata)))
+                }
+            })
+    }
+
+    pub fn with_provider(mut self, provider: impl EnrichmentProvider + 'static) -> Self {
+        self.providers.push(Arc::new(provider));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.providers.is_empty()
+    }
+
+    /// Одновременно загружает данные всех провайдеров для `articles`.
+    pub async fn fetch(
+        &self,
+        articles: &[EnrichedArticle],
+        language: SupportedLanguage,
+    ) -> PendingEnrichment {
+        if articles.is_empty() {
+            return PendingEnrichment {
+                enrichments: Vec::new(),
+            };
+        }
+
+        let enrichments = join_all(
+            self.providers
+                .iter()
+                .map(|provider| provider.fetch(articles, language)),
+        )
+        .await;
+
+        PendingEnrichment {
+            enrichments: self
+                .providers
+                .iter()
+                .map(|provider| provider.name())
+                .zip(enrichments)
+                .collect(),
+        }
+    }
+}
+
+/// Идентификаторы Wikidata статей, у которых они есть.
+fn wikidata_ids(articles: &[EnrichedArticle]) -> Vec<String> {
+    articles
+        .iter()
+        .filter_map(|article| wikidata_id(article).map(str::to_string))
+        .collect()
+}
+
+fn wikidata_id(article: &EnrichedArticle) -> Option<&str> {
+    article
+        .batch_info
+        .as_ref()
+        .and_then(|info| info.wikidata_id.as_deref())
+}
+
+/// Загружает значения по идентификаторам Wikidata статей и возвращает их
+/// применение. При ошибке источника статьи остаются как есть.
+async fn fetch_by_wikidata_id<T, F>(
+    name: &str,
+    articles: &[EnrichedArticle],
+    fetch: impl FnOnce(Vec<String>) -> F,
+    apply: fn(&mut EnrichedArticle, &T),
+) -> Enrichment
+where
+    T: Send + 'static,
+    F: Future<Output = WikiResult<HashMap<String, T>>>,
+{
+    let ids = wikidata_ids(articles);
+    if ids.is_empty() {
+        return Box::new(|_| {});
+    }
+
+    let values = match fetch(ids).await {
+        Ok(values) => values,
+        Err(e) => {
+            tracing::warn!("⚠️ Не удалось получить {}: {}", name, e);
+            return Box::new(|_| {});
+        }
+    };
+
+    Box::new(move |articles| {
+        for article in articles {
+            if let Some(value) = wikidata_id(article).and_then(|id| values.get(id)) {
+                apply(article, value);
+            }
+        }
+    })
+}
+
+/// Краткие описания сущностей на языке раздела.
+pub struct WikidataDescriptionProvider {
+    wikidata: Arc<WikidataService>,
+}
+
+impl WikidataDescriptionProvider {
+    pub fn new(wikidata: Arc<WikidataService>) -> Self {
+        Self { wikidata }
+    }
+}
+
+#[async_trait]
+impl EnrichmentProvider for WikidataDescriptionProvider {
+    fn name(&self) -> &'static str {
+        "wikidata_descriptions"
+    }
+
+    async fn fetch(&self, articles: &[EnrichedArticle], language: SupportedLanguage) -> Enrichment {
+        fetch_by_wikidata_id(
+            self.name(),
+            articles,
+            |ids| self.wikidata.get_descriptions(ids, language),
+            |article, description| article.wikidata_description = Some(description.clone()),
+        )
+        .await
+    }
+}
+
+/// Тип сущности по P31 — для значка в результатах.
+pub struct WikidataInstanceOfProvider {
+    wikidata: Arc<WikidataService>,
+}
+
+impl WikidataInstanceOfProvider {
+    pub fn new(wikidata: Arc<WikidataService>) -> Self {
+        Self { wikidata }
+    }
+}
+
+#[async_trait]
+impl EnrichmentProvider for WikidataInstanceOfProvider {
+    fn name(&self) -> &'static str {
+        "wikidata_instance_of"
+    }
+
+    async fn fetch(
+        &self,
+        articles: &[EnrichedArticle],
+        _language: SupportedLanguage,
+    ) -> Enrichment {
+        fetch_by_wikidata_id(
+            self.name(),
+            articles,
+            |ids| self.wikidata.get_instance_of(ids),
+            |article, classes| article.entity_kind = EntityKind::from_class_ids(classes),
+        )
+        .await
+    }
+}
+
+/// Координаты P625. Утверждения запрашиваются и кэшируются вместе с P31, а
+/// одновременные запросы объединяются, поэтому рядом с
+/// [`WikidataInstanceOfProvider`] отдельного запроса нет.
+pub struct WikidataCoordinatesProvider {
+    wikidata: Arc<WikidataService>,
+}
+
+impl WikidataCoordinatesProvider {
+    pub fn new(wikidata: Arc<WikidataService>) -> Self {
+        Self { wikidata }
+    }
+}
+
+#[async_trait]
+impl EnrichmentProvider for WikidataCoordinatesProvider {
+    fn name(&self) -> &'static str {
+        "wikidata_coordinates"
+    }
+
+    async fn fetch(
+        &self,
+        articles: &[EnrichedArticle],
+        _language: SupportedLanguage,
+    ) -> Enrichment {
+        fetch_by_wikidata_id(
+            self.name(),
+            articles,
+            |ids| self.wikidata.get_coordinates(ids),
+            // Координаты Wikidata только дополняют отсутствующие в Wikipedia
+            |article, coordinates| {
+                if let Some(info) = article.batch_info.as_mut() {
+                    info.coordinates.get_or_insert_with(|| coordinates.clone());
+                }
+            },
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use crate::models::ArticleBatchInfo;
+    use crate::test_support::article;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Ничего не меняет, только считает вызовы `fetch`.
+    struct NoopProvider {
+        calls: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl EnrichmentProvider for NoopProvider {
+        fn name(&self) -> &'static str {
+            "noop"
+        }
+
+        async fn fetch(
+            &self,
+            _articles: &[EnrichedArticle],
+            _language: SupportedLanguage,
+        ) -> Enrichment {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Box::new(|_| {})
+        }
+    }
+
+    fn article_with_wikidata_id(title: &str, wikidata_id: &str) -> EnrichedArticle {
+        crate::test_support::article_with(
+            title,
+            ArticleBatchInfo {
+                wikidata_id: Some(wikidata_id.to_string()),
+                ..Default::default()
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn test_noop_provider_leaves_articles_unchanged() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let pipeline = EnrichmentPipeline::new().with_provider(NoopProvider {
+            calls: Arc::clone(&calls),
+        });
+
+        let mut articles = vec![article("Пушкин")];
+        pipeline
+            .fetch(&articles, SupportedLanguage::Russian)
+            .await
+            .apply(&mut articles);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(articles[0].basic_info.title, "Пушкин");
+        assert_eq!(articles[0].wikidata_description, None);
+
+        // Пустую выдачу обогащать нечем
+        let _ = pipeline.fetch(&[], SupportedLanguage::Russian).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_enrichment_applies_to_reordered_articles() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "props".into(),
+                "descriptions".into(),
+            ))
+            .with_body(
+                r#"{"entities": {
+                    "Q1": {"descriptions": {"ru": {"language": "ru", "value": "первая"}}},
+                    "Q2": {"descriptions": {"ru": {"language": "ru", "value": "вторая"}}}
+                }}"#,
+            )
+            .create_async()
+            .await;
+
+        let mut config = AppConfig::for_tests();
+        config.wikidata.enrichers = vec![EnrichmentSource::Descriptions];
+        let wikidata = WikidataService::new(config)
+            .unwrap()
+            .with_api_base(server.url());
+        let pipeline = EnrichmentPipeline::from_wikidata(Arc::new(wikidata));
+
+        let mut articles = vec![
+            article_with_wikidata_id("Первая", "Q1"),
+            article_with_wikidata_id("Вторая", "Q2"),
+        ];
+        let pending = pipeline.fetch(&articles, SupportedLanguage::Russian).await;
+        articles.reverse();
+        pending.apply(&mut articles);
+
+        assert_eq!(articles[0].basic_info.title, "Вторая");
+        assert_eq!(articles[0].wikidata_description.as_deref(), Some("вторая"));
+        assert_eq!(articles[1].wikidata_description.as_deref(), Some("первая"));
+    }
+
+    #[tokio::test]
+    async fn test_unavailable_wikidata_is_requested_once_per_kind() {
+        let mut server = mockito::Server::new_async().await;
+        let mut mocks = Vec::new();
+        for props in ["descriptions", "claims"] {
+            mocks.push(
+                server
+                    .mock("GET", "/w/api.php")
+                    .match_query(mockito::Matcher::UrlEncoded("props".into(), props.into()))
+                    .with_status(500)
+                    .expect(1)
+                    .create_async()
+                    .await,
+            );
+        }
+
+        let mut config = AppConfig::for_tests();
+        config.retry.max_attempts = 1;
+        let wikidata = WikidataService::new(config)
+            .unwrap()
+            .with_api_base(server.url());
+        let pipeline = EnrichmentPipeline::from_wikidata(Arc::new(wikidata));
+
+        let mut articles = vec![article_with_wikidata_id("Пушкин", "Q7200")];
+        pipeline
+            .fetch(&articles, SupportedLanguage::Russian)
+            .await
+            .apply(&mut articles);
+
+        for mock in mocks {
+            mock.assert_async().await;
+        }
+        assert_eq!(articles[0].wikidata_description, None);
+        assert_eq!(articles[0].entity_kind, None);
+    }
+
+    #[test]
+    fn test_pipeline_follows_configured_sources() {
+        let mut config = AppConfig::for_tests();
+        config.wikidata.enrichers = vec![EnrichmentSource::Descriptions];
+        let wikidata = Arc::new(WikidataService::new(config.clone()).unwrap());
+        let names: Vec<_> = EnrichmentPipeline::from_wikidata(wikidata)
+            .providers
+            .iter()
+            .map(|provider| provider.name())
+            .collect();
+        assert_eq!(names, vec!["wikidata_descriptions"]);
+
+        config.wikidata.enabled = false;
+        let wikidata = Arc::new(WikidataService::new(config).unwrap());
+        assert!(EnrichmentPipeline::from_wikidata(wikidata).is_empty());
+    }
+}
@@ -0,0 +1,216 @@
+use std::collections::HashSet;
+use teloxide::types::InlineQueryResult;
+use tracing::warn;
+use url::Url;
+
+use crate::config::TELEGRAM_MAX_INLINE_RESULTS;
+use crate::errors::{WikiError, WikiResult};
+
+/// Наибольшая длина идентификатора inline-результата в байтах.
+pub const TELEGRAM_MAX_RESULT_ID_BYTES: usize = 64;
+
+/// Проверяет ответ на inline-запрос на ограничения Telegram: не больше
+/// [`TELEGRAM_MAX_INLINE_RESULTS`] результатов, непустые уникальные
+/// идентификаторы до [`TELEGRAM_MAX_RESULT_ID_BYTES`] байт и изображения
+/// статей и фото только по https. Ошибка описывает первое нарушение.
+pub fn validate_inline_results(results: &[InlineQueryResult]) -> WikiResult<()> {
+    if results.len() > TELEGRAM_MAX_INLINE_RESULTS {
+        return Err(WikiError::internal(format!(
+            "too many inline results: {} > {TELEGRAM_MAX_INLINE_RESULTS}",
+            results.len()
+        )));
+    }
+
+    let mut seen_ids = HashSet::new();
+    for result in results {
+        if let Some(violation) =
+            id_violation(result, &mut seen_ids).or_else(|| image_violation(result))
+        {
+            return Err(WikiError::internal(violation));
+        }
+    }
+    Ok(())
+}
+
+/// Приводит ответ к ограничениям Telegram, чтобы один неверный результат не
+/// привёл к отказу (400) для всего ответа: лишние результаты и результаты с
+/// неверным идентификатором отбрасываются, миниатюра статьи не по https
+/// убирается, фото не по https отбрасывается целиком. Каждое исправление
+/// пишется в лог.
+pub fn enforce_inline_limits(results: Vec<InlineQueryResult>) -> Vec<InlineQueryResult> {
+    if results.len() > TELEGRAM_MAX_INLINE_RESULTS {
+        warn!(
+            "⚠️ Inline-результатов {}, лишние отброшены (лимит Telegram {})",
+            results.len(),
+            TELEGRAM_MAX_INLINE_RESULTS
+        );
+    }
+
+    let mut seen_ids = HashSet::new();
+    results
+        .into_iter()
+        .take(TELEGRAM_MAX_INLINE_RESULTS)
+        .filter_map(|mut result| {
+            if let Some(violation) = id_violation(&result, &mut seen_ids) {
+                warn!("⚠️ Inline-результат отброшен: {}", violation);
+                return None;
+            }
+
+            match &mut result {
+                InlineQueryResult::Article(article)
+                    if article.thumb_url.as_ref().is_some_and(|url| !is_https(url)) =>
+                {
+                    warn!("⚠️ Миниатюра результата '{}' убрана: не https", article.id);
+                    article.thumb_url = None;
+                    article.thumb_width = None;
+                    article.thumb_height = None;
+                }
+                _ => {
+                    if let Some(violation) = image_violation(&result) {
+                        warn!("⚠️ Inline-результат отброшен: {}", violation);
+                        return None;
+                    }
+                }
+            }
+            Some(result)
+        })
+        .collect()
+}
+
+/// Нарушение правил для идентификатора результата; корректный идентификатор
+/// запоминается в `seen_ids`.
+fn id_violation(result: &InlineQueryResult, seen_ids: &mut HashSet<String>) -> Option<String> {
+    let id = result_id(result);
+    if id.is_empty() {
+        return Some("empty result id".to_string());
+    }
+    if id.len() > TELEGRAM_MAX_RESULT_ID_BYTES {
+        return Some(format!(
+            "result id '{id}' is {} bytes long, limit is {TELEGRAM_MAX_RESULT_ID_BYTES}",
+            id.len()
+        ));
+    }
+    if !seen_ids.insert(id.to_string()) {
+        return Some(format!("duplicate result id '{id}'"));
+    }
+    None
+}
+
+/// Изображение статьи или фото не по https. Остальные виды результатов бот
+/// не отправляет, поэтому их изображения не проверяются.
+fn image_violation(result: &InlineQueryResult) -> Option<String> {
+    let (id, urls): (&str, Vec<&Url>) = match result {
+        InlineQueryResult::Article(article) => (&article.id, article.thumb_url.iter().collect()),
+        InlineQueryResult::Photo(photo) => (&photo.id, vec![&photo.photo_url, &photo.thumb_url]),
+        _ => return None,
+    };
+
+    urls.into_iter()
+        .find(|url| !is_https(url))
+        .map(|url| format!("result '{id}' has a non-https image: {url}"))
+}
+
+fn is_https(url: &Url) -> bool {
+    url.scheme() == "https"
+}
+
+fn result_id(result: &InlineQueryResult) -> &str {
+    match result {
+        InlineQueryResult::CachedAudio(result) => &result.id,
+        InlineQueryResult::CachedDocument(result) => &result.id,
+        InlineQueryResult::CachedGif(result) => &result.id,
+        InlineQueryResult::CachedMpeg4Gif(result) => &result.id,
+        InlineQueryResult::CachedPhoto(result) => &result.id,
+        InlineQueryResult::CachedSticker(result) => &result.id,
+        InlineQueryResult::CachedVideo(result) => &result.id,
+        InlineQueryResult::CachedVoice(result) => &result.id,
+        InlineQueryResult::Article(result) => &result.id,
+        InlineQueryResult::Audio(result) => &result.id,
+        InlineQueryResult::Contact(result) => &result.id,
+        InlineQueryResult::Game(result) => &result.id,
+        InlineQueryResult::Document(result) => &result.id,
+        InlineQueryResult::Gif(result) => &result.id,
+        InlineQueryResult::Location(result) => &result.id,
+        InlineQueryResult::Mpeg4Gif(result) => &result.id,
+        InlineQueryResult::Photo(result) => &result.id,
+        InlineQueryResult::Venue(result) => &result.id,
+        InlineQueryResult::Video(result) => &result.id,
+        InlineQueryResult::Voice(result) => &result.id,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use teloxide::types::{
+        InlineQueryResultArticle, InlineQueryResultPhoto, InputMessageContent,
+        InputMessageContentText,
+    };
+
+    fn article(id: &str) -> InlineQueryResult {
+        InlineQueryResult::Article(InlineQueryResultArticle::new(
+            id,
+            "Статья",
+            InputMessageContent::Text(InputMessageContentText::new("Текст")),
+        ))
+    }
+
+    fn ids(results: &[InlineQueryResult]) -> Vec<&str> {
+        results.iter().map(result_id).collect()
+    }
+
+    #[test]
+    fn test_over_limit_results_are_truncated() {
+        let results: Vec<_> = (0..60).map(|idx| article(&idx.to_string())).collect();
+        assert!(validate_inline_results(&results).is_err());
+
+        let results = enforce_inline_limits(results);
+        assert_eq!(results.len(), TELEGRAM_MAX_INLINE_RESULTS);
+        assert_eq!(ids(&results)[49], "49");
+        assert!(validate_inline_results(&results).is_ok());
+    }
+
+    #[test]
+    fn test_oversized_and_duplicate_ids_are_dropped() {
+        let long_id = "x".repeat(TELEGRAM_MAX_RESULT_ID_BYTES + 1);
+        // 32 кириллических символа — уже 64 байта, 33 — больше лимита
+        let cyrillic_ok = "я".repeat(32);
+        let cyrillic_long = "я".repeat(33);
+        let results = vec![
+            article("a"),
+            article(&long_id),
+            article(&cyrillic_ok),
+            article(&cyrillic_long),
+            article("a"),
+            article(""),
+        ];
+        assert!(validate_inline_results(&results).is_err());
+
+        let results = enforce_inline_limits(results);
+        assert_eq!(ids(&results), vec!["a", cyrillic_ok.as_str()]);
+        assert!(validate_inline_results(&results).is_ok());
+    }
+
+    #[test]
+    fn test_non_https_images_are_repaired_or_dropped() {
+        let http = Url::parse("http://upload.wikimedia.org/a.jpg").unwrap();
+        let https = Url::parse("https://upload.wikimedia.org/a.jpg").unwrap();
+        let InlineQueryResult::Article(with_thumb) = article("thumb") else {
+            unreachable!()
+        };
+        let results = vec![
+            InlineQueryResult::Article(with_thumb.thumb_url(http.clone()).thumb_width(300)),
+            InlineQueryResult::Photo(InlineQueryResultPhoto::new("photo", http, https.clone())),
+            InlineQueryResult::Photo(InlineQueryResultPhoto::new("ok", https.clone(), https)),
+        ];
+        assert!(validate_inline_results(&results).is_err());
+
+        let results = enforce_inline_limits(results);
+        assert_eq!(ids(&results), vec!["thumb", "ok"]);
+        let InlineQueryResult::Article(article) = &results[0] else {
+            panic!("expected an article");
+        };
+        assert_eq!(article.thumb_url, None);
+        assert_eq!(article.thumb_width, None);
+    }
+}
@@ -1,37 +1,136 @@
+use chrono::Utc;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
 use std::sync::Arc;
+use std::time::Instant;
 use teloxide::{
     prelude::*,
     types::{
         InlineKeyboardButton, InlineKeyboardMarkup, InlineQueryResult, InlineQueryResultArticle,
-        InputMessageContent, InputMessageContentText, ParseMode,
+        InlineQueryResultPhoto, InputMessageContent, InputMessageContentText, ParseMode, User,
     },
 };
-use tracing::{error, info};
+use tracing::{debug, error, info, warn};
+use url::Url;
 
-use crate::config::languages::SupportedLanguage;
+use crate::config::languages::{detect_language, SupportedLanguage};
+use crate::config::{ExtractFormat, PrefixOnlyQuery, ResultStyle, TelegramConfig};
 use crate::errors::{UserFriendlyError, WikiError};
-use crate::models::EnrichedArticle;
-use crate::services::{WikidataApi, WikidataService, WikipediaApi, WikipediaService};
-use crate::utils::{format_article_description, format_error_message, format_no_results_message};
+use crate::handlers::{
+    definition_word, enforce_inline_limits, is_expired_inline_query, is_parse_entities_error,
+    parse_contents_query, plain_text_inline_results, prefix_only_language, rank_articles,
+    search_project_query, table_of_contents_message, ContentFilter, EnrichmentPipeline,
+    QuerySearchResult,
+};
+use crate::models::{Definition, EnrichedArticle, InterwikiHit, CONTENT_LENGTH, SUMMARY_LENGTH};
+use crate::services::{
+    AnalyticsSink, QueryEvent, QueryGenerations, SearchHistory, TrendingQueries, UserPreferences,
+    WikidataService, WikipediaService,
+};
+use crate::utils::{
+    escape_html, escape_markdown, format_error_message, format_last_edited,
+    format_no_results_message, format_suggestion_message, format_total_hits, link,
+    TELEGRAM_CAPTION_LIMIT, TELEGRAM_MESSAGE_LIMIT,
+};
+
+/// Сколько категорий показывать кнопками под результатом.
+const MAX_CATEGORY_BUTTONS: usize = 3;
+
+/// Ограничение Telegram на длину inline-запроса.
+const MAX_INLINE_QUERY_LENGTH: usize = 256;
+
+/// Текст невидимой ссылки на изображение статьи (пробел нулевой ширины).
+const HIDDEN_LINK_TEXT: &str = "\u{200B}";
+
+/// Наименьшая ширина изображения, при которой статья показывается фото.
+const MIN_INLINE_PHOTO_WIDTH: u32 = 400;
 
 pub struct InlineQueryHandler {
     wikipedia_service: Arc<WikipediaService>,
-    wikidata_service: Arc<WikidataService>,
+    wiktionary_service: Arc<WikipediaService>,
+    enrichment: EnrichmentPipeline,
+    config: TelegramConfig,
+    analytics: Option<AnalyticsSink>,
+    trending: Option<Arc<TrendingQueries>>,
+    history: Option<Arc<SearchHistory>>,
+    preferences: Option<Arc<UserPreferences>>,
+    content_filter: ContentFilter,
+    /// Номера запросов пользователей — чтобы не отвечать на устаревшие.
+    generations: QueryGenerations,
 }
 
 impl InlineQueryHandler {
     pub fn new(
         wikipedia_service: Arc<WikipediaService>,
+        wiktionary_service: Arc<WikipediaService>,
         wikidata_service: Arc<WikidataService>,
+        config: TelegramConfig,
     ) -> Self {
+        let content_filter = ContentFilter::from_config(wikipedia_service.config());
+        let analytics = AnalyticsSink::from_config(&config, wikipedia_service.tasks());
+
         Self {
             wikipedia_service,
-            wikidata_service,
+            wiktionary_service,
+            enrichment: EnrichmentPipeline::from_wikidata(wikidata_service),
+            analytics,
+            trending: None,
+            history: None,
+            preferences: None,
+            content_filter,
+            generations: QueryGenerations::new(),
+            config,
+        }
+    }
+
+    /// Заменяет провайдеров обогащения, выбранных в конфигурации Wikidata.
+    pub fn with_enrichment(mut self, enrichment: EnrichmentPipeline) -> Self {
+        self.enrichment = enrichment;
+        self
+    }
+
+    /// Учитывать успешные запросы в счётчике для `/trending`.
+    pub fn with_trending(mut self, trending: Arc<TrendingQueries>) -> Self {
+        self.trending = Some(trending);
+        self
+    }
+
+    /// Запоминать запросы пользователей и предлагать их в пустом запросе.
+    pub fn with_history(mut self, history: Arc<SearchHistory>) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    /// Учитывать выбранный пользователем командой `/style` вид результатов.
+    pub fn with_preferences(mut self, preferences: Arc<UserPreferences>) -> Self {
+        self.preferences = Some(preferences);
+        self
+    }
+
+    /// Вид результатов для пользователя: его выбор или значение из конфигурации.
+    async fn result_style(&self, user: UserId) -> ResultStyle {
+        match &self.preferences {
+            Some(preferences) => preferences.result_style(user).await,
+            None => None,
         }
+        .unwrap_or(self.config.result_style)
+    }
+
+    /// Локаль интерфейса: выбранная через `/ui` или язык клиента Telegram.
+    async fn ui_locale(&self, user: &User) -> Option<String> {
+        let preferred = match &self.preferences {
+            Some(preferences) => preferences.ui_locale(user.id).await,
+            None => None,
+        };
+        preferred
+            .map(|locale| locale.code().to_string())
+            .or_else(|| user.language_code.clone())
     }
 
     pub async fn handle(&self, bot: Bot, q: InlineQuery) -> ResponseResult<()> {
         let query = q.query.trim();
+        let generation = self.generations.begin(q.from.id).await;
 
         let user_info = q
             .from
@@ -44,28 +143,61 @@ impl InlineQueryHandler {
             info!("🔍 {} ищет: '{}'", user_info, query);
         }
 
+        let locale = self.ui_locale(&q.from).await;
+        let locale = locale.as_deref();
         let results = if query.is_empty() {
-            self.handle_empty_query().await
+            self.handle_empty_query(q.from.id, locale).await
         } else {
-            self.handle_search_query(query).await
+            self.handle_search_query(q.from.id, query, locale).await
         };
 
-        match results {
-            Ok(inline_results) => {
-                bot.answer_inline_query(q.id, inline_results).await?;
-            }
+        let inline_results = match results {
+            Ok(inline_results) => enforce_inline_limits(inline_results),
             Err(e) => {
                 error!("Error handling inline query: {:?}", e);
-                let error_result = vec![self.create_error_result(&e)];
-                bot.answer_inline_query(q.id, error_result).await?;
+                vec![self.create_error_result(&e)]
+            }
+        };
+
+        // Пока шёл поиск, пользователь изменил запрос: новый ответ уже в пути
+        if !self.generations.is_current(q.from.id, generation).await {
+            debug!("⏭️ Запрос '{}' устарел, ответ не отправляется", query);
+            return Ok(());
+        }
+
+        let mut answer = bot
+            .answer_inline_query(q.id.clone(), inline_results.clone())
+            .await;
+        if let Err(e) = &answer {
+            if is_parse_entities_error(e) {
+                warn!(
+                    "⚠️ Telegram не разобрал разметку результатов, отвечаю простым текстом: {}",
+                    e
+                );
+                answer = bot
+                    .answer_inline_query(q.id, plain_text_inline_results(inline_results))
+                    .await;
             }
         }
 
-        Ok(())
+        match answer {
+            Ok(_) => Ok(()),
+            // Пользователь успел изменить запрос или ответ шёл слишком долго —
+            // ожидаемая ситуация, а не ошибка бота
+            Err(e) if is_expired_inline_query(&e) => {
+                debug!("⌛ Inline-запрос устарел до ответа: {}", e);
+                Ok(())
+            }
+            Err(e) => Err(e),
+        }
     }
 
-    async fn handle_empty_query(&self) -> Result<Vec<InlineQueryResult>, WikiError> {
-        let keyboard = self.create_language_selection_keyboard();
+    async fn handle_empty_query(
+        &self,
+        user: UserId,
+        locale: Option<&str>,
+    ) -> Result<Vec<InlineQueryResult>, WikiError> {
+        let keyboard = self.create_language_selection_keyboard(locale);
 
         let result = InlineQueryResultArticle::new(
             "lang_select",
@@ -77,65 +209,158 @@ impl InlineQueryHandler {
         .description("Поддерживается 100+ языков! Начните с кода языка")
         .reply_markup(keyboard);
 
-        Ok(vec![InlineQueryResult::Article(result)])
+        let mut results = vec![InlineQueryResult::Article(result)];
+        if let Some(history) = &self.history {
+            results.extend(history_results(&history.recent(user).await));
+        }
+
+        Ok(results)
     }
 
-    async fn handle_search_query(&self, query: &str) -> Result<Vec<InlineQueryResult>, WikiError> {
-        let (language, search_query) = crate::services::parse_query_with_language(query);
+    async fn handle_search_query(
+        &self,
+        user: UserId,
+        query: &str,
+        locale: Option<&str>,
+    ) -> Result<Vec<InlineQueryResult>, WikiError> {
+        let default_language = self.wikipedia_service.config().default_language();
+        if let Some(language) = prefix_only_language(query, default_language) {
+            return match self.config.prefix_only_query {
+                PrefixOnlyQuery::Prompt => Ok(vec![Self::create_prefix_prompt_result(
+                    query, language, locale,
+                )]),
+                PrefixOnlyQuery::LanguageKeyboard => self.handle_empty_query(user, locale).await,
+            };
+        }
 
-        let enriched_articles = match self
-            .wikipedia_service
-            .get_enriched_articles_optimized(&search_query, language)
-            .await
-        {
-            Ok(articles) => articles,
-            Err(_) => {
-                self.wikipedia_service
-                    .get_enriched_articles(&search_query, language)
-                    .await?
-            }
-        };
+        if let Some((language, title)) = parse_contents_query(query, default_language) {
+            return self.handle_contents_query(&title, language).await;
+        }
 
-        if enriched_articles.is_empty() {
-            return Ok(vec![self.create_no_results_result(&search_query, language)]);
+        let started = Instant::now();
+        let QuerySearchResult {
+            language,
+            search_query,
+            articles: enriched_articles,
+            suggestion,
+            total_hits,
+            interwiki,
+            cache_hit,
+        } = search_project_query(
+            &self.wikipedia_service,
+            &self.wiktionary_service,
+            &self.enrichment,
+            query,
+        )
+        .await?;
+
+        if let Some(analytics) = &self.analytics {
+            analytics.record(QueryEvent {
+                lang: language.code(),
+                query_len: search_query.chars().count(),
+                result_count: enriched_articles.len(),
+                cache_hit,
+                latency_ms: started.elapsed().as_millis() as u64,
+                query: Some(search_query.clone()),
+            });
         }
 
-        let wikidata_ids: Vec<String> = enriched_articles
-            .iter()
-            .filter_map(|article| {
-                article
-                    .batch_info
-                    .as_ref()
-                    .and_then(|info| info.wikidata_id.clone())
-            })
-            .collect();
+        if !enriched_articles.is_empty() {
+            if let Some(trending) = &self.trending {
+                trending.record(&search_query);
+            }
+            if let Some(history) = &self.history {
+                history.record(user, query).await;
+            }
+        }
 
-        let wikidata_descriptions = if !wikidata_ids.is_empty() {
-            self.wikidata_service
-                .get_descriptions(wikidata_ids, language)
-                .await
-                .unwrap_or_default()
-        } else {
-            std::collections::HashMap::new()
-        };
+        let definition = self.definition_result(query, &search_query, language).await;
 
-        let results = self
-            .build_article_results(enriched_articles, wikidata_descriptions)
+        if enriched_articles.is_empty() {
+            let mut results: Vec<_> = definition.into_iter().collect();
+            if let Some(suggestion) = suggestion {
+                results.push(self.create_suggestion_result(&suggestion, language));
+            }
+            results.push(self.create_no_results_result(&search_query, language, locale));
+            self.append_interwiki_results(&mut results, &interwiki, language);
+            return Ok(results);
+        }
+
+        let style = self.result_style(user).await;
+        let mut results = self
+            .build_article_results(enriched_articles, &search_query, language, locale, style)
             .await;
+        if let Some(total_hits) = total_hits {
+            add_total_hits_hint(&mut results, total_hits, locale);
+        }
+
+        // Все статьи могли быть скрыты фильтром содержимого
+        if results.is_empty() {
+            results.push(self.create_no_results_result(&search_query, language, locale));
+        }
+        self.append_interwiki_results(&mut results, &interwiki, language);
+        if let Some(definition) = definition {
+            results.insert(0, definition);
+            results.truncate(self.config.inline_results_limit());
+        }
 
         Ok(results)
     }
 
-    fn create_language_selection_keyboard(&self) -> InlineKeyboardMarkup {
-        let popular_languages = SupportedLanguage::popular_languages();
+    async fn handle_contents_query(
+        &self,
+        title: &str,
+        language: SupportedLanguage,
+    ) -> Result<Vec<InlineQueryResult>, WikiError> {
+        let message = table_of_contents_message(&self.wikipedia_service, title, language).await?;
+
+        let result = InlineQueryResultArticle::new(
+            "toc",
+            format!("📑 Содержание: {title}"),
+            InputMessageContent::Text(
+                InputMessageContentText::new(message)
+                    .parse_mode(ParseMode::MarkdownV2)
+                    .disable_web_page_preview(true),
+            ),
+        )
+        .description("Оглавление статьи со ссылками на разделы");
+
+        Ok(vec![InlineQueryResult::Article(result)])
+    }
+
+    /// Подсказка на запрос из одного префикса: язык выбран, осталось ввести
+    /// сам запрос.
+    fn create_prefix_prompt_result(
+        prefix: &str,
+        language: SupportedLanguage,
+        locale: Option<&str>,
+    ) -> InlineQueryResult {
+        let name = language.name_for_locale(locale);
+        let prefix = prefix.trim();
+
+        InlineQueryResult::Article(
+            InlineQueryResultArticle::new(
+                "prefix_prompt",
+                format!("{} Введите запрос после «{prefix}»", language.flag_emoji()),
+                InputMessageContent::Text(InputMessageContentText::new(format!(
+                    "Чтобы искать в разделе «{name}», введите запрос после «{prefix}», \
+                     например: {prefix}Wikipedia"
+                ))),
+            )
+            .description(format!("Поиск в разделе «{name}»")),
+        )
+    }
+
+    fn create_language_selection_keyboard(&self, locale: Option<&str>) -> InlineKeyboardMarkup {
+        let languages = self.config.selection_languages();
 
         let mut rows: Vec<Vec<InlineKeyboardButton>> = Vec::new();
 
-        for chunk in popular_languages.chunks(2) {
+        for chunk in languages.chunks(self.config.keyboard_row_width()) {
             let row: Vec<InlineKeyboardButton> = chunk
                 .iter()
                 .map(|lang| {
-                    let display = format!("{} {}", lang.flag_emoji(), lang.display_name());
+                    let display = format!("{} {}", lang.flag_emoji(), lang.name_for_locale(locale));
                     let query = format!("{}:", lang.code());
                     InlineKeyboardButton::switch_inline_query(display, query)
                 })
@@ -148,65 +373,81 @@ impl InlineQueryHandler {
 
     async fn build_article_results(
         &self,
-        mut enriched_articles: Vec<EnrichedArticle>,
-        wikidata_descriptions: std::collections::HashMap<String, String>,
+        enriched_articles: Vec<EnrichedArticle>,
+        query: &str,
+        language: SupportedLanguage,
+        locale: Option<&str>,
+        style: ResultStyle,
     ) -> Vec<InlineQueryResult> {
         tracing::debug!(
             "🏗️ Строим результаты для {} статей",
             enriched_articles.len()
         );
 
-        enriched_articles.sort_by(|a, b| match (a.relevance_index, b.relevance_index) {
-            (Some(idx_a), Some(idx_b)) => idx_a.cmp(&idx_b),
-            (Some(_), None) => std::cmp::Ordering::Less,
-            (None, Some(_)) => std::cmp::Ordering::Greater,
-            (None, None) => {
-                let has_image_a = a.image_url().is_some();
-                let has_image_b = b.image_url().is_some();
-
-                if has_image_a && !has_image_b {
-                    std::cmp::Ordering::Less
-                } else if !has_image_a && has_image_b {
-                    std::cmp::Ordering::Greater
-                } else {
-                    let word_count_a = a.word_count().unwrap_or(0);
-                    let word_count_b = b.word_count().unwrap_or(0);
-                    word_count_b.cmp(&word_count_a)
-                }
-            }
-        });
-
         let mut results = Vec::new();
+        let mut used_ids = HashSet::new();
+        let limit = self.config.inline_results_limit();
+        let enriched_articles = rank_articles(
+            enriched_articles,
+            &self.content_filter,
+            query,
+            self.wikipedia_service.config().ranking.title_similarity,
+        );
 
-        for (idx, mut article) in enriched_articles.into_iter().enumerate() {
-            if let Some(batch_info) = &article.batch_info {
-                if let Some(wikidata_id) = &batch_info.wikidata_id {
-                    if let Some(description) = wikidata_descriptions.get(wikidata_id) {
-                        article.wikidata_description = Some(description.clone());
-                    }
-                }
+        for (idx, article) in enriched_articles.into_iter().take(limit).enumerate() {
+            let mut description = article.best_description(
+                SUMMARY_LENGTH,
+                self.wikipedia_service.config().description_source,
+            );
+            if let Some(last_edited) = self.last_edited_line(&article, locale) {
+                description.push('\n');
+                description.push_str(&last_edited);
             }
 
-            let description = article.best_description(100);
-            let content = article.best_content(300);
+            let title = match article.entity_kind {
+                Some(kind) => format!("{} {}", kind.emoji(), article.basic_info.title),
+                None => article.basic_info.title.clone(),
+            };
 
-            let message_text = format_article_description(
-                &article.basic_info.title,
-                &content,
-                &article.article_url,
-            );
+            let mut id = article_result_id(&article, language);
+            if !used_ids.insert(id.clone()) {
+                id = format!("{id}_{idx}");
+            }
+
+            if let Some(mut photo_result) = self.create_photo_result(&id, &article, style) {
+                photo_result = photo_result.title(title).description(description);
+                if let Some(keyboard) = self.create_article_keyboard(&article, language, style) {
+                    photo_result = photo_result.reply_markup(keyboard);
+                }
+                results.push(InlineQueryResult::Photo(photo_result));
+                continue;
+            }
 
             let mut article_result = InlineQueryResultArticle::new(
-                format!("article_{idx}"),
-                &article.basic_info.title,
-                InputMessageContent::Text(
-                    InputMessageContentText::new(message_text).parse_mode(ParseMode::MarkdownV2),
-                ),
+                id,
+                title,
+                self.article_message_content(&article, style),
             )
             .description(description);
 
+            if self.config.use_article_url_preview {
+                if let Ok(url) = Url::parse(&article.article_url) {
+                    article_result = article_result.url(url).hide_url(false);
+                }
+            }
+
             if let Some(image_url) = article.valid_image_url() {
                 article_result = article_result.thumb_url(image_url);
+
+                if let Some((width, height)) = article.image_dimensions() {
+                    article_result = article_result
+                        .thumb_width(width as i32)
+                        .thumb_height(height as i32);
+                }
+            }
+
+            if let Some(keyboard) = self.create_article_keyboard(&article, language, style) {
+                article_result = article_result.reply_markup(keyboard);
             }
 
             results.push(InlineQueryResult::Article(article_result));
@@ -216,25 +457,343 @@ impl InlineQueryHandler {
         results
     }
 
-    fn create_no_results_result(
+    /// Фото-результат для статьи с крупным изображением при
+    /// `inline_photo_results`: фото с карточкой статьи в подписи, обрезанной
+    /// до лимита. `None` — изображения нет или оно слишком мало, и статья
+    /// показывается обычной карточкой.
+    fn create_photo_result(
+        &self,
+        id: &str,
+        article: &EnrichedArticle,
+        style: ResultStyle,
+    ) -> Option<InlineQueryResultPhoto> {
+        if !self.config.inline_photo_results {
+            return None;
+        }
+
+        let (photo_url, width, height) = article.valid_photo()?;
+        if width < MIN_INLINE_PHOTO_WIDTH {
+            return None;
+        }
+        let thumb_url = article
+            .valid_image_url()
+            .unwrap_or_else(|| photo_url.clone());
+
+        let length = match style {
+            ResultStyle::Compact => SUMMARY_LENGTH,
+            ResultStyle::Detailed => CONTENT_LENGTH,
+        };
+        let format = self.wikipedia_service.config().extract_format;
+        let caption = article.to_card(
+            length,
+            &self.config.branding,
+            format,
+            TELEGRAM_CAPTION_LIMIT,
+        );
+
+        Some(
+            InlineQueryResultPhoto::new(id, photo_url, thumb_url)
+                .photo_width(width as i32)
+                .photo_height(height as i32)
+                .caption(caption)
+                .parse_mode(format.parse_mode()),
+        )
+    }
+
+    /// «🕒 обновлено 3 дня назад» для описания результата, если это включено и
+    /// у статьи есть время последней правки.
+    fn last_edited_line(&self, article: &EnrichedArticle, locale: Option<&str>) -> Option<String> {
+        if !self.config.show_last_edited {
+            return None;
+        }
+
+        let timestamp = article.basic_info.timestamp.as_deref()?;
+        format_last_edited(timestamp, Utc::now(), locale).map(|text| format!("🕒 {text}"))
+    }
+
+    /// Сообщение, которое отправляется при выборе результата: карточка статьи
+    /// (в компактном виде — с коротким описанием) или, при
+    /// `use_article_url_preview`, только ссылка, по которой Telegram построит превью.
+    /// При `embed_article_image` карточка начинается невидимой ссылкой на
+    /// изображение: Telegram строит превью по первой ссылке в сообщении.
+    fn article_message_content(
+        &self,
+        article: &EnrichedArticle,
+        style: ResultStyle,
+    ) -> InputMessageContent {
+        if self.config.use_article_url_preview {
+            return InputMessageContent::Text(
+                InputMessageContentText::new(link(&article.basic_info.title, &article.article_url))
+                    .disable_web_page_preview(false)
+                    .parse_mode(ParseMode::MarkdownV2),
+            );
+        }
+
+        let format = self.wikipedia_service.config().extract_format;
+        let length = match style {
+            ResultStyle::Compact => SUMMARY_LENGTH,
+            ResultStyle::Detailed => CONTENT_LENGTH,
+        };
+        let image_link = self.embedded_image_url(article).map(|url| match format {
+            ExtractFormat::Plain => link(HIDDEN_LINK_TEXT, url.as_str()),
+            ExtractFormat::Html => format!(
+                "<a href=\"{}\">{HIDDEN_LINK_TEXT}</a>",
+                escape_html(url.as_str())
+            ),
+        });
+        let text = article.to_card(
+            length,
+            &self.config.branding,
+            format,
+            TELEGRAM_MESSAGE_LIMIT - image_link.as_ref().map_or(0, |link| link.chars().count()),
+        );
+
+        let content = match image_link {
+            Some(image_link) => InputMessageContentText::new(format!("{image_link}{text}"))
+                .disable_web_page_preview(false),
+            None => InputMessageContentText::new(text),
+        };
+
+        InputMessageContent::Text(content.parse_mode(format.parse_mode()))
+    }
+
+    /// Изображение для превью в сообщении статьи, если `embed_article_image`
+    /// включён: крупное, если есть, иначе миниатюра.
+    fn embedded_image_url(&self, article: &EnrichedArticle) -> Option<Url> {
+        if !self.config.embed_article_image {
+            return None;
+        }
+        article
+            .valid_photo()
+            .map(|(url, _, _)| url)
+            .or_else(|| article.valid_image_url())
+    }
+
+    /// Клавиатура под результатом: ссылка на статью и кнопки категорий,
+    /// если они включены в конфигурации. В компактном виде категорий нет.
+    fn create_article_keyboard(
+        &self,
+        article: &EnrichedArticle,
+        language: SupportedLanguage,
+        style: ResultStyle,
+    ) -> Option<InlineKeyboardMarkup> {
+        let mut rows = Vec::new();
+
+        if self.config.show_open_button {
+            // В URL-кнопке MarkdownV2 не действует, поэтому ссылка передаётся как есть
+            match Url::parse(&article.article_url) {
+                Ok(url) => rows.push(vec![InlineKeyboardButton::url("📖 Open in Wikipedia", url)]),
+                Err(e) => tracing::warn!(
+                    "⚠️ Некорректная ссылка на статью {}: {}",
+                    article.article_url,
+                    e
+                ),
+            }
+        }
+
+        if self.config.show_category_buttons && style == ResultStyle::Detailed {
+            rows.extend(Self::create_category_buttons(article, language));
+        }
+
+        if rows.is_empty() {
+            None
+        } else {
+            Some(InlineKeyboardMarkup::new(rows))
+        }
+    }
+
+    /// Кнопки с самыми короткими (обычно самыми общими) категориями статьи
+    /// для поиска внутри категории через `cat:`.
+    fn create_category_buttons(
+        article: &EnrichedArticle,
+        language: SupportedLanguage,
+    ) -> Vec<Vec<InlineKeyboardButton>> {
+        let mut categories: Vec<&String> = article.topic_categories().iter().collect();
+        categories.sort_by_key(|category| category.chars().count());
+
+        categories
+            .into_iter()
+            .map(|category| (category, format!("{}:cat:{}", language.code(), category)))
+            .filter(|(_, query)| query.chars().count() <= MAX_INLINE_QUERY_LENGTH)
+            .take(MAX_CATEGORY_BUTTONS)
+            .map(|(category, query)| {
+                vec![InlineKeyboardButton::switch_inline_query_current_chat(
+                    format!("🏷️ {category}"),
+                    query,
+                )]
+            })
+            .collect()
+    }
+
+    /// Определение из Викисловаря для запроса из одного слова, если
+    /// `show_definitions` включён. Нет статьи или ошибка — результата нет.
+    async fn definition_result(
         &self,
         query: &str,
+        search_query: &str,
+        language: SupportedLanguage,
+    ) -> Option<InlineQueryResult> {
+        if !self.config.show_definitions {
+            return None;
+        }
+        let word = definition_word(query, search_query)?;
+
+        match self.wiktionary_service.get_definition(word, language).await {
+            Ok(definition) => definition.as_ref().map(Self::create_definition_result),
+            Err(e) => {
+                warn!("⚠️ Не удалось получить определение '{}': {}", word, e);
+                None
+            }
+        }
+    }
+
+    fn create_definition_result(definition: &Definition) -> InlineQueryResult {
+        let message = format!(
+            "📘 *{}*\n\n{}\n\n{}",
+            escape_markdown(&definition.word),
+            escape_markdown(&definition.text),
+            link("Викисловарь", &definition.url)
+        );
+
+        let mut result = InlineQueryResultArticle::new(
+            "definition",
+            format!("📘 Definition: {}", definition.word),
+            InputMessageContent::Text(
+                InputMessageContentText::new(message)
+                    .parse_mode(ParseMode::MarkdownV2)
+                    .disable_web_page_preview(true),
+            ),
+        )
+        .description(definition.text.clone());
+        if let Ok(url) = Url::parse(&definition.url) {
+            result = result.url(url);
+        }
+        InlineQueryResult::Article(result)
+    }
+
+    /// Дописывает после статей результаты из других проектов Викимедиа — с
+    /// пометкой проекта в заголовке, в пределах лимита inline-результатов.
+    fn append_interwiki_results(
+        &self,
+        results: &mut Vec<InlineQueryResult>,
+        hits: &[InterwikiHit],
+        language: SupportedLanguage,
+    ) {
+        let free = self
+            .config
+            .inline_results_limit()
+            .saturating_sub(results.len());
+
+        let interwiki_results = hits
+            .iter()
+            .filter_map(|hit| {
+                let url = hit.resolved_url(language)?;
+                Some((hit, url))
+            })
+            .take(free)
+            .enumerate()
+            .map(|(idx, (hit, url))| {
+                let content = format!(
+                    "{} {}: {}",
+                    hit.icon(),
+                    escape_markdown(hit.project_name()),
+                    link(hit.display_title(), &url)
+                );
+                let mut article = InlineQueryResultArticle::new(
+                    format!("iw_{idx}"),
+                    format!("{} {}", hit.icon(), hit.display_title()),
+                    InputMessageContent::Text(
+                        InputMessageContentText::new(content).parse_mode(ParseMode::MarkdownV2),
+                    ),
+                )
+                .description(format!("{} · другой проект Викимедиа", hit.project_name()));
+                if let Ok(url) = Url::parse(&url) {
+                    article = article.url(url);
+                }
+                InlineQueryResult::Article(article)
+            });
+
+        results.extend(interwiki_results);
+    }
+
+    fn create_suggestion_result(
+        &self,
+        suggestion: &str,
         language: SupportedLanguage,
     ) -> InlineQueryResult {
-        let message = format_no_results_message(query, language.display_name());
+        let keyboard = InlineKeyboardMarkup::new(vec![vec![
+            InlineKeyboardButton::switch_inline_query_current_chat(
+                format!("🔍 Искать «{suggestion}»"),
+                format!("{}:{}", language.code(), suggestion),
+            ),
+        ]]);
 
         InlineQueryResult::Article(
             InlineQueryResultArticle::new(
-                "no_results",
-                "Ничего не найдено",
+                "suggestion",
+                format!("🤔 Возможно, вы имели в виду: {suggestion}?"),
                 InputMessageContent::Text(
-                    InputMessageContentText::new(message).parse_mode(ParseMode::MarkdownV2),
+                    InputMessageContentText::new(format_suggestion_message(suggestion))
+                        .parse_mode(ParseMode::MarkdownV2),
                 ),
             )
-            .description("Попробуйте изменить запрос"),
+            .description("Нажмите, чтобы искать исправленный запрос")
+            .reply_markup(keyboard),
         )
     }
 
+    fn create_no_results_result(
+        &self,
+        query: &str,
+        language: SupportedLanguage,
+        locale: Option<&str>,
+    ) -> InlineQueryResult {
+        let message = format_no_results_message(query, language.name_for_locale(locale));
+
+        let mut result = InlineQueryResultArticle::new(
+            "no_results",
+            "Ничего не найдено",
+            InputMessageContent::Text(
+                InputMessageContentText::new(message).parse_mode(ParseMode::MarkdownV2),
+            ),
+        )
+        .description("Попробуйте изменить запрос");
+
+        if let Some(keyboard) = Self::create_language_switch_keyboard(query, language) {
+            result = result.reply_markup(keyboard);
+        }
+
+        InlineQueryResult::Article(result)
+    }
+
+    /// Кнопка повторного поиска в разделе, чья письменность совпадает с
+    /// запросом: например, `en:Пушкин` предлагается искать в русской Википедии.
+    fn create_language_switch_keyboard(
+        query: &str,
+        language: SupportedLanguage,
+    ) -> Option<InlineKeyboardMarkup> {
+        let detected = detect_language(query)?;
+        if detected.script() == language.script() {
+            return None;
+        }
+
+        let switch_query = format!("{}:{}", detected.code(), query);
+        if switch_query.chars().count() > MAX_INLINE_QUERY_LENGTH {
+            return None;
+        }
+
+        Some(InlineKeyboardMarkup::new(vec![vec![
+            InlineKeyboardButton::switch_inline_query_current_chat(
+                format!(
+                    "Try in {} {}",
+                    detected.flag_emoji(),
+                    detected.display_name()
+                ),
+                switch_query,
+            ),
+        ]]))
+    }
+
     fn create_error_result(&self, error: &WikiError) -> InlineQueryResult {
         let message = format_error_message(&error.user_message());
 
@@ -258,3 +817,750 @@ pub async fn inline_query_handler(
 ) -> ResponseResult<()> {
     handler.handle(bot, q).await
 }
+
+/// Недавние запросы пользователя в пустом inline-запросе. Кнопка под
+/// отправленным результатом снова подставляет запрос в строку ввода.
+fn history_results(queries: &[String]) -> Vec<InlineQueryResult> {
+    queries
+        .iter()
+        .enumerate()
+        .map(|(idx, query)| {
+            let keyboard = InlineKeyboardMarkup::new(vec![vec![
+                InlineKeyboardButton::switch_inline_query_current_chat(
+                    "🔍 Повторить поиск",
+                    query.clone(),
+                ),
+            ]]);
+
+            InlineQueryResult::Article(
+                InlineQueryResultArticle::new(
+                    format!("history_{idx}"),
+                    format!("🕘 {query}"),
+                    InputMessageContent::Text(InputMessageContentText::new(format!(
+                        "🔍 Поиск в Википедии: {query}"
+                    ))),
+                )
+                .description("Недавний запрос — нажмите, чтобы повторить")
+                .reply_markup(keyboard),
+            )
+        })
+        .collect()
+}
+
+/// Дописывает «🔎 ~N результатов» в описание первого результата, если поиск
+/// нашёл больше статей, чем показано.
+fn add_total_hits_hint(results: &mut [InlineQueryResult], total_hits: u64, locale: Option<&str>) {
+    if total_hits <= results.len() as u64 {
+        return;
+    }
+    if let Some(InlineQueryResult::Article(first)) = results.first_mut() {
+        let hint = format!("🔎 {}", format_total_hits(total_hits, locale));
+        first.description = Some(match first.description.take() {
+            Some(description) => format!("{description}\n{hint}"),
+            None => hint,
+        });
+    }
+}
+
+/// Идентификатор inline-результата, не зависящий от позиции статьи в выдаче:
+/// `art_{язык}_{pageid}`, а без pageid — хеш заголовка. Одна и та же статья
+/// получает один и тот же id на любой странице, разные статьи — разные.
+/// Длина укладывается в ограничение Telegram в 64 байта.
+fn article_result_id(article: &EnrichedArticle, language: SupportedLanguage) -> String {
+    let language = article.source_language.unwrap_or(language);
+    match article.basic_info.pageid {
+        Some(pageid) => format!("art_{}_{pageid}", language.code()),
+        None => {
+            let mut hasher = DefaultHasher::new();
+            article.basic_info.title.hash(&mut hasher);
+            format!("art_{}_t{:016x}", language.code(), hasher.finish())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AppConfig, TELEGRAM_MAX_INLINE_RESULTS};
+    use crate::services::UiLocale;
+    use crate::test_support::{self, articles};
+    use teloxide::types::InlineKeyboardButtonKind;
+
+    fn handler_with_limit(max_inline_results: usize) -> InlineQueryHandler {
+        handler_with(|telegram| telegram.max_inline_results = max_inline_results)
+    }
+
+    fn handler_with(configure: impl FnOnce(&mut TelegramConfig)) -> InlineQueryHandler {
+        let mut config = AppConfig::for_tests();
+        configure(&mut config.telegram);
+
+        InlineQueryHandler::new(
+            Arc::new(WikipediaService::new(config.clone()).unwrap()),
+            Arc::new(WikipediaService::new(config.clone()).unwrap()),
+            Arc::new(WikidataService::new(config.clone()).unwrap()),
+            config.telegram,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_inline_results_respect_configured_limit() {
+        let handler = handler_with_limit(5);
+        let results = handler
+            .build_article_results(
+                articles(20),
+                "Статья",
+                SupportedLanguage::Russian,
+                None,
+                ResultStyle::Detailed,
+            )
+            .await;
+        assert_eq!(results.len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_inline_results_never_exceed_telegram_cap() {
+        let handler = handler_with_limit(500);
+        let results = handler
+            .build_article_results(
+                articles(80),
+                "Статья",
+                SupportedLanguage::Russian,
+                None,
+                ResultStyle::Detailed,
+            )
+            .await;
+        assert_eq!(results.len(), TELEGRAM_MAX_INLINE_RESULTS);
+    }
+
+    fn result_ids(results: &[InlineQueryResult]) -> Vec<String> {
+        results
+            .iter()
+            .map(|result| match result {
+                InlineQueryResult::Article(article) => article.id.clone(),
+                _ => panic!("unexpected result kind"),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_result_ids_are_stable_across_pages() {
+        let handler = handler_with_limit(10);
+        let all = articles(20);
+        let first_page: Vec<_> = all[..10].to_vec();
+        let second_page: Vec<_> = all[10..].to_vec();
+
+        let first = result_ids(
+            &handler
+                .build_article_results(
+                    first_page.clone(),
+                    "Статья",
+                    SupportedLanguage::Russian,
+                    None,
+                    ResultStyle::Detailed,
+                )
+                .await,
+        );
+        let second = result_ids(
+            &handler
+                .build_article_results(
+                    second_page,
+                    "Статья",
+                    SupportedLanguage::Russian,
+                    None,
+                    ResultStyle::Detailed,
+                )
+                .await,
+        );
+
+        assert_eq!(first.len(), 10);
+        assert!(first.iter().all(|id| !second.contains(id)));
+        assert!(first.iter().chain(&second).all(|id| id.len() <= 64));
+        assert!(first.contains(&"art_ru_0".to_string()));
+
+        let repeated = result_ids(
+            &handler
+                .build_article_results(
+                    first_page,
+                    "Статья",
+                    SupportedLanguage::Russian,
+                    None,
+                    ResultStyle::Detailed,
+                )
+                .await,
+        );
+        assert_eq!(first, repeated);
+    }
+
+    #[test]
+    fn test_result_id_without_pageid_uses_title() {
+        let mut article = articles(1).remove(0);
+        article.basic_info.pageid = None;
+        let id = article_result_id(&article, SupportedLanguage::English);
+        assert!(id.starts_with("art_en_t"));
+        assert_eq!(id, article_result_id(&article, SupportedLanguage::English));
+    }
+
+    fn message_text(result: &InlineQueryResult) -> &InputMessageContentText {
+        match result {
+            InlineQueryResult::Article(InlineQueryResultArticle {
+                input_message_content: InputMessageContent::Text(text),
+                ..
+            }) => text,
+            _ => panic!("unexpected result kind"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_article_body_is_default_content() {
+        let handler = handler_with(|telegram| telegram.use_article_url_preview = false);
+        let results = handler
+            .build_article_results(
+                articles(1),
+                "Статья",
+                SupportedLanguage::Russian,
+                None,
+                ResultStyle::Detailed,
+            )
+            .await;
+
+        let text = message_text(&results[0]);
+        assert!(text.message_text.starts_with("📖 *Статья 0*"));
+        assert!(text.message_text.contains("Описание"));
+        assert_eq!(text.disable_web_page_preview, None);
+        match &results[0] {
+            InlineQueryResult::Article(article) => assert!(article.url.is_none()),
+            _ => unreachable!(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_url_preview_mode_sends_only_link() {
+        let handler = handler_with(|telegram| telegram.use_article_url_preview = true);
+        let results = handler
+            .build_article_results(
+                articles(1),
+                "Статья",
+                SupportedLanguage::Russian,
+                None,
+                ResultStyle::Detailed,
+            )
+            .await;
+
+        let text = message_text(&results[0]);
+        assert_eq!(
+            text.message_text,
+            "[Статья 0](https://ru.wikipedia.org/wiki/0)"
+        );
+        assert_eq!(text.disable_web_page_preview, Some(false));
+        match &results[0] {
+            InlineQueryResult::Article(article) => {
+                assert_eq!(
+                    article.url.as_ref().map(Url::as_str),
+                    Some("https://ru.wikipedia.org/wiki/0")
+                );
+                assert_eq!(article.hide_url, Some(false));
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn reply_markup(result: &InlineQueryResult) -> Option<&InlineKeyboardMarkup> {
+        match result {
+            InlineQueryResult::Article(article) => article.reply_markup.as_ref(),
+            _ => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_open_button_links_to_article() {
+        let handler = handler_with(|telegram| telegram.show_open_button = true);
+        let results = handler
+            .build_article_results(
+                articles(1),
+                "Статья",
+                SupportedLanguage::Russian,
+                None,
+                ResultStyle::Detailed,
+            )
+            .await;
+
+        let keyboard = reply_markup(&results[0]).expect("keyboard is attached");
+        let button = &keyboard.inline_keyboard[0][0];
+        assert_eq!(button.text, "📖 Open in Wikipedia");
+        match &button.kind {
+            InlineKeyboardButtonKind::Url(url) => {
+                assert_eq!(url.as_str(), "https://ru.wikipedia.org/wiki/0")
+            }
+            kind => panic!("unexpected button kind: {kind:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_open_button_is_optional() {
+        let handler = handler_with(|telegram| telegram.show_open_button = false);
+        let results = handler
+            .build_article_results(
+                articles(1),
+                "Статья",
+                SupportedLanguage::Russian,
+                None,
+                ResultStyle::Detailed,
+            )
+            .await;
+        assert!(reply_markup(&results[0]).is_none());
+    }
+
+    #[test]
+    fn test_language_switch_button_on_script_mismatch() {
+        let handler = handler_with_limit(10);
+
+        let result = handler.create_no_results_result("Пушкин", SupportedLanguage::English, None);
+        let keyboard = reply_markup(&result).expect("switch button is attached");
+        let button = &keyboard.inline_keyboard[0][0];
+        assert_eq!(button.text, "Try in 🇷🇺 русской");
+        match &button.kind {
+            InlineKeyboardButtonKind::SwitchInlineQueryCurrentChat(query) => {
+                assert_eq!(query, "ru:Пушкин")
+            }
+            kind => panic!("unexpected button kind: {kind:?}"),
+        }
+
+        // Та же письменность: другой язык угадать нельзя, кнопки нет
+        let result = handler.create_no_results_result("Пушкин", SupportedLanguage::Ukrainian, None);
+        assert!(reply_markup(&result).is_none());
+        let result = handler.create_no_results_result("Pushkin", SupportedLanguage::German, None);
+        assert!(reply_markup(&result).is_none());
+    }
+
+    #[test]
+    fn test_language_keyboard_follows_configured_columns() {
+        let row_widths = |columns: usize| {
+            let handler = handler_with(|telegram| {
+                telegram.keyboard_languages = ["ru", "en", "de", "fr", "es", "uk", "it"]
+                    .map(str::to_string)
+                    .to_vec();
+                telegram.keyboard_columns = columns;
+            });
+            handler
+                .create_language_selection_keyboard(None)
+                .inline_keyboard
+                .iter()
+                .map(Vec::len)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(row_widths(2), vec![2, 2, 2, 1]);
+        assert_eq!(row_widths(3), vec![3, 3, 1]);
+        assert_eq!(row_widths(1), vec![1; 7]);
+        // Вне 1..=8 значение ограничивается
+        assert_eq!(row_widths(0), vec![1; 7]);
+        assert_eq!(row_widths(20), vec![7]);
+    }
+
+    #[tokio::test]
+    async fn test_empty_query_offers_recent_searches() {
+        let history = Arc::new(SearchHistory::new(5));
+        let user = UserId(42);
+        history.record(user, "Пушкин").await;
+        history.record(user, "en:Einstein").await;
+        let handler = handler_with_limit(10).with_history(Arc::clone(&history));
+
+        let results = handler.handle_empty_query(user, None).await.unwrap();
+        assert_eq!(
+            result_ids(&results),
+            vec!["lang_select", "history_0", "history_1"]
+        );
+        let button = &reply_markup(&results[1]).unwrap().inline_keyboard[0][0];
+        match &button.kind {
+            InlineKeyboardButtonKind::SwitchInlineQueryCurrentChat(query) => {
+                assert_eq!(query, "en:Einstein")
+            }
+            kind => panic!("unexpected button kind: {kind:?}"),
+        }
+
+        let results = handler.handle_empty_query(UserId(7), None).await.unwrap();
+        assert_eq!(result_ids(&results), vec!["lang_select"]);
+    }
+
+    fn article_ids_and_titles(results: &[InlineQueryResult]) -> Vec<(String, String)> {
+        results
+            .iter()
+            .map(|result| match result {
+                InlineQueryResult::Article(article) => (article.id.clone(), article.title.clone()),
+                _ => panic!("unexpected result kind"),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_prefix_only_query_prompts_instead_of_searching() {
+        let handler = handler_with(|_| {});
+        let results = handler
+            .handle_search_query(UserId(1), "en:", Some("ru"))
+            .await
+            .unwrap();
+
+        let results = article_ids_and_titles(&results);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0, "prefix_prompt");
+        assert!(results[0].1.contains("«en:»"), "{}", results[0].1);
+    }
+
+    #[tokio::test]
+    async fn test_prefix_only_query_can_show_language_keyboard() {
+        let handler =
+            handler_with(|telegram| telegram.prefix_only_query = PrefixOnlyQuery::LanguageKeyboard);
+        let results = handler
+            .handle_search_query(UserId(1), "wikt:de:", None)
+            .await
+            .unwrap();
+
+        assert_eq!(article_ids_and_titles(&results)[0].0, "lang_select");
+    }
+
+    fn long_article() -> EnrichedArticle {
+        let mut article = test_support::article_with(
+            "Статья",
+            crate::models::ArticleBatchInfo {
+                extract: Some("Очень подробное описание статьи. ".repeat(20)),
+                categories: vec!["Физики".to_string()],
+                ..Default::default()
+            },
+        );
+        article.basic_info.pageid = Some(1);
+        article
+    }
+
+    #[tokio::test]
+    async fn test_photo_result_only_for_articles_with_large_image() {
+        let mut with_photo = long_article();
+        let info = with_photo.batch_info.as_mut().unwrap();
+        info.image_url = Some("https://upload.wikimedia.org/thumb/300px-Ulm.jpg".to_string());
+        info.photo = Some(crate::models::ArticlePhoto {
+            url: "https://commons.wikimedia.org/wiki/Special:FilePath/Ulm.jpg?width=1280"
+                .to_string(),
+            width: 1280,
+            height: 853,
+        });
+        let mut small_photo = with_photo.clone();
+        small_photo.basic_info.pageid = Some(2);
+        small_photo
+            .batch_info
+            .as_mut()
+            .unwrap()
+            .photo
+            .as_mut()
+            .unwrap()
+            .width = 200;
+        let mut without_photo = long_article();
+        without_photo.basic_info.pageid = Some(3);
+        let articles = vec![with_photo, small_photo, without_photo];
+
+        let handler = handler_with(|telegram| telegram.inline_photo_results = true);
+        let results = handler
+            .build_article_results(
+                articles.clone(),
+                "Статья",
+                SupportedLanguage::Russian,
+                None,
+                ResultStyle::Detailed,
+            )
+            .await;
+        match &results[0] {
+            InlineQueryResult::Photo(photo) => {
+                assert!(photo
+                    .photo_url
+                    .as_str()
+                    .contains("Special:FilePath/Ulm.jpg"));
+                assert!(photo.thumb_url.as_str().ends_with("300px-Ulm.jpg"));
+                assert_eq!(photo.title.as_deref(), Some("Статья"));
+                assert!(photo.caption.as_ref().unwrap().starts_with("📖 *Статья*"));
+            }
+            other => panic!("expected photo result, got {other:?}"),
+        }
+        assert!(matches!(results[1], InlineQueryResult::Article(_)));
+        assert!(matches!(results[2], InlineQueryResult::Article(_)));
+
+        // Без настройки — всегда карточки
+        let handler = handler_with(|telegram| telegram.inline_photo_results = false);
+        let results = handler
+            .build_article_results(
+                articles,
+                "Статья",
+                SupportedLanguage::Russian,
+                None,
+                ResultStyle::Detailed,
+            )
+            .await;
+        assert!(matches!(results[0], InlineQueryResult::Article(_)));
+    }
+
+    #[tokio::test]
+    async fn test_wikidata_subtitle_complements_extract() {
+        let mut article = long_article();
+        article.wikidata_description = Some("немецкий физик".to_string());
+
+        for enabled in [true, false] {
+            let handler = handler_with(|telegram| {
+                telegram.use_article_url_preview = false;
+                telegram.branding.show_wikidata_subtitle = enabled;
+            });
+            let results = handler
+                .build_article_results(
+                    vec![article.clone()],
+                    "Статья",
+                    SupportedLanguage::Russian,
+                    None,
+                    ResultStyle::Detailed,
+                )
+                .await;
+            let text = &message_text(&results[0]).message_text;
+            assert!(text.contains("Очень подробное описание статьи"));
+            assert_eq!(
+                text.starts_with("📖 *Статья*\n_немецкий физик_\n\n"),
+                enabled,
+                "{text}"
+            );
+            assert_eq!(text.contains("немецкий физик"), enabled);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_embedded_image_link_is_escaped_and_previewed() {
+        let mut article = long_article();
+        article.batch_info.as_mut().unwrap().image_url =
+            Some("https://upload.wikimedia.org/thumb/300px-Ulm_(Donau).jpg".to_string());
+
+        let handler = handler_with(|telegram| {
+            telegram.use_article_url_preview = false;
+            telegram.embed_article_image = true;
+        });
+        let results = handler
+            .build_article_results(
+                vec![article.clone()],
+                "Статья",
+                SupportedLanguage::Russian,
+                None,
+                ResultStyle::Detailed,
+            )
+            .await;
+        let content = message_text(&results[0]);
+        assert!(content.message_text.starts_with(
+            "[\u{200B}](https://upload.wikimedia.org/thumb/300px-Ulm_(Donau\\).jpg)📖 *Статья*"
+        ));
+        assert_eq!(content.disable_web_page_preview, Some(false));
+
+        let handler = handler_with(|telegram| {
+            telegram.use_article_url_preview = false;
+            telegram.embed_article_image = false;
+        });
+        let results = handler
+            .build_article_results(
+                vec![article],
+                "Статья",
+                SupportedLanguage::Russian,
+                None,
+                ResultStyle::Detailed,
+            )
+            .await;
+        assert!(message_text(&results[0])
+            .message_text
+            .starts_with("📖 *Статья*"));
+    }
+
+    #[tokio::test]
+    async fn test_html_extract_format_sends_html_cards() {
+        let mut config = AppConfig::for_tests();
+        config.wikipedia.extract_format = ExtractFormat::Html;
+        config.telegram.use_article_url_preview = false;
+        config.telegram.embed_article_image = true;
+        let handler = InlineQueryHandler::new(
+            Arc::new(WikipediaService::new(config.clone()).unwrap()),
+            Arc::new(WikipediaService::new(config.clone()).unwrap()),
+            Arc::new(WikidataService::new(config.clone()).unwrap()),
+            config.telegram,
+        );
+
+        let mut article = long_article();
+        let batch_info = article.batch_info.as_mut().unwrap();
+        batch_info.extract_html =
+            Some("<p><b>Статья</b> — <i>пример</i> &amp; текст</p>".to_string());
+        batch_info.image_url = Some("https://upload.wikimedia.org/a.jpg?x=1&y=2".to_string());
+
+        let results = handler
+            .build_article_results(
+                vec![article],
+                "Статья",
+                SupportedLanguage::Russian,
+                None,
+                ResultStyle::Detailed,
+            )
+            .await;
+        let content = message_text(&results[0]);
+        assert_eq!(content.parse_mode, Some(ParseMode::Html));
+        assert!(content.message_text.starts_with(
+            "<a href=\"https://upload.wikimedia.org/a.jpg?x=1&amp;y=2\">\u{200B}</a>"
+        ));
+        assert!(content
+            .message_text
+            .contains("<b>Статья</b> — <i>пример</i> &amp; текст"));
+        assert!(!content.message_text.contains('*'));
+    }
+
+    async fn styled_result(style: ResultStyle) -> InlineQueryResult {
+        let handler = handler_with(|telegram| {
+            telegram.use_article_url_preview = false;
+            telegram.show_open_button = true;
+            telegram.show_category_buttons = true;
+        });
+        let mut results = handler
+            .build_article_results(
+                vec![long_article()],
+                "Статья",
+                SupportedLanguage::Russian,
+                None,
+                style,
+            )
+            .await;
+        results.remove(0)
+    }
+
+    #[tokio::test]
+    async fn test_compact_style_sends_short_card_with_link_only() {
+        let compact = styled_result(ResultStyle::Compact).await;
+        let detailed = styled_result(ResultStyle::Detailed).await;
+
+        let compact_text = &message_text(&compact).message_text;
+        let detailed_text = &message_text(&detailed).message_text;
+        assert!(compact_text.starts_with("📖 *Статья*"));
+        assert!(compact_text.len() < detailed_text.len());
+
+        let compact_keyboard = reply_markup(&compact).expect("link button is attached");
+        assert_eq!(compact_keyboard.inline_keyboard.len(), 1);
+        assert!(matches!(
+            compact_keyboard.inline_keyboard[0][0].kind,
+            InlineKeyboardButtonKind::Url(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_detailed_style_keeps_category_buttons() {
+        let detailed = styled_result(ResultStyle::Detailed).await;
+        let keyboard = reply_markup(&detailed).expect("keyboard is attached");
+        assert_eq!(keyboard.inline_keyboard.len(), 2);
+        assert!(matches!(
+            keyboard.inline_keyboard[1][0].kind,
+            InlineKeyboardButtonKind::SwitchInlineQueryCurrentChat(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_user_preference_overrides_configured_style() {
+        let preferences = Arc::new(UserPreferences::new());
+        let user = UserId(42);
+        preferences
+            .set_result_style(user, ResultStyle::Compact)
+            .await;
+        let handler = handler_with(|telegram| telegram.result_style = ResultStyle::Detailed)
+            .with_preferences(preferences);
+
+        assert_eq!(handler.result_style(user).await, ResultStyle::Compact);
+        assert_eq!(handler.result_style(UserId(7)).await, ResultStyle::Detailed);
+    }
+
+    fn prompt_description(results: &[InlineQueryResult]) -> String {
+        match &results[0] {
+            InlineQueryResult::Article(article) => article.description.clone().unwrap_or_default(),
+            _ => panic!("unexpected result kind"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ui_locale_is_independent_of_search_language() {
+        let preferences = Arc::new(UserPreferences::new());
+        preferences
+            .set_ui_locale(UserId(42), UiLocale::English)
+            .await;
+        let handler = handler_with(|_| {}).with_preferences(preferences);
+        let mut telegram_user = User {
+            id: UserId(42),
+            is_bot: false,
+            first_name: "Иван".to_string(),
+            last_name: None,
+            username: None,
+            language_code: Some("ru".to_string()),
+            is_premium: false,
+            added_to_attachment_menu: false,
+        };
+
+        // Интерфейс на английском, поиск — в русском разделе
+        let locale = handler.ui_locale(&telegram_user).await;
+        assert_eq!(locale.as_deref(), Some("en"));
+        let results = handler
+            .handle_search_query(telegram_user.id, "ru:", locale.as_deref())
+            .await
+            .unwrap();
+        assert_eq!(prompt_description(&results), "Поиск в разделе «Русский»");
+
+        // Без выбора в /ui действует язык клиента Telegram
+        telegram_user.id = UserId(7);
+        let locale = handler.ui_locale(&telegram_user).await;
+        assert_eq!(locale.as_deref(), Some("ru"));
+        let results = handler
+            .handle_search_query(telegram_user.id, "en:", locale.as_deref())
+            .await
+            .unwrap();
+        assert_eq!(prompt_description(&results), "Поиск в разделе «английской»");
+    }
+
+    #[tokio::test]
+    async fn test_interwiki_results_follow_articles() {
+        let handler = handler_with_limit(3);
+        let mut results = handler
+            .build_article_results(
+                articles(1),
+                "Статья",
+                SupportedLanguage::Russian,
+                None,
+                ResultStyle::Detailed,
+            )
+            .await;
+        let hits = [
+            InterwikiHit {
+                iw: "wikt".to_string(),
+                title: "wikt:статья".to_string(),
+                url: None,
+            },
+            InterwikiHit {
+                iw: "meta".to_string(),
+                title: "meta:Статья".to_string(),
+                url: None,
+            },
+            InterwikiHit {
+                iw: "d".to_string(),
+                title: "d:Q1".to_string(),
+                url: None,
+            },
+            InterwikiHit {
+                iw: "q".to_string(),
+                title: "q:Статья".to_string(),
+                url: None,
+            },
+        ];
+        handler.append_interwiki_results(&mut results, &hits, SupportedLanguage::Russian);
+
+        // Ссылку на неизвестный проект не собрать, лишнее отсекается лимитом
+        assert_eq!(
+            article_ids_and_titles(&results),
+            vec![
+                ("art_ru_0".to_string(), "Статья 0".to_string()),
+                ("iw_0".to_string(), "📚 статья".to_string()),
+                ("iw_1".to_string(), "🗃️ Q1".to_string()),
+            ]
+        );
+        assert_eq!(
+            message_text(&results[1]).message_text,
+            "📚 Викисловарь: [статья](https://ru.wiktionary.org/wiki/%D1%81%D1%82%D0%B0%D1%82%D1%8C%D1%8F)"
+        );
+    }
+}
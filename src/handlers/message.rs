@@ -1,14 +1,105 @@
+use moka::future::Cache;
 use std::sync::Arc;
-use teloxide::{prelude::*, types::ParseMode};
-use tracing::error;
+use std::time::Duration;
+use teloxide::{
+    prelude::*,
+    types::{InputFile, ParseMode, User},
+};
+use tracing::{error, info};
 
-use crate::utils::format_welcome_message;
+use crate::config::{ResultStyle, TelegramConfig};
+use crate::errors::UserFriendlyError;
+use crate::handlers::{
+    prefix_only_language, search_project_query, send_with_plain_text_fallback,
+    table_of_contents_message, ContentFilter, EnrichmentPipeline, QuerySearchResult,
+};
+use crate::models::{EnrichedArticle, CONTENT_LENGTH};
+use crate::services::{
+    SearchHistory, TrendingQueries, UiLocale, UserPreferences, WikidataService, WikipediaService,
+};
+use crate::utils::{
+    format_error_message, format_no_results_message, format_suggestion_message,
+    format_welcome_message, truncate_for_telegram, TELEGRAM_CAPTION_LIMIT, TELEGRAM_MESSAGE_LIMIT,
+};
 
-pub struct MessageHandler;
+/// Сколько статей отправлять в ответ на поиск в личном чате.
+const MAX_MESSAGE_RESULTS: usize = 3;
+
+/// Как часто один пользователь может отправлять отзыв.
+const FEEDBACK_COOLDOWN: Duration = Duration::from_secs(60);
+
+/// Сколько помнить последний запрос пользователя для отзывов.
+const LAST_QUERY_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Ограничение на длину отзыва, чтобы отчёт поместился в одно сообщение.
+const MAX_FEEDBACK_LENGTH: usize = 2000;
+
+pub struct MessageHandler {
+    wikipedia_service: Arc<WikipediaService>,
+    wiktionary_service: Arc<WikipediaService>,
+    enrichment: EnrichmentPipeline,
+    config: TelegramConfig,
+    content_filter: ContentFilter,
+    /// Пользователи, недавно отправившие отзыв.
+    feedback_cooldowns: Cache<UserId, ()>,
+    /// Последний поисковый запрос пользователя — прикладывается к отзыву.
+    last_queries: Cache<UserId, String>,
+    /// Общий с inline-режимом счётчик популярных запросов.
+    trending: Option<Arc<TrendingQueries>>,
+    /// Общая с inline-режимом история запросов — для `/clearhistory`.
+    history: Option<Arc<SearchHistory>>,
+    /// Общие с inline-режимом настройки пользователей — для `/style` и `/ui`.
+    preferences: Option<Arc<UserPreferences>>,
+}
 
 impl MessageHandler {
-    pub fn new() -> Self {
-        Self
+    pub fn new(
+        wikipedia_service: Arc<WikipediaService>,
+        wiktionary_service: Arc<WikipediaService>,
+        wikidata_service: Arc<WikidataService>,
+        config: TelegramConfig,
+    ) -> Self {
+        let content_filter = ContentFilter::from_config(wikipedia_service.config());
+
+        Self {
+            wikipedia_service,
+            wiktionary_service,
+            enrichment: EnrichmentPipeline::from_wikidata(wikidata_service),
+            config,
+            content_filter,
+            feedback_cooldowns: Cache::builder().time_to_live(FEEDBACK_COOLDOWN).build(),
+            last_queries: Cache::builder()
+                .time_to_live(LAST_QUERY_TTL)
+                .max_capacity(10_000)
+                .build(),
+            trending: None,
+            history: None,
+            preferences: None,
+        }
+    }
+
+    /// Заменяет провайдеров обогащения, выбранных в конфигурации Wikidata.
+    pub fn with_enrichment(mut self, enrichment: EnrichmentPipeline) -> Self {
+        self.enrichment = enrichment;
+        self
+    }
+
+    /// Включает `/trending` со счётчиком, который пополняет inline-режим.
+    pub fn with_trending(mut self, trending: Arc<TrendingQueries>) -> Self {
+        self.trending = Some(trending);
+        self
+    }
+
+    /// Включает `/clearhistory` для истории inline-запросов.
+    pub fn with_history(mut self, history: Arc<SearchHistory>) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    /// Включает `/style` и `/ui` для выбора вида результатов и языка интерфейса.
+    pub fn with_preferences(mut self, preferences: Arc<UserPreferences>) -> Self {
+        self.preferences = Some(preferences);
+        self
     }
 
     pub async fn handle(&self, bot: Bot, msg: Message) -> ResponseResult<()> {
@@ -16,37 +107,392 @@ impl MessageHandler {
             return Ok(());
         };
 
-        match text {
+        let (command, args) = text
+            .split_once(char::is_whitespace)
+            .map(|(command, args)| (command, args.trim()))
+            .unwrap_or((text, ""));
+        // Команды в группах приходят в виде "/search@BotName"
+        let command = command.split('@').next().unwrap_or(command);
+
+        match command {
             "/start" => self.handle_start_command(bot, &msg).await,
             "/help" => self.handle_help_command(bot, &msg).await,
+            "/search" => self.handle_search_command(bot, &msg, args).await,
+            "/contents" => self.handle_contents_command(bot, &msg, args).await,
+            "/feedback" => self.handle_feedback_command(bot, &msg, args).await,
+            "/trending" => self.handle_trending_command(bot, &msg).await,
+            "/clearhistory" => self.handle_clear_history_command(bot, &msg).await,
+            "/style" => self.handle_style_command(bot, &msg, args).await,
+            "/ui" => self.handle_ui_command(bot, &msg, args).await,
+            _ if !text.starts_with('/') && msg.chat.is_private() => {
+                self.handle_search_command(bot, &msg, text.trim()).await
+            }
             _ => self.handle_unknown_command(bot, &msg).await,
         }
     }
 
-    async fn handle_start_command(&self, bot: Bot, msg: &Message) -> ResponseResult<()> {
-        let welcome_text = format_welcome_message();
+    async fn handle_feedback_command(
+        &self,
+        bot: Bot,
+        msg: &Message,
+        text: &str,
+    ) -> ResponseResult<()> {
+        let Some(feedback_chat_id) = self.config.feedback_chat_id else {
+            bot.send_message(msg.chat.id, "Отзывы через бота сейчас не принимаются.")
+                .await?;
+            return Ok(());
+        };
+
+        if text.is_empty() {
+            send_with_plain_text_fallback(
+                bot.send_message(
+                    msg.chat.id,
+                    "Опишите проблему: `/feedback по запросу Пушкин открывается не та статья`",
+                )
+                .parse_mode(ParseMode::MarkdownV2),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let Some(user) = msg.from() else {
+            return Ok(());
+        };
+
+        if self.feedback_cooldowns.contains_key(&user.id) {
+            bot.send_message(
+                msg.chat.id,
+                "Вы уже отправили отзыв недавно, попробуйте через минуту.",
+            )
+            .await?;
+            return Ok(());
+        }
+        self.feedback_cooldowns.insert(user.id, ()).await;
+
+        let last_query = self.last_queries.get(&user.id).await;
+        let report = format_feedback_report(user, text, last_query.as_deref());
+
+        info!("📝 Отзыв от пользователя {}", user.id);
+        let reply = match bot.send_message(ChatId(feedback_chat_id), report).await {
+            Ok(_) => "Спасибо! Отзыв отправлен разработчикам.",
+            Err(e) => {
+                error!("Failed to forward feedback: {:?}", e);
+                self.feedback_cooldowns.invalidate(&user.id).await;
+                "Не удалось отправить отзыв, попробуйте позже."
+            }
+        };
+
+        bot.send_message(msg.chat.id, reply).await?;
+        Ok(())
+    }
+
+    async fn handle_trending_command(&self, bot: Bot, msg: &Message) -> ResponseResult<()> {
+        let reply = match &self.trending {
+            Some(trending) => format_trending_message(
+                &trending.top(
+                    self.config.trending_max_items,
+                    self.config.trending_min_count,
+                ),
+                trending.window(),
+            ),
+            None => "Статистика популярных запросов сейчас не ведётся.".to_string(),
+        };
+
+        bot.send_message(msg.chat.id, reply).await?;
+        Ok(())
+    }
+
+    async fn handle_clear_history_command(&self, bot: Bot, msg: &Message) -> ResponseResult<()> {
+        let reply = match (&self.history, msg.from()) {
+            (Some(history), Some(user)) => {
+                history.clear(user.id).await;
+                "🧹 История запросов очищена."
+            }
+            _ => "История запросов не ведётся.",
+        };
+
+        bot.send_message(msg.chat.id, reply).await?;
+        Ok(())
+    }
+
+    async fn handle_style_command(
+        &self,
+        bot: Bot,
+        msg: &Message,
+        args: &str,
+    ) -> ResponseResult<()> {
+        let reply = match (&self.preferences, msg.from()) {
+            (Some(preferences), Some(user)) if args.is_empty() => {
+                let style = preferences
+                    .result_style(user.id)
+                    .await
+                    .unwrap_or(self.config.result_style);
+                format!(
+                    "Сейчас inline-результаты {}. Изменить: /style compact или /style detailed.",
+                    result_style_name(style)
+                )
+            }
+            (Some(preferences), Some(user)) => match args.parse::<ResultStyle>() {
+                Ok(style) => {
+                    preferences.set_result_style(user.id, style).await;
+                    format!("✅ Теперь inline-результаты {}.", result_style_name(style))
+                }
+                Err(_) => "Укажите вид: /style compact или /style detailed.".to_string(),
+            },
+            _ => "Настройки пользователей сейчас не сохраняются.".to_string(),
+        };
+
+        bot.send_message(msg.chat.id, reply).await?;
+        Ok(())
+    }
+
+    async fn handle_ui_command(&self, bot: Bot, msg: &Message, args: &str) -> ResponseResult<()> {
+        let reply = match (&self.preferences, msg.from()) {
+            (Some(preferences), Some(user)) if args.is_empty() => {
+                let current = match preferences.ui_locale(user.id).await {
+                    Some(locale) => locale.code().to_string(),
+                    None => "как в Telegram".to_string(),
+                };
+                format!(
+                    "Язык интерфейса: {current}. Изменить: /ui ru или /ui en. \
+                     Язык поиска задаётся префиксом запроса, например en:Berlin."
+                )
+            }
+            (Some(preferences), Some(user)) => match args.parse::<UiLocale>() {
+                Ok(locale) => {
+                    preferences.set_ui_locale(user.id, locale).await;
+                    format!("✅ Язык интерфейса: {}.", locale.code())
+                }
+                Err(_) => "Укажите язык интерфейса: /ui ru или /ui en.".to_string(),
+            },
+            _ => "Настройки пользователей сейчас не сохраняются.".to_string(),
+        };
+
+        bot.send_message(msg.chat.id, reply).await?;
+        Ok(())
+    }
+
+    /// Локаль интерфейса: выбранная через `/ui` или язык клиента Telegram.
+    async fn ui_locale(&self, user: &User) -> Option<String> {
+        let preferred = match &self.preferences {
+            Some(preferences) => preferences.ui_locale(user.id).await,
+            None => None,
+        };
+        preferred
+            .map(|locale| locale.code().to_string())
+            .or_else(|| user.language_code.clone())
+    }
+
+    async fn handle_contents_command(
+        &self,
+        bot: Bot,
+        msg: &Message,
+        query: &str,
+    ) -> ResponseResult<()> {
+        if query.is_empty() {
+            send_with_plain_text_fallback(
+                bot.send_message(
+                    msg.chat.id,
+                    "Укажите статью: `/contents Пушкин` или `/contents en:Albert Einstein`",
+                )
+                .parse_mode(ParseMode::MarkdownV2),
+            )
+            .await?;
+            return Ok(());
+        }
 
-        bot.send_message(msg.chat.id, welcome_text)
-            .parse_mode(ParseMode::MarkdownV2)
+        info!("📑 Чат {} запрашивает оглавление: '{}'", msg.chat.id, query);
+
+        let (language, title) = self.wikipedia_service.parse_query(query);
+        let message =
+            match table_of_contents_message(&self.wikipedia_service, &title, language).await {
+                Ok(message) => message,
+                Err(e) => {
+                    error!("Error handling contents command: {:?}", e);
+                    format_error_message(&e.user_message())
+                }
+            };
+
+        send_with_plain_text_fallback(
+            bot.send_message(msg.chat.id, message)
+                .parse_mode(ParseMode::MarkdownV2)
+                .disable_web_page_preview(true),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn handle_search_command(
+        &self,
+        bot: Bot,
+        msg: &Message,
+        query: &str,
+    ) -> ResponseResult<()> {
+        // Пустой запрос или один префикс языка (`/search en:`)
+        let default_language = self.wikipedia_service.config().default_language();
+        if prefix_only_language(query, default_language).is_some() {
+            send_with_plain_text_fallback(
+                bot.send_message(
+                    msg.chat.id,
+                    "Укажите запрос: `/search Пушкин` или `/search en:Albert Einstein`",
+                )
+                .parse_mode(ParseMode::MarkdownV2),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        info!("🔍 Чат {} ищет: '{}'", msg.chat.id, query);
+        if let Some(user) = msg.from() {
+            self.last_queries.insert(user.id, query.to_string()).await;
+        }
+
+        let QuerySearchResult {
+            language,
+            search_query,
+            articles,
+            suggestion,
+            ..
+        } = match search_project_query(
+            &self.wikipedia_service,
+            &self.wiktionary_service,
+            &self.enrichment,
+            query,
+        )
+        .await
+        {
+            Ok(result) => result,
+            Err(e) => {
+                error!("Error handling search command: {:?}", e);
+                send_with_plain_text_fallback(
+                    bot.send_message(msg.chat.id, format_error_message(&e.user_message()))
+                        .parse_mode(ParseMode::MarkdownV2),
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        let articles = self.content_filter.apply(articles);
+        if articles.is_empty() {
+            let locale = match msg.from() {
+                Some(user) => self.ui_locale(user).await,
+                None => None,
+            };
+            let mut message = format_no_results_message(
+                &search_query,
+                language.name_for_locale(locale.as_deref()),
+            );
+            if let Some(suggestion) = suggestion {
+                message.push_str("\n\n");
+                message.push_str(&format_suggestion_message(&suggestion));
+            }
+
+            send_with_plain_text_fallback(
+                bot.send_message(msg.chat.id, message)
+                    .parse_mode(ParseMode::MarkdownV2),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        for (idx, article) in articles.into_iter().take(MAX_MESSAGE_RESULTS).enumerate() {
+            if idx == 0
+                && self.config.send_top_result_as_photo
+                && self.send_article_photo(&bot, msg, &article).await
+            {
+                continue;
+            }
+
+            let format = self.wikipedia_service.config().extract_format;
+            let message_text = article.to_card(
+                CONTENT_LENGTH,
+                &self.config.branding,
+                format,
+                TELEGRAM_MESSAGE_LIMIT,
+            );
+
+            send_with_plain_text_fallback(
+                bot.send_message(msg.chat.id, message_text)
+                    .parse_mode(format.parse_mode()),
+            )
             .await
             .map_err(|e| {
-                error!("Failed to send welcome message: {:?}", e);
+                error!("Failed to send search result: {:?}", e);
                 e
             })?;
+        }
 
         Ok(())
     }
 
-    async fn handle_help_command(&self, bot: Bot, msg: &Message) -> ResponseResult<()> {
-        let help_text = self.create_help_message();
+    /// Отправляет статью фото с подписью, обрезанной до лимита. Возвращает
+    /// `false`, если у статьи нет изображения или Telegram отклонил фото, —
+    /// тогда вызывающий отправляет обычное текстовое сообщение.
+    async fn send_article_photo(
+        &self,
+        bot: &Bot,
+        msg: &Message,
+        article: &EnrichedArticle,
+    ) -> bool {
+        let Some(image_url) = article.valid_image_url() else {
+            return false;
+        };
+
+        let format = self.wikipedia_service.config().extract_format;
+        let caption = article.to_card(
+            CONTENT_LENGTH,
+            &self.config.branding,
+            format,
+            TELEGRAM_CAPTION_LIMIT,
+        );
 
-        bot.send_message(msg.chat.id, help_text)
-            .parse_mode(ParseMode::MarkdownV2)
+        match bot
+            .send_photo(msg.chat.id, InputFile::url(image_url))
+            .caption(caption)
+            .parse_mode(format.parse_mode())
             .await
-            .map_err(|e| {
-                error!("Failed to send help message: {:?}", e);
-                e
-            })?;
+        {
+            Ok(_) => true,
+            Err(e) => {
+                error!(
+                    "Failed to send article photo, falling back to text: {:?}",
+                    e
+                );
+                false
+            }
+        }
+    }
+
+    async fn handle_start_command(&self, bot: Bot, msg: &Message) -> ResponseResult<()> {
+        let welcome_text = truncate_for_telegram(&format_welcome_message(), TELEGRAM_MESSAGE_LIMIT);
+
+        send_with_plain_text_fallback(
+            bot.send_message(msg.chat.id, welcome_text)
+                .parse_mode(ParseMode::MarkdownV2),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to send welcome message: {:?}", e);
+            e
+        })?;
+
+        Ok(())
+    }
+
+    async fn handle_help_command(&self, bot: Bot, msg: &Message) -> ResponseResult<()> {
+        let help_text = truncate_for_telegram(&self.create_help_message(), TELEGRAM_MESSAGE_LIMIT);
+
+        send_with_plain_text_fallback(
+            bot.send_message(msg.chat.id, help_text)
+                .parse_mode(ParseMode::MarkdownV2),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to send help message: {:?}", e);
+            e
+        })?;
 
         Ok(())
     }
@@ -75,22 +521,65 @@ impl MessageHandler {
 • `de:Berlin` — поиск в немецкой
 • `fr:Paris` — поиск во французской
 • `ja:東京` — поиск в японской
+• `wikt:en:word` — поиск в английском Викисловаре
+
+💬 **Поиск в личном чате:**
+Просто отправьте боту запрос или используйте `/search запрос`
 
 ⚙️ **Поддерживаемые команды:**
 /start — показать приветствие
 /help — показать эту справку
+/search — найти статьи прямо в чате
+/contents — оглавление статьи со ссылками на разделы
+/feedback — сообщить о неверном результате
+/trending — популярные запросы
+/clearhistory — очистить историю inline\-запросов
+/style — компактные или подробные inline\-результаты
+/ui — язык интерфейса \(ru или en\), независимо от языка поиска
 
 🚀 **Начните использовать бота прямо сейчас\!**"#
             .to_string()
     }
 }
 
-impl Default for MessageHandler {
-    fn default() -> Self {
-        Self::new()
+/// Текст отзыва для чата разработчиков. Отправляется без разметки, поэтому
+/// ничего не экранируется.
+fn format_feedback_report(user: &User, text: &str, last_query: Option<&str>) -> String {
+    let author = match &user.username {
+        Some(username) => format!("@{username} ({})", user.id),
+        None => format!("{} ({})", user.full_name(), user.id),
+    };
+    let text: String = text.chars().take(MAX_FEEDBACK_LENGTH).collect();
+
+    let mut report = format!("📝 Отзыв от {author}\n\n{text}");
+    if let Some(query) = last_query {
+        report.push_str(&format!("\n\n🔍 Последний запрос: {query}"));
+    }
+    report
+}
+
+/// Вид inline-результатов словами — для ответов на `/style`.
+fn result_style_name(style: ResultStyle) -> &'static str {
+    match style {
+        ResultStyle::Compact => "компактные: короткое описание и ссылка",
+        ResultStyle::Detailed => "подробные: описание и кнопки категорий",
     }
 }
 
+/// Список популярных запросов для `/trending`. Отправляется без разметки.
+fn format_trending_message(top: &[(String, u32)], window: Duration) -> String {
+    let minutes = (window.as_secs() / 60).max(1);
+    if top.is_empty() {
+        return format!("🔥 За последние {minutes} мин. популярных запросов пока нет.");
+    }
+
+    let mut message = format!("🔥 Популярные запросы за последние {minutes} мин.:\n");
+    for (position, (query, count)) in top.iter().enumerate() {
+        message.push_str(&format!("\n{}. {query} — {count}", position + 1));
+    }
+    message
+}
+
 pub async fn message_handler(
     bot: Bot,
     msg: Message,
@@ -98,3 +587,51 @@ pub async fn message_handler(
 ) -> ResponseResult<()> {
     handler.handle(bot, msg).await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(username: Option<&str>) -> User {
+        User {
+            id: UserId(42),
+            is_bot: false,
+            first_name: "Иван".to_string(),
+            last_name: Some("Петров".to_string()),
+            username: username.map(str::to_string),
+            language_code: None,
+            is_premium: false,
+            added_to_attachment_menu: false,
+        }
+    }
+
+    #[test]
+    fn test_feedback_report_includes_author_and_last_query() {
+        let report =
+            format_feedback_report(&user(Some("ivan")), "Не та статья", Some("en:Pushkin"));
+        assert_eq!(
+            report,
+            "📝 Отзыв от @ivan (42)\n\nНе та статья\n\n🔍 Последний запрос: en:Pushkin"
+        );
+
+        let report = format_feedback_report(&user(None), "Ошибка", None);
+        assert_eq!(report, "📝 Отзыв от Иван Петров (42)\n\nОшибка");
+    }
+
+    #[test]
+    fn test_feedback_report_is_capped() {
+        let text = "а".repeat(MAX_FEEDBACK_LENGTH * 2);
+        let report = format_feedback_report(&user(None), &text, None);
+        assert!(report.chars().count() < MAX_FEEDBACK_LENGTH + 100);
+    }
+
+    #[test]
+    fn test_trending_message() {
+        let top = vec![("пушкин".to_string(), 12), ("einstein".to_string(), 4)];
+        assert_eq!(
+            format_trending_message(&top, Duration::from_secs(3600)),
+            "🔥 Популярные запросы за последние 60 мин.:\n\n1. пушкин — 12\n2. einstein — 4"
+        );
+        assert!(format_trending_message(&[], Duration::from_secs(600)).contains("пока нет"));
+    }
+}
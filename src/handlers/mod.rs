@@ -1,5 +1,15 @@
+pub mod content_filter;
+pub mod enrichment;
+pub mod inline_limits;
 pub mod inline_query;
 pub mod message;
+pub mod search;
+pub mod telegram_errors;
 
+pub use content_filter::*;
+pub use enrichment::*;
+pub use inline_limits::*;
 pub use inline_query::*;
 pub use message::*;
+pub use search::*;
+pub use telegram_errors::*;
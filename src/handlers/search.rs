@@ -0,0 +1,1095 @@
+use crate::config::languages::{detect_unambiguous_language, SupportedLanguage};
+use crate::config::projects::{split_project_prefix, WikiProject};
+use crate::errors::{WikiError, WikiResult};
+use crate::handlers::{ContentFilter, EnrichmentPipeline};
+use crate::models::{EnrichedArticle, InterwikiHit};
+use crate::services::{WikipediaApi, WikipediaService};
+use crate::utils::{format_table_of_contents, is_latin_only, latin_to_cyrillic};
+
+/// При скольких найденных статьях выдача считается скудной и дополняется
+/// результатами из других проектов Викимедиа.
+pub const THIN_RESULTS_THRESHOLD: usize = 3;
+
+/// Результат поиска по пользовательскому запросу.
+pub struct QuerySearchResult {
+    /// Язык, в котором в итоге выполнялся поиск.
+    pub language: SupportedLanguage,
+    /// Запрос без префикса языка — для сообщений пользователю.
+    pub search_query: String,
+    pub articles: Vec<EnrichedArticle>,
+    /// Исправленный запрос от MediaWiki, если ничего не найдено.
+    pub suggestion: Option<String>,
+    /// Сколько всего статей нашёл поиск, если MediaWiki это сообщил.
+    pub total_hits: Option<u64>,
+    /// Результаты из других проектов, если статей меньше
+    /// [`THIN_RESULTS_THRESHOLD`]; показываются после статей.
+    pub interwiki: Vec<InterwikiHit>,
+    /// Был ли результат для запроса уже в кэше до поиска.
+    pub cache_hit: bool,
+}
+
+/// Запрос оглавления `toc:Название` с необязательным префиксом языка (`en:toc:…`).
+pub fn parse_contents_query(
+    query: &str,
+    default_language: SupportedLanguage,
+) -> Option<(SupportedLanguage, String)> {
+    let (language, rest) =
+        crate::services::parse_query_with_default_language(query, default_language);
+    let title = rest.strip_prefix("toc:")?.trim();
+    (!title.is_empty()).then(|| (language, title.to_string()))
+}
+
+/// Язык запроса, в котором после префиксов (`en:`, `wikt:`, `wikt:de:`)
+/// ничего не осталось, — искать такой запрос бессмысленно.
+pub fn prefix_only_language(
+    query: &str,
+    default_language: SupportedLanguage,
+) -> Option<SupportedLanguage> {
+    let (_, query) = split_project_prefix(query.trim());
+    let (language, search_query) =
+        crate::services::parse_query_with_default_language(query, default_language);
+    search_query.trim().is_empty().then_some(language)
+}
+
+/// Слово, для которого стоит показать определение из Викисловаря: запрос к
+/// Wikipedia (`query` — как ввёл пользователь, `search_query` — без префикса
+/// языка) из одного слова без служебных префиксов вроде `cat:`.
+pub fn definition_word<'a>(query: &str, search_query: &'a str) -> Option<&'a str> {
+    let (project, _) = split_project_prefix(query.trim());
+    let word = search_query.trim();
+    (project == WikiProject::Wikipedia
+        && word.chars().count() >= 2
+        && !word.contains(char::is_whitespace)
+        && !word.contains(':'))
+    .then_some(word)
+}
+
+/// Оглавление статьи в виде MarkdownV2-сообщения со ссылками на разделы.
+pub async fn table_of_contents_message(
+    wikipedia_service: &WikipediaService,
+    title: &str,
+    language: SupportedLanguage,
+) -> WikiResult<String> {
+    let sections = wikipedia_service.get_sections(title, language).await?;
+    let article_url = wikipedia_service.get_article_url(title, language);
+
+    Ok(format_table_of_contents(title, &article_url, &sections))
+}
+
+/// Точка входа для обработчиков: запросы с префиксом `wikt:` уходят в
+/// Викисловарь, остальные — в Wikipedia через [`search_user_query`].
+pub async fn search_project_query(
+    wikipedia_service: &WikipediaService,
+    wiktionary_service: &WikipediaService,
+    enrichment: &EnrichmentPipeline,
+    query: &str,
+) -> WikiResult<QuerySearchResult> {
+    let (service, query) = project_service(wikipedia_service, wiktionary_service, query);
+    search_user_query(service, enrichment, query).await
+}
+
+/// Лучшая статья по запросу — та, что inline-режим показал бы первой, или
+/// `None`, если ничего не нашлось. Запрос разбирается так же, как в
+/// [`search_project_query`], но подсказки и счётчики не запрашиваются.
+pub async fn search_top(
+    wikipedia_service: &WikipediaService,
+    wiktionary_service: &WikipediaService,
+    enrichment: &EnrichmentPipeline,
+    content_filter: &ContentFilter,
+    query: &str,
+) -> WikiResult<Option<EnrichedArticle>> {
+    let (service, query) = project_service(wikipedia_service, wiktionary_service, query);
+    let found = find_articles(service, enrichment, query).await?;
+
+    Ok(rank_articles(
+        found.articles,
+        content_filter,
+        &found.search_query,
+        service.config().ranking.title_similarity,
+    )
+    .into_iter()
+    .next())
+}
+
+/// Сервис проекта по префиксу (`wikt:`) и запрос без него.
+fn project_service<'a, 'q>(
+    wikipedia_service: &'a WikipediaService,
+    wiktionary_service: &'a WikipediaService,
+    query: &'q str,
+) -> (&'a WikipediaService, &'q str) {
+    let (project, query) = split_project_prefix(query);
+    let service = match project {
+        WikiProject::Wiktionary => wiktionary_service,
+        WikiProject::Wikipedia | WikiProject::Wikivoyage => wikipedia_service,
+    };
+    (service, query)
+}
+
+/// Разбирает запрос пользователя (префикс языка, `cat:`) и ищет статьи. Для
+/// запросов без префикса при включённом `auto_language` язык сначала
+/// угадывается по письменности, а если она неоднозначна (латиница), поиск идёт
+/// во всех популярных разделах. Отсутствие результатов возвращается пустым списком.
+pub async fn search_user_query(
+    wikipedia_service: &WikipediaService,
+    enrichment: &EnrichmentPipeline,
+    query: &str,
+) -> WikiResult<QuerySearchResult> {
+    let FoundArticles {
+        language,
+        search_query,
+        api_query,
+        articles,
+        cache_hit,
+    } = find_articles(wikipedia_service, enrichment, query).await?;
+
+    let (suggestion, total_hits) = if articles.is_empty() {
+        let suggestion = wikipedia_service
+            .get_search_suggestion(&api_query, language)
+            .await
+            .unwrap_or_default();
+        (suggestion, None)
+    } else {
+        (
+            None,
+            wikipedia_service.total_hits(&api_query, language).await,
+        )
+    };
+
+    let interwiki = if articles.len() < THIN_RESULTS_THRESHOLD {
+        wikipedia_service.interwiki_hits(&api_query, language).await
+    } else {
+        Vec::new()
+    };
+
+    Ok(QuerySearchResult {
+        language,
+        search_query,
+        articles,
+        suggestion,
+        total_hits,
+        interwiki,
+        cache_hit,
+    })
+}
+
+/// Статьи по запросу без подсказок, счётчиков и результатов других проектов.
+struct FoundArticles {
+    language: SupportedLanguage,
+    search_query: String,
+    /// Запрос, ушедший в API: с развёрнутым фильтром `cat:`.
+    api_query: String,
+    articles: Vec<EnrichedArticle>,
+    cache_hit: bool,
+}
+
+/// Поисковая часть [`search_user_query`]: разбор префикса языка и `cat:`,
+/// выбор раздела и сам поиск.
+async fn find_articles(
+    wikipedia_service: &WikipediaService,
+    enrichment: &EnrichmentPipeline,
+    query: &str,
+) -> WikiResult<FoundArticles> {
+    let (language, search_query) = wikipedia_service.parse_query(query);
+    let api_query = crate::services::expand_category_filter(&search_query);
+    let has_language_prefix = search_query != query;
+    let auto_language = wikipedia_service.config().auto_language && !has_language_prefix;
+    let detected_language = auto_language
+        .then(|| detect_unambiguous_language(&search_query))
+        .flatten();
+    let language = detected_language.unwrap_or(language);
+    let cache_hit = wikipedia_service.has_cached_results(&api_query, language);
+
+    let (language, articles) = if auto_language && detected_language.is_none() {
+        match search_enriched_articles_auto(wikipedia_service, enrichment, &api_query).await {
+            Ok(result) => result,
+            Err(WikiError::NoResults { .. }) => (language, Vec::new()),
+            Err(e) => return Err(e),
+        }
+    } else {
+        match search_with_translit_fallback(wikipedia_service, enrichment, &api_query, language)
+            .await
+        {
+            Ok(articles) => (found_language(&articles, language), articles),
+            Err(WikiError::NoResults { .. }) => (language, Vec::new()),
+            Err(e) => return Err(e),
+        }
+    };
+
+    Ok(FoundArticles {
+        language,
+        search_query,
+        api_query,
+        articles,
+        cache_hit,
+    })
+}
+
+/// Если латинский запрос к русскому или украинскому разделу ничего не нашёл,
+/// повторяет поиск в кириллической транслитерации ("Pushkin" → "Пушкин").
+async fn search_with_translit_fallback(
+    wikipedia_service: &WikipediaService,
+    enrichment: &EnrichmentPipeline,
+    query: &str,
+    language: SupportedLanguage,
+) -> WikiResult<Vec<EnrichedArticle>> {
+    let primary = search_enriched_articles(wikipedia_service, enrichment, query, language).await;
+
+    let primary_is_empty = match &primary {
+        Ok(articles) => articles.is_empty(),
+        Err(WikiError::NoResults { .. }) => true,
+        Err(_) => false,
+    };
+
+    let should_transliterate = wikipedia_service.config().transliterate_latin
+        && matches!(
+            language,
+            SupportedLanguage::Russian | SupportedLanguage::Ukrainian
+        )
+        && !query.contains(':')
+        && is_latin_only(query);
+
+    if !primary_is_empty || !should_transliterate {
+        return primary;
+    }
+
+    let transliterated = latin_to_cyrillic(query);
+    tracing::info!("🔤 Транслитерация '{}' → '{}'", query, transliterated);
+
+    match search_enriched_articles(wikipedia_service, enrichment, &transliterated, language).await {
+        Ok(articles) if !articles.is_empty() => Ok(articles),
+        _ => primary,
+    }
+}
+
+/// Общий для inline-режима и команд путь поиска: статьи Wikipedia,
+/// отсортированные по релевантности и дополненные провайдерами `enrichment`
+/// (описания и типы сущностей из Wikidata и т. п.).
+///
+/// Порядок запросов:
+/// 1. Поиск в Wikipedia — до него идентификаторы Wikidata неизвестны.
+/// 2. Одновременно: запросы всех провайдеров `enrichment` и локальная
+///    сортировка статей, которой их данные не нужны.
+/// 3. Загруженные данные применяются к уже отсортированным статьям.
+pub async fn search_enriched_articles(
+    wikipedia_service: &WikipediaService,
+    enrichment: &EnrichmentPipeline,
+    query: &str,
+    language: SupportedLanguage,
+) -> WikiResult<Vec<EnrichedArticle>> {
+    // Когда уходить на обычный поиск, решает сам сервис
+    let enriched_articles = wikipedia_service
+        .get_enriched_articles_optimized(query, language)
+        .await?;
+
+    let language = found_language(&enriched_articles, language);
+    Ok(enrich_articles(wikipedia_service, enrichment, enriched_articles, language).await)
+}
+
+/// Язык раздела, где нашлись статьи: отличается от запрошенного, если поиск
+/// ушёл по цепочке `fallback_languages`.
+fn found_language(articles: &[EnrichedArticle], requested: SupportedLanguage) -> SupportedLanguage {
+    articles
+        .first()
+        .and_then(|article| article.source_language)
+        .unwrap_or(requested)
+}
+
+/// Вариант [`search_enriched_articles`] для запросов без префикса языка: язык
+/// выбирается через [`WikipediaService::search_best_effort`].
+pub async fn search_enriched_articles_auto(
+    wikipedia_service: &WikipediaService,
+    enrichment: &EnrichmentPipeline,
+    query: &str,
+) -> WikiResult<(SupportedLanguage, Vec<EnrichedArticle>)> {
+    let (language, enriched_articles) = wikipedia_service.search_best_effort(query).await?;
+
+    let enriched_articles =
+        enrich_articles(wikipedia_service, enrichment, enriched_articles, language).await;
+
+    Ok((language, enriched_articles))
+}
+
+/// Сортирует статьи и дополняет их провайдерами `enrichment`. Сортировке
+/// данные провайдеров не нужны, поэтому она идёт одновременно с их запросами.
+async fn enrich_articles(
+    wikipedia_service: &WikipediaService,
+    enrichment: &EnrichmentPipeline,
+    enriched_articles: Vec<EnrichedArticle>,
+    language: SupportedLanguage,
+) -> Vec<EnrichedArticle> {
+    // Фикстуры уже содержат всё нужное, сеть в этом режиме не используется
+    if wikipedia_service.uses_fixtures() {
+        let mut enriched_articles = enriched_articles;
+        enriched_articles.sort_by(compare_articles);
+        return enriched_articles;
+    }
+
+    let (pending, order) = tokio::join!(enrichment.fetch(&enriched_articles, language), async {
+        sorted_order(&enriched_articles)
+    },);
+
+    let mut slots: Vec<_> = enriched_articles.into_iter().map(Some).collect();
+    let mut enriched_articles: Vec<_> = order
+        .into_iter()
+        .filter_map(|idx| slots[idx].take())
+        .collect();
+    pending.apply(&mut enriched_articles);
+    enriched_articles
+}
+
+/// Индексы статей в порядке выдачи.
+fn sorted_order(articles: &[EnrichedArticle]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..articles.len()).collect();
+    order.sort_by(|&a, &b| compare_articles(&articles[a], &articles[b]));
+    order
+}
+
+/// Сначала порядок релевантности API, затем статьи с картинкой, затем более длинные.
+fn compare_articles(a: &EnrichedArticle, b: &EnrichedArticle) -> std::cmp::Ordering {
+    match (a.relevance_index, b.relevance_index) {
+        (Some(idx_a), Some(idx_b)) => idx_a.cmp(&idx_b),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => {
+            let has_image_a = a.image_url().is_some();
+            let has_image_b = b.image_url().is_some();
+
+            if has_image_a && !has_image_b {
+                std::cmp::Ordering::Less
+            } else if !has_image_a && has_image_b {
+                std::cmp::Ordering::Greater
+            } else {
+                let word_count_a = a.word_count().unwrap_or(0);
+                let word_count_b = b.word_count().unwrap_or(0);
+                word_count_b.cmp(&word_count_a)
+            }
+        }
+    }
+}
+
+/// Статьи в порядке показа: без скрытых фильтром, с поправкой на похожесть
+/// заголовка на запрос поверх порядка поиска.
+pub fn rank_articles(
+    articles: Vec<EnrichedArticle>,
+    content_filter: &ContentFilter,
+    query: &str,
+    title_similarity: f64,
+) -> Vec<EnrichedArticle> {
+    rerank_by_title_similarity(content_filter.apply(articles), query, title_similarity)
+}
+
+/// Переранжирует статьи с учётом похожести заголовка на запрос. Статья на
+/// позиции `i` получает оценку `i - weight * similarity`, где similarity от 0
+/// до 1, поэтому точное совпадение поднимается не больше чем на `weight`
+/// позиций — это тай-брейк, а не замена релевантности API.
+pub fn rerank_by_title_similarity(
+    articles: Vec<EnrichedArticle>,
+    query: &str,
+    weight: f64,
+) -> Vec<EnrichedArticle> {
+    if weight <= 0.0 || articles.len() < 2 {
+        return articles;
+    }
+
+    let query = query.trim().to_lowercase();
+    let mut scored: Vec<(f64, EnrichedArticle)> = articles
+        .into_iter()
+        .enumerate()
+        .map(|(position, article)| {
+            let title = article.basic_info.title.to_lowercase();
+            let similarity = strsim::normalized_levenshtein(&query, &title);
+            (position as f64 - weight * similarity, article)
+        })
+        .collect();
+
+    scored.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+    scored.into_iter().map(|(_, article)| article).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use crate::models::EntityKind;
+    use crate::services::WikidataService;
+    use crate::test_support::titled;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_prefix_only_language() {
+        assert_eq!(
+            prefix_only_language("en:", SupportedLanguage::Russian),
+            Some(SupportedLanguage::English)
+        );
+        assert_eq!(
+            prefix_only_language(" de:  ", SupportedLanguage::Russian),
+            Some(SupportedLanguage::German)
+        );
+        assert_eq!(
+            prefix_only_language("wikt:", SupportedLanguage::English),
+            Some(SupportedLanguage::English)
+        );
+        assert_eq!(
+            prefix_only_language("wikt:fr:", SupportedLanguage::Russian),
+            Some(SupportedLanguage::French)
+        );
+        assert_eq!(
+            prefix_only_language("en:Einstein", SupportedLanguage::Russian),
+            None
+        );
+        assert_eq!(
+            prefix_only_language("Пушкин", SupportedLanguage::Russian),
+            None
+        );
+    }
+
+    fn titles(articles: &[EnrichedArticle]) -> Vec<&str> {
+        articles
+            .iter()
+            .map(|article| article.basic_info.title.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_rerank_promotes_exact_title_over_near_miss() {
+        let articles = titled(&["Париж (значения)", "Париж", "Парижская коммуна"]);
+        let reranked = rerank_by_title_similarity(articles, "париж", 1.5);
+        assert_eq!(
+            titles(&reranked),
+            vec!["Париж", "Париж (значения)", "Парижская коммуна"]
+        );
+    }
+
+    #[test]
+    fn test_rerank_does_not_override_strong_relevance() {
+        let articles = titled(&["Альфа", "Бета", "Гамма", "Дельта", "Эпсилон", "Марс"]);
+        let reranked = rerank_by_title_similarity(articles, "Марс", 1.5);
+        assert_eq!(titles(&reranked)[0], "Альфа");
+        assert_eq!(titles(&reranked)[4], "Марс");
+
+        let articles = titled(&["Париж (значения)", "Париж"]);
+        let reranked = rerank_by_title_similarity(articles, "Париж", 0.0);
+        assert_eq!(titles(&reranked), vec!["Париж (значения)", "Париж"]);
+    }
+
+    #[tokio::test]
+    async fn test_wikidata_is_skipped_when_disabled() {
+        let mut wikipedia_server = mockito::Server::new_async().await;
+        let mut wikidata_server = mockito::Server::new_async().await;
+
+        wikipedia_server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::Any)
+            .with_body(
+                r#"{
+                    "query": {
+                        "pages": {
+                            "1": {
+                                "pageid": 1,
+                                "title": "Article",
+                                "index": 1,
+                                "extract": "Article extract.",
+                                "pageprops": {"wikibase_item": "Q1"}
+                            }
+                        }
+                    }
+                }"#,
+            )
+            .create_async()
+            .await;
+
+        let wikidata_mock = wikidata_server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::Any)
+            .with_body(r#"{"entities": {}}"#)
+            .expect(0)
+            .create_async()
+            .await;
+
+        let mut config = AppConfig::for_tests();
+        config.wikidata.enabled = false;
+
+        let wikipedia_service = WikipediaService::new(config.clone())
+            .unwrap()
+            .with_api_base(wikipedia_server.url());
+        let wikidata_service = WikidataService::new(config)
+            .unwrap()
+            .with_api_base(wikidata_server.url());
+
+        let articles = search_enriched_articles(
+            &wikipedia_service,
+            &EnrichmentPipeline::from_wikidata(Arc::new(wikidata_service)),
+            "Article",
+            SupportedLanguage::English,
+        )
+        .await
+        .unwrap();
+
+        wikidata_mock.assert_async().await;
+        assert_eq!(articles.len(), 1);
+        assert!(articles[0].wikidata_description.is_none());
+        assert!(articles[0].entity_kind.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_wikidata_requests_overlap() {
+        const DELAY: std::time::Duration = std::time::Duration::from_millis(400);
+
+        let mut wikipedia_server = mockito::Server::new_async().await;
+        let mut wikidata_server = mockito::Server::new_async().await;
+
+        wikipedia_server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::Any)
+            .with_body(
+                r#"{
+                    "query": {
+                        "pages": {
+                            "1": {
+                                "pageid": 1,
+                                "title": "Douglas Adams",
+                                "index": 1,
+                                "extract": "English writer.",
+                                "pageprops": {"wikibase_item": "Q42"}
+                            }
+                        }
+                    }
+                }"#,
+            )
+            .create_async()
+            .await;
+
+        let descriptions_mock = wikidata_server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "props".into(),
+                "descriptions".into(),
+            ))
+            .with_chunked_body(|writer| {
+                std::thread::sleep(DELAY);
+                writer.write_all(
+                    br#"{"entities": {"Q42": {"descriptions": {"en": {"language": "en", "value": "English writer"}}}}}"#,
+                )
+            })
+            .expect(1)
+            .create_async()
+            .await;
+
+        let claims_mock = wikidata_server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "props".into(),
+                "claims".into(),
+            ))
+            .with_chunked_body(|writer| {
+                std::thread::sleep(DELAY);
+                writer.write_all(
+                    br#"{"entities": {"Q42": {"claims": {"P31": [{"mainsnak": {"datavalue": {"value": {"id": "Q5"}}}}]}}}}"#,
+                )
+            })
+            .expect(1)
+            .create_async()
+            .await;
+
+        let config = AppConfig::for_tests();
+        let wikipedia_service = WikipediaService::new(config.clone())
+            .unwrap()
+            .with_api_base(wikipedia_server.url());
+        let wikidata_service = WikidataService::new(config)
+            .unwrap()
+            .with_api_base(wikidata_server.url());
+
+        let started = std::time::Instant::now();
+        let articles = search_enriched_articles(
+            &wikipedia_service,
+            &EnrichmentPipeline::from_wikidata(Arc::new(wikidata_service)),
+            "Douglas Adams",
+            SupportedLanguage::English,
+        )
+        .await
+        .unwrap();
+        let elapsed = started.elapsed();
+
+        descriptions_mock.assert_async().await;
+        claims_mock.assert_async().await;
+        assert_eq!(
+            articles[0].wikidata_description.as_deref(),
+            Some("English writer")
+        );
+        assert_eq!(articles[0].entity_kind, Some(EntityKind::Person));
+        // Последовательные запросы заняли бы не меньше 2 * DELAY.
+        assert!(
+            elapsed < DELAY * 2,
+            "Wikidata requests did not overlap: {elapsed:?}"
+        );
+    }
+
+    async fn mock_wikipedia_search(
+        server: &mut mockito::ServerGuard,
+        query: &str,
+        title: Option<&str>,
+    ) -> mockito::Mock {
+        let body = match title {
+            Some(title) => format!(
+                r#"{{"query": {{"pages": {{"1": {{"pageid": 1, "title": "{title}", "index": 1, "extract": "Extract."}}}}}}}}"#
+            ),
+            None => r#"{"batchcomplete": ""}"#.to_string(),
+        };
+
+        server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "gsrsearch".into(),
+                query.into(),
+            ))
+            .with_body(body)
+            .create_async()
+            .await
+    }
+
+    async fn mock_empty_legacy_search(server: &mut mockito::ServerGuard, query: &str) {
+        server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "srsearch".into(),
+                query.into(),
+            ))
+            .with_body(r#"{"query": {"search": []}}"#)
+            .create_async()
+            .await;
+    }
+
+    fn services_for(server: &mockito::ServerGuard) -> (WikipediaService, WikidataService) {
+        let mut config = AppConfig::for_tests();
+        config.wikidata.enabled = false;
+        config.wikipedia.transliterate_latin = true;
+
+        (
+            WikipediaService::new(config.clone())
+                .unwrap()
+                .with_api_base(server.url()),
+            WikidataService::new(config).unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_no_results_are_not_searched_again() {
+        let mut server = mockito::Server::new_async().await;
+        // Сервис сам уходит на обычный поиск после 503 — и только один раз
+        let unified = server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "generator".into(),
+                "search".into(),
+            ))
+            .with_status(503)
+            .expect(1)
+            .create_async()
+            .await;
+        let legacy = server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded("list".into(), "search".into()))
+            .with_body(r#"{"query": {"search": []}}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let mut config = AppConfig::for_tests();
+        config.retry.max_attempts = 1;
+        let wikipedia_service = WikipediaService::new(config)
+            .unwrap()
+            .with_api_base(server.url());
+
+        let result = search_enriched_articles(
+            &wikipedia_service,
+            &EnrichmentPipeline::new(),
+            "Qwxzv",
+            SupportedLanguage::English,
+        )
+        .await;
+
+        assert!(matches!(result, Err(WikiError::NoResults { .. })));
+        unified.assert_async().await;
+        legacy.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_is_not_retried_through_legacy_search() {
+        let mut server = mockito::Server::new_async().await;
+        let unified = server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "generator".into(),
+                "search".into(),
+            ))
+            .with_status(429)
+            .expect(1)
+            .create_async()
+            .await;
+        let legacy = server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded("list".into(), "search".into()))
+            .expect(0)
+            .create_async()
+            .await;
+
+        let mut config = AppConfig::for_tests();
+        config.retry.max_attempts = 1;
+        let wikipedia_service = WikipediaService::new(config)
+            .unwrap()
+            .with_api_base(server.url());
+
+        let result = search_enriched_articles(
+            &wikipedia_service,
+            &EnrichmentPipeline::new(),
+            "Einstein",
+            SupportedLanguage::English,
+        )
+        .await;
+
+        assert!(matches!(result, Err(WikiError::RateLimited { .. })));
+        unified.assert_async().await;
+        legacy.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_translit_fallback_fires_on_empty_results() {
+        let mut server = mockito::Server::new_async().await;
+        let latin = mock_wikipedia_search(&mut server, "Pushkin", None).await;
+        mock_empty_legacy_search(&mut server, "Pushkin").await;
+        let cyrillic =
+            mock_wikipedia_search(&mut server, "Пушкин", Some("Пушкин, Александр Сергеевич")).await;
+
+        let (wikipedia_service, wikidata_service) = services_for(&server);
+        let result = search_user_query(
+            &wikipedia_service,
+            &EnrichmentPipeline::from_wikidata(Arc::new(wikidata_service)),
+            "Pushkin",
+        )
+        .await
+        .unwrap();
+
+        latin.assert_async().await;
+        cyrillic.assert_async().await;
+        assert_eq!(
+            result.articles[0].basic_info.title,
+            "Пушкин, Александр Сергеевич"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_translit_fallback_skipped_when_primary_has_results() {
+        let mut server = mockito::Server::new_async().await;
+        mock_wikipedia_search(&mut server, "Pushkin", Some("Pushkin (band)")).await;
+        let cyrillic = mock_wikipedia_search(&mut server, "Пушкин", Some("Пушкин"))
+            .await
+            .expect(0);
+
+        let (wikipedia_service, wikidata_service) = services_for(&server);
+        let result = search_user_query(
+            &wikipedia_service,
+            &EnrichmentPipeline::from_wikidata(Arc::new(wikidata_service)),
+            "Pushkin",
+        )
+        .await
+        .unwrap();
+
+        cyrillic.assert_async().await;
+        assert_eq!(result.articles[0].basic_info.title, "Pushkin (band)");
+    }
+
+    #[tokio::test]
+    async fn test_translit_fallback_skipped_for_other_languages() {
+        let mut server = mockito::Server::new_async().await;
+        mock_wikipedia_search(&mut server, "Pushkin", None).await;
+        mock_empty_legacy_search(&mut server, "Pushkin").await;
+        let cyrillic = mock_wikipedia_search(&mut server, "Пушкин", Some("Пушкин"))
+            .await
+            .expect(0);
+
+        let (wikipedia_service, wikidata_service) = services_for(&server);
+        let result = search_user_query(
+            &wikipedia_service,
+            &EnrichmentPipeline::from_wikidata(Arc::new(wikidata_service)),
+            "en:Pushkin",
+        )
+        .await
+        .unwrap();
+
+        cyrillic.assert_async().await;
+        assert!(result.articles.is_empty());
+    }
+
+    #[test]
+    fn test_definition_word() {
+        assert_eq!(definition_word("ru:слово", "слово"), Some("слово"));
+        assert_eq!(
+            definition_word(" Serendipity ", " Serendipity "),
+            Some("Serendipity")
+        );
+        assert_eq!(definition_word("Albert Einstein", "Albert Einstein"), None);
+        assert_eq!(definition_word("cat:Физики", "cat:Физики"), None);
+        assert_eq!(definition_word("wikt:word", "word"), None);
+        assert_eq!(definition_word("я", "я"), None);
+    }
+
+    #[test]
+    fn test_parse_contents_query() {
+        assert_eq!(
+            parse_contents_query("toc:Пушкин", SupportedLanguage::Russian),
+            Some((SupportedLanguage::Russian, "Пушкин".to_string()))
+        );
+        assert_eq!(
+            parse_contents_query("en:toc: Albert Einstein", SupportedLanguage::Russian),
+            Some((SupportedLanguage::English, "Albert Einstein".to_string()))
+        );
+        assert_eq!(
+            parse_contents_query("toc:", SupportedLanguage::Russian),
+            None
+        );
+        assert_eq!(
+            parse_contents_query("Пушкин", SupportedLanguage::Russian),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_wikt_prefix_routes_to_wiktionary() {
+        let mut server = mockito::Server::new_async().await;
+        let wikipedia = server
+            .mock(
+                "GET",
+                mockito::Matcher::Regex(r"^/wikipedia\.org/".to_string()),
+            )
+            .expect(0)
+            .create_async()
+            .await;
+        let wiktionary = server
+            .mock("GET", "/wiktionary.org/en/w/api.php")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("gsrsearch".into(), "word".into()),
+                mockito::Matcher::UrlEncoded("prop".into(), "extracts|pageprops|categories".into()),
+            ]))
+            .with_body(
+                r#"{"query": {"pages": {"1": {"pageid": 1, "title": "word", "index": 1, "extract": "A unit of language."}}}}"#,
+            )
+            .create_async()
+            .await;
+
+        let (wikipedia_service, wikidata_service) = services_for(&server);
+        let api_base = format!("{}/{{project}}/{{lang}}", server.url());
+        let wikipedia_service = wikipedia_service.with_api_base(api_base.clone());
+        let wiktionary_service = services_for(&server)
+            .0
+            .with_api_base(api_base)
+            .with_project(WikiProject::Wiktionary);
+
+        let result = search_project_query(
+            &wikipedia_service,
+            &wiktionary_service,
+            &EnrichmentPipeline::from_wikidata(Arc::new(wikidata_service)),
+            "wikt:en:word",
+        )
+        .await
+        .unwrap();
+
+        wikipedia.assert_async().await;
+        wiktionary.assert_async().await;
+        assert_eq!(result.language, SupportedLanguage::English);
+        assert_eq!(result.articles.len(), 1);
+        assert_eq!(
+            result.articles[0].article_url,
+            "https://en.wiktionary.org/wiki/word"
+        );
+        assert!(result.articles[0].image_url().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_search_top_parses_prefix_and_ranks_like_inline_results() {
+        let mut server = mockito::Server::new_async().await;
+        // Подсказки, счётчики и интервики для одной статьи не нужны
+        let extra = server
+            .mock("GET", mockito::Matcher::Any)
+            .expect(0)
+            .create_async()
+            .await;
+        // Поиск ставит точное совпадение вторым, переранжирование — первым
+        let german = server
+            .mock("GET", "/de/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "gsrsearch".into(),
+                "Einstein".into(),
+            ))
+            .with_body(
+                r#"{"query": {"pages": {
+                    "1": {"pageid": 1, "title": "Relativitätstheorie", "index": 1, "extract": "Theorie."},
+                    "2": {"pageid": 2, "title": "Einstein", "index": 2, "extract": "Physiker."}
+                }}}"#,
+            )
+            .create_async()
+            .await;
+
+        let mut config = AppConfig::for_tests();
+        config.wikidata.enabled = false;
+        let wikipedia_service = WikipediaService::new(config.clone())
+            .unwrap()
+            .with_api_base(format!("{}/{{lang}}", server.url()));
+        let wiktionary_service = WikipediaService::new(config.clone())
+            .unwrap()
+            .with_project(WikiProject::Wiktionary);
+        let content_filter = ContentFilter::from_config(&config.wikipedia);
+
+        let top = search_top(
+            &wikipedia_service,
+            &wiktionary_service,
+            &EnrichmentPipeline::new(),
+            &content_filter,
+            "de:Einstein",
+        )
+        .await
+        .unwrap()
+        .expect("top article");
+
+        german.assert_async().await;
+        extra.assert_async().await;
+        assert_eq!(top.basic_info.title, "Einstein");
+        assert_eq!(top.article_url, "https://de.wikipedia.org/wiki/Einstein");
+
+        let articles = search_enriched_articles(
+            &wikipedia_service,
+            &EnrichmentPipeline::new(),
+            "Einstein",
+            SupportedLanguage::German,
+        )
+        .await
+        .unwrap();
+        let shown = rank_articles(
+            articles,
+            &content_filter,
+            "Einstein",
+            config.wikipedia.ranking.title_similarity,
+        );
+        assert_eq!(shown[0].basic_info.title, top.basic_info.title);
+    }
+
+    #[tokio::test]
+    async fn test_configured_default_language_applies_to_unprefixed_queries() {
+        let mut server = mockito::Server::new_async().await;
+        let english = server
+            .mock("GET", "/en/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "gsrsearch".into(),
+                "Einstein".into(),
+            ))
+            .with_body(
+                r#"{"query": {"pages": {"736": {"pageid": 736, "title": "Albert Einstein", "index": 1, "extract": "Physicist."}}}}"#,
+            )
+            .create_async()
+            .await;
+
+        let mut config = AppConfig::for_tests();
+        config.wikidata.enabled = false;
+        config.wikipedia.auto_language = false;
+        config.wikipedia.default_language = "en".to_string();
+        let wikipedia_service = WikipediaService::new(config.clone())
+            .unwrap()
+            .with_api_base(format!("{}/{{lang}}", server.url()));
+        let wikidata_service = WikidataService::new(config).unwrap();
+
+        let result = search_user_query(
+            &wikipedia_service,
+            &EnrichmentPipeline::from_wikidata(Arc::new(wikidata_service)),
+            "Einstein",
+        )
+        .await
+        .unwrap();
+
+        english.assert_async().await;
+        assert_eq!(result.language, SupportedLanguage::English);
+        assert_eq!(result.articles[0].basic_info.title, "Albert Einstein");
+    }
+
+    #[tokio::test]
+    async fn test_auto_language_searches_only_detected_edition() {
+        let mut server = mockito::Server::new_async().await;
+        let russian = server
+            .mock("GET", "/ru/w/api.php")
+            .match_query(mockito::Matcher::Any)
+            .with_body(
+                r#"{"query": {"pages": {"1": {"pageid": 1, "title": "Пушкин, Александр Сергеевич", "index": 1, "extract": "Поэт."}}}}"#,
+            )
+            .expect_at_least(1)
+            .create_async()
+            .await;
+        let mut others = Vec::new();
+        for language in SupportedLanguage::popular_languages()
+            .iter()
+            .filter(|language| **language != SupportedLanguage::Russian)
+        {
+            others.push(
+                server
+                    .mock("GET", format!("/{}/w/api.php", language.code()).as_str())
+                    .match_query(mockito::Matcher::Any)
+                    .expect(0)
+                    .create_async()
+                    .await,
+            );
+        }
+
+        let mut config = AppConfig::for_tests();
+        config.wikidata.enabled = false;
+        config.wikipedia.auto_language = true;
+        let wikipedia_service = WikipediaService::new(config.clone())
+            .unwrap()
+            .with_api_base(format!("{}/{{lang}}", server.url()));
+        let wikidata_service = WikidataService::new(config).unwrap();
+
+        let result = search_user_query(
+            &wikipedia_service,
+            &EnrichmentPipeline::from_wikidata(Arc::new(wikidata_service)),
+            "Пушкин",
+        )
+        .await
+        .unwrap();
+
+        russian.assert_async().await;
+        for mock in others {
+            mock.assert_async().await;
+        }
+        assert_eq!(result.language, SupportedLanguage::Russian);
+        assert_eq!(
+            result.articles[0].basic_info.title,
+            "Пушкин, Александр Сергеевич"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fixture_mode_answers_without_network() {
+        let mut config = AppConfig::for_tests();
+        config.wikipedia.fixtures_dir =
+            Some(std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures"));
+        config.wikidata.api_base = Some("http://127.0.0.1:9".to_string());
+
+        let wikipedia_service = WikipediaService::new(config.clone())
+            .unwrap()
+            .with_api_base("http://127.0.0.1:9");
+        let wikidata_service = WikidataService::new(config).unwrap();
+        assert!(wikipedia_service.uses_fixtures());
+
+        let result = search_user_query(
+            &wikipedia_service,
+            &EnrichmentPipeline::from_wikidata(Arc::new(wikidata_service)),
+            "en:Albert Einstein",
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result.language, SupportedLanguage::English);
+        assert_eq!(result.articles.len(), 1);
+        assert_eq!(
+            result.articles[0].get_wikidata_description(),
+            Some("German-born theoretical physicist")
+        );
+        assert!(result.suggestion.is_none());
+    }
+}
@@ -0,0 +1,161 @@
+use teloxide::{
+    payloads::SendMessage,
+    prelude::*,
+    requests::{HasPayload, JsonRequest},
+    types::{InlineQueryResult, InputMessageContent, ParseMode},
+    ApiError, RequestError,
+};
+use tracing::warn;
+
+use crate::utils::{html_to_plain_text, strip_markdown_v2};
+
+/// Текст ошибки Telegram для устаревшего inline-запроса. teloxide распознаёт
+/// его только в точном написании, а Telegram присылает «query ID» в разном
+/// регистре, поэтому сравниваем без учёта регистра.
+const EXPIRED_QUERY_MESSAGE: &str = "query is too old and response timeout expired";
+
+/// Начало текста ошибки Telegram о некорректной разметке. teloxide
+/// распознаёт только текст без подробностей, а Telegram обычно добавляет
+/// позицию ошибки («…: Can't find end of the entity starting at byte offset 12»).
+const PARSE_ENTITIES_MESSAGE: &str = "can't parse entities";
+
+/// Ответ на inline-запрос опоздал: Telegram уже забыл запрос (пользователь
+/// продолжил печатать или сеть была медленной). Такие ошибки ожидаемы и не
+/// требуют внимания.
+pub fn is_expired_inline_query(error: &RequestError) -> bool {
+    match error {
+        RequestError::Api(ApiError::InvalidQueryId) => true,
+        RequestError::Api(ApiError::Unknown(message)) => {
+            message.to_lowercase().contains(EXPIRED_QUERY_MESSAGE)
+        }
+        _ => false,
+    }
+}
+
+/// Telegram не смог разобрать разметку сообщения — значит, где-то ошибка
+/// экранирования.
+pub fn is_parse_entities_error(error: &RequestError) -> bool {
+    match error {
+        RequestError::Api(ApiError::CantParseEntities) => true,
+        RequestError::Api(ApiError::Unknown(message)) => {
+            message.to_lowercase().contains(PARSE_ENTITIES_MESSAGE)
+        }
+        _ => false,
+    }
+}
+
+/// Отправляет сообщение, а если Telegram отклонил его разметку, повторяет
+/// отправку без `parse_mode` с текстом без разметки: пусть пользователь
+/// получит хотя бы простой текст, чем ничего.
+pub async fn send_with_plain_text_fallback(
+    request: JsonRequest<SendMessage>,
+) -> ResponseResult<Message> {
+    let mut fallback = request.clone();
+    match request.send().await {
+        Err(e) if is_parse_entities_error(&e) && fallback.payload_ref().parse_mode.is_some() => {
+            warn!(
+                "⚠️ Telegram не разобрал разметку, отправляю простым текстом: {}",
+                e
+            );
+            let payload = fallback.payload_mut();
+            payload.text = to_plain_text(&payload.text, payload.parse_mode.take());
+            fallback.send().await
+        }
+        result => result,
+    }
+}
+
+/// Те же inline-результаты, но с текстом сообщений без разметки — для
+/// повторного ответа, если Telegram отклонил разметку одного из них.
+pub fn plain_text_inline_results(results: Vec<InlineQueryResult>) -> Vec<InlineQueryResult> {
+    results
+        .into_iter()
+        .map(|mut result| {
+            if let InlineQueryResult::Article(article) = &mut result {
+                if let InputMessageContent::Text(content) = &mut article.input_message_content {
+                    content.message_text =
+                        to_plain_text(&content.message_text, content.parse_mode.take());
+                }
+            }
+            result
+        })
+        .collect()
+}
+
+fn to_plain_text(text: &str, parse_mode: Option<ParseMode>) -> String {
+    match parse_mode {
+        Some(ParseMode::Html) => html_to_plain_text(text),
+        Some(_) => strip_markdown_v2(text),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expired_inline_query_is_recognized() {
+        assert!(is_expired_inline_query(&RequestError::Api(
+            ApiError::InvalidQueryId
+        )));
+        assert!(is_expired_inline_query(&RequestError::Api(
+            ApiError::Unknown(
+                "Bad Request: query is too old and response timeout expired or query ID is invalid"
+                    .to_string()
+            )
+        )));
+
+        assert!(!is_expired_inline_query(&RequestError::Api(
+            ApiError::MessageNotModified
+        )));
+        assert!(!is_expired_inline_query(&RequestError::RetryAfter(
+            std::time::Duration::from_secs(5)
+        )));
+    }
+
+    #[test]
+    fn test_parse_entities_error_is_recognized() {
+        assert!(is_parse_entities_error(&RequestError::Api(
+            ApiError::CantParseEntities
+        )));
+        assert!(is_parse_entities_error(&RequestError::Api(
+            ApiError::Unknown(
+                "Bad Request: can't parse entities: Character '.' is reserved and must be escaped \
+             with the preceding '\\'"
+                    .to_string()
+            )
+        )));
+
+        assert!(!is_parse_entities_error(&RequestError::Api(
+            ApiError::MessageTextIsEmpty
+        )));
+        assert!(!is_parse_entities_error(&RequestError::Api(
+            ApiError::Unknown("Bad Request: chat not found".to_string())
+        )));
+    }
+
+    #[test]
+    fn test_plain_text_inline_results() {
+        use teloxide::types::{InlineQueryResultArticle, InputMessageContentText};
+
+        let article = InlineQueryResultArticle::new(
+            "1",
+            "Einstein",
+            InputMessageContent::Text(
+                InputMessageContentText::new("*Albert Einstein*\\. Physicist")
+                    .parse_mode(ParseMode::MarkdownV2),
+            ),
+        );
+        let results = plain_text_inline_results(vec![InlineQueryResult::Article(article)]);
+
+        let InlineQueryResult::Article(article) = &results[0] else {
+            panic!("expected an article");
+        };
+        let InputMessageContent::Text(content) = &article.input_message_content else {
+            panic!("expected text content");
+        };
+        assert_eq!(content.message_text, "Albert Einstein. Physicist");
+        assert_eq!(content.parse_mode, None);
+    }
+}
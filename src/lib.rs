@@ -5,11 +5,15 @@ pub mod models;
 pub mod services;
 pub mod utils;
 
+#[cfg(test)]
+mod test_support;
+
 pub use config::AppConfig;
 pub use errors::{UserFriendlyError, WikiError, WikiResult};
 pub use handlers::*;
 pub use models::*;
 pub use services::*;
+pub use utils::BackgroundTasks;
 
 pub fn init_logging(config: &config::LoggingConfig) -> Result<(), WikiError> {
     use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
@@ -72,18 +76,92 @@ pub fn init_logging(config: &config::LoggingConfig) -> Result<(), WikiError> {
 }
 
 pub fn create_services(config: AppConfig) -> WikiResult<(WikipediaService, WikidataService)> {
+    if !config.wikidata.enabled {
+        tracing::info!("ℹ️ Обогащение через Wikidata выключено (ENABLE_WIKIDATA=false)");
+    }
+
     let wikipedia_service = WikipediaService::new(config.clone())?;
     let wikidata_service = WikidataService::new(config)?;
 
     Ok((wikipedia_service, wikidata_service))
 }
 
+/// Сервис поиска по Викисловарю (запросы с префиксом `wikt:`).
+pub fn create_wiktionary_service(config: AppConfig) -> WikiResult<WikipediaService> {
+    Ok(WikipediaService::new(config)?.with_project(config::projects::WikiProject::Wiktionary))
+}
+
+/// Режим `--check-config`: собирает сервисы из конфигурации (HTTP-клиент,
+/// прокси, базовые адреса API) и один раз обращается к Wikipedia. Возвращает
+/// текстовую сводку для вывода в консоль.
+pub async fn check_config(config: AppConfig) -> WikiResult<String> {
+    let (wikipedia_service, wikidata_service) = create_services(config.clone())?;
+    create_wiktionary_service(config.clone())?;
+
+    let language = config.wikipedia.default_language();
+    wikipedia_service.probe(language).await?;
+
+    let mut summary = vec![
+        format!("✅ Конфигурация корректна (v{})", env!("CARGO_PKG_VERSION")),
+        format!(
+            "🔑 Токен бота: задан ({} символов)",
+            config.telegram.bot_token.len()
+        ),
+        format!("📡 API доступен: {}", wikipedia_service.api_url(language)),
+        format!(
+            "🧩 Wikidata: {}",
+            if wikidata_service.is_enabled() {
+                "включена"
+            } else {
+                "выключена"
+            }
+        ),
+    ];
+    if wikipedia_service.uses_fixtures() {
+        summary.push("🧪 Ответы берутся из фикстур, сеть не проверялась".to_string());
+    }
+
+    Ok(summary.join("\n"))
+}
+
 pub fn create_handlers(
     wikipedia_service: std::sync::Arc<WikipediaService>,
+    wiktionary_service: std::sync::Arc<WikipediaService>,
     wikidata_service: std::sync::Arc<WikidataService>,
+    telegram_config: config::TelegramConfig,
 ) -> (InlineQueryHandler, MessageHandler) {
-    let inline_handler = InlineQueryHandler::new(wikipedia_service, wikidata_service);
-    let message_handler = MessageHandler::new();
+    let trending = TrendingQueries::from_config(&telegram_config).map(std::sync::Arc::new);
+    let history = SearchHistory::from_config(&telegram_config).map(std::sync::Arc::new);
+
+    let mut inline_handler = InlineQueryHandler::new(
+        std::sync::Arc::clone(&wikipedia_service),
+        std::sync::Arc::clone(&wiktionary_service),
+        std::sync::Arc::clone(&wikidata_service),
+        telegram_config.clone(),
+    );
+    let mut message_handler = MessageHandler::new(
+        wikipedia_service,
+        wiktionary_service,
+        wikidata_service,
+        telegram_config,
+    );
+
+    // Inline-режим пополняет счётчик, /trending его читает
+    if let Some(trending) = trending {
+        inline_handler = inline_handler.with_trending(std::sync::Arc::clone(&trending));
+        message_handler = message_handler.with_trending(trending);
+    }
+
+    // Inline-режим пишет историю и показывает её, /clearhistory очищает
+    if let Some(history) = history {
+        inline_handler = inline_handler.with_history(std::sync::Arc::clone(&history));
+        message_handler = message_handler.with_history(history);
+    }
+
+    // /style сохраняет выбор пользователя, inline-режим по нему оформляет результаты
+    let preferences = std::sync::Arc::new(UserPreferences::new());
+    let inline_handler = inline_handler.with_preferences(std::sync::Arc::clone(&preferences));
+    let message_handler = message_handler.with_preferences(preferences);
 
     (inline_handler, message_handler)
 }
@@ -3,8 +3,9 @@ use teloxide::{dispatching::Dispatcher, prelude::*};
 use tracing::{error, info};
 
 use wiki_article_finder_telegram::{
-    create_handlers, create_services, init_logging, inline_query_handler, AppConfig,
-    InlineQueryHandler, MessageHandler, WikiError,
+    check_config, create_handlers, create_services, create_wiktionary_service, init_logging,
+    inline_query_handler, AppConfig, BackgroundTasks, InlineQueryHandler, MessageHandler,
+    WikiError,
 };
 
 fn create_dispatcher(
@@ -47,6 +48,24 @@ fn create_dispatcher(
 async fn main() -> Result<(), WikiError> {
     dotenv::dotenv().ok();
 
+    if std::env::args().any(|arg| arg == "--check-config") {
+        // Проверка конфигурации без запуска бота: код выхода 0 или 1 для CI/CD
+        let result = match AppConfig::from_env() {
+            Ok(config) => check_config(config).await,
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(summary) => {
+                println!("{summary}");
+                std::process::exit(0);
+            }
+            Err(e) => {
+                eprintln!("❌ Ошибка конфигурации: {e}");
+                std::process::exit(1);
+            }
+        }
+    }
+
     let config = AppConfig::from_env()?;
 
     init_logging(&config.logging)?;
@@ -56,13 +75,21 @@ async fn main() -> Result<(), WikiError> {
         env!("CARGO_PKG_VERSION")
     );
 
+    // Общий реестр фоновых задач: останавливается после завершения диспетчера
+    let tasks = BackgroundTasks::new();
+
     let (wikipedia_service, wikidata_service) = create_services(config.clone())?;
-    let wikipedia_service = Arc::new(wikipedia_service);
+    let wikipedia_service = Arc::new(wikipedia_service.with_tasks(tasks.clone()));
+    wikipedia_service.spawn_cache_warmup();
+    let wiktionary_service =
+        Arc::new(create_wiktionary_service(config.clone())?.with_tasks(tasks.clone()));
     let wikidata_service = Arc::new(wikidata_service);
 
     let (inline_handler, message_handler) = create_handlers(
         Arc::clone(&wikipedia_service),
+        wiktionary_service,
         Arc::clone(&wikidata_service),
+        config.telegram.clone(),
     );
     let inline_handler = Arc::new(inline_handler);
     let message_handler = Arc::new(message_handler);
@@ -73,5 +100,8 @@ async fn main() -> Result<(), WikiError> {
 
     dispatcher.dispatch().await;
 
+    info!("Stopping background tasks");
+    tasks.shutdown().await;
+
     Ok(())
 }
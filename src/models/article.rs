@@ -2,6 +2,23 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use url::Url;
 
+use super::{ClaimValue, EntityKind, InfoboxFacts, InterwikiHit};
+use crate::config::languages::SupportedLanguage;
+use crate::config::{Branding, DescriptionSource, ExtractFormat};
+use crate::utils::{
+    escape_html, format_article_description_subtitled, format_article_markup_html_subtitled,
+    html_to_plain_text, sanitize_telegram_html, truncate_for_telegram,
+};
+
+/// Длина краткого описания статьи в списке inline-результатов.
+pub const SUMMARY_LENGTH: usize = 100;
+
+/// Длина текста статьи в отправляемом сообщении.
+pub const CONTENT_LENGTH: usize = 300;
+
+/// Хосты, миниатюры с которых Telegram принимает без проблем.
+const IMAGE_HOST_SUFFIXES: &[&str] = &[".wikimedia.org", ".wikipedia.org"];
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WikipediaSearchItem {
     pub title: String,
@@ -15,15 +32,101 @@ pub struct WikipediaSearchItem {
     pub timestamp: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ArticleBatchInfo {
     pub image_url: Option<String>,
+    #[serde(default)]
+    pub image_width: Option<u32>,
+    #[serde(default)]
+    pub image_height: Option<u32>,
     pub extract: Option<String>,
+    /// Исходный HTML текста статьи, если он запрошен
+    /// (`ExtractFormat::Html`); `extract` при этом — его текстовая версия.
+    #[serde(default)]
+    pub extract_html: Option<String>,
+    /// Краткое описание статьи из её `pageprops` (`wikibase-shortdesc`),
+    /// уже на языке раздела.
+    #[serde(default)]
+    pub short_description: Option<String>,
     pub wikidata_id: Option<String>,
     #[serde(default)]
     pub coordinates: Option<Coordinates>,
     #[serde(default)]
     pub categories: Vec<String>,
+    /// Лучшая оценка качества статьи по проектам (`prop=pageassessments`).
+    #[serde(default)]
+    pub quality: Option<QualityClass>,
+    /// `extract` уже ограничен API целыми предложениями (`exsentences`),
+    /// и обрезать его повторно не нужно.
+    #[serde(default)]
+    pub sentence_bounded: bool,
+    /// Крупное изображение статьи из `piprop=original` для фото-результатов.
+    #[serde(default)]
+    pub photo: Option<ArticlePhoto>,
+}
+
+/// Изображение статьи в размере, подходящем для отправки фото.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArticlePhoto {
+    pub url: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Класс качества статьи из оценок вики-проектов (английская Википедия и
+/// разделы с расширением PageAssessments).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum QualityClass {
+    Stub,
+    Start,
+    C,
+    B,
+    A,
+    Good,
+    FeaturedList,
+    Featured,
+}
+
+impl QualityClass {
+    pub fn from_class(class: &str) -> Option<Self> {
+        match class.trim().to_lowercase().as_str() {
+            "fa" => Some(Self::Featured),
+            "fl" => Some(Self::FeaturedList),
+            "ga" => Some(Self::Good),
+            "a" => Some(Self::A),
+            "b" => Some(Self::B),
+            "c" => Some(Self::C),
+            "start" => Some(Self::Start),
+            "stub" => Some(Self::Stub),
+            _ => None,
+        }
+    }
+
+    /// Вклад класса в оценку статьи, от 0 (заготовка) до 1 (избранная).
+    pub fn score(self) -> f64 {
+        match self {
+            Self::Featured => 1.0,
+            Self::FeaturedList => 0.9,
+            Self::Good => 0.7,
+            Self::A => 0.6,
+            Self::B => 0.3,
+            Self::C => 0.15,
+            Self::Start => 0.05,
+            Self::Stub => 0.0,
+        }
+    }
+}
+
+/// Лучший класс из `pageassessments`: объекта «проект → {class, importance}».
+/// Проекты без класса и непонятные значения (в том числе пустой массив,
+/// которым API отвечает при отсутствии оценок) пропускаются.
+pub fn quality_from_assessments(assessments: Option<&serde_json::Value>) -> Option<QualityClass> {
+    assessments?
+        .as_object()?
+        .values()
+        .filter_map(|assessment| assessment.get("class")?.as_str())
+        .filter_map(QualityClass::from_class)
+        .max()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -39,6 +142,12 @@ pub struct EnrichedArticle {
     pub wikidata_description: Option<String>,
     pub article_url: String,
     pub relevance_index: Option<i32>,
+    pub entity_kind: Option<EntityKind>,
+    /// Раздел, в котором статья нашлась, если поиск ушёл по цепочке
+    /// `fallback_languages`.
+    pub source_language: Option<SupportedLanguage>,
+    /// Факты из карточки статьи, если их запрашивали (`get_infobox`).
+    pub infobox: Option<InfoboxFacts>,
 }
 
 impl EnrichedArticle {
@@ -54,29 +163,121 @@ impl EnrichedArticle {
             wikidata_description,
             article_url,
             relevance_index: None,
+            entity_kind: None,
+            source_language: None,
+            infobox: None,
         }
     }
 
-    pub fn best_description(&self, max_length: usize) -> String {
-        // Всегда используем текст статьи для краткого описания для консистентности
-        // Wikidata описание доступно через self.wikidata_description если нужно отдельно
-        if let Some(ref batch_info) = self.batch_info {
-            if let Some(ref extract) = batch_info.extract {
-                if !extract.trim().is_empty() {
-                    return truncate_string(extract, max_length);
-                }
-            }
-        }
+    /// Краткое описание для списка результатов. Порядок задаёт `source`; по
+    /// умолчанию это описание из `pageprops`, затем текст статьи, описание
+    /// Wikidata и snippet поиска.
+    pub fn best_description(&self, max_length: usize, source: DescriptionSource) -> String {
+        let batch_info = self.batch_info.as_ref();
+        let short_description = batch_info.and_then(|info| info.short_description.as_deref());
+        let extract = batch_info.and_then(|info| info.extract.as_deref());
+        let wikidata = self.wikidata_description.as_deref();
+        let snippet = Some(self.basic_info.snippet.as_str());
 
-        // Fallback на snippet из search API
-        if !self.basic_info.snippet.trim().is_empty() {
-            return truncate_string(&self.basic_info.snippet, max_length);
+        let candidates = match source {
+            DescriptionSource::ShortDescFirst => [short_description, extract, wikidata, snippet],
+            DescriptionSource::ExtractFirst => [extract, short_description, wikidata, snippet],
+            DescriptionSource::WikidataFirst => [wikidata, short_description, extract, snippet],
+        };
+
+        if let Some(description) = candidates
+            .into_iter()
+            .flatten()
+            .find(|text| !text.trim().is_empty())
+        {
+            let sentence_bounded = batch_info.is_some_and(|info| info.sentence_bounded);
+            if sentence_bounded && Some(description) == extract {
+                return description.to_string();
+            }
+            return truncate_string(description, max_length);
         }
 
         // Последний fallback - название статьи
         format!("Статья из Википедии: {}", self.basic_info.title)
     }
 
+    /// Карточка статьи в MarkdownV2: заголовок, текст до `max_content`
+    /// символов и ссылка на статью. При `show_wikidata_subtitle` под
+    /// заголовком курсивом идёт описание Wikidata: короткая строка дополняет
+    /// текст статьи, а не заменяет его.
+    pub fn to_markdown(&self, max_content: usize, branding: &Branding) -> String {
+        format_article_description_subtitled(
+            &self.basic_info.title,
+            self.subtitle(branding),
+            &self.best_content(max_content),
+            &self.article_url,
+            branding,
+        )
+    }
+
+    /// Описание Wikidata для подзаголовка карточки, если он включён.
+    fn subtitle(&self, branding: &Branding) -> Option<&str> {
+        self.wikidata_description
+            .as_deref()
+            .map(str::trim)
+            .filter(|description| branding.show_wikidata_subtitle && !description.is_empty())
+    }
+
+    /// Та же карточка для `ParseMode::Html`. Если текст статьи получен в HTML,
+    /// в карточке остаётся его разрешённое Telegram форматирование.
+    pub fn to_html(&self, max_content: usize, branding: &Branding) -> String {
+        let description_html = self
+            .batch_info
+            .as_ref()
+            .and_then(|info| info.extract_html.as_deref())
+            .filter(|html| !html.trim().is_empty())
+            .map_or_else(
+                || escape_html(&self.best_content(max_content)),
+                |html| sanitize_telegram_html(html, max_content),
+            );
+
+        format_article_markup_html_subtitled(
+            &self.basic_info.title,
+            self.subtitle(branding),
+            &description_html,
+            &self.article_url,
+            branding,
+        )
+    }
+
+    /// Карточка для сообщения Telegram не длиннее `limit` символов в разметке
+    /// [`ExtractFormat::parse_mode`]: HTML, если текст статей запрашивается в
+    /// HTML, иначе MarkdownV2.
+    pub fn to_card(
+        &self,
+        max_content: usize,
+        branding: &Branding,
+        format: ExtractFormat,
+        limit: usize,
+    ) -> String {
+        match format {
+            ExtractFormat::Plain => {
+                truncate_for_telegram(&self.to_markdown(max_content, branding), limit)
+            }
+            ExtractFormat::Html => {
+                // Лимит Telegram считается по тексту без тегов; HTML нельзя
+                // обрезать как строку, поэтому сокращается сам текст статьи
+                let mut max_content = max_content;
+                loop {
+                    let card = self.to_html(max_content, branding);
+                    let excess = html_to_plain_text(&card)
+                        .chars()
+                        .count()
+                        .saturating_sub(limit);
+                    if excess == 0 || max_content == 0 {
+                        return card;
+                    }
+                    max_content = max_content.saturating_sub(excess);
+                }
+            }
+        }
+    }
+
     /// Получить Wikidata описание если доступно
     pub fn get_wikidata_description(&self) -> Option<&str> {
         self.wikidata_description.as_deref()
@@ -100,8 +301,22 @@ impl EnrichedArticle {
             .and_then(|info| info.image_url.as_deref())
     }
 
+    /// Ссылка на миниатюру, которую можно отдать Telegram, см. [`telegram_image_url`].
     pub fn valid_image_url(&self) -> Option<Url> {
-        self.image_url().and_then(|url| Url::parse(url).ok())
+        telegram_image_url(self.image_url()?)
+    }
+
+    /// Крупное изображение для фото-результата: проверенная так же, как
+    /// миниатюра, ссылка и размеры.
+    pub fn valid_photo(&self) -> Option<(Url, u32, u32)> {
+        let photo = self.batch_info.as_ref()?.photo.as_ref()?;
+        Some((telegram_image_url(&photo.url)?, photo.width, photo.height))
+    }
+
+    /// Размеры миниатюры, если они известны (у изображений из Wikidata их может не быть).
+    pub fn image_dimensions(&self) -> Option<(u32, u32)> {
+        let info = self.batch_info.as_ref()?;
+        Some((info.image_width?, info.image_height?))
     }
 
     pub fn has_coordinates(&self) -> bool {
@@ -111,6 +326,14 @@ impl EnrichedArticle {
             .is_some()
     }
 
+    /// Видимые категории статьи без префикса пространства имён.
+    pub fn topic_categories(&self) -> &[String] {
+        self.batch_info
+            .as_ref()
+            .map(|info| info.categories.as_slice())
+            .unwrap_or_default()
+    }
+
     pub fn word_count(&self) -> Option<u32> {
         self.basic_info.wordcount
     }
@@ -129,26 +352,80 @@ pub struct WikipediaSearchResponse {
 #[derive(Debug, Deserialize)]
 pub struct WikipediaSearchQuery {
     pub search: Vec<WikipediaSearchItem>,
+    #[serde(default)]
+    pub searchinfo: Option<WikipediaSearchInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WikipediaSearchInfo {
+    #[serde(default)]
+    pub totalhits: Option<u64>,
+    /// Исправленный вариант запроса, если MediaWiki считает его опечаткой.
+    #[serde(default)]
+    pub suggestion: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct WikipediaBatchResponse {
+    /// Отсутствует, если в ответе нечего вернуть (например, все страницы
+    /// пакета не существуют) — тогда MediaWiki отдаёт только `batchcomplete`.
+    #[serde(default)]
     pub query: WikipediaBatchQuery,
+    /// Параметры продолжения, если MediaWiki вернул данные не для всех страниц.
+    #[serde(default, rename = "continue")]
+    pub continuation: Option<HashMap<String, serde_json::Value>>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct WikipediaBatchQuery {
+    #[serde(deserialize_with = "deserialize_pages_leniently")]
     pub pages: HashMap<String, WikipediaPageInfo>,
+    /// Исправления написания запрошенных `titles=` («einstein» → «Einstein»).
+    #[serde(default)]
+    pub normalized: Vec<TitleMapping>,
+    /// Перенаправления, по которым прошёл запрос с `redirects=1`.
+    #[serde(default)]
+    pub redirects: Vec<TitleMapping>,
+}
+
+impl WikipediaBatchQuery {
+    /// Заголовок страницы, в которую превратился запрошенный `title` после
+    /// нормализации и перенаправлений.
+    pub fn resolve_title(&self, title: &str) -> String {
+        let mut resolved = title.to_string();
+        for mappings in [&self.normalized, &self.redirects] {
+            if let Some(mapping) = mappings.iter().find(|mapping| mapping.from == resolved) {
+                resolved = mapping.to.clone();
+            }
+        }
+        resolved
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TitleMapping {
+    pub from: String,
+    pub to: String,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct WikipediaPageInfo {
+    /// У несуществующих страниц (`missing`) идентификатора или заголовка может не быть.
+    #[serde(default)]
     pub pageid: u64,
+    #[serde(default)]
     pub title: String,
+    /// Страница не существует: MediaWiki возвращает её для `titles=`/`pageids=`
+    /// с пустым `"missing": ""`.
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    pub missing: bool,
     #[serde(default)]
     pub extract: Option<String>,
     #[serde(default)]
     pub thumbnail: Option<WikipediaThumbnail>,
+    /// Исходное изображение (`piprop=original`) — на случай, если миниатюры нет.
+    #[serde(default)]
+    pub original: Option<WikipediaThumbnail>,
     #[serde(default)]
     pub pageimage: Option<String>,
     #[serde(default)]
@@ -157,6 +434,9 @@ pub struct WikipediaPageInfo {
     pub coordinates: Option<Vec<WikipediaCoordinate>>,
     #[serde(default)]
     pub categories: Option<Vec<WikipediaCategory>>,
+    /// Сырые оценки `prop=pageassessments`, см. [`quality_from_assessments`].
+    #[serde(default)]
+    pub pageassessments: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -166,9 +446,48 @@ pub struct WikipediaThumbnail {
     pub height: u32,
 }
 
+impl WikipediaThumbnail {
+    /// Миниатюра из исходного изображения `piprop=original`: ссылка
+    /// `Special:FilePath` вики, где лежит файл, с шириной не больше `max_width`.
+    /// `None`, если ссылка не ведёт на файл upload.wikimedia.org.
+    pub fn from_original(original: &WikipediaThumbnail, max_width: u32) -> Option<Self> {
+        let url = url::Url::parse(&original.source).ok()?;
+        if url.host_str() != Some("upload.wikimedia.org") {
+            return None;
+        }
+
+        // /{проект}/{вики}/{x}/{xy}/{файл}
+        let segments: Vec<&str> = url.path_segments()?.collect();
+        let [project, wiki, _, _, file] = segments[..] else {
+            return None;
+        };
+        if file.is_empty() {
+            return None;
+        }
+        let host = if wiki == "commons" {
+            "commons.wikimedia.org".to_string()
+        } else {
+            format!("{wiki}.{project}.org")
+        };
+
+        let width = original.width.min(max_width);
+        let height = match original.width {
+            0 => original.height,
+            full => (u64::from(original.height) * u64::from(width) / u64::from(full)) as u32,
+        };
+        Some(Self {
+            source: format!("https://{host}/wiki/Special:FilePath/{file}?width={width}"),
+            width,
+            height,
+        })
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct WikipediaPageProps {
     pub wikibase_item: Option<String>,
+    #[serde(default, rename = "wikibase-shortdesc")]
+    pub short_description: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -180,6 +499,15 @@ pub struct WikipediaCoordinate {
 #[derive(Debug, Deserialize)]
 pub struct WikipediaCategory {
     pub title: String,
+    /// Присутствует у скрытых (служебных) категорий при `clprop=hidden`.
+    #[serde(default)]
+    pub hidden: Option<serde_json::Value>,
+}
+
+impl WikipediaCategory {
+    pub fn is_hidden(&self) -> bool {
+        !matches!(self.hidden, None | Some(serde_json::Value::Bool(false)))
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -190,8 +518,89 @@ pub struct WikidataResponse {
 #[derive(Debug, Deserialize)]
 pub struct WikidataEntity {
     pub descriptions: Option<HashMap<String, WikidataDescription>>,
+    #[serde(default)]
+    pub claims: Option<HashMap<String, Vec<WikidataClaim>>>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct WikidataClaim {
+    pub mainsnak: WikidataSnak,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WikidataSnak {
+    #[serde(default)]
+    pub datavalue: Option<WikidataDataValue>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WikidataDataValue {
+    pub value: serde_json::Value,
+    /// Тип значения: `wikibase-entityid`, `time`, `quantity`, `globecoordinate`…
+    #[serde(default, rename = "type")]
+    pub value_type: Option<String>,
+}
+
+impl WikidataEntity {
+    /// Идентификаторы сущностей, на которые ссылается свойство (например, `P31`).
+    pub fn entity_ids_for(&self, property: &str) -> Vec<String> {
+        self.claims
+            .as_ref()
+            .and_then(|claims| claims.get(property))
+            .map(|claims| {
+                claims
+                    .iter()
+                    .filter_map(|claim| claim.mainsnak.datavalue.as_ref())
+                    .filter_map(|datavalue| datavalue.value.get("id"))
+                    .filter_map(|id| id.as_str())
+                    .map(|id| id.to_string())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Типизированные значения свойства (даты и количества) в порядке
+    /// утверждений. Значения других типов пропускаются.
+    pub fn claim_values(&self, property: &str) -> Vec<ClaimValue> {
+        self.claims
+            .as_ref()
+            .and_then(|claims| claims.get(property))
+            .map(|claims| {
+                claims
+                    .iter()
+                    .filter_map(|claim| claim.mainsnak.datavalue.as_ref())
+                    .filter_map(ClaimValue::from_datavalue)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Координаты из первого утверждения P625 на Земле. Утверждения с другим
+    /// глобусом (Луна, Марс) пропускаются.
+    pub fn earth_coordinates(&self) -> Option<Coordinates> {
+        self.claims
+            .as_ref()?
+            .get("P625")?
+            .iter()
+            .filter_map(|claim| claim.mainsnak.datavalue.as_ref())
+            .find_map(|datavalue| {
+                let value = &datavalue.value;
+                let globe = value.get("globe").and_then(|globe| globe.as_str());
+                if globe.is_some_and(|globe| globe != WIKIDATA_EARTH_GLOBE) {
+                    return None;
+                }
+
+                Some(Coordinates {
+                    lat: value.get("latitude")?.as_f64()?,
+                    lon: value.get("longitude")?.as_f64()?,
+                })
+            })
+    }
+}
+
+/// Глобус Земли в значениях P625.
+const WIKIDATA_EARTH_GLOBE: &str = "http://www.wikidata.org/entity/Q2";
+
 #[derive(Debug, Deserialize)]
 pub struct WikidataDescription {
     pub language: String,
@@ -200,12 +609,24 @@ pub struct WikidataDescription {
 
 #[derive(Debug, Deserialize)]
 pub struct UnifiedWikipediaResponse {
+    /// Отсутствует, если генератор ничего не нашёл.
+    #[serde(default)]
     pub query: UnifiedWikipediaQuery,
+    /// Параметры продолжения, если MediaWiki вернул данные не для всех страниц.
+    #[serde(default, rename = "continue")]
+    pub continuation: Option<HashMap<String, serde_json::Value>>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct UnifiedWikipediaQuery {
+    #[serde(deserialize_with = "deserialize_pages_leniently")]
     pub pages: HashMap<String, UnifiedWikipediaPage>,
+    /// `gsrinfo` генератора поиска; у prefixsearch отсутствует.
+    #[serde(default)]
+    pub searchinfo: Option<WikipediaSearchInfo>,
+    /// Результаты из других проектов Викимедиа (`gsrinterwiki`).
+    #[serde(default)]
+    pub interwiki: Vec<InterwikiHit>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -213,10 +634,15 @@ pub struct UnifiedWikipediaPage {
     pub pageid: u64,
     pub title: String,
     pub index: Option<i32>,
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    pub missing: bool,
     #[serde(default)]
     pub extract: Option<String>,
     #[serde(default)]
     pub thumbnail: Option<WikipediaThumbnail>,
+    /// Исходное изображение (`piprop=original`) — на случай, если миниатюры нет.
+    #[serde(default)]
+    pub original: Option<WikipediaThumbnail>,
     #[serde(default)]
     pub pageimage: Option<String>,
     #[serde(default)]
@@ -225,6 +651,82 @@ pub struct UnifiedWikipediaPage {
     pub coordinates: Option<Vec<WikipediaCoordinate>>,
     #[serde(default)]
     pub categories: Option<Vec<WikipediaCategory>>,
+    /// Сырые оценки `prop=pageassessments`, см. [`quality_from_assessments`].
+    #[serde(default)]
+    pub pageassessments: Option<serde_json::Value>,
+}
+
+/// Ссылка на изображение, которую можно отдать Telegram: только https и
+/// только хосты Викимедиа. `http` повышается до `https`, остальное
+/// отбрасывается — одна неверная миниатюра иначе ломает весь inline-ответ.
+fn telegram_image_url(raw: &str) -> Option<Url> {
+    let mut url = match Url::parse(raw) {
+        Ok(url) => url,
+        Err(e) => {
+            tracing::debug!("🖼️ Изображение отброшено, неверный адрес '{}': {}", raw, e);
+            return None;
+        }
+    };
+
+    match url.scheme() {
+        "https" => {}
+        "http" => {
+            url.set_scheme("https").ok()?;
+            tracing::debug!("🖼️ Изображение переведено на https: {}", url);
+        }
+        scheme => {
+            tracing::debug!("🖼️ Изображение отброшено, схема {}: {}", scheme, raw);
+            return None;
+        }
+    }
+
+    let host = url.host_str().unwrap_or_default();
+    if !IMAGE_HOST_SUFFIXES
+        .iter()
+        .any(|suffix| host.ends_with(suffix))
+    {
+        tracing::debug!("🖼️ Изображение отброшено, хост не из Викимедиа: {}", raw);
+        return None;
+    }
+    Some(url)
+}
+
+/// Разбирает `query.pages` постранично: страница с неожиданным форматом поля
+/// (например, координаты объектом вместо массива) пропускается с
+/// предупреждением, а не ломает весь ответ.
+fn deserialize_pages_leniently<'de, D, T>(deserializer: D) -> Result<HashMap<String, T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: serde::de::DeserializeOwned,
+{
+    let raw_pages = HashMap::<String, serde_json::Value>::deserialize(deserializer)?;
+
+    Ok(raw_pages
+        .into_iter()
+        .filter_map(|(key, value)| match serde_json::from_value(value) {
+            Ok(page) => Some((key, page)),
+            Err(e) => {
+                tracing::warn!(
+                    "⚠️ Пропускаю страницу {} с некорректными данными: {}",
+                    key,
+                    e
+                );
+                None
+            }
+        })
+        .collect())
+}
+
+/// Флаг MediaWiki: в `formatversion=1` это поле с пустой строкой, само
+/// наличие которого означает `true`; в `formatversion=2` — обычный bool.
+fn deserialize_flag<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(!matches!(
+        serde_json::Value::deserialize(deserializer)?,
+        serde_json::Value::Bool(false) | serde_json::Value::Null
+    ))
 }
 
 fn truncate_string(text: &str, max_chars: usize) -> String {
@@ -244,6 +746,7 @@ fn truncate_string(text: &str, max_chars: usize) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support;
 
     #[test]
     fn test_truncate_string() {
@@ -264,11 +767,8 @@ mod tests {
         };
 
         let batch_info = ArticleBatchInfo {
-            image_url: None,
             extract: Some("Better extract".to_string()),
-            wikidata_id: None,
-            coordinates: None,
-            categories: vec![],
+            ..Default::default()
         };
 
         let article = EnrichedArticle::new(
@@ -278,6 +778,294 @@ mod tests {
             "http://example.com".to_string(),
         );
 
-        assert_eq!(article.best_description(100), "Better extract");
+        assert_eq!(
+            article.best_description(100, DescriptionSource::default()),
+            "Better extract"
+        );
+    }
+
+    #[test]
+    fn test_image_dimensions_require_both_sides() {
+        let basic_info = WikipediaSearchItem {
+            title: "Test".to_string(),
+            snippet: String::new(),
+            pageid: Some(1),
+            size: None,
+            wordcount: None,
+            timestamp: None,
+        };
+        let batch_info = ArticleBatchInfo {
+            image_url: Some("https://upload.wikimedia.org/thumb.jpg".to_string()),
+            image_width: Some(300),
+            image_height: Some(200),
+            ..Default::default()
+        };
+
+        let mut article = EnrichedArticle::new(
+            basic_info,
+            Some(batch_info),
+            None,
+            "http://example.com".to_string(),
+        );
+        assert_eq!(article.image_dimensions(), Some((300, 200)));
+
+        article.batch_info.as_mut().unwrap().image_height = None;
+        assert_eq!(article.image_dimensions(), None);
+    }
+
+    fn article_with_image(image_url: &str) -> EnrichedArticle {
+        test_support::article_with(
+            "Test",
+            ArticleBatchInfo {
+                image_url: Some(image_url.to_string()),
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_valid_image_url_accepts_wikimedia_uploads() {
+        let url = "https://upload.wikimedia.org/wikipedia/commons/thumb/a/a1/E.jpg/300px-E.jpg";
+        assert_eq!(
+            article_with_image(url).valid_image_url().map(String::from),
+            Some(url.to_string())
+        );
+    }
+
+    #[test]
+    fn test_valid_image_url_upgrades_http() {
+        let article =
+            article_with_image("http://commons.wikimedia.org/wiki/Special:FilePath/E.jpg");
+        assert_eq!(
+            article.valid_image_url().map(String::from).as_deref(),
+            Some("https://commons.wikimedia.org/wiki/Special:FilePath/E.jpg")
+        );
+    }
+
+    #[test]
+    fn test_valid_image_url_rejects_other_hosts() {
+        for url in [
+            "https://example.com/e.jpg",
+            "https://wikimedia.org.evil.com/e.jpg",
+            "ftp://upload.wikimedia.org/e.jpg",
+            "not a url",
+        ] {
+            assert_eq!(article_with_image(url).valid_image_url(), None, "{url}");
+        }
+    }
+
+    #[test]
+    fn test_sentence_bounded_extract_is_not_truncated_again() {
+        let extract = "Москва — столица России. Крупнейший по численности населения город страны.";
+        let mut article = article_with_image("https://upload.wikimedia.org/e.jpg");
+        let info = article.batch_info.as_mut().unwrap();
+        info.extract = Some(extract.to_string());
+
+        assert_eq!(
+            article.best_description(40, DescriptionSource::ExtractFirst),
+            "Москва — столица России. Крупнейший по..."
+        );
+
+        article.batch_info.as_mut().unwrap().sentence_bounded = true;
+        assert_eq!(
+            article.best_description(40, DescriptionSource::ExtractFirst),
+            extract
+        );
+    }
+
+    #[test]
+    fn test_malformed_page_does_not_sink_response() {
+        let json = r#"{
+            "query": {
+                "pages": {
+                    "1": {"pageid": 1, "title": "Valid", "index": 1, "coordinates": [{"lat": 55.75, "lon": 37.61}]},
+                    "2": {"pageid": 2, "title": "Broken", "index": 2, "coordinates": {"lat": 1.0, "lon": 2.0}}
+                }
+            }
+        }"#;
+
+        let response: UnifiedWikipediaResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(response.query.pages.len(), 1);
+        assert_eq!(response.query.pages["1"].title, "Valid");
+
+        let response: WikipediaBatchResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(response.query.pages.len(), 1);
+        assert!(response.query.pages.contains_key("1"));
+    }
+
+    #[test]
+    fn test_batchcomplete_only_response_is_empty() {
+        let json = r#"{"batchcomplete": ""}"#;
+
+        let response: WikipediaBatchResponse = serde_json::from_str(json).unwrap();
+        assert!(response.query.pages.is_empty());
+        assert!(response.continuation.is_none());
+
+        let response: UnifiedWikipediaResponse = serde_json::from_str(json).unwrap();
+        assert!(response.query.pages.is_empty());
+        assert!(response.query.searchinfo.is_none());
+    }
+
+    fn article_with_extract(title: &str, extract: &str) -> EnrichedArticle {
+        let mut article = test_support::article_with(
+            title,
+            ArticleBatchInfo {
+                extract: Some(extract.to_string()),
+                ..Default::default()
+            },
+        );
+        article.article_url = "https://en.wikipedia.org/wiki/C%2B%2B_(language)".to_string();
+        article
+    }
+
+    #[test]
+    fn test_to_markdown_escapes_special_characters() {
+        let article = article_with_extract("C++ (language)", "Version 2.0 [draft]!");
+        assert_eq!(
+            article.to_markdown(CONTENT_LENGTH, &Branding::default()),
+            "📖 *C\\+\\+ \\(language\\)*\n\nVersion 2\\.0 \\[draft\\]\\!\n\n\
+             🔗 [Читать полностью](https://en.wikipedia.org/wiki/C%2B%2B_(language\\))"
+        );
+    }
+
+    #[test]
+    fn test_to_html_escapes_special_characters() {
+        let article = article_with_extract("AT&T <Inc>", "a < b & \"c\"");
+        assert_eq!(
+            article.to_html(CONTENT_LENGTH, &Branding::default()),
+            "📖 <b>AT&amp;T &lt;Inc&gt;</b>\n\na &lt; b &amp; &quot;c&quot;\n\n\
+             🔗 <a href=\"https://en.wikipedia.org/wiki/C%2B%2B_(language)\">Читать полностью</a>"
+        );
+    }
+
+    #[test]
+    fn test_renderers_truncate_content() {
+        let article = article_with_extract("Title", "one two three four five");
+        assert!(article
+            .to_markdown(9, &Branding::default())
+            .contains("one two\\.\\.\\."));
+        assert!(article
+            .to_html(9, &Branding::default())
+            .contains("one two..."));
+    }
+
+    #[test]
+    fn test_html_card_fits_visible_text_limit() {
+        let article = article_with_extract("Title", &"слово & ".repeat(100));
+        let card = article.to_card(
+            CONTENT_LENGTH,
+            &Branding::default(),
+            ExtractFormat::Html,
+            200,
+        );
+        assert!(html_to_plain_text(&card).chars().count() <= 200);
+        assert!(card.contains("&amp;"));
+        assert!(card.ends_with("Читать полностью</a>"));
+    }
+
+    #[test]
+    fn test_best_description_precedence() {
+        let mut article = article_with_extract("Title", "Extract text");
+        article.basic_info.snippet = "Search snippet".to_string();
+        article.wikidata_description = Some("Wikidata description".to_string());
+        article.batch_info.as_mut().unwrap().short_description =
+            Some("Short description".to_string());
+        assert_eq!(
+            article.best_description(100, DescriptionSource::default()),
+            "Short description"
+        );
+
+        article.batch_info.as_mut().unwrap().short_description = Some("  ".to_string());
+        assert_eq!(
+            article.best_description(100, DescriptionSource::default()),
+            "Extract text"
+        );
+
+        article.batch_info.as_mut().unwrap().extract = None;
+        assert_eq!(
+            article.best_description(100, DescriptionSource::default()),
+            "Wikidata description"
+        );
+
+        article.wikidata_description = None;
+        assert_eq!(
+            article.best_description(100, DescriptionSource::default()),
+            "Search snippet"
+        );
+
+        article.basic_info.snippet = String::new();
+        assert_eq!(
+            article.best_description(100, DescriptionSource::default()),
+            "Статья из Википедии: Title"
+        );
+    }
+
+    #[test]
+    fn test_best_description_source_order() {
+        let mut article = article_with_extract("Title", "Extract text");
+        article.basic_info.snippet = "Search snippet".to_string();
+        article.wikidata_description = Some("Wikidata description".to_string());
+        article.batch_info.as_mut().unwrap().short_description =
+            Some("Short description".to_string());
+
+        let cases = [
+            (DescriptionSource::ShortDescFirst, "Short description"),
+            (DescriptionSource::ExtractFirst, "Extract text"),
+            (DescriptionSource::WikidataFirst, "Wikidata description"),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(
+                article.best_description(100, source),
+                expected,
+                "{source:?}"
+            );
+        }
+
+        // Пустой приоритетный источник уступает следующему по порядку
+        article.wikidata_description = Some(" ".to_string());
+        assert_eq!(
+            article.best_description(100, DescriptionSource::WikidataFirst),
+            "Short description"
+        );
+        article.batch_info.as_mut().unwrap().extract = None;
+        assert_eq!(
+            article.best_description(100, DescriptionSource::ExtractFirst),
+            "Short description"
+        );
+    }
+
+    #[test]
+    fn test_page_props_short_description() {
+        let props: WikipediaPageProps = serde_json::from_str(
+            r#"{"wikibase_item": "Q42", "wikibase-shortdesc": "English writer (1952–2001)"}"#,
+        )
+        .unwrap();
+        assert_eq!(props.wikibase_item.as_deref(), Some("Q42"));
+        assert_eq!(
+            props.short_description.as_deref(),
+            Some("English writer (1952–2001)")
+        );
+    }
+
+    #[test]
+    fn test_quality_from_assessments() {
+        let assessments: serde_json::Value = serde_json::from_str(
+            r#"{
+                "Physics": {"class": "B", "importance": "Top"},
+                "Biography": {"class": "FA", "importance": "High"},
+                "Germany": {"class": "", "importance": "Mid"}
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            quality_from_assessments(Some(&assessments)),
+            Some(QualityClass::Featured)
+        );
+
+        let unassessed = serde_json::json!({"Physics": {"class": "Unassessed"}});
+        assert_eq!(quality_from_assessments(Some(&unassessed)), None);
+        assert_eq!(quality_from_assessments(Some(&serde_json::json!([]))), None);
+        assert_eq!(quality_from_assessments(None), None);
+        assert!(QualityClass::Featured.score() > QualityClass::Stub.score());
     }
 }
@@ -0,0 +1,372 @@
+use crate::utils::group_thousands;
+
+use super::WikidataDataValue;
+
+/// Префикс ссылок на сущности в значениях Wikidata (единицы измерения и т. п.).
+const WIKIDATA_ENTITY_PREFIX: &str = "http://www.wikidata.org/entity/";
+
+/// Типизированное значение утверждения Wikidata. Поддерживаются только
+/// типы, которые бот умеет показывать: даты (`time`) и количества (`quantity`).
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClaimValue {
+    Time(WikidataTime),
+    Quantity(WikidataQuantity),
+}
+
+impl ClaimValue {
+    /// Разбирает `datavalue` утверждения по его полю `type`.
+    pub fn from_datavalue(datavalue: &WikidataDataValue) -> Option<Self> {
+        match datavalue.value_type.as_deref()? {
+            "time" => WikidataTime::from_value(&datavalue.value).map(Self::Time),
+            "quantity" => WikidataQuantity::from_value(&datavalue.value).map(Self::Quantity),
+            _ => None,
+        }
+    }
+
+    /// Значение для показа пользователю: по-русски для русской или
+    /// неизвестной локали, иначе по-английски.
+    pub fn format(&self, locale: Option<&str>) -> String {
+        match self {
+            Self::Time(time) => time.format(locale),
+            Self::Quantity(quantity) => quantity.format(locale),
+        }
+    }
+}
+
+/// Точность даты Wikidata (поле `precision`). Более грубые точности
+/// (миллионы лет и т. п.) не поддерживаются.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TimePrecision {
+    Millennium,
+    Century,
+    Decade,
+    Year,
+    Month,
+    Day,
+}
+
+impl TimePrecision {
+    fn from_code(code: u64) -> Option<Self> {
+        match code {
+            6 => Some(Self::Millennium),
+            7 => Some(Self::Century),
+            8 => Some(Self::Decade),
+            9 => Some(Self::Year),
+            10 => Some(Self::Month),
+            11.. => Some(Self::Day),
+            _ => None,
+        }
+    }
+}
+
+/// Дата из утверждения: год (отрицательный — до н. э.) и, если точность
+/// позволяет, месяц и день.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WikidataTime {
+    pub year: i64,
+    pub month: Option<u32>,
+    pub day: Option<u32>,
+    pub precision: TimePrecision,
+}
+
+impl WikidataTime {
+    /// Разбирает `{"time": "+1879-03-14T00:00:00Z", "precision": 11, …}`.
+    pub fn from_value(value: &serde_json::Value) -> Option<Self> {
+        let precision = TimePrecision::from_code(value.get("precision")?.as_u64()?)?;
+        let time = value.get("time")?.as_str()?;
+
+        let (negative, time) = match time.as_bytes().first()? {
+            b'-' => (true, &time[1..]),
+            b'+' => (false, &time[1..]),
+            _ => (false, time),
+        };
+        let mut parts = time.split(['-', 'T']);
+        let year: i64 = parts.next()?.parse().ok()?;
+        let month: u32 = parts.next()?.parse().ok()?;
+        let day: u32 = parts.next()?.parse().ok()?;
+
+        let month =
+            (precision >= TimePrecision::Month && (1..=12).contains(&month)).then_some(month);
+        let day = (precision >= TimePrecision::Day && month.is_some() && (1..=31).contains(&day))
+            .then_some(day);
+        // Точность выше, чем позволяют сами данные, понижается до известной
+        let precision = match (precision, month, day) {
+            (TimePrecision::Day, Some(_), None) => TimePrecision::Month,
+            (TimePrecision::Day | TimePrecision::Month, None, _) => TimePrecision::Year,
+            (precision, _, _) => precision,
+        };
+
+        Some(Self {
+            year: if negative { -year } else { year },
+            month,
+            day,
+            precision,
+        })
+    }
+
+    pub fn format(&self, locale: Option<&str>) -> String {
+        let russian = locale.is_none_or(|code| code.to_lowercase().starts_with("ru"));
+        let year = self.year.abs();
+
+        let date = match (self.precision, self.month, self.day) {
+            (TimePrecision::Day, Some(month), Some(day)) if russian => {
+                format!(
+                    "{day} {} {year}",
+                    RUSSIAN_MONTHS_GENITIVE[month as usize - 1]
+                )
+            }
+            (TimePrecision::Day, Some(month), Some(day)) => {
+                format!("{day} {} {year}", ENGLISH_MONTHS[month as usize - 1])
+            }
+            (TimePrecision::Month, Some(month), _) if russian => {
+                format!("{} {year}", RUSSIAN_MONTHS[month as usize - 1])
+            }
+            (TimePrecision::Month, Some(month), _) => {
+                format!("{} {year}", ENGLISH_MONTHS[month as usize - 1])
+            }
+            (TimePrecision::Decade, _, _) if russian => format!("{}-е", year / 10 * 10),
+            (TimePrecision::Decade, _, _) => format!("{}s", year / 10 * 10),
+            (TimePrecision::Century, _, _) if russian => {
+                format!("{} век", roman_numeral((year - 1) / 100 + 1))
+            }
+            (TimePrecision::Century, _, _) => {
+                format!("{} century", english_ordinal((year - 1) / 100 + 1))
+            }
+            (TimePrecision::Millennium, _, _) if russian => {
+                format!("{} тысячелетие", roman_numeral((year - 1) / 1000 + 1))
+            }
+            (TimePrecision::Millennium, _, _) => {
+                format!("{} millennium", english_ordinal((year - 1) / 1000 + 1))
+            }
+            _ => year.to_string(),
+        };
+
+        match (self.year < 0, russian) {
+            (false, _) => date,
+            (true, true) => format!("{date} до н. э."),
+            (true, false) => format!("{date} BC"),
+        }
+    }
+}
+
+const RUSSIAN_MONTHS: [&str; 12] = [
+    "январь",
+    "февраль",
+    "март",
+    "апрель",
+    "май",
+    "июнь",
+    "июль",
+    "август",
+    "сентябрь",
+    "октябрь",
+    "ноябрь",
+    "декабрь",
+];
+
+const RUSSIAN_MONTHS_GENITIVE: [&str; 12] = [
+    "января",
+    "февраля",
+    "марта",
+    "апреля",
+    "мая",
+    "июня",
+    "июля",
+    "августа",
+    "сентября",
+    "октября",
+    "ноября",
+    "декабря",
+];
+
+const ENGLISH_MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+fn roman_numeral(mut number: i64) -> String {
+    const NUMERALS: [(i64, &str); 13] = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+
+    let mut roman = String::new();
+    for (value, numeral) in NUMERALS {
+        while number >= value {
+            roman.push_str(numeral);
+            number -= value;
+        }
+    }
+    roman
+}
+
+fn english_ordinal(number: i64) -> String {
+    let suffix = match (number % 10, number % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{number}{suffix}")
+}
+
+/// Количество из утверждения: число и, если указана, единица измерения
+/// (идентификатор сущности Wikidata, например `Q712226` — квадратный километр).
+#[derive(Debug, Clone, PartialEq)]
+pub struct WikidataQuantity {
+    pub amount: f64,
+    pub unit: Option<String>,
+}
+
+impl WikidataQuantity {
+    /// Разбирает `{"amount": "+13149803", "unit": "1", …}`. Единица `"1"`
+    /// означает безразмерную величину.
+    pub fn from_value(value: &serde_json::Value) -> Option<Self> {
+        let amount = value.get("amount")?.as_str()?;
+        let amount: f64 = amount.strip_prefix('+').unwrap_or(amount).parse().ok()?;
+        let unit = value
+            .get("unit")
+            .and_then(|unit| unit.as_str())
+            .and_then(|unit| unit.strip_prefix(WIKIDATA_ENTITY_PREFIX))
+            .map(str::to_string);
+
+        Some(Self { amount, unit })
+    }
+
+    /// Число с разделением разрядов и, для известных единиц, их сокращением.
+    pub fn format(&self, locale: Option<&str>) -> String {
+        let russian = locale.is_none_or(|code| code.to_lowercase().starts_with("ru"));
+        let (thousands, decimal) = if russian { (' ', ',') } else { (',', '.') };
+
+        let magnitude = self.amount.abs();
+        let mut number = group_thousands(magnitude.trunc() as u64, thousands);
+        let fraction = format!("{:.2}", magnitude.fract());
+        let fraction = fraction.trim_start_matches('0').trim_end_matches('0');
+        if fraction.len() > 1 {
+            number.push(decimal);
+            number.push_str(&fraction[1..]);
+        }
+        if self.amount < 0.0 {
+            number.insert(0, '-');
+        }
+
+        match self
+            .unit
+            .as_deref()
+            .and_then(|unit| unit_symbol(unit, russian))
+        {
+            Some(symbol) => format!("{number} {symbol}"),
+            None => number,
+        }
+    }
+}
+
+/// Сокращения распространённых единиц. Для остальных показывается только число.
+fn unit_symbol(unit: &str, russian: bool) -> Option<&'static str> {
+    let (ru, en) = match unit {
+        "Q11573" => ("м", "m"),
+        "Q828224" => ("км", "km"),
+        "Q25343" => ("м²", "m²"),
+        "Q712226" => ("км²", "km²"),
+        "Q11570" => ("кг", "kg"),
+        "Q25250" => ("В", "V"),
+        _ => return None,
+    };
+    Some(if russian { ru } else { en })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn datavalue(json: &str) -> WikidataDataValue {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_decode_time_claim_with_precision() {
+        let birth = ClaimValue::from_datavalue(&datavalue(
+            r#"{"type": "time", "value": {"time": "+1879-03-14T00:00:00Z", "precision": 11,
+                "calendarmodel": "http://www.wikidata.org/entity/Q1985727"}}"#,
+        ))
+        .unwrap();
+        assert_eq!(
+            birth,
+            ClaimValue::Time(WikidataTime {
+                year: 1879,
+                month: Some(3),
+                day: Some(14),
+                precision: TimePrecision::Day,
+            })
+        );
+        assert_eq!(birth.format(Some("ru")), "14 марта 1879");
+        assert_eq!(birth.format(Some("en")), "14 March 1879");
+
+        // Год основания Москвы: месяц и день в данных нулевые
+        let inception = ClaimValue::from_datavalue(&datavalue(
+            r#"{"type": "time", "value": {"time": "+1147-00-00T00:00:00Z", "precision": 9}}"#,
+        ))
+        .unwrap();
+        assert_eq!(inception.format(None), "1147");
+
+        let century = WikidataTime::from_value(
+            &serde_json::json!({"time": "-0500-00-00T00:00:00Z", "precision": 7}),
+        )
+        .unwrap();
+        assert_eq!(century.format(Some("ru")), "V век до н. э.");
+        assert_eq!(century.format(Some("en")), "5th century BC");
+    }
+
+    #[test]
+    fn test_decode_quantity_claim() {
+        let population = ClaimValue::from_datavalue(&datavalue(
+            r#"{"type": "quantity", "value": {"amount": "+13149803", "unit": "1"}}"#,
+        ))
+        .unwrap();
+        assert_eq!(
+            population,
+            ClaimValue::Quantity(WikidataQuantity {
+                amount: 13_149_803.0,
+                unit: None,
+            })
+        );
+        assert_eq!(population.format(Some("ru")), "13 149 803");
+        assert_eq!(population.format(Some("en")), "13,149,803");
+
+        let area = ClaimValue::from_datavalue(&datavalue(
+            r#"{"type": "quantity", "value": {"amount": "+2561.5",
+                "unit": "http://www.wikidata.org/entity/Q712226"}}"#,
+        ))
+        .unwrap();
+        assert_eq!(area.format(None), "2 561,5 км²");
+        assert_eq!(area.format(Some("en")), "2,561.5 km²");
+    }
+
+    #[test]
+    fn test_unsupported_datavalue_is_skipped() {
+        let item = datavalue(r#"{"type": "wikibase-entityid", "value": {"id": "Q5"}}"#);
+        assert_eq!(ClaimValue::from_datavalue(&item), None);
+    }
+}
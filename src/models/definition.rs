@@ -0,0 +1,99 @@
+use crate::utils::truncate_string;
+
+/// Краткое определение слова из Викисловаря.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Definition {
+    /// Заголовок статьи Викисловаря.
+    pub word: String,
+    pub text: String,
+    pub url: String,
+}
+
+/// Разделы статьи Викисловаря, в которых нет толкования слова.
+const NON_DEFINITION_SECTIONS: &[&str] = &[
+    "etymology",
+    "pronunciation",
+    "alternative forms",
+    "anagrams",
+    "references",
+    "see also",
+    "этимология",
+    "произношение",
+    "морфологические и синтаксические свойства",
+    "етимологія",
+    "вимова",
+    "морфологічні та синтаксичні властивості",
+];
+
+/// Сколько символов определения показывать.
+const DEFINITION_LENGTH: usize = 200;
+
+/// Первое толкование из текста статьи Викисловаря (`explaintext` с
+/// `exsectionformat=wiki`): первая строка первого раздела, который не
+/// этимология, не произношение и не морфология. Строка с самим словом
+/// (`word (plural words)`), нумерация и примеры после `◆` отбрасываются.
+pub fn first_definition(extract: &str, word: &str) -> Option<String> {
+    let word = word.to_lowercase();
+    let mut skip_section = false;
+
+    for line in extract.lines().map(str::trim) {
+        if line.starts_with('=') {
+            let heading = line.trim_matches('=').trim().to_lowercase();
+            skip_section = NON_DEFINITION_SECTIONS.contains(&heading.as_str());
+            continue;
+        }
+        if skip_section || line.is_empty() || line.to_lowercase().starts_with(&word) {
+            continue;
+        }
+
+        let definition = line
+            .trim_start_matches(|c: char| c.is_ascii_digit() || c == '.' || c == ')')
+            .split('◆')
+            .next()
+            .unwrap_or_default()
+            .trim();
+        if !definition.is_empty() {
+            return Some(truncate_string(definition, DEFINITION_LENGTH));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_definition_english() {
+        let extract = "== English ==\n\n=== Etymology ===\nFrom Middle English word.\n\n\
+                       === Pronunciation ===\nIPA: /wɜːd/\n\n=== Noun ===\nword (plural words)\n\n\
+                       The smallest unit of language that has a particular meaning.\n\
+                       A promise.";
+        assert_eq!(
+            first_definition(extract, "word").as_deref(),
+            Some("The smallest unit of language that has a particular meaning.")
+        );
+    }
+
+    #[test]
+    fn test_first_definition_russian() {
+        let extract = "= Русский =\n\n=== Морфологические и синтаксические свойства ===\n\
+                       сло́-во\nСуществительное, неодушевлённое, средний род.\n\n\
+                       === Произношение ===\n[ˈsɫovə]\n\n=== Семантические свойства ===\n\n\
+                       ==== Значение ====\n1. единица речи ◆ Отсутствует пример употребления.\n\
+                       2. речь, способность говорить";
+        assert_eq!(
+            first_definition(extract, "слово").as_deref(),
+            Some("единица речи")
+        );
+    }
+
+    #[test]
+    fn test_first_definition_without_meaning() {
+        assert_eq!(first_definition("", "word"), None);
+        assert_eq!(
+            first_definition("== English ==\n=== Etymology ===\nUnknown.", "word"),
+            None
+        );
+    }
+}
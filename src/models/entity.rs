@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+
+/// Тип сущности по Wikidata-свойству P31 ("экземпляр класса").
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EntityKind {
+    Person,
+    City,
+    Settlement,
+    Country,
+    Organization,
+    University,
+    Film,
+    TvSeries,
+    Book,
+    Album,
+    Song,
+    VideoGame,
+    Taxon,
+    Mountain,
+    WaterBody,
+    Conflict,
+    ChemicalCompound,
+}
+
+impl EntityKind {
+    pub fn from_class_id(class_id: &str) -> Option<Self> {
+        match class_id {
+            "Q5" => Some(Self::Person),
+            "Q515" | "Q1549591" | "Q5119" => Some(Self::City),
+            "Q486972" | "Q532" | "Q3957" => Some(Self::Settlement),
+            "Q6256" | "Q3624078" | "Q7275" => Some(Self::Country),
+            "Q43229" | "Q4830453" | "Q783794" | "Q891723" => Some(Self::Organization),
+            "Q3918" | "Q902104" => Some(Self::University),
+            "Q11424" | "Q24869" => Some(Self::Film),
+            "Q5398426" => Some(Self::TvSeries),
+            "Q571" | "Q7725634" | "Q47461344" => Some(Self::Book),
+            "Q482994" | "Q208569" => Some(Self::Album),
+            "Q7366" | "Q134556" => Some(Self::Song),
+            "Q7889" => Some(Self::VideoGame),
+            "Q16521" => Some(Self::Taxon),
+            "Q8502" | "Q8072" => Some(Self::Mountain),
+            "Q4022" | "Q23397" | "Q165" => Some(Self::WaterBody),
+            "Q178561" | "Q198" | "Q645883" => Some(Self::Conflict),
+            "Q11173" => Some(Self::ChemicalCompound),
+            _ => None,
+        }
+    }
+
+    /// Первый распознанный класс из списка P31; `None`, если ни один не известен.
+    pub fn from_class_ids(class_ids: &[String]) -> Option<Self> {
+        class_ids.iter().find_map(|id| Self::from_class_id(id))
+    }
+
+    pub fn emoji(&self) -> &'static str {
+        match self {
+            Self::Person => "👤",
+            Self::City => "🏙️",
+            Self::Settlement => "🏘️",
+            Self::Country => "🌍",
+            Self::Organization => "🏢",
+            Self::University => "🎓",
+            Self::Film => "🎬",
+            Self::TvSeries => "📺",
+            Self::Book => "📚",
+            Self::Album => "💿",
+            Self::Song => "🎵",
+            Self::VideoGame => "🎮",
+            Self::Taxon => "🧬",
+            Self::Mountain => "⛰️",
+            Self::WaterBody => "🌊",
+            Self::Conflict => "⚔️",
+            Self::ChemicalCompound => "🧪",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Person => "Человек",
+            Self::City => "Город",
+            Self::Settlement => "Населённый пункт",
+            Self::Country => "Страна",
+            Self::Organization => "Организация",
+            Self::University => "Университет",
+            Self::Film => "Фильм",
+            Self::TvSeries => "Телесериал",
+            Self::Book => "Книга",
+            Self::Album => "Альбом",
+            Self::Song => "Песня",
+            Self::VideoGame => "Видеоигра",
+            Self::Taxon => "Таксон",
+            Self::Mountain => "Гора",
+            Self::WaterBody => "Водоём",
+            Self::Conflict => "Конфликт",
+            Self::ChemicalCompound => "Химическое соединение",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_class_ids() {
+        assert_eq!(
+            EntityKind::from_class_ids(&["Q5".to_string()]),
+            Some(EntityKind::Person)
+        );
+        assert_eq!(
+            EntityKind::from_class_ids(&["Q999999999".to_string(), "Q1549591".to_string()]),
+            Some(EntityKind::City)
+        );
+        assert_eq!(EntityKind::from_class_ids(&[]), None);
+        assert_eq!(
+            EntityKind::from_class_ids(&["Q999999999".to_string()]),
+            None
+        );
+    }
+}
@@ -0,0 +1,295 @@
+use std::collections::HashMap;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// Начала названий шаблонов-карточек в поддерживаемых разделах.
+const INFOBOX_TEMPLATE_PREFIXES: &[&str] = &["infobox", "карточка", "інфобокс", "ficha de"];
+
+static REF_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?s)<ref[^>/]*/>|<ref[^>]*>.*?</ref>|<!--.*?-->")
+        .expect("Failed to compile ref regex")
+});
+
+static TAG_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"<[^>]*>").expect("Failed to compile HTML tag regex"));
+
+/// Разбирает первую карточку (`{{Infobox …}}`, `{{Карточка …}}`) в вики-тексте
+/// статьи в плоский словарь «параметр → значение». Ключи приводятся к нижнему
+/// регистру, `_` заменяется пробелом. В значениях ссылки `[[цель|текст]]`
+/// заменяются текстом, сноски, комментарии и HTML-теги убираются.
+///
+/// Это разбор «по возможности», а не полноценный парсер вики-текста:
+/// - распознаются только шаблоны из `INFOBOX_TEMPLATE_PREFIXES`; карточки с
+///   собственными названиями (`{{Персона}}`, `{{Учёный}}`) не находятся;
+/// - вложенные шаблоны отбрасываются, кроме дат вида `{{birth date|1879|3|14}}`
+///   и `{{Дата рождения|14|3|1879}}`, которые становятся `1879-03-14`;
+/// - параметры, которые карточка подтягивает из Wikidata, в вики-тексте
+///   отсутствуют и в результат не попадают.
+pub fn parse_infobox(wikitext: &str) -> HashMap<String, String> {
+    let Some(body) = find_infobox(wikitext) else {
+        return HashMap::new();
+    };
+
+    split_top_level(body)
+        .into_iter()
+        .skip(1)
+        .filter_map(|parameter| {
+            let (key, value) = parameter.split_once('=')?;
+            let key = normalize_key(key);
+            let value = clean_value(value);
+            (!key.is_empty() && !value.is_empty()).then_some((key, value))
+        })
+        .collect()
+}
+
+/// Несколько часто нужных фактов из карточки, независимо от языка раздела.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InfoboxFacts {
+    pub birth_date: Option<String>,
+    pub death_date: Option<String>,
+    pub country: Option<String>,
+    pub population: Option<String>,
+}
+
+impl InfoboxFacts {
+    pub fn from_infobox(infobox: &HashMap<String, String>) -> Self {
+        let fact = |keys: &[&str]| keys.iter().find_map(|key| infobox.get(*key).cloned());
+
+        Self {
+            birth_date: fact(&[
+                "birth date",
+                "дата рождения",
+                "дата народження",
+                "date de naissance",
+                "fecha de nacimiento",
+            ]),
+            death_date: fact(&[
+                "death date",
+                "дата смерти",
+                "дата смерті",
+                "date de décès",
+                "fecha de fallecimiento",
+            ]),
+            country: fact(&["country", "страна", "країна", "pays", "país", "land"]),
+            population: fact(&[
+                "population total",
+                "population",
+                "население",
+                "населення",
+                "población",
+                "einwohner",
+            ]),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Содержимое первого шаблона-карточки без внешних `{{` и `}}`.
+fn find_infobox(wikitext: &str) -> Option<&str> {
+    let mut search_from = 0;
+    while let Some(offset) = wikitext[search_from..].find("{{") {
+        let start = search_from + offset + 2;
+        let name = wikitext[start..]
+            .trim_start()
+            .split(['|', '}', '\n'])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+
+        if INFOBOX_TEMPLATE_PREFIXES
+            .iter()
+            .any(|prefix| name.starts_with(prefix))
+        {
+            let end = matching_template_end(wikitext, start)?;
+            return Some(&wikitext[start..end]);
+        }
+        search_from = start;
+    }
+    None
+}
+
+/// Позиция `}}`, закрывающего шаблон, тело которого начинается с `start`.
+fn matching_template_end(wikitext: &str, start: usize) -> Option<usize> {
+    let bytes = wikitext.as_bytes();
+    let mut depth = 1usize;
+    let mut i = start;
+    while i + 1 < bytes.len() {
+        match &bytes[i..i + 2] {
+            b"{{" => {
+                depth += 1;
+                i += 2;
+            }
+            b"}}" => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+                i += 2;
+            }
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Делит тело шаблона по `|`, не заходя во вложенные шаблоны и ссылки.
+fn split_top_level(body: &str) -> Vec<&str> {
+    let bytes = body.as_bytes();
+    let mut parts = Vec::new();
+    let (mut templates, mut links) = (0usize, 0usize);
+    let mut part_start = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        let pair = bytes.get(i..i + 2);
+        match pair {
+            Some(b"{{") => templates += 1,
+            Some(b"}}") => templates = templates.saturating_sub(1),
+            Some(b"[[") => links += 1,
+            Some(b"]]") => links = links.saturating_sub(1),
+            _ => {
+                if bytes[i] == b'|' && templates == 0 && links == 0 {
+                    parts.push(&body[part_start..i]);
+                    part_start = i + 1;
+                }
+                i += 1;
+                continue;
+            }
+        }
+        i += 2;
+    }
+    parts.push(&body[part_start..]);
+    parts
+}
+
+fn normalize_key(key: &str) -> String {
+    key.trim().replace('_', " ").to_lowercase()
+}
+
+fn clean_value(value: &str) -> String {
+    let value = REF_REGEX.replace_all(value, "");
+    let value = replace_templates(&value);
+    let value = replace_links(&value);
+    let value = TAG_REGEX.replace_all(&value, " ");
+    let value = value.replace("'''", "").replace("''", "");
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Заменяет вложенные шаблоны: даты — на `ГГГГ-ММ-ДД`, остальное убирает.
+fn replace_templates(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("{{") {
+        result.push_str(&rest[..start]);
+        let Some(end) = matching_template_end(rest, start + 2) else {
+            return result;
+        };
+        if let Some(date) = template_date(&rest[start + 2..end]) {
+            result.push_str(&date);
+        }
+        rest = &rest[end + 2..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Дата из шаблона с тремя числовыми аргументами: год первым (en) или
+/// последним (ru, uk).
+fn template_date(template: &str) -> Option<String> {
+    let numbers: Vec<u32> = template
+        .split('|')
+        .skip(1)
+        .map(str::trim)
+        .filter(|arg| !arg.contains('='))
+        .map_while(|arg| arg.parse().ok())
+        .collect();
+
+    let (year, month, day) = match numbers[..] {
+        [year, month, day, ..] if year > 31 => (year, month, day),
+        [day, month, year, ..] if year > 31 => (year, month, day),
+        _ => return None,
+    };
+    ((1..=12).contains(&month) && (1..=31).contains(&day))
+        .then(|| format!("{year:04}-{month:02}-{day:02}"))
+}
+
+/// `[[цель|текст]]` → `текст`, `[[цель]]` → `цель`.
+fn replace_links(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("[[") {
+        result.push_str(&rest[..start]);
+        let Some(len) = rest[start + 2..].find("]]") else {
+            rest = &rest[start + 2..];
+            continue;
+        };
+        let link = &rest[start + 2..start + 2 + len];
+        result.push_str(link.rsplit('|').next().unwrap_or(link));
+        rest = &rest[start + 2 + len + 2..];
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EINSTEIN: &str = r#"{{Short description|German-born physicist (1879–1955)}}
+{{Infobox scientist
+| name        = Albert Einstein
+| image       = Einstein 1921 by F Schmutzer - restoration.jpg
+| birth_date  = {{Birth date|df=y|1879|3|14}}
+| birth_place = [[Ulm]], [[Kingdom of Württemberg|Württemberg]], [[German Empire]]
+| death_date  = {{Death date and age|df=y|1955|4|18|1879|3|14}}
+| citizenship = {{Plainlist|
+* [[Kingdom of Württemberg|Württemberg]]
+}}
+| known_for   = [[General relativity]]<ref>{{cite web|url=https://example.org}}</ref>
+| awards      = <!-- none listed -->
+}}
+'''Albert Einstein''' was a [[theoretical physicist]]."#;
+
+    const MOSCOW: &str = r#"{{Карточка города
+ |Русское название = Москва
+ |Страна = [[Россия]]
+ |Население = 13 149 803<ref name="pop"/>
+ |Дата основания = {{Дата рождения|4|4|1147}}
+}}"#;
+
+    #[test]
+    fn test_parse_infobox_english() {
+        let infobox = parse_infobox(EINSTEIN);
+        assert_eq!(infobox["name"], "Albert Einstein");
+        assert_eq!(infobox["birth date"], "1879-03-14");
+        assert_eq!(infobox["death date"], "1955-04-18");
+        assert_eq!(infobox["birth place"], "Ulm, Württemberg, German Empire");
+        assert_eq!(infobox["known for"], "General relativity");
+        // Пустые после очистки значения и списки-шаблоны отбрасываются
+        assert!(!infobox.contains_key("awards"));
+        assert!(!infobox.contains_key("citizenship"));
+        assert!(!infobox.contains_key("short description"));
+    }
+
+    #[test]
+    fn test_parse_infobox_russian_facts() {
+        let infobox = parse_infobox(MOSCOW);
+        assert_eq!(infobox["русское название"], "Москва");
+        assert_eq!(infobox["дата основания"], "1147-04-04");
+
+        let facts = InfoboxFacts::from_infobox(&infobox);
+        assert_eq!(facts.country.as_deref(), Some("Россия"));
+        assert_eq!(facts.population.as_deref(), Some("13 149 803"));
+        assert_eq!(facts.birth_date, None);
+    }
+
+    #[test]
+    fn test_parse_infobox_without_infobox() {
+        assert!(parse_infobox("'''Stub''' is a short article. {{stub}}").is_empty());
+        assert!(parse_infobox("{{Infobox person | name = Unclosed").is_empty());
+        assert!(InfoboxFacts::from_infobox(&HashMap::new()).is_empty());
+    }
+}
@@ -0,0 +1,115 @@
+use serde::Deserialize;
+
+use crate::config::languages::SupportedLanguage;
+
+/// Результат поиска в другом проекте Викимедиа (`query.interwiki`): статья
+/// Викисловаря, элемент Wikidata и т. п.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct InterwikiHit {
+    /// Интервики-префикс проекта: `wikt`, `d`, `voy`…
+    pub iw: String,
+    /// Заголовок, обычно вместе с префиксом (`wikt:слово`).
+    pub title: String,
+    /// Полная ссылка, если запрошена `iwurl=1`.
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+/// Значок, название и домен (`{lang}` — язык раздела) известных проектов.
+fn sister_project(iw: &str) -> Option<(&'static str, &'static str, &'static str)> {
+    let project = match iw.to_lowercase().as_str() {
+        "wikt" | "wiktionary" => ("📚", "Викисловарь", "{lang}.wiktionary.org"),
+        "d" | "wikidata" => ("🗃️", "Wikidata", "www.wikidata.org"),
+        "voy" | "wikivoyage" => ("🧳", "Викигид", "{lang}.wikivoyage.org"),
+        "q" | "wikiquote" => ("💬", "Викицитатник", "{lang}.wikiquote.org"),
+        "s" | "wikisource" => ("📜", "Викитека", "{lang}.wikisource.org"),
+        "c" | "commons" => ("🖼️", "Викисклад", "commons.wikimedia.org"),
+        _ => return None,
+    };
+    Some(project)
+}
+
+impl InterwikiHit {
+    /// Заголовок без интервики-префикса.
+    pub fn display_title(&self) -> &str {
+        self.title
+            .split_once(':')
+            .filter(|(prefix, _)| prefix.eq_ignore_ascii_case(&self.iw))
+            .map_or(self.title.as_str(), |(_, title)| title)
+            .trim()
+    }
+
+    pub fn icon(&self) -> &'static str {
+        sister_project(&self.iw).map_or("🔗", |(icon, _, _)| icon)
+    }
+
+    pub fn project_name(&self) -> &'static str {
+        sister_project(&self.iw).map_or("другой проект Викимедиа", |(_, name, _)| name)
+    }
+
+    /// Ссылка из ответа API, а без неё — собранная для известного проекта.
+    pub fn resolved_url(&self, language: SupportedLanguage) -> Option<String> {
+        if let Some(url) = self.url.as_ref().filter(|url| !url.is_empty()) {
+            return Some(url.clone());
+        }
+
+        let (_, _, host) = sister_project(&self.iw)?;
+        let title = self.display_title();
+        (!title.is_empty()).then(|| {
+            format!(
+                "https://{}/wiki/{}",
+                host.replace("{lang}", language.code()),
+                urlencoding::encode(&title.replace(' ', "_"))
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::UnifiedWikipediaResponse;
+
+    #[test]
+    fn test_deserialize_interwiki_hits() {
+        let json = r#"{
+            "batchcomplete": "",
+            "query": {
+                "interwiki": [
+                    {"title": "wikt:эйнштейний", "iw": "wikt", "url": "https://ru.wiktionary.org/wiki/%D1%8D%D0%B9%D0%BD%D1%88%D1%82%D0%B5%D0%B9%D0%BD%D0%B8%D0%B9"},
+                    {"title": "d:Q937", "iw": "d"}
+                ],
+                "pages": {}
+            }
+        }"#;
+
+        let response: UnifiedWikipediaResponse = serde_json::from_str(json).unwrap();
+        let hits = response.query.interwiki;
+        assert_eq!(hits.len(), 2);
+
+        assert_eq!(hits[0].display_title(), "эйнштейний");
+        assert_eq!(hits[0].icon(), "📚");
+        assert_eq!(hits[0].project_name(), "Викисловарь");
+        assert_eq!(
+            hits[0].resolved_url(SupportedLanguage::Russian).as_deref(),
+            hits[0].url.as_deref()
+        );
+
+        assert_eq!(hits[1].display_title(), "Q937");
+        assert_eq!(
+            hits[1].resolved_url(SupportedLanguage::Russian).as_deref(),
+            Some("https://www.wikidata.org/wiki/Q937")
+        );
+    }
+
+    #[test]
+    fn test_unknown_project_needs_url() {
+        let hit = InterwikiHit {
+            iw: "meta".to_string(),
+            title: "meta:Main Page".to_string(),
+            url: None,
+        };
+        assert_eq!(hit.icon(), "🔗");
+        assert_eq!(hit.resolved_url(SupportedLanguage::English), None);
+    }
+}
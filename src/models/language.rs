@@ -1,4 +1,4 @@
-pub use crate::config::languages::SupportedLanguage;
+pub use crate::config::languages::{LanguageSource, ParsedQuery, SupportedLanguage};
 
 #[derive(Debug, Clone)]
 pub struct WikipediaLanguage {
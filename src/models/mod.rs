@@ -1,5 +1,17 @@
 pub mod article;
+pub mod claim;
+pub mod definition;
+pub mod entity;
+pub mod infobox;
+pub mod interwiki;
 pub mod language;
+pub mod section;
 
 pub use article::*;
+pub use claim::*;
+pub use definition::*;
+pub use entity::*;
+pub use infobox::*;
+pub use interwiki::*;
 pub use language::*;
+pub use section::*;
@@ -0,0 +1,99 @@
+use serde::Deserialize;
+
+/// Раздел статьи из оглавления (`action=parse&prop=sections`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    /// Уровень вложенности в оглавлении, начиная с 1.
+    pub level: u8,
+    pub title: String,
+    pub anchor: String,
+}
+
+impl Section {
+    /// Ссылка на раздел внутри статьи.
+    pub fn url(&self, article_url: &str) -> String {
+        format!("{article_url}#{}", encode_section_anchor(&self.anchor))
+    }
+}
+
+/// Кодирует якорь раздела в «точечном» формате MediaWiki (`$wgFragmentMode`
+/// legacy): пробелы становятся `_`, остальные байты кроме `[A-Za-z0-9_.:-]`
+/// записываются как `.XX`. Такой якорь состоит только из ASCII и не требует
+/// экранирования в ссылке.
+pub fn encode_section_anchor(anchor: &str) -> String {
+    anchor
+        .replace(' ', "_")
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'_' | b'.' | b':' | b'-' => {
+                (byte as char).to_string()
+            }
+            _ => format!(".{byte:02X}"),
+        })
+        .collect()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WikipediaParseResponse {
+    #[serde(default)]
+    pub parse: Option<WikipediaParse>,
+    #[serde(default)]
+    pub error: Option<WikipediaApiError>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WikipediaParse {
+    pub title: String,
+    #[serde(default)]
+    pub sections: Vec<WikipediaParseSection>,
+    /// Вики-текст страницы (`prop=wikitext`, `formatversion=2`).
+    #[serde(default)]
+    pub wikitext: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WikipediaParseSection {
+    pub toclevel: u8,
+    /// Заголовок раздела; может содержать HTML-разметку.
+    pub line: String,
+    pub anchor: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WikipediaApiError {
+    pub code: String,
+    #[serde(default)]
+    pub info: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_section_anchor() {
+        assert_eq!(encode_section_anchor("Early life"), "Early_life");
+        assert_eq!(
+            encode_section_anchor("Биография"),
+            ".D0.91.D0.B8.D0.BE.D0.B3.D1.80.D0.B0.D1.84.D0.B8.D1.8F"
+        );
+        assert_eq!(
+            encode_section_anchor("1905: Annus mirabilis"),
+            "1905:_Annus_mirabilis"
+        );
+        assert_eq!(encode_section_anchor("Q&A (2020)"), "Q.26A_.282020.29");
+    }
+
+    #[test]
+    fn test_section_url() {
+        let section = Section {
+            level: 2,
+            title: "Early life".to_string(),
+            anchor: "Early life".to_string(),
+        };
+        assert_eq!(
+            section.url("https://en.wikipedia.org/wiki/Albert%20Einstein"),
+            "https://en.wikipedia.org/wiki/Albert%20Einstein#Early_life"
+        );
+    }
+}
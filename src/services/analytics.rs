@@ -0,0 +1,187 @@
+use serde::Serialize;
+use std::path::PathBuf;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::config::TelegramConfig;
+use crate::utils::BackgroundTasks;
+
+/// Сколько событий может ждать записи; при переполнении новые события отбрасываются.
+const ANALYTICS_QUEUE_CAPACITY: usize = 1024;
+
+/// Анонимная запись об одном inline-запросе. Идентификатор пользователя не
+/// сохраняется никогда, текст запроса — только при `analytics_include_query`.
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryEvent {
+    pub lang: &'static str,
+    pub query_len: usize,
+    pub result_count: usize,
+    pub cache_hit: bool,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+}
+
+/// Пишет события запросов в JSONL-файл в фоновой задаче, чтобы запись не
+/// добавляла задержку ответу пользователю.
+pub struct AnalyticsSink {
+    sender: mpsc::Sender<QueryEvent>,
+    writer: JoinHandle<()>,
+    include_query: bool,
+}
+
+impl AnalyticsSink {
+    /// Создаёт sink, если в конфигурации задан `analytics_path`.
+    /// Должен вызываться внутри Tokio runtime.
+    pub fn from_config(config: &TelegramConfig, tasks: &BackgroundTasks) -> Option<Self> {
+        let path = config.analytics_path.clone()?;
+        Some(Self::new(path, config.analytics_include_query, tasks))
+    }
+
+    /// Запись идёт в задаче из `tasks`: при их остановке очередь дописывается
+    /// и файл сбрасывается на диск.
+    pub fn new(path: impl Into<PathBuf>, include_query: bool, tasks: &BackgroundTasks) -> Self {
+        let path = path.into();
+        let (sender, receiver) = mpsc::channel(ANALYTICS_QUEUE_CAPACITY);
+        let writer = tasks.spawn_with_shutdown(|shutdown| write_events(path, receiver, shutdown));
+
+        Self {
+            sender,
+            writer,
+            include_query,
+        }
+    }
+
+    /// Ставит событие в очередь на запись; никогда не ждёт диска.
+    pub fn record(&self, mut event: QueryEvent) {
+        if !self.include_query {
+            event.query = None;
+        }
+
+        if let Err(e) = self.sender.try_send(event) {
+            tracing::debug!("📉 Событие аналитики отброшено: {}", e);
+        }
+    }
+
+    /// Дожидается записи всех событий из очереди.
+    pub async fn close(self) {
+        drop(self.sender);
+        if let Err(e) = self.writer.await {
+            tracing::warn!("⚠️ Задача записи аналитики завершилась с ошибкой: {}", e);
+        }
+    }
+}
+
+async fn write_events(
+    path: PathBuf,
+    mut receiver: mpsc::Receiver<QueryEvent>,
+    shutdown: CancellationToken,
+) {
+    let mut file = match tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .await
+    {
+        Ok(file) => file,
+        Err(e) => {
+            tracing::error!("❌ Не удалось открыть файл аналитики {:?}: {}", path, e);
+            return;
+        }
+    };
+
+    loop {
+        let event = tokio::select! {
+            event = receiver.recv() => event,
+            // Новые события больше не принимаются, уже поставленные дописываются
+            _ = shutdown.cancelled() => {
+                receiver.close();
+                receiver.recv().await
+            }
+        };
+        let Some(event) = event else {
+            break;
+        };
+
+        let mut line = match serde_json::to_string(&event) {
+            Ok(line) => line,
+            Err(e) => {
+                tracing::warn!("⚠️ Не удалось сериализовать событие аналитики: {}", e);
+                continue;
+            }
+        };
+        line.push('\n');
+
+        if let Err(e) = file.write_all(line.as_bytes()).await {
+            tracing::warn!("⚠️ Не удалось записать событие аналитики: {}", e);
+        }
+    }
+
+    if let Err(e) = file.flush().await {
+        tracing::warn!("⚠️ Не удалось сбросить файл аналитики: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(query: &str) -> QueryEvent {
+        QueryEvent {
+            lang: "ru",
+            query_len: query.chars().count(),
+            result_count: 3,
+            cache_hit: false,
+            latency_ms: 42,
+            query: Some(query.to_string()),
+        }
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "wiki-analytics-{}-{}.jsonl",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[tokio::test]
+    async fn test_events_are_appended_without_raw_query() {
+        let path = temp_path("anonymous");
+        let sink = AnalyticsSink::new(&path, false, &BackgroundTasks::new());
+        sink.record(event("Пушкин"));
+        sink.record(event("Einstein"));
+        sink.close().await;
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<serde_json::Value> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["lang"], "ru");
+        assert_eq!(lines[0]["query_len"], 6);
+        assert_eq!(lines[1]["latency_ms"], 42);
+        assert!(lines.iter().all(|line| line.get("query").is_none()));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_raw_query_is_opt_in() {
+        let path = temp_path("with-query");
+        let sink = AnalyticsSink::new(&path, true, &BackgroundTasks::new());
+        sink.record(event("Пушкин"));
+        sink.close().await;
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let line: serde_json::Value = serde_json::from_str(contents.trim()).unwrap();
+        assert_eq!(line["query"], "Пушкин");
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
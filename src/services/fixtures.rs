@@ -0,0 +1,263 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::config::languages::SupportedLanguage;
+use crate::config::projects::WikiProject;
+use crate::errors::{WikiError, WikiResult};
+use crate::models::{ArticleBatchInfo, EnrichedArticle, WikipediaSearchItem};
+use crate::services::WikipediaApi;
+
+/// Статья в файле фикстур. Поля совпадают с [`EnrichedArticle`]; ссылка на
+/// статью и порядок релевантности вычисляются при загрузке.
+#[derive(Debug, Deserialize)]
+struct FixtureArticle {
+    basic_info: WikipediaSearchItem,
+    #[serde(default)]
+    batch_info: Option<ArticleBatchInfo>,
+    #[serde(default)]
+    wikidata_description: Option<String>,
+}
+
+/// Заготовленные ответы для работы без сети (`WIKI_FIXTURES_DIR`).
+///
+/// Формат: в каталоге по одному файлу на язык, `{код языка}.json`, с объектом
+/// «запрос → список статей в порядке релевантности»:
+///
+/// ```json
+/// {
+///   "эйнштейн": [
+///     {
+///       "basic_info": {"title": "Эйнштейн, Альберт", "snippet": "…", "pageid": 736},
+///       "batch_info": {"image_url": null, "extract": "…", "wikidata_id": "Q937"},
+///       "wikidata_description": "физик-теоретик"
+///     }
+///   ]
+/// }
+/// ```
+///
+/// Запросы сравниваются без учёта регистра и лишних пробелов. Файлы с
+/// неизвестным кодом языка пропускаются.
+#[derive(Debug, Default)]
+pub struct FixtureStore {
+    project: WikiProject,
+    articles: HashMap<String, Vec<EnrichedArticle>>,
+}
+
+impl FixtureStore {
+    pub fn load(dir: &Path, project: WikiProject) -> WikiResult<Self> {
+        let entries = std::fs::read_dir(dir).map_err(|e| {
+            WikiError::config(format!(
+                "Failed to read fixtures dir {}: {e}",
+                dir.display()
+            ))
+        })?;
+
+        let mut store = Self {
+            project,
+            articles: HashMap::new(),
+        };
+
+        for entry in entries {
+            let path = entry
+                .map_err(|e| WikiError::config(format!("Failed to read fixtures dir: {e}")))?
+                .path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+
+            let Some(language) = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(SupportedLanguage::from_code)
+            else {
+                tracing::warn!("⚠️ Пропускаю файл фикстур с неизвестным языком: {:?}", path);
+                continue;
+            };
+
+            let contents = std::fs::read_to_string(&path).map_err(|e| {
+                WikiError::config(format!("Failed to read fixture {}: {e}", path.display()))
+            })?;
+            let queries: HashMap<String, Vec<FixtureArticle>> = serde_json::from_str(&contents)
+                .map_err(|e| {
+                    WikiError::config(format!("Invalid fixture {}: {e}", path.display()))
+                })?;
+
+            for (query, articles) in queries {
+                let articles = articles
+                    .into_iter()
+                    .enumerate()
+                    .map(|(idx, article)| store.to_enriched(article, idx, language))
+                    .collect();
+                store.articles.insert(Self::key(&query, language), articles);
+            }
+        }
+
+        tracing::info!(
+            "🧪 Загружено {} запросов из фикстур {}",
+            store.articles.len(),
+            dir.display()
+        );
+
+        Ok(store)
+    }
+
+    fn key(query: &str, language: SupportedLanguage) -> String {
+        let query = query.split_whitespace().collect::<Vec<_>>().join(" ");
+        format!("{}:{}", language.code(), query.to_lowercase())
+    }
+
+    fn to_enriched(
+        &self,
+        article: FixtureArticle,
+        idx: usize,
+        language: SupportedLanguage,
+    ) -> EnrichedArticle {
+        let article_url = self.get_article_url(&article.basic_info.title, language);
+        let mut enriched = EnrichedArticle::new(
+            article.basic_info,
+            article.batch_info,
+            article.wikidata_description,
+            article_url,
+        );
+        enriched.relevance_index = Some(idx as i32 + 1);
+        enriched
+    }
+
+    fn lookup(&self, query: &str, language: SupportedLanguage) -> WikiResult<Vec<EnrichedArticle>> {
+        self.articles
+            .get(&Self::key(query, language))
+            .cloned()
+            .ok_or_else(|| WikiError::NoResults {
+                query: query.to_string(),
+            })
+    }
+}
+
+#[async_trait]
+impl WikipediaApi for FixtureStore {
+    async fn search(
+        &self,
+        query: &str,
+        language: SupportedLanguage,
+    ) -> WikiResult<Vec<WikipediaSearchItem>> {
+        Ok(self
+            .lookup(query, language)?
+            .into_iter()
+            .map(|article| article.basic_info)
+            .collect())
+    }
+
+    async fn get_batch_info(
+        &self,
+        pageids: Vec<u64>,
+        language: SupportedLanguage,
+    ) -> WikiResult<HashMap<u64, ArticleBatchInfo>> {
+        let prefix = format!("{}:", language.code());
+
+        Ok(self
+            .articles
+            .iter()
+            .filter(|(key, _)| key.starts_with(&prefix))
+            .flat_map(|(_, articles)| articles)
+            .filter_map(|article| {
+                let pageid = article.basic_info.pageid?;
+                let batch_info = article.batch_info.clone()?;
+                pageids.contains(&pageid).then_some((pageid, batch_info))
+            })
+            .collect())
+    }
+
+    /// Заголовки сравниваются без учёта регистра — перенаправлений в фикстурах нет.
+    async fn get_batch_info_by_titles(
+        &self,
+        titles: Vec<String>,
+        language: SupportedLanguage,
+    ) -> WikiResult<HashMap<String, ArticleBatchInfo>> {
+        let prefix = format!("{}:", language.code());
+        let articles: Vec<&EnrichedArticle> = self
+            .articles
+            .iter()
+            .filter(|(key, _)| key.starts_with(&prefix))
+            .flat_map(|(_, articles)| articles)
+            .collect();
+
+        Ok(titles
+            .into_iter()
+            .filter_map(|title| {
+                let article = articles.iter().find(|article| {
+                    article.basic_info.title.to_lowercase() == title.to_lowercase()
+                })?;
+                let batch_info = article.batch_info.clone()?;
+                Some((title, batch_info))
+            })
+            .collect())
+    }
+
+    async fn get_enriched_articles(
+        &self,
+        query: &str,
+        language: SupportedLanguage,
+    ) -> WikiResult<Vec<EnrichedArticle>> {
+        self.lookup(query, language)
+    }
+
+    async fn get_enriched_articles_optimized(
+        &self,
+        query: &str,
+        language: SupportedLanguage,
+    ) -> WikiResult<Vec<EnrichedArticle>> {
+        self.lookup(query, language)
+    }
+
+    fn get_article_url(&self, title: &str, language: SupportedLanguage) -> String {
+        format!(
+            "https://{}.{}/wiki/{}",
+            language.code(),
+            self.project.domain(),
+            urlencoding::encode(title)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bundled() -> FixtureStore {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+        FixtureStore::load(&dir, WikiProject::Wikipedia).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_bundled_fixtures_load() {
+        let store = bundled();
+
+        let articles = store
+            .get_enriched_articles_optimized("  Эйнштейн ", SupportedLanguage::Russian)
+            .await
+            .unwrap();
+        assert!(!articles.is_empty());
+        assert_eq!(articles[0].relevance_index, Some(1));
+        assert!(articles[0]
+            .article_url
+            .starts_with("https://ru.wikipedia.org/wiki/"));
+
+        let pageid = articles[0].basic_info.pageid.unwrap();
+        let batch = store
+            .get_batch_info(vec![pageid], SupportedLanguage::Russian)
+            .await
+            .unwrap();
+        assert!(batch.contains_key(&pageid));
+    }
+
+    #[tokio::test]
+    async fn test_missing_query_is_no_results() {
+        let store = bundled();
+        let result = store
+            .get_enriched_articles("нет такой фикстуры", SupportedLanguage::Russian)
+            .await;
+        assert!(matches!(result, Err(WikiError::NoResults { .. })));
+    }
+}
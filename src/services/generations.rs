@@ -0,0 +1,76 @@
+use moka::future::Cache;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use teloxide::types::UserId;
+
+/// Сколько помнить счётчик пользователя, который перестал печатать.
+const GENERATIONS_IDLE_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Для скольких пользователей хранить счётчики одновременно.
+const GENERATIONS_MAX_USERS: u64 = 100_000;
+
+/// Номера inline-запросов каждого пользователя. Пока пользователь печатает,
+/// Telegram присылает запрос на каждое изменение, и поиски по ним идут
+/// параллельно. Ответ отправляется, только если за время поиска не пришёл
+/// более новый запрос, — иначе поздно завершившийся старый поиск показал бы
+/// результаты для уже стёртого текста.
+pub struct QueryGenerations {
+    users: Cache<UserId, Arc<AtomicU64>>,
+}
+
+impl QueryGenerations {
+    pub fn new() -> Self {
+        Self {
+            users: Cache::builder()
+                .time_to_idle(GENERATIONS_IDLE_TTL)
+                .max_capacity(GENERATIONS_MAX_USERS)
+                .build(),
+        }
+    }
+
+    /// Регистрирует новый запрос пользователя и возвращает его номер.
+    pub async fn begin(&self, user: UserId) -> u64 {
+        self.counter(user).await.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    /// Остаётся ли запрос с номером `generation` последним у пользователя.
+    pub async fn is_current(&self, user: UserId, generation: u64) -> bool {
+        self.counter(user).await.load(Ordering::SeqCst) == generation
+    }
+
+    async fn counter(&self, user: UserId) -> Arc<AtomicU64> {
+        self.users
+            .get_with(user, async { Arc::new(AtomicU64::new(0)) })
+            .await
+    }
+}
+
+impl Default for QueryGenerations {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_only_latest_generation_is_current() {
+        let generations = QueryGenerations::new();
+        let user = UserId(42);
+
+        let first = generations.begin(user).await;
+        assert!(generations.is_current(user, first).await);
+
+        let second = generations.begin(user).await;
+        assert!(!generations.is_current(user, first).await);
+        assert!(generations.is_current(user, second).await);
+
+        // Запросы других пользователей не вытесняют друг друга
+        let other = generations.begin(UserId(7)).await;
+        assert!(generations.is_current(UserId(7), other).await);
+        assert!(generations.is_current(user, second).await);
+    }
+}
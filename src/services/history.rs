@@ -0,0 +1,84 @@
+use moka::future::Cache;
+use std::time::Duration;
+use teloxide::types::UserId;
+
+use crate::config::TelegramConfig;
+use crate::utils::normalize_whitespace;
+
+/// Сколько хранить историю пользователя, который не ищет.
+const SEARCH_HISTORY_IDLE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Для скольких пользователей хранить историю одновременно.
+const SEARCH_HISTORY_MAX_USERS: u64 = 10_000;
+
+/// Последние запросы каждого пользователя — для быстрого повтора из пустого
+/// inline-запроса. Хранится только в памяти и очищается `/clearhistory`.
+pub struct SearchHistory {
+    capacity: usize,
+    users: Cache<UserId, Vec<String>>,
+}
+
+impl SearchHistory {
+    /// Создаёт историю, если `search_history_size` больше нуля.
+    pub fn from_config(config: &TelegramConfig) -> Option<Self> {
+        (config.search_history_size > 0).then(|| Self::new(config.search_history_size))
+    }
+
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            users: Cache::builder()
+                .time_to_idle(SEARCH_HISTORY_IDLE_TTL)
+                .max_capacity(SEARCH_HISTORY_MAX_USERS)
+                .build(),
+        }
+    }
+
+    /// Запоминает запрос. Повторный запрос поднимается наверх, самый старый
+    /// вытесняется, когда история заполнена.
+    pub async fn record(&self, user: UserId, query: &str) {
+        let query = normalize_whitespace(query);
+        if query.is_empty() {
+            return;
+        }
+
+        let mut queries = self.users.get(&user).await.unwrap_or_default();
+        let lowercase = query.to_lowercase();
+        queries.retain(|existing| existing.to_lowercase() != lowercase);
+        queries.insert(0, query);
+        queries.truncate(self.capacity);
+        self.users.insert(user, queries).await;
+    }
+
+    /// Запросы пользователя, начиная с последнего.
+    pub async fn recent(&self, user: UserId) -> Vec<String> {
+        self.users.get(&user).await.unwrap_or_default()
+    }
+
+    pub async fn clear(&self, user: UserId) {
+        self.users.invalidate(&user).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_history_keeps_most_recent_first() {
+        let history = SearchHistory::new(3);
+        let user = UserId(42);
+        for query in ["Пушкин", "Einstein", "Berlin", "  einstein ", "Moscow"] {
+            history.record(user, query).await;
+        }
+
+        assert_eq!(
+            history.recent(user).await,
+            vec!["Moscow", "einstein", "Berlin"]
+        );
+        assert!(history.recent(UserId(7)).await.is_empty());
+
+        history.clear(user).await;
+        assert!(history.recent(user).await.is_empty());
+    }
+}
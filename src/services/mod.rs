@@ -1,5 +1,84 @@
+pub mod analytics;
+pub mod fixtures;
+pub mod generations;
+pub mod history;
+pub mod path_health;
+pub mod preferences;
+pub mod trending;
 pub mod wikidata;
 pub mod wikipedia;
 
+pub use analytics::*;
+pub use fixtures::*;
+pub use generations::*;
+pub use history::*;
+pub use path_health::*;
+pub use preferences::*;
+pub use trending::*;
 pub use wikidata::*;
 pub use wikipedia::*;
+
+use std::sync::Once;
+use std::time::Duration;
+
+use crate::config::AppConfig;
+use crate::errors::{WikiError, WikiResult};
+
+static DEFAULT_USER_AGENT_WARNING: Once = Once::new();
+
+/// HTTP-клиент для API Викимедиа с заданным таймаутом, общим User-Agent и
+/// настройками пула соединений. Правила Wikimedia требуют описательный User-Agent с
+/// контактами, поэтому непереопределённый User-Agent по умолчанию вызывает
+/// предупреждение.
+pub(crate) fn build_http_client(
+    config: &AppConfig,
+    timeout: Duration,
+) -> WikiResult<reqwest::Client> {
+    if config.wikipedia.has_default_user_agent() {
+        DEFAULT_USER_AGENT_WARNING.call_once(|| {
+            tracing::warn!(
+                "⚠️ Используется User-Agent по умолчанию. Укажите свои контакты в WIKI_USER_AGENT, \
+                 иначе Wikimedia может ограничить запросы"
+            );
+        });
+    }
+
+    let wikipedia = &config.wikipedia;
+    let mut builder = reqwest::Client::builder()
+        .timeout(timeout)
+        .user_agent(wikipedia.user_agent_header());
+
+    if let Some(max_idle) = wikipedia.pool_max_idle_per_host {
+        builder = builder.pool_max_idle_per_host(max_idle);
+    }
+    if let Some(idle_timeout) = wikipedia.pool_idle_timeout_secs {
+        builder = builder.pool_idle_timeout(Duration::from_secs(idle_timeout));
+    }
+    if wikipedia.http2_prior_knowledge {
+        builder = builder.http2_prior_knowledge();
+    }
+
+    builder
+        .build()
+        .map_err(|e| WikiError::internal(format!("Failed to create HTTP client: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_http_client_with_pool_settings() {
+        let mut config = AppConfig::for_tests();
+        assert!(build_http_client(&config, config.http_timeout()).is_ok());
+
+        config.wikipedia.pool_max_idle_per_host = Some(32);
+        config.wikipedia.pool_idle_timeout_secs = Some(90);
+        config.wikipedia.http2_prior_knowledge = true;
+        assert!(build_http_client(&config, config.http_timeout()).is_ok());
+
+        config.wikipedia.pool_max_idle_per_host = Some(0);
+        config.wikipedia.pool_idle_timeout_secs = Some(0);
+        assert!(build_http_client(&config, config.http_timeout()).is_ok());
+    }
+}
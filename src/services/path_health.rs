@@ -0,0 +1,157 @@
+use moka::future::Cache;
+use std::time::{Duration, Instant};
+
+use crate::config::languages::SupportedLanguage;
+
+/// Каким путём искать статьи: одним unified-запросом (`generator=search`)
+/// или обычным поиском с отдельным пакетным запросом сведений.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchPath {
+    Unified,
+    Legacy,
+}
+
+/// Состояние unified-пути для одного языка.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathStatus {
+    pub language: SupportedLanguage,
+    pub preferred: SearchPath,
+    /// Сбоев unified-пути подряд.
+    pub consecutive_failures: u32,
+    /// Через сколько unified-путь попробуют снова; `None`, если пауза прошла.
+    pub retry_in: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct UnifiedFailures {
+    consecutive: u32,
+    retry_at: Instant,
+}
+
+/// Здоровье unified-пути по языкам. После сбоя unified-путь пропускается на
+/// паузу, которая удваивается с каждым сбоем подряд до `max_cooldown`; когда
+/// пауза проходит, делается одна пробная попытка. Успех сбрасывает счётчик.
+#[derive(Clone)]
+pub struct PathHealth {
+    base_cooldown: Duration,
+    max_cooldown: Duration,
+    failures: Cache<SupportedLanguage, UnifiedFailures>,
+}
+
+impl PathHealth {
+    pub fn new(base_cooldown: Duration, max_cooldown: Duration) -> Self {
+        let max_cooldown = max_cooldown.max(base_cooldown);
+        Self {
+            base_cooldown,
+            max_cooldown,
+            // Сбои, после которых долго не было запросов, забываются
+            failures: Cache::builder()
+                .time_to_live(max_cooldown.saturating_mul(2))
+                .build(),
+        }
+    }
+
+    /// Каким путём искать сейчас.
+    pub async fn preferred(&self, language: SupportedLanguage) -> SearchPath {
+        match self.failures.get(&language).await {
+            Some(failures) if Instant::now() < failures.retry_at => SearchPath::Legacy,
+            _ => SearchPath::Unified,
+        }
+    }
+
+    /// Запоминает сбой unified-пути и возвращает паузу до следующей попытки.
+    pub async fn record_failure(&self, language: SupportedLanguage) -> Duration {
+        let consecutive = self
+            .failures
+            .get(&language)
+            .await
+            .map_or(1, |failures| failures.consecutive.saturating_add(1));
+        let cooldown = self
+            .base_cooldown
+            .saturating_mul(2u32.saturating_pow(consecutive - 1))
+            .min(self.max_cooldown);
+
+        self.failures
+            .insert(
+                language,
+                UnifiedFailures {
+                    consecutive,
+                    retry_at: Instant::now() + cooldown,
+                },
+            )
+            .await;
+        cooldown
+    }
+
+    pub async fn record_success(&self, language: SupportedLanguage) {
+        self.failures.invalidate(&language).await;
+    }
+
+    /// Языки, у которых были сбои unified-пути, — для отладки.
+    pub async fn statuses(&self) -> Vec<PathStatus> {
+        self.failures.run_pending_tasks().await;
+        let now = Instant::now();
+        let mut statuses: Vec<_> = self
+            .failures
+            .iter()
+            .map(|(language, failures)| {
+                let retry_in = failures.retry_at.checked_duration_since(now);
+                PathStatus {
+                    language: *language,
+                    preferred: if retry_in.is_some() {
+                        SearchPath::Legacy
+                    } else {
+                        SearchPath::Unified
+                    },
+                    consecutive_failures: failures.consecutive,
+                    retry_in,
+                }
+            })
+            .collect();
+        statuses.sort_by_key(|status| status.language.code());
+        statuses
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_repeated_failures_prefer_legacy_with_growing_pause() {
+        let health = PathHealth::new(Duration::from_millis(50), Duration::from_millis(120));
+        let language = SupportedLanguage::English;
+        assert_eq!(health.preferred(language).await, SearchPath::Unified);
+
+        assert_eq!(
+            health.record_failure(language).await,
+            Duration::from_millis(50)
+        );
+        assert_eq!(health.preferred(language).await, SearchPath::Legacy);
+        assert_eq!(
+            health.preferred(SupportedLanguage::Russian).await,
+            SearchPath::Unified
+        );
+
+        // Пауза прошла — пробная попытка через unified; её сбой удваивает паузу
+        tokio::time::sleep(Duration::from_millis(70)).await;
+        assert_eq!(health.preferred(language).await, SearchPath::Unified);
+        assert_eq!(
+            health.record_failure(language).await,
+            Duration::from_millis(100)
+        );
+        assert_eq!(
+            health.record_failure(language).await,
+            Duration::from_millis(120)
+        );
+
+        let statuses = health.statuses().await;
+        assert_eq!(statuses.len(), 1);
+        assert_eq!(statuses[0].preferred, SearchPath::Legacy);
+        assert_eq!(statuses[0].consecutive_failures, 3);
+
+        health.record_success(language).await;
+        assert_eq!(health.preferred(language).await, SearchPath::Unified);
+        assert!(health.statuses().await.is_empty());
+    }
+}
@@ -0,0 +1,115 @@
+use moka::future::Cache;
+use std::time::Duration;
+use teloxide::types::UserId;
+
+use crate::config::ResultStyle;
+
+/// Сколько помнить настройки пользователя, который не пользуется ботом.
+const PREFERENCES_IDLE_TTL: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Для скольких пользователей хранить настройки одновременно.
+const PREFERENCES_MAX_USERS: u64 = 100_000;
+
+/// Язык интерфейса бота (кнопки, подсказки, сообщения) — независимо от
+/// раздела Wikipedia, в котором идёт поиск.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UiLocale {
+    Russian,
+    English,
+}
+
+impl UiLocale {
+    /// Код локали в том виде, в каком его ждут функции форматирования.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Russian => "ru",
+            Self::English => "en",
+        }
+    }
+}
+
+impl std::str::FromStr for UiLocale {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "ru" => Ok(Self::Russian),
+            "en" => Ok(Self::English),
+            other => Err(format!("unknown UI locale '{other}', expected ru or en")),
+        }
+    }
+}
+
+/// Настройки пользователей, выбранные командами бота. Хранятся только в
+/// памяти: после перезапуска действуют значения из конфигурации.
+pub struct UserPreferences {
+    result_styles: Cache<UserId, ResultStyle>,
+    /// Язык интерфейса из `/ui`; язык поиска от него не зависит.
+    ui_locales: Cache<UserId, UiLocale>,
+}
+
+impl UserPreferences {
+    pub fn new() -> Self {
+        Self {
+            result_styles: Cache::builder()
+                .time_to_idle(PREFERENCES_IDLE_TTL)
+                .max_capacity(PREFERENCES_MAX_USERS)
+                .build(),
+            ui_locales: Cache::builder()
+                .time_to_idle(PREFERENCES_IDLE_TTL)
+                .max_capacity(PREFERENCES_MAX_USERS)
+                .build(),
+        }
+    }
+
+    /// Выбранный пользователем вид inline-результатов, если он его выбирал.
+    pub async fn result_style(&self, user: UserId) -> Option<ResultStyle> {
+        self.result_styles.get(&user).await
+    }
+
+    pub async fn set_result_style(&self, user: UserId, style: ResultStyle) {
+        self.result_styles.insert(user, style).await;
+    }
+
+    /// Выбранный пользователем язык интерфейса, если он его выбирал.
+    pub async fn ui_locale(&self, user: UserId) -> Option<UiLocale> {
+        self.ui_locales.get(&user).await
+    }
+
+    pub async fn set_ui_locale(&self, user: UserId, locale: UiLocale) {
+        self.ui_locales.insert(user, locale).await;
+    }
+}
+
+impl Default for UserPreferences {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_result_style_is_per_user() {
+        let preferences = UserPreferences::new();
+        assert_eq!(preferences.result_style(UserId(1)).await, None);
+
+        preferences
+            .set_result_style(UserId(1), ResultStyle::Compact)
+            .await;
+        assert_eq!(
+            preferences.result_style(UserId(1)).await,
+            Some(ResultStyle::Compact)
+        );
+        assert_eq!(preferences.result_style(UserId(2)).await, None);
+    }
+
+    #[test]
+    fn test_parse_ui_locale() {
+        assert_eq!(" EN ".parse::<UiLocale>(), Ok(UiLocale::English));
+        assert_eq!("ru".parse::<UiLocale>(), Ok(UiLocale::Russian));
+        assert!("de".parse::<UiLocale>().is_err());
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::config::TelegramConfig;
+use crate::utils::normalize_whitespace;
+
+/// На сколько интервалов делится окно: счётчики устаревают по одному
+/// интервалу, а не все сразу.
+const TRENDING_BUCKETS: u32 = 12;
+
+/// Счётчик частоты запросов за скользящее окно. Хранит только нормализованный
+/// текст запроса и число повторов — без пользователей и времени отдельных
+/// запросов. Общий для inline-режима (пишет) и команды `/trending` (читает).
+#[derive(Debug)]
+pub struct TrendingQueries {
+    window: Duration,
+    bucket_span: Duration,
+    buckets: Mutex<VecDeque<Bucket>>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    started: Instant,
+    counts: HashMap<String, u32>,
+}
+
+impl TrendingQueries {
+    /// Создаёт счётчик, если в конфигурации задано окно `trending_window_secs`.
+    pub fn from_config(config: &TelegramConfig) -> Option<Self> {
+        config
+            .trending_window_secs
+            .filter(|&secs| secs > 0)
+            .map(|secs| Self::new(Duration::from_secs(secs)))
+    }
+
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            bucket_span: window / TRENDING_BUCKETS,
+            buckets: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    pub fn record(&self, query: &str) {
+        self.record_at(query, Instant::now());
+    }
+
+    /// Самые частые запросы окна по убыванию частоты (при равенстве — по
+    /// алфавиту), не реже `min_count` раз и не больше `limit` штук.
+    pub fn top(&self, limit: usize, min_count: u32) -> Vec<(String, u32)> {
+        self.top_at(limit, min_count, Instant::now())
+    }
+
+    fn record_at(&self, query: &str, now: Instant) {
+        let query = normalize_whitespace(query).to_lowercase();
+        if query.is_empty() {
+            return;
+        }
+
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        self.evict_expired(&mut buckets, now);
+
+        let needs_new_bucket = buckets
+            .back()
+            .is_none_or(|bucket| now.duration_since(bucket.started) >= self.bucket_span);
+        if needs_new_bucket {
+            buckets.push_back(Bucket {
+                started: now,
+                counts: HashMap::new(),
+            });
+        }
+
+        if let Some(bucket) = buckets.back_mut() {
+            *bucket.counts.entry(query).or_default() += 1;
+        }
+    }
+
+    fn top_at(&self, limit: usize, min_count: u32, now: Instant) -> Vec<(String, u32)> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        self.evict_expired(&mut buckets, now);
+
+        let mut totals: HashMap<&str, u32> = HashMap::new();
+        for bucket in buckets.iter() {
+            for (query, count) in &bucket.counts {
+                *totals.entry(query).or_default() += count;
+            }
+        }
+
+        let mut top: Vec<(String, u32)> = totals
+            .into_iter()
+            .filter(|&(_, count)| count >= min_count)
+            .map(|(query, count)| (query.to_string(), count))
+            .collect();
+        top.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        top.truncate(limit);
+        top
+    }
+
+    fn evict_expired(&self, buckets: &mut VecDeque<Bucket>, now: Instant) {
+        while buckets
+            .front()
+            .is_some_and(|bucket| now.duration_since(bucket.started) >= self.window)
+        {
+            buckets.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_top_counts_normalized_queries() {
+        let trending = TrendingQueries::new(Duration::from_secs(3600));
+        let now = Instant::now();
+        for query in [
+            "Пушкин",
+            "  пушкин ",
+            "ПУШКИН",
+            "Einstein",
+            "Einstein",
+            "Berlin",
+        ] {
+            trending.record_at(query, now);
+        }
+
+        assert_eq!(
+            trending.top_at(10, 2, now),
+            vec![("пушкин".to_string(), 3), ("einstein".to_string(), 2)]
+        );
+        assert_eq!(trending.top_at(1, 1, now), vec![("пушкин".to_string(), 3)]);
+    }
+
+    #[test]
+    fn test_old_queries_leave_the_window() {
+        let trending = TrendingQueries::new(Duration::from_secs(120));
+        let start = Instant::now();
+        trending.record_at("old", start);
+        trending.record_at("old", start);
+        trending.record_at("fresh", start + Duration::from_secs(100));
+
+        let later = start + Duration::from_secs(130);
+        assert_eq!(
+            trending.top_at(10, 1, later),
+            vec![("fresh".to_string(), 1)]
+        );
+    }
+}
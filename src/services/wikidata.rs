@@ -1,11 +1,20 @@
 use async_trait::async_trait;
 use moka::future::Cache;
 use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
 
-use crate::config::AppConfig;
+use crate::config::{AppConfig, EnrichmentSource};
 use crate::errors::{WikiError, WikiResult};
-use crate::models::{SupportedLanguage, WikidataResponse, WikipediaLanguage};
-use crate::utils::clean_description;
+use crate::models::{
+    ClaimValue, Coordinates, SupportedLanguage, WikidataResponse, WikipediaLanguage,
+};
+use crate::utils::{clean_description, RetryPolicy};
+
+const WIKIDATA_API_BASE: &str = "https://www.wikidata.org";
+
+/// Ограничение `wbgetentities` на количество идентификаторов в одном запросе.
+const WIKIDATA_MAX_IDS_PER_REQUEST: usize = 50;
 
 #[async_trait]
 pub trait WikidataApi {
@@ -14,27 +23,96 @@ pub trait WikidataApi {
         wikidata_ids: Vec<String>,
         language: SupportedLanguage,
     ) -> WikiResult<HashMap<String, String>>;
+
+    async fn get_instance_of(
+        &self,
+        wikidata_ids: Vec<String>,
+    ) -> WikiResult<HashMap<String, Vec<String>>>;
+
+    async fn get_coordinates(
+        &self,
+        wikidata_ids: Vec<String>,
+    ) -> WikiResult<HashMap<String, Coordinates>>;
 }
 
 pub struct WikidataService {
     client: reqwest::Client,
+    retry: RetryPolicy,
+    api_base: String,
     cache: Cache<String, HashMap<String, String>>,
+    claims_cache: Cache<String, Arc<EntityClaimsMap>>,
+    /// Типизированные значения свойств из `get_claims`.
+    typed_claims_cache: Cache<String, Arc<HashMap<String, Vec<ClaimValue>>>>,
+    /// Ограничивает число одновременных запросов пачек (`max_concurrency`).
+    chunk_permits: Arc<Semaphore>,
+    enabled: bool,
+    enrichers: Vec<EnrichmentSource>,
+}
+
+/// Утверждения сущности, которые использует бот.
+#[derive(Debug, Clone, Default)]
+struct EntityClaims {
+    classes: Vec<String>,
+    coordinates: Option<Coordinates>,
 }
 
+type EntityClaimsMap = HashMap<String, EntityClaims>;
+
 impl WikidataService {
     pub fn new(config: AppConfig) -> WikiResult<Self> {
-        let client = reqwest::Client::builder()
-            .timeout(config.http_timeout())
-            .user_agent(&config.wikipedia.user_agent)
-            .build()
-            .map_err(|e| WikiError::internal(format!("Failed to create HTTP client: {e}")))?;
+        let client = super::build_http_client(&config, config.wikidata_timeout())?;
 
         let cache = Cache::builder()
-            .time_to_live(config.cache_ttl())
-            .max_capacity(config.cache.max_capacity)
+            .time_to_live(config.wikidata_cache_ttl())
+            .max_capacity(config.wikidata.cache_capacity)
+            .build();
+
+        let claims_cache = Cache::builder()
+            .time_to_live(config.wikidata_cache_ttl())
+            .max_capacity(config.wikidata.cache_capacity)
+            .build();
+
+        let typed_claims_cache = Cache::builder()
+            .time_to_live(config.wikidata_cache_ttl())
+            .max_capacity(config.wikidata.cache_capacity)
             .build();
 
-        Ok(Self { client, cache })
+        let service = Self {
+            client,
+            retry: RetryPolicy::from_config(&config.retry),
+            api_base: WIKIDATA_API_BASE.to_string(),
+            cache,
+            claims_cache,
+            typed_claims_cache,
+            chunk_permits: Arc::new(Semaphore::new(config.wikidata.max_concurrency.max(1))),
+            enabled: config.wikidata.enabled,
+            enrichers: config.wikidata.enrichers.clone(),
+        };
+
+        Ok(match config.wikidata.api_base {
+            Some(api_base) => service.with_api_base(api_base),
+            None => service,
+        })
+    }
+
+    /// Направляет запросы на другой экземпляр Wikibase API (зеркало или тестовый сервер).
+    pub fn with_api_base(mut self, api_base: impl Into<String>) -> Self {
+        self.api_base = api_base.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Включено ли обогащение результатов данными Wikidata (`ENABLE_WIKIDATA`).
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Какие данные Wikidata добавлять к результатам поиска (`WIKIDATA_ENRICHERS`).
+    pub fn enrichers(&self) -> &[EnrichmentSource] {
+        &self.enrichers
+    }
+
+    fn api_url(&self) -> String {
+        format!("{}/w/api.php", self.api_base)
     }
 
     fn cache_key(&self, wikidata_ids: &[String], language: SupportedLanguage) -> String {
@@ -43,6 +121,12 @@ impl WikidataService {
         format!("wikidata:{}:{:?}", language.code(), sorted_ids)
     }
 
+    fn claims_cache_key(&self, wikidata_ids: &[String]) -> String {
+        let mut sorted_ids = wikidata_ids.to_vec();
+        sorted_ids.sort();
+        format!("wikidata:claims:{sorted_ids:?}")
+    }
+
     async fn get_descriptions_internal(
         &self,
         wikidata_ids: Vec<String>,
@@ -52,45 +136,203 @@ impl WikidataService {
             return Ok(HashMap::new());
         }
 
-        const WIKIDATA_API_URL: &str = "https://www.wikidata.org/w/api.php";
+        let language_chain = Self::description_language_chain(language);
+        let languages_str = language_chain.join("|");
+
+        let wikidata_response = self
+            .fetch_entities(
+                &wikidata_ids,
+                &[("props", "descriptions"), ("languages", &languages_str)],
+            )
+            .await?;
+
+        Ok(Self::parse_descriptions(wikidata_response, &language_chain))
+    }
+
+    /// Запрашивает сущности пачками по [`WIKIDATA_MAX_IDS_PER_REQUEST`] параллельно,
+    /// но не больше `max_concurrency` пачек одновременно, и объединяет ответы в один.
+    async fn fetch_entities(
+        &self,
+        wikidata_ids: &[String],
+        extra_params: &[(&str, &str)],
+    ) -> WikiResult<WikidataResponse> {
+        let requests = wikidata_ids
+            .chunks(WIKIDATA_MAX_IDS_PER_REQUEST)
+            .map(|chunk| self.fetch_entities_chunk(chunk, extra_params));
+
+        let responses = futures::future::try_join_all(requests).await?;
+
+        let entities = responses
+            .into_iter()
+            .flat_map(|response| response.entities)
+            .collect();
+
+        Ok(WikidataResponse { entities })
+    }
+
+    async fn fetch_entities_chunk(
+        &self,
+        wikidata_ids: &[String],
+        extra_params: &[(&str, &str)],
+    ) -> WikiResult<WikidataResponse> {
+        let _permit = self
+            .chunk_permits
+            .acquire()
+            .await
+            .expect("Wikidata semaphore is never closed");
 
         let ids_str = wikidata_ids.join("|");
 
-        let params = [
+        let mut params = vec![
             ("action", "wbgetentities"),
             ("format", "json"),
-            ("ids", &ids_str),
-            ("props", "descriptions"),
-            ("languages", language.code()),
+            ("ids", ids_str.as_str()),
         ];
+        params.extend_from_slice(extra_params);
 
+        let url = self.api_url();
         let response = self
-            .client
-            .get(WIKIDATA_API_URL)
-            .query(&params)
-            .send()
+            .retry
+            .execute(|| async {
+                let response = self.client.get(&url).query(&params).send().await?;
+                if !response.status().is_success() {
+                    return Err(WikiError::from_response(response));
+                }
+                Ok(response)
+            })
             .await?;
 
-        if !response.status().is_success() {
-            return Err(WikiError::Network(response.error_for_status().unwrap_err()));
-        }
+        Ok(response.json().await?)
+    }
 
-        let wikidata_response: WikidataResponse = response.json().await?;
+    /// Запрошенный язык, затем английский как запасной вариант.
+    fn description_language_chain(language: SupportedLanguage) -> Vec<&'static str> {
+        let mut chain = vec![language.code()];
+        if language != SupportedLanguage::English {
+            chain.push(SupportedLanguage::English.code());
+        }
+        chain
+    }
 
+    /// Берёт для каждой сущности первое непустое описание по цепочке языков.
+    fn parse_descriptions(
+        response: WikidataResponse,
+        language_chain: &[&str],
+    ) -> HashMap<String, String> {
         let mut descriptions = HashMap::new();
 
-        for (entity_id, entity) in wikidata_response.entities {
-            if let Some(entity_descriptions) = entity.descriptions {
-                if let Some(description) = entity_descriptions.get(language.code()) {
-                    let cleaned_description = clean_description(&description.value);
-                    if !cleaned_description.is_empty() {
-                        descriptions.insert(entity_id, cleaned_description);
-                    }
-                }
+        for (entity_id, entity) in response.entities {
+            let Some(entity_descriptions) = entity.descriptions else {
+                continue;
+            };
+
+            let description = language_chain
+                .iter()
+                .filter_map(|code| entity_descriptions.get(*code))
+                .map(|description| clean_description(&description.value))
+                .find(|description| !description.is_empty());
+
+            if let Some(description) = description {
+                descriptions.insert(entity_id, description);
             }
         }
 
-        Ok(descriptions)
+        descriptions
+    }
+
+    /// P31 и P625 сущностей из одного запроса `props=claims`. Одновременные
+    /// вызовы с тем же набором идентификаторов ждут один и тот же запрос.
+    async fn get_entity_claims(
+        &self,
+        wikidata_ids: Vec<String>,
+    ) -> WikiResult<Arc<EntityClaimsMap>> {
+        let cache_key = self.claims_cache_key(&wikidata_ids);
+
+        self.claims_cache
+            .try_get_with(cache_key, async {
+                let wikidata_response = self
+                    .fetch_entities(&wikidata_ids, &[("props", "claims")])
+                    .await?;
+                Ok(Arc::new(Self::parse_claims(wikidata_response)))
+            })
+            .await
+            .map_err(WikiError::from_shared)
+    }
+
+    /// Даты и количества из утверждений сущности `id` для свойств
+    /// `properties`, например P1082 (население) или P571 (дата основания).
+    /// Свойства без значений этих типов в результат не попадают.
+    pub async fn get_claims(
+        &self,
+        id: &str,
+        properties: &[&str],
+    ) -> WikiResult<HashMap<String, Vec<ClaimValue>>> {
+        if properties.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let mut sorted_properties = properties.to_vec();
+        sorted_properties.sort_unstable();
+        let cache_key = format!("wikidata:typed:{id}:{}", sorted_properties.join("|"));
+
+        let claims = self
+            .typed_claims_cache
+            .try_get_with(cache_key, async {
+                let response = self
+                    .fetch_entities(&[id.to_string()], &[("props", "claims")])
+                    .await?;
+                let claims = response
+                    .entities
+                    .get(id)
+                    .map(|entity| {
+                        properties
+                            .iter()
+                            .map(|property| (property.to_string(), entity.claim_values(property)))
+                            .filter(|(_, values)| !values.is_empty())
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                Ok(Arc::new(claims))
+            })
+            .await
+            .map_err(WikiError::from_shared)?;
+
+        Ok(claims.as_ref().clone())
+    }
+
+    /// Оставляет из утверждений только нужные боту: классы P31 и земные координаты P625.
+    fn parse_claims(response: WikidataResponse) -> EntityClaimsMap {
+        response
+            .entities
+            .into_iter()
+            .map(|(entity_id, entity)| {
+                let claims = EntityClaims {
+                    classes: entity.entity_ids_for("P31"),
+                    coordinates: entity.earth_coordinates(),
+                };
+                (entity_id, claims)
+            })
+            .collect()
+    }
+
+    /// Собирает значения P31 для каждой сущности. Сущности без P31 в результат не попадают.
+    fn instance_of(claims: &EntityClaimsMap) -> HashMap<String, Vec<String>> {
+        claims
+            .iter()
+            .filter(|(_, claims)| !claims.classes.is_empty())
+            .map(|(entity_id, claims)| (entity_id.clone(), claims.classes.clone()))
+            .collect()
+    }
+
+    /// Собирает земные координаты P625. Сущности без них в результат не попадают.
+    fn coordinates(claims: &EntityClaimsMap) -> HashMap<String, Coordinates> {
+        claims
+            .iter()
+            .filter_map(|(entity_id, claims)| {
+                let coordinates = claims.coordinates.clone()?;
+                Some((entity_id.clone(), coordinates))
+            })
+            .collect()
     }
 }
 
@@ -118,6 +360,30 @@ impl WikidataApi for WikidataService {
 
         Ok(descriptions)
     }
+
+    async fn get_instance_of(
+        &self,
+        wikidata_ids: Vec<String>,
+    ) -> WikiResult<HashMap<String, Vec<String>>> {
+        if wikidata_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let claims = self.get_entity_claims(wikidata_ids).await?;
+        Ok(Self::instance_of(&claims))
+    }
+
+    async fn get_coordinates(
+        &self,
+        wikidata_ids: Vec<String>,
+    ) -> WikiResult<HashMap<String, Coordinates>> {
+        if wikidata_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let claims = self.get_entity_claims(wikidata_ids).await?;
+        Ok(Self::coordinates(&claims))
+    }
 }
 
 pub async fn get_wikidata_descriptions_batch_lang(
@@ -138,8 +404,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_empty_wikidata_ids() {
-        std::env::set_var("BOT_TOKEN", "test_token_123");
-        let config = AppConfig::from_env().unwrap();
+        let config = AppConfig::for_tests();
         let service = WikidataService::new(config).unwrap();
 
         let result = service
@@ -151,8 +416,7 @@ mod tests {
 
     #[test]
     fn test_cache_key_generation() {
-        std::env::set_var("BOT_TOKEN", "test_token_123");
-        let config = AppConfig::from_env().unwrap();
+        let config = AppConfig::for_tests();
         let service = WikidataService::new(config).unwrap();
 
         let key1 = service.cache_key(
@@ -172,4 +436,289 @@ mod tests {
         );
         assert_ne!(key1, key3); // Разные языки
     }
+
+    #[test]
+    fn test_descriptions_fall_back_to_english() {
+        let json = r#"{
+            "entities": {
+                "Q1": {
+                    "descriptions": {
+                        "uk": {"language": "uk", "value": "столиця України"},
+                        "en": {"language": "en", "value": "capital of Ukraine"}
+                    }
+                },
+                "Q2": {
+                    "descriptions": {
+                        "en": {"language": "en", "value": "third planet from the Sun"}
+                    }
+                },
+                "Q3": {"descriptions": {}}
+            }
+        }"#;
+
+        let response: WikidataResponse = serde_json::from_str(json).unwrap();
+        let chain = WikidataService::description_language_chain(SupportedLanguage::Ukrainian);
+        assert_eq!(chain, vec!["uk", "en"]);
+
+        let descriptions = WikidataService::parse_descriptions(response, &chain);
+
+        assert_eq!(descriptions["Q1"], "столиця України");
+        assert_eq!(descriptions["Q2"], "third planet from the Sun");
+        assert!(!descriptions.contains_key("Q3"));
+    }
+
+    #[test]
+    fn test_description_language_chain_for_english() {
+        assert_eq!(
+            WikidataService::description_language_chain(SupportedLanguage::English),
+            vec!["en"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_descriptions_are_fetched_in_chunks() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::Any)
+            .with_body_from_request(|request| {
+                let url = url::Url::parse(&format!("http://localhost{}", request.path_and_query()))
+                    .unwrap();
+                let ids = url
+                    .query_pairs()
+                    .find(|(key, _)| key == "ids")
+                    .map(|(_, value)| value.into_owned())
+                    .unwrap();
+                assert!(ids.split('|').count() <= WIKIDATA_MAX_IDS_PER_REQUEST);
+
+                let entities: serde_json::Map<String, serde_json::Value> = ids
+                    .split('|')
+                    .map(|id| {
+                        let entity = serde_json::json!({
+                            "descriptions": {"en": {"language": "en", "value": format!("entity {id}")}}
+                        });
+                        (id.to_string(), entity)
+                    })
+                    .collect();
+
+                serde_json::json!({ "entities": entities }).to_string().into_bytes()
+            })
+            .expect(2)
+            .create_async()
+            .await;
+
+        let config = AppConfig::for_tests();
+        let service = WikidataService::new(config)
+            .unwrap()
+            .with_api_base(server.url());
+
+        let ids: Vec<String> = (1..=75).map(|n| format!("Q{n}")).collect();
+        let descriptions = service
+            .get_descriptions(ids.clone(), SupportedLanguage::English)
+            .await
+            .unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(descriptions.len(), 75);
+        for id in &ids {
+            assert_eq!(descriptions[id], format!("entity {id}"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_claims_decodes_requested_properties() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("ids".into(), "Q649".into()),
+                mockito::Matcher::UrlEncoded("props".into(), "claims".into()),
+            ]))
+            .with_body(
+                r#"{"entities": {"Q649": {"claims": {
+                    "P1082": [{"mainsnak": {"datavalue": {"type": "quantity",
+                        "value": {"amount": "+13149803", "unit": "1"}}}}],
+                    "P571": [{"mainsnak": {"datavalue": {"type": "time",
+                        "value": {"time": "+1147-00-00T00:00:00Z", "precision": 9}}}}],
+                    "P31": [{"mainsnak": {"datavalue": {"type": "wikibase-entityid",
+                        "value": {"id": "Q515"}}}}]
+                }}}}"#,
+            )
+            .expect(1)
+            .create_async()
+            .await;
+
+        let service = WikidataService::new(AppConfig::for_tests())
+            .unwrap()
+            .with_api_base(server.url());
+
+        let claims = service
+            .get_claims("Q649", &["P1082", "P571", "P31"])
+            .await
+            .unwrap();
+        assert_eq!(claims["P1082"][0].format(Some("ru")), "13 149 803");
+        assert_eq!(claims["P571"][0].format(Some("en")), "1147");
+        // P31 ссылается на сущность, а не на дату или количество
+        assert!(!claims.contains_key("P31"));
+
+        // Повторный запрос берётся из кэша
+        service
+            .get_claims("Q649", &["P571", "P1082", "P31"])
+            .await
+            .unwrap();
+        mock.assert_async().await;
+    }
+
+    #[test]
+    fn test_parse_instance_of() {
+        let json = r#"{
+            "entities": {
+                "Q42": {
+                    "claims": {
+                        "P31": [{"mainsnak": {"datavalue": {"value": {"entity-type": "item", "id": "Q5"}}}}]
+                    }
+                },
+                "Q649": {
+                    "claims": {
+                        "P31": [
+                            {"mainsnak": {"datavalue": {"value": {"id": "Q1549591"}}}},
+                            {"mainsnak": {"datavalue": {"value": {"id": "Q515"}}}},
+                            {"mainsnak": {"snaktype": "novalue"}}
+                        ]
+                    }
+                },
+                "Q1": {"claims": {}},
+                "Q404": {"missing": ""}
+            }
+        }"#;
+
+        let response: WikidataResponse = serde_json::from_str(json).unwrap();
+        let instance_of = WikidataService::instance_of(&WikidataService::parse_claims(response));
+
+        assert_eq!(instance_of.len(), 2);
+        assert_eq!(instance_of["Q42"], vec!["Q5".to_string()]);
+        assert_eq!(
+            instance_of["Q649"],
+            vec!["Q1549591".to_string(), "Q515".to_string()]
+        );
+        assert!(!instance_of.contains_key("Q1"));
+        assert!(!instance_of.contains_key("Q404"));
+    }
+
+    #[test]
+    fn test_parse_p625_coordinates() {
+        let json = r#"{
+            "entities": {
+                "Q649": {
+                    "claims": {
+                        "P625": [{
+                            "mainsnak": {
+                                "snaktype": "value",
+                                "property": "P625",
+                                "datavalue": {
+                                    "value": {
+                                        "latitude": 55.75583333,
+                                        "longitude": 37.61730556,
+                                        "altitude": null,
+                                        "precision": 0.00027777777777778,
+                                        "globe": "http://www.wikidata.org/entity/Q2"
+                                    },
+                                    "type": "globecoordinate"
+                                }
+                            }
+                        }]
+                    }
+                },
+                "Q3303": {
+                    "claims": {
+                        "P625": [{
+                            "mainsnak": {
+                                "datavalue": {
+                                    "value": {
+                                        "latitude": 0.6875,
+                                        "longitude": 23.43,
+                                        "globe": "http://www.wikidata.org/entity/Q405"
+                                    }
+                                }
+                            }
+                        }]
+                    }
+                },
+                "Q1": {"claims": {}}
+            }
+        }"#;
+
+        let response: WikidataResponse = serde_json::from_str(json).unwrap();
+        let coordinates = WikidataService::coordinates(&WikidataService::parse_claims(response));
+
+        assert_eq!(coordinates.len(), 1);
+        let moscow = &coordinates["Q649"];
+        assert!((moscow.lat - 55.75583333).abs() < 1e-9);
+        assert!((moscow.lon - 37.61730556).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_chunk_requests_respect_max_concurrency() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+
+        // Сервер считает одновременно обрабатываемые запросы и отвечает с задержкой
+        let server = {
+            let in_flight = in_flight.clone();
+            let max_in_flight = max_in_flight.clone();
+            tokio::spawn(async move {
+                loop {
+                    let (mut socket, _) = listener.accept().await.unwrap();
+                    let in_flight = in_flight.clone();
+                    let max_in_flight = max_in_flight.clone();
+                    tokio::spawn(async move {
+                        let mut request = Vec::new();
+                        let mut buffer = [0u8; 4096];
+                        while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                            let read = socket.read(&mut buffer).await.unwrap();
+                            if read == 0 {
+                                return;
+                            }
+                            request.extend_from_slice(&buffer[..read]);
+                        }
+
+                        let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                        max_in_flight.fetch_max(current, Ordering::SeqCst);
+                        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                        in_flight.fetch_sub(1, Ordering::SeqCst);
+
+                        let body = r#"{"entities": {}}"#;
+                        let response = format!(
+                            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+                             Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                            body.len()
+                        );
+                        socket.write_all(response.as_bytes()).await.unwrap();
+                    });
+                }
+            })
+        };
+
+        let mut config = AppConfig::for_tests();
+        config.wikidata.max_concurrency = 2;
+        let service = WikidataService::new(config)
+            .unwrap()
+            .with_api_base(format!("http://{address}"));
+
+        let ids: Vec<String> = (1..=WIKIDATA_MAX_IDS_PER_REQUEST * 6)
+            .map(|id| format!("Q{id}"))
+            .collect();
+        service
+            .get_descriptions(ids, SupportedLanguage::English)
+            .await
+            .unwrap();
+
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 2);
+        server.abort();
+    }
 }
@@ -1,14 +1,91 @@
 use async_trait::async_trait;
 use moka::future::Cache;
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use crate::config::{AppConfig, WikipediaConfig};
+use crate::config::projects::WikiProject;
+use crate::config::{AppConfig, ExtractFormat, WikipediaConfig};
 use crate::errors::{WikiError, WikiResult};
 use crate::models::{
-    ArticleBatchInfo, Coordinates, EnrichedArticle, SupportedLanguage, UnifiedWikipediaResponse,
-    WikipediaBatchResponse, WikipediaLanguage, WikipediaSearchItem, WikipediaSearchResponse,
+    first_definition, parse_infobox, quality_from_assessments, ArticleBatchInfo, ArticlePhoto,
+    Coordinates, Definition, EnrichedArticle, InterwikiHit, ParsedQuery, Section,
+    SupportedLanguage, UnifiedWikipediaPage, UnifiedWikipediaResponse, WikipediaBatchResponse,
+    WikipediaCategory, WikipediaLanguage, WikipediaPageInfo, WikipediaParse,
+    WikipediaParseResponse, WikipediaSearchItem, WikipediaSearchResponse, WikipediaThumbnail,
 };
-use crate::utils::clean_html;
+use crate::services::{FixtureStore, PathHealth, PathStatus, SearchPath};
+use crate::utils::is_retryable;
+use crate::utils::{
+    clean_html, clean_snippet, html_to_plain_text, normalize_whitespace, sanitize_search_query,
+    strip_extract_noise, strip_leading_parenthetical, strip_namespace_prefix, BackgroundTasks,
+    RetryPolicy,
+};
+
+/// Адрес языкового раздела по умолчанию; `{lang}` заменяется кодом языка,
+/// `{project}` заменяется доменом проекта (`wikipedia.org`, `wiktionary.org`).
+const WIKIMEDIA_API_BASE: &str = "https://{lang}.{project}";
+
+/// Пауза перед прогревом кэша, чтобы он не конкурировал с запуском бота.
+const CACHE_WARMUP_DELAY: Duration = Duration::from_secs(5);
+
+/// Сколько запросов прогрева выполняется одновременно.
+const CACHE_WARMUP_CONCURRENCY: usize = 2;
+
+/// Сколько не пробовать unified-запрос после его сбоя; каждый следующий сбой
+/// подряд удваивает паузу.
+const UNIFIED_FAILURE_COOLDOWN: Duration = Duration::from_secs(60);
+
+/// Наибольшая пауза unified-запроса после сбоев подряд.
+const UNIFIED_FAILURE_MAX_COOLDOWN: Duration = Duration::from_secs(15 * 60);
+
+/// Сколько считать фоновое обновление результата выполняющимся, если задача
+/// так и не завершилась.
+const UNIFIED_REFRESH_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Ширина миниатюр статей (`pithumbsize`).
+const THUMBNAIL_SIZE: &str = "300";
+
+/// Та же ширина числом — для миниатюр, собранных из исходного изображения.
+const THUMBNAIL_WIDTH: u32 = 300;
+
+/// Наибольшая ширина фото для фото-результатов inline-режима.
+const PHOTO_WIDTH: u32 = 1280;
+
+/// Длина extract'а в unified-запросе (`exchars`), если не заданы
+/// `extract_sentences`.
+const EXTRACT_CHARS: &str = "400";
+
+/// Разделы Википедии с расширением PageAssessments (`prop=pageassessments`).
+/// В остальных разделах оценки не запрашиваются.
+const PAGE_ASSESSMENT_LANGUAGES: &[SupportedLanguage] = &[SupportedLanguage::English];
+
+/// `prop` для статей Википедии вместе с оценками качества.
+const PAGE_PROPS_WITH_ASSESSMENTS: &str =
+    "extracts|pageimages|pageprops|coordinates|categories|pageassessments";
+
+/// Результат unified-поиска в кэше вместе со временем получения — для
+/// мягкого TTL.
+#[derive(Clone)]
+struct CachedArticles {
+    articles: Vec<EnrichedArticle>,
+    /// Сколько всего статей нашёл поиск (`searchinfo.totalhits`), если известно.
+    total_hits: Option<u64>,
+    /// Результаты из других проектов Викимедиа (`query.interwiki`).
+    interwiki: Vec<InterwikiHit>,
+    fetched_at: Instant,
+}
+
+impl CachedArticles {
+    fn new(articles: Vec<EnrichedArticle>) -> Self {
+        Self {
+            articles,
+            total_hits: None,
+            interwiki: Vec::new(),
+            fetched_at: Instant::now(),
+        }
+    }
+}
 
 #[async_trait]
 pub trait WikipediaApi {
@@ -24,6 +101,16 @@ pub trait WikipediaApi {
         language: SupportedLanguage,
     ) -> WikiResult<HashMap<u64, ArticleBatchInfo>>;
 
+    /// То же, что `get_batch_info`, но по заголовкам: когда точные названия
+    /// уже известны, не нужен поисковый запрос ради pageid. Заголовки
+    /// нормализуются и проходят перенаправления на стороне MediaWiki, а ключи
+    /// результата — заголовки в том виде, в котором их запросили.
+    async fn get_batch_info_by_titles(
+        &self,
+        titles: Vec<String>,
+        language: SupportedLanguage,
+    ) -> WikiResult<HashMap<String, ArticleBatchInfo>>;
+
     async fn get_enriched_articles(
         &self,
         query: &str,
@@ -39,693 +126,4073 @@ pub trait WikipediaApi {
     fn get_article_url(&self, title: &str, language: SupportedLanguage) -> String;
 }
 
+/// Заполненность одного кэша.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheUsage {
+    pub capacity: Option<u64>,
+    pub entries: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheStats {
+    pub search: CacheUsage,
+    pub batch: CacheUsage,
+    pub unified: CacheUsage,
+    /// Языки, где unified-путь недавно давал сбои, и выбранный для них путь.
+    pub paths: Vec<PathStatus>,
+}
+
+/// Клонирование дешёвое: кэши и HTTP-клиент общие у всех копий.
+#[derive(Clone)]
 pub struct WikipediaService {
     client: reqwest::Client,
+    retry: RetryPolicy,
     config: WikipediaConfig,
+    project: WikiProject,
+    api_base: String,
+    fixtures: Option<Arc<FixtureStore>>,
     search_cache: Cache<String, Vec<WikipediaSearchItem>>,
     batch_cache: Cache<String, HashMap<u64, ArticleBatchInfo>>,
-    unified_cache: Cache<String, Vec<EnrichedArticle>>,
+    /// Сведения о статьях, запрошенных по заголовкам; ключ — запрошенный заголовок.
+    title_batch_cache: Cache<String, HashMap<String, ArticleBatchInfo>>,
+    unified_cache: Cache<String, CachedArticles>,
+    /// Определения из Викисловаря; `None` — у слова нет статьи или толкования.
+    definition_cache: Cache<String, Option<Definition>>,
+    /// См. `CacheConfig::soft_ttl_secs`.
+    soft_ttl: Option<Duration>,
+    /// Ключи unified-кэша, для которых уже идёт фоновое обновление.
+    unified_refreshing: Cache<String, ()>,
+    /// Языки, для которых unified-запрос недавно не удался: пока идёт пауза,
+    /// сразу идём обычным поиском, не тратя лишний запрос.
+    path_health: PathHealth,
+    /// Фоновые задачи сервиса; останавливаются `BackgroundTasks::shutdown`.
+    tasks: BackgroundTasks,
 }
 
 impl WikipediaService {
     pub fn new(config: AppConfig) -> WikiResult<Self> {
-        let client = reqwest::Client::builder()
-            .timeout(config.http_timeout())
-            .user_agent(&config.wikipedia.user_agent)
-            .build()
-            .map_err(|e| WikiError::internal(format!("Failed to create HTTP client: {e}")))?;
+        let client = super::build_http_client(&config, config.http_timeout())?;
 
         let search_cache = Cache::builder()
             .time_to_live(config.cache_ttl())
-            .max_capacity(config.cache.max_capacity)
+            .max_capacity(config.search_cache_capacity())
             .build();
 
         let batch_cache = Cache::builder()
             .time_to_live(config.cache_ttl())
-            .max_capacity(config.cache.max_capacity / 2)
+            .max_capacity(config.batch_cache_capacity())
+            .build();
+
+        let title_batch_cache = Cache::builder()
+            .time_to_live(config.cache_ttl())
+            .max_capacity(config.batch_cache_capacity())
             .build();
 
         let unified_cache = Cache::builder()
             .time_to_live(config.cache_ttl())
-            .max_capacity(config.cache.max_capacity / 4)
+            .max_capacity(config.unified_cache_capacity())
+            .build();
+
+        let definition_cache = Cache::builder()
+            .time_to_live(config.cache_ttl())
+            .max_capacity(config.search_cache_capacity())
             .build();
 
+        let api_base = config
+            .wikipedia
+            .api_base
+            .clone()
+            .unwrap_or_else(|| WIKIMEDIA_API_BASE.to_string());
+
+        let fixtures = config
+            .wikipedia
+            .fixtures_dir
+            .as_deref()
+            .map(|dir| FixtureStore::load(dir, WikiProject::default()).map(Arc::new))
+            .transpose()?;
+
+        let soft_ttl = config.cache_soft_ttl();
+
         Ok(Self {
             client,
+            retry: RetryPolicy::from_config(&config.retry),
             config: config.wikipedia,
+            project: WikiProject::default(),
+            api_base: api_base.trim_end_matches('/').to_string(),
+            fixtures,
             search_cache,
             batch_cache,
+            title_batch_cache,
             unified_cache,
+            definition_cache,
+            soft_ttl,
+            unified_refreshing: Cache::builder()
+                .time_to_live(UNIFIED_REFRESH_TIMEOUT)
+                .build(),
+            path_health: PathHealth::new(UNIFIED_FAILURE_COOLDOWN, UNIFIED_FAILURE_MAX_COOLDOWN),
+            tasks: BackgroundTasks::new(),
         })
     }
 
-    fn search_cache_key(&self, query: &str, language: SupportedLanguage) -> String {
-        format!("search:{}:{}", language.code(), query.to_lowercase())
+    /// Запускает фоновые задачи в общем реестре, чтобы остановить их вместе
+    /// с остальными при завершении бота.
+    pub fn with_tasks(mut self, tasks: BackgroundTasks) -> Self {
+        self.tasks = tasks;
+        self
     }
 
-    fn batch_cache_key(&self, pageids: &[u64], language: SupportedLanguage) -> String {
-        let mut sorted_pageids = pageids.to_vec();
-        sorted_pageids.sort();
-        format!("batch:{}:{:?}", language.code(), sorted_pageids)
+    pub fn tasks(&self) -> &BackgroundTasks {
+        &self.tasks
     }
 
-    async fn search_internal(
-        &self,
-        query: &str,
-        language: SupportedLanguage,
-    ) -> WikiResult<Vec<WikipediaSearchItem>> {
-        let url = format!("https://{}.wikipedia.org/w/api.php", language.code());
+    /// Направляет запросы на зеркало или тестовый сервер вместо `https://{lang}.wikipedia.org`.
+    /// Плейсхолдеры `{lang}` и `{project}` в адресе заменяются кодом языка и
+    /// доменом проекта; без них все языки и проекты обслуживаются одним адресом.
+    pub fn with_api_base(mut self, api_base: impl Into<String>) -> Self {
+        self.api_base = api_base.into().trim_end_matches('/').to_string();
+        self
+    }
 
-        let params = [
-            ("action", "query"),
-            ("list", "search"),
-            ("srsearch", query),
-            ("format", "json"),
-            ("srlimit", &self.config.max_search_results.to_string()),
-            ("srprop", "snippet|titlesnippet|size|wordcount|timestamp"),
-        ];
+    /// Переключает сервис на другой проект Викимедиа (например, Викисловарь).
+    /// У каждого проекта свой экземпляр сервиса и свои кэши. Фикстуры содержат
+    /// только статьи Wikipedia, поэтому для других проектов отключаются.
+    pub fn with_project(mut self, project: WikiProject) -> Self {
+        self.project = project;
+        if project != WikiProject::Wikipedia {
+            self.fixtures = None;
+        }
+        self
+    }
 
-        let response = self.client.get(&url).query(&params).send().await?;
+    /// Паузы unified-пути после сбоев вместо стандартных минут — для тестов.
+    #[cfg(test)]
+    pub(crate) fn with_unified_cooldown(mut self, base: Duration, max: Duration) -> Self {
+        self.path_health = PathHealth::new(base, max);
+        self
+    }
 
-        if !response.status().is_success() {
-            return Err(WikiError::Network(response.error_for_status().unwrap_err()));
+    /// Отвечает ли сервис из фикстур вместо обращения к сети.
+    pub fn uses_fixtures(&self) -> bool {
+        self.fixtures.is_some()
+    }
+
+    pub fn project(&self) -> WikiProject {
+        self.project
+    }
+
+    pub fn config(&self) -> &WikipediaConfig {
+        &self.config
+    }
+
+    /// Язык и текст запроса; без префикса — язык `DEFAULT_LANGUAGE`.
+    pub fn parse_query(&self, query: &str) -> (SupportedLanguage, String) {
+        self.parse_query_details(query).into_parts()
+    }
+
+    /// Как [`WikipediaService::parse_query`], но с тем, откуда взят язык.
+    pub fn parse_query_details(&self, query: &str) -> ParsedQuery {
+        ParsedQuery::parse(query, self.config.default_language())
+    }
+
+    /// Запускает в фоне прогрев кэша запросами из `warm_queries`; запуск бота
+    /// его не ждёт.
+    pub fn spawn_cache_warmup(&self) {
+        if self.config.warm_queries.is_empty() {
+            return;
         }
 
-        let search_response: WikipediaSearchResponse = response.json().await?;
+        let service = self.clone();
+        self.tasks.spawn(async move {
+            tokio::time::sleep(CACHE_WARMUP_DELAY).await;
+            service.warm_cache().await;
+        });
+    }
 
-        let articles: Vec<WikipediaSearchItem> = search_response
-            .query
-            .search
-            .into_iter()
-            .map(|mut item| {
-                item.snippet = clean_html(&item.snippet);
-                item
-            })
-            .collect();
+    /// Загружает в кэш результаты `warm_queries` по [`CACHE_WARMUP_CONCURRENCY`]
+    /// запросов за раз. Если Wikipedia недоступна или не отвечает, прогрев
+    /// прекращается, чтобы не нагружать её дальше. Возвращает число
+    /// прогретых запросов.
+    pub async fn warm_cache(&self) -> usize {
+        let queries = &self.config.warm_queries;
+        let started = Instant::now();
+        let mut warmed = 0;
+
+        for batch in queries.chunks(CACHE_WARMUP_CONCURRENCY) {
+            let results = futures::future::join_all(batch.iter().map(|query| async move {
+                let (language, query) = self.parse_query(query);
+                let result = self.search_single_language(&query, language).await;
+                (query, result)
+            }))
+            .await;
+
+            let mut unavailable = false;
+            for (query, result) in results {
+                match result {
+                    Ok(_) => warmed += 1,
+                    Err(WikiError::NoResults { .. }) => {
+                        tracing::debug!("🔥 По запросу прогрева '{}' ничего не найдено", query);
+                    }
+                    Err(e) => {
+                        tracing::warn!("⚠️ Не удалось прогреть кэш для '{}': {}", query, e);
+                        unavailable |= is_retryable(&e);
+                    }
+                }
+            }
+            if unavailable {
+                tracing::warn!("⚠️ Wikipedia не отвечает, прогрев кэша остановлен");
+                break;
+            }
+        }
 
-        Ok(articles)
+        tracing::info!(
+            "🔥 Кэш прогрет: {} из {} запросов за {:?}",
+            warmed,
+            queries.len(),
+            started.elapsed()
+        );
+        warmed
     }
 
-    async fn get_batch_info_internal(
-        &self,
-        pageids: Vec<u64>,
-        language: SupportedLanguage,
-    ) -> WikiResult<HashMap<u64, ArticleBatchInfo>> {
-        if pageids.is_empty() {
-            return Ok(HashMap::new());
+    /// Ёмкость и текущее число записей в кэшах (после применения отложенных
+    /// вытеснений) и состояние unified-пути по языкам.
+    pub async fn cache_stats(&self) -> CacheStats {
+        async fn usage<V>(cache: &Cache<String, V>) -> CacheUsage
+        where
+            V: Clone + Send + Sync + 'static,
+        {
+            cache.run_pending_tasks().await;
+            CacheUsage {
+                capacity: cache.policy().max_capacity(),
+                entries: cache.entry_count(),
+            }
         }
 
-        let url = format!("https://{}.wikipedia.org/w/api.php", language.code());
+        CacheStats {
+            search: usage(&self.search_cache).await,
+            batch: usage(&self.batch_cache).await,
+            unified: usage(&self.unified_cache).await,
+            paths: self.path_health.statuses().await,
+        }
+    }
 
-        let pageids_str = pageids
-            .iter()
-            .map(|id| id.to_string())
-            .collect::<Vec<_>>()
-            .join("|");
+    /// Дешёвая проверка доступности API (`meta=siteinfo`) для `--check-config`.
+    /// В режиме фикстур сеть не нужна, и проверка всегда успешна.
+    pub async fn probe(&self, language: SupportedLanguage) -> WikiResult<()> {
+        if self.fixtures.is_some() {
+            return Ok(());
+        }
 
         let params = [
             ("action", "query"),
+            ("meta", "siteinfo"),
+            ("siprop", "general"),
             ("format", "json"),
-            ("pageids", &pageids_str),
-            (
-                "prop",
-                "extracts|pageimages|pageprops|coordinates|categories",
-            ),
-            ("exintro", "1"),
-            ("explaintext", "1"),
-            ("exlimit", "max"),
-            ("piprop", "thumbnail"),
-            ("pithumbsize", "300"),
-            ("pilimit", "max"),
-            ("coprop", "lat|lon"),
-            ("cllimit", "10"),
         ];
 
-        let response = self.client.get(&url).query(&params).send().await?;
+        self.get_response(&self.api_url(language), &params).await?;
+
+        Ok(())
+    }
+
+    /// GET к API с повторами по `RetryPolicy`; неуспешный статус — ошибка.
+    async fn get_response(
+        &self,
+        url: &str,
+        params: &[(&str, &str)],
+    ) -> WikiResult<reqwest::Response> {
+        self.retry.execute(|| self.send_checked(url, params)).await
+    }
+
+    /// GET к API с разбором JSON внутри той же попытки: тело, оборванное
+    /// посреди JSON, повторяется по `RetryPolicy` как сетевой сбой, а ответ
+    /// другой структуры — нет.
+    async fn get_json<T: serde::de::DeserializeOwned>(
+        &self,
+        url: &str,
+        params: &[(&str, &str)],
+    ) -> WikiResult<T> {
+        self.retry
+            .execute(|| async {
+                let response_text = self.send_checked(url, params).await?.text().await?;
+                serde_json::from_str(&response_text).map_err(WikiError::from_json)
+            })
+            .await
+    }
 
+    async fn send_checked(
+        &self,
+        url: &str,
+        params: &[(&str, &str)],
+    ) -> WikiResult<reqwest::Response> {
+        let response = self.client.get(url).query(params).send().await?;
         if !response.status().is_success() {
-            return Err(WikiError::Network(response.error_for_status().unwrap_err()));
+            return Err(WikiError::from_response(response));
         }
+        Ok(response)
+    }
 
-        let batch_response: WikipediaBatchResponse = response.json().await?;
+    pub(crate) fn api_url(&self, language: SupportedLanguage) -> String {
+        format!(
+            "{}/w/api.php",
+            self.api_base
+                .replace("{lang}", language.code())
+                .replace("{project}", self.project.domain())
+        )
+    }
 
-        let mut result = HashMap::new();
+    /// Ключи кэша строятся по нормализованному запросу, чтобы « Einstein » и
+    /// «einstein» попадали в одну запись. В API уходит исходный запрос.
+    fn search_cache_key(&self, query: &str, language: SupportedLanguage) -> String {
+        format!(
+            "search:{}:{}:{}",
+            self.settings_hash(),
+            language.code(),
+            normalize_cache_query(query)
+        )
+    }
 
-        for (page_id_str, page_info) in batch_response.query.pages {
-            if let Ok(page_id) = page_id_str.parse::<u64>() {
-                let image_url = page_info
-                    .thumbnail
-                    .as_ref()
-                    .map(|thumb| thumb.source.clone());
+    fn unified_cache_key(&self, query: &str, language: SupportedLanguage) -> String {
+        format!(
+            "unified:{}:{}:{}",
+            self.settings_hash(),
+            language.code(),
+            normalize_cache_query(query)
+        )
+    }
 
-                let coordinates = page_info
-                    .coordinates
-                    .as_ref()
-                    .and_then(|coords| coords.first())
-                    .map(|coord| Coordinates {
-                        lat: coord.lat,
-                        lon: coord.lon,
-                    });
-
-                let categories = page_info
-                    .categories
-                    .unwrap_or_default()
-                    .into_iter()
-                    .map(|cat| cat.title)
-                    .collect();
-
-                let wikidata_id = page_info
-                    .pageprops
-                    .as_ref()
-                    .and_then(|props| props.wikibase_item.clone());
-
-                let batch_info = ArticleBatchInfo {
-                    image_url,
-                    extract: page_info.extract,
-                    wikidata_id,
-                    coordinates,
-                    categories,
-                };
+    /// Короткий хеш настроек, от которых зависит содержимое закэшированного
+    /// ответа. Входит в ключи всех кэшей, чтобы после смены настроек не
+    /// отдавать результаты, собранные по старым. FNV-1a, а не `DefaultHasher`,
+    /// чтобы хеш не менялся между версиями Rust.
+    fn settings_hash(&self) -> String {
+        let settings = format!(
+            "{}|{}|{:?}|{:?}|{}|{}|{:?}|{:?}|{}|{}|{}",
+            self.project.domain(),
+            self.config.max_search_results,
+            self.config.search_profile,
+            self.config.search_what,
+            self.config.sanitize_queries,
+            self.config.trim_parentheticals,
+            self.config.extract_format,
+            self.config.extract_sentences,
+            self.config.ranking.quality,
+            THUMBNAIL_SIZE,
+            EXTRACT_CHARS,
+        );
+
+        let hash = settings
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+            });
+        format!("{:08x}", hash as u32)
+    }
 
-                result.insert(page_id, batch_info);
+    /// Резервные разделы для `language` из `fallback_languages`: без самого
+    /// `language` и без повторов, поэтому цепочка не может зациклиться.
+    fn fallback_chain(&self, language: SupportedLanguage) -> Vec<SupportedLanguage> {
+        let mut chain: Vec<SupportedLanguage> = Vec::new();
+        for fallback in self
+            .config
+            .fallback_languages
+            .iter()
+            .filter_map(|code| SupportedLanguage::from_code(code))
+        {
+            if fallback != language && !chain.contains(&fallback) {
+                chain.push(fallback);
             }
         }
-
-        Ok(result)
+        chain
     }
 
-    async fn search_and_get_info_unified(
+    /// Поиск в одном языковом разделе: unified-запрос с кэшем и переходом на
+    /// обычный поиск при сбое.
+    async fn search_single_language(
         &self,
         query: &str,
         language: SupportedLanguage,
     ) -> WikiResult<Vec<EnrichedArticle>> {
-        if query.trim().is_empty() {
-            return Err(WikiError::NoResults {
-                query: query.to_string(),
-            });
+        if let Some(fixtures) = &self.fixtures {
+            return fixtures
+                .get_enriched_articles_optimized(query, language)
+                .await;
         }
 
-        let url = format!("https://{}.wikipedia.org/w/api.php", language.code());
+        let cache_key = self.unified_cache_key(query, language);
 
-        let params = [
-            ("action", "query"),
-            ("format", "json"),
-            ("generator", "search"),
-            ("gsrsearch", query),
-            ("gsrlimit", &self.config.max_search_results.to_string()),
-            ("gsrprop", "snippet|titlesnippet|size|wordcount|timestamp"),
-            (
-                "prop",
-                "extracts|pageimages|pageprops|coordinates|categories",
-            ),
-            ("exintro", "1"),
-            ("explaintext", "1"),
-            ("exchars", "400"),
-            ("exlimit", "max"),
-            ("piprop", "thumbnail"),
-            ("pithumbsize", "300"),
-            ("pilimit", "max"),
-            ("coprop", "lat|lon"),
-            ("cllimit", "10"),
-        ];
+        if let Some(cached) = self.unified_cache.get(&cache_key).await {
+            if self
+                .soft_ttl
+                .is_some_and(|soft_ttl| cached.fetched_at.elapsed() >= soft_ttl)
+            {
+                self.spawn_unified_refresh(cache_key, query, language).await;
+            }
+            return Ok(cached.articles);
+        }
 
-        tracing::info!("📡 Unified API запрос: {} для '{}'", url, query);
+        let Some(deadline) = self
+            .config
+            .cached_fallback_deadline_ms
+            .map(Duration::from_millis)
+        else {
+            return self.load_single_language(cache_key, query, language).await;
+        };
 
-        let response = self.client.get(&url).query(&params).send().await?;
+        // Запрос выполняется в отдельной задаче, чтобы после дедлайна он
+        // продолжился и заполнил кэш, даже если мы ответим раньше
+        let service = self.clone();
+        let owned_query = query.to_string();
+        let mut fetch = self.tasks.spawn(async move {
+            service
+                .load_single_language(cache_key, &owned_query, language)
+                .await
+        });
+        let finish = |joined: Result<Option<_>, tokio::task::JoinError>| {
+            joined
+                .map_err(|e| WikiError::internal(format!("Search task failed: {e}")))?
+                .unwrap_or_else(|| Err(WikiError::internal("Search cancelled by shutdown")))
+        };
 
-        if !response.status().is_success() {
-            return Err(WikiError::Network(response.error_for_status().unwrap_err()));
+        if let Ok(joined) = tokio::time::timeout(deadline, &mut fetch).await {
+            return finish(joined);
         }
 
-        let response_text = response.text().await?;
-        let unified_response: UnifiedWikipediaResponse = serde_json::from_str(&response_text)?;
+        if let Some((related_query, articles)) = self.related_cached_results(query, language) {
+            tracing::info!(
+                "⏱️ Поиск '{}' не уложился в {:?}, отдаём кэш для '{}'",
+                query,
+                deadline,
+                related_query
+            );
+            return Ok(articles);
+        }
 
-        tracing::info!(
-            "📊 Получено {} страниц от unified API",
-            unified_response.query.pages.len()
-        );
+        finish(fetch.await)
+    }
 
-        let mut enriched_articles = Vec::new();
-        let mut titles_without_extract = Vec::new();
+    async fn load_single_language(
+        &self,
+        cache_key: String,
+        query: &str,
+        language: SupportedLanguage,
+    ) -> WikiResult<Vec<EnrichedArticle>> {
+        // Одновременные промахи по одному ключу ждут один общий запрос
+        self.unified_cache
+            .try_get_with(cache_key, self.fetch_single_language(query, language))
+            .await
+            .map(|cached| cached.articles)
+            .map_err(WikiError::from_shared)
+    }
 
-        // Сначала собираем все статьи и определяем какие нуждаются в fallback
-        let mut temp_articles = Vec::new();
+    /// Непустой результат из кэша для самого длинного ранее искавшегося запроса,
+    /// которым начинается `query` («эйнш» для «эйнштейн»), — так при наборе
+    /// запроса по буквам есть что показать, пока ждём ответ API.
+    fn related_cached_results(
+        &self,
+        query: &str,
+        language: SupportedLanguage,
+    ) -> Option<(String, Vec<EnrichedArticle>)> {
+        let query = normalize_cache_query(query);
+        let prefix = self.unified_cache_key("", language);
 
-        for (page_id, page_info) in unified_response.query.pages {
-            tracing::debug!(
-                "🔍 Обрабатываю страницу: '{}' (ID: {})",
-                page_info.title,
-                page_id
-            );
+        self.unified_cache
+            .iter()
+            .filter_map(|(key, cached)| {
+                let cached_query = key.strip_prefix(&prefix)?;
+                (!cached_query.is_empty()
+                    && cached_query != query
+                    && query.starts_with(cached_query)
+                    && !cached.articles.is_empty())
+                .then(|| (cached_query.to_string(), cached.articles))
+            })
+            .max_by_key(|(cached_query, _)| cached_query.len())
+    }
 
-            let has_extract = page_info
-                .extract
-                .as_ref()
-                .is_some_and(|e| !e.trim().is_empty());
+    async fn fetch_single_language(
+        &self,
+        query: &str,
+        language: SupportedLanguage,
+    ) -> WikiResult<CachedArticles> {
+        if self.path_health.preferred(language).await == SearchPath::Legacy {
+            return self
+                .get_enriched_articles(query, language)
+                .await
+                .map(CachedArticles::new);
+        }
 
-            if !has_extract {
-                titles_without_extract.push(page_info.title.clone());
-                tracing::debug!(
-                    "❌ Extract отсутствует для '{}', добавляем в fallback",
-                    page_info.title
-                );
-            } else {
-                tracing::debug!(
-                    "✅ Extract найден для '{}': {} символов",
-                    page_info.title,
-                    page_info.extract.as_ref().unwrap().len()
-                );
+        let error = match self.search_and_get_info_unified(query, language).await {
+            Ok(cached) => {
+                self.path_health.record_success(language).await;
+                return Ok(cached);
             }
+            Err(e) => e,
+        };
 
-            temp_articles.push((page_id, page_info));
+        if !should_fallback_to_legacy(&error) {
+            return Err(error);
         }
 
-        // Batch fallback для всех статей без extract
-        let fallback_snippets = if !titles_without_extract.is_empty() {
-            tracing::info!(
-                "🔄 Batch fallback для {} статей без extract",
-                titles_without_extract.len()
-            );
-            self.get_batch_search_snippets(&titles_without_extract, language)
-                .await
-                .unwrap_or_default()
-        } else {
-            std::collections::HashMap::new()
-        };
+        let cooldown = self.path_health.record_failure(language).await;
+        tracing::warn!(
+            "⚠️ Unified API недоступен для {}: {}, переключаемся на обычный поиск (пауза {:?})",
+            language.code(),
+            error,
+            cooldown
+        );
+        self.get_enriched_articles(query, language)
+            .await
+            .map(CachedArticles::new)
+    }
 
-        // Теперь создаем enriched articles
-        for (_page_id, page_info) in temp_articles {
-            let image_url = page_info
-                .thumbnail
-                .as_ref()
-                .map(|thumb| thumb.source.clone());
+    /// Каким путём сейчас ищутся статьи на языке.
+    pub async fn preferred_path(&self, language: SupportedLanguage) -> SearchPath {
+        self.path_health.preferred(language).await
+    }
 
-            let coordinates = page_info
-                .coordinates
-                .as_ref()
-                .and_then(|coords| coords.first())
-                .map(|coord| Coordinates {
-                    lat: coord.lat,
-                    lon: coord.lon,
-                });
+    /// Обновляет устаревший по мягкому TTL результат в фоне; пока обновление
+    /// идёт, пользователи получают прежний результат из кэша. Путь выбирается
+    /// так же, как при промахе кэша: пока unified-путь на паузе, обновление
+    /// идёт обычным поиском, а его сбои учитываются в `PathHealth`.
+    async fn spawn_unified_refresh(
+        &self,
+        cache_key: String,
+        query: &str,
+        language: SupportedLanguage,
+    ) {
+        if self.unified_refreshing.contains_key(&cache_key) {
+            return;
+        }
+        self.unified_refreshing.insert(cache_key.clone(), ()).await;
+
+        let service = self.clone();
+        let query = query.to_string();
+        self.tasks.spawn(async move {
+            tracing::debug!("🔄 Фоновое обновление результатов: {}", cache_key);
+            match service.fetch_single_language(&query, language).await {
+                Ok(cached) => {
+                    service
+                        .unified_cache
+                        .insert(cache_key.clone(), cached)
+                        .await;
+                }
+                Err(e) => {
+                    tracing::warn!("⚠️ Не удалось обновить результаты '{}': {}", query, e);
+                }
+            }
+            service.unified_refreshing.invalidate(&cache_key).await;
+        });
+    }
 
-            let categories = page_info
-                .categories
-                .unwrap_or_default()
-                .into_iter()
-                .map(|cat| cat.title)
-                .collect();
+    /// Сколько всего статей нашёл уже выполненный поиск по запросу — для
+    /// подсказки «~N результатов». `None`, если результата нет в кэше или
+    /// он получен обычным поиском без `totalhits`.
+    pub async fn total_hits(&self, query: &str, language: SupportedLanguage) -> Option<u64> {
+        self.unified_cache
+            .get(&self.unified_cache_key(query, language))
+            .await?
+            .total_hits
+    }
 
-            let wikidata_id = page_info
-                .pageprops
-                .as_ref()
-                .and_then(|props| props.wikibase_item.clone());
+    /// Результаты из других проектов Викимедиа, найденные уже выполненным
+    /// поиском по запросу. Пусто, если результата нет в кэше.
+    pub async fn interwiki_hits(
+        &self,
+        query: &str,
+        language: SupportedLanguage,
+    ) -> Vec<InterwikiHit> {
+        self.unified_cache
+            .get(&self.unified_cache_key(query, language))
+            .await
+            .map(|cached| cached.interwiki)
+            .unwrap_or_default()
+    }
 
-            let batch_info = ArticleBatchInfo {
-                image_url,
-                extract: page_info.extract.clone(),
-                wikidata_id,
-                coordinates,
-                categories,
-            };
-
-            let snippet = if let Some(ref extract) = page_info.extract {
-                if !extract.trim().is_empty() {
-                    Self::create_snippet_from_extract(extract)
-                } else {
-                    fallback_snippets
-                        .get(&page_info.title)
-                        .cloned()
-                        .unwrap_or_else(|| page_info.title.clone())
-                }
-            } else {
-                fallback_snippets
-                    .get(&page_info.title)
-                    .cloned()
-                    .unwrap_or_else(|| page_info.title.clone())
-            };
-
-            let basic_info = WikipediaSearchItem {
-                title: page_info.title.clone(),
-                snippet: snippet.clone(),
-                pageid: Some(page_info.pageid),
-                size: None,
-                wordcount: None,
-                timestamp: None,
-            };
-
-            let article_url = self.get_article_url(&page_info.title, language);
-
-            let enriched_article =
-                EnrichedArticle::new(basic_info, Some(batch_info), None, article_url)
-                    .with_relevance_index(page_info.index);
+    /// Краткое определение слова для сервиса Викисловаря: первое толкование
+    /// из статьи о слове (сначала как написано, затем в нижнем регистре).
+    /// `None`, если статьи нет или толкование не нашлось.
+    pub async fn get_definition(
+        &self,
+        word: &str,
+        language: SupportedLanguage,
+    ) -> WikiResult<Option<Definition>> {
+        let word = word.trim();
+        let cache_key = format!("definition:{}:{}", language.code(), word);
+
+        self.definition_cache
+            .try_get_with(cache_key, self.fetch_definition(word, language))
+            .await
+            .map_err(WikiError::from_shared)
+    }
 
-            enriched_articles.push(enriched_article);
+    async fn fetch_definition(
+        &self,
+        word: &str,
+        language: SupportedLanguage,
+    ) -> WikiResult<Option<Definition>> {
+        let mut candidates = vec![word.to_string()];
+        let lowercase = word.to_lowercase();
+        if lowercase != word {
+            candidates.push(lowercase);
         }
+        let titles = candidates.join("|");
 
-        tracing::info!("✅ Создано {} обогащенных статей", enriched_articles.len());
+        let params = [
+            ("action", "query"),
+            ("format", "json"),
+            ("titles", titles.as_str()),
+            ("redirects", "1"),
+            ("prop", "extracts"),
+            ("explaintext", "1"),
+            ("exsectionformat", "wiki"),
+        ];
+        tracing::debug!("📘 Запрос определения '{}' ({})", word, language.code());
+        let response = self.get_response(&self.api_url(language), &params).await?;
+        let batch: WikipediaBatchResponse = response.json().await?;
 
-        enriched_articles.sort_by(|a, b| match (a.relevance_index, b.relevance_index) {
-            (Some(idx_a), Some(idx_b)) => idx_a.cmp(&idx_b),
-            (Some(_), None) => std::cmp::Ordering::Less,
-            (None, Some(_)) => std::cmp::Ordering::Greater,
-            (None, None) => {
-                let score_a = Self::calculate_article_score(a);
-                let score_b = Self::calculate_article_score(b);
-                score_b
-                    .partial_cmp(&score_a)
-                    .unwrap_or(std::cmp::Ordering::Equal)
-            }
+        let definition = candidates.iter().find_map(|candidate| {
+            let title = batch.query.resolve_title(candidate);
+            let page = batch
+                .query
+                .pages
+                .values()
+                .find(|page| page.title == title)?;
+            let text = first_definition(page.extract.as_deref()?, &page.title)?;
+            Some(Definition {
+                url: self.get_article_url(&page.title, language),
+                word: page.title.clone(),
+                text,
+            })
         });
+        Ok(definition)
+    }
 
-        Ok(enriched_articles)
+    /// Есть ли в кэше готовый результат поиска по запросу — для аналитики.
+    pub fn has_cached_results(&self, query: &str, language: SupportedLanguage) -> bool {
+        self.unified_cache
+            .contains_key(&self.unified_cache_key(query, language))
     }
 
-    fn calculate_article_score(article: &EnrichedArticle) -> f64 {
-        let mut score = 0.0;
+    fn batch_cache_key(&self, pageids: &[u64], language: SupportedLanguage) -> String {
+        let mut sorted_pageids = pageids.to_vec();
+        sorted_pageids.sort();
+        format!(
+            "batch:{}:{}:{:?}",
+            self.settings_hash(),
+            language.code(),
+            sorted_pageids
+        )
+    }
 
-        if let Some(batch_info) = &article.batch_info {
-            if batch_info.image_url.is_some() {
-                score += 10.0;
-            }
+    fn title_batch_cache_key(&self, titles: &[String], language: SupportedLanguage) -> String {
+        let mut sorted_titles = titles.to_vec();
+        sorted_titles.sort();
+        format!(
+            "titles:{}:{}:{:?}",
+            self.settings_hash(),
+            language.code(),
+            sorted_titles
+        )
+    }
 
-            if let Some(extract) = &batch_info.extract {
-                score += (extract.len() as f64 / 100.0).min(20.0);
-            }
+    /// Схлопывает пробелы, заменяет запрос синонимом, опционально санитизирует
+    /// и обрезает запрос до `max_query_length` символов перед отправкой в API.
+    fn prepare_query(&self, query: &str) -> WikiResult<String> {
+        let mut prepared = normalize_whitespace(query);
 
-            if batch_info.wikidata_id.is_some() {
-                score += 15.0;
-            }
+        if let Some(replacement) = self.query_synonym(&prepared) {
+            tracing::debug!("🔁 Синоним запроса: '{}' → '{}'", prepared, replacement);
+            prepared = normalize_whitespace(replacement);
+        }
 
-            if batch_info.coordinates.is_some() {
-                score += 5.0;
-            }
+        if self.config.sanitize_queries {
+            prepared = sanitize_search_query(&prepared);
+        }
 
-            score += batch_info.categories.len() as f64;
+        if prepared.chars().count() > self.config.max_query_length {
+            prepared = prepared
+                .chars()
+                .take(self.config.max_query_length)
+                .collect::<String>()
+                .trim_end()
+                .to_string();
         }
 
-        if let Some(wordcount) = article.basic_info.wordcount {
-            score += (wordcount as f64 / 1000.0).min(30.0);
+        if prepared.is_empty() {
+            return Err(WikiError::NoResults {
+                query: query.to_string(),
+            });
         }
 
-        score
+        Ok(prepared)
     }
 
-    fn create_snippet_from_extract(extract: &str) -> String {
-        const MAX_SNIPPET_LENGTH: usize = 200;
+    /// Замена для запроса из `query_synonyms`. Сравнивается весь запрос целиком
+    /// без учёта регистра, чтобы не переписывать запросы частично.
+    fn query_synonym(&self, query: &str) -> Option<&str> {
+        let query = query.to_lowercase();
+        self.config
+            .query_synonyms
+            .iter()
+            .find(|(alias, _)| normalize_whitespace(alias).to_lowercase() == query)
+            .map(|(_, replacement)| replacement.as_str())
+    }
 
-        if extract.len() <= MAX_SNIPPET_LENGTH {
-            return extract.to_string();
+    /// Значения `srwhat` по порядку попыток: после пустого `nearmatch`
+    /// запрос повторяется по тексту. `None` — параметр не передаётся.
+    fn search_what_attempts(&self) -> Vec<Option<&str>> {
+        match self.config.search_what.as_deref() {
+            Some("nearmatch") => vec![Some("nearmatch"), Some("text")],
+            what => vec![what],
         }
+    }
 
-        let mut result = String::with_capacity(MAX_SNIPPET_LENGTH);
+    async fn search_internal(
+        &self,
+        query: &str,
+        language: SupportedLanguage,
+    ) -> WikiResult<Vec<WikipediaSearchItem>> {
+        let query = self.prepare_query(query)?;
+        let url = self.api_url(language);
+
+        let max_results = self.config.max_search_results.to_string();
+        let attempts = self.search_what_attempts();
+        let mut articles = Vec::new();
+        for (attempt, what) in attempts.iter().enumerate() {
+            let mut params = vec![
+                ("action", "query"),
+                ("list", "search"),
+                ("srsearch", query.as_str()),
+                ("format", "json"),
+                ("srlimit", max_results.as_str()),
+                ("srprop", "snippet|titlesnippet|size|wordcount|timestamp"),
+            ];
+            if let Some(profile) = &self.config.search_profile {
+                params.push(("srqiprofile", profile.as_str()));
+            }
+            if let Some(what) = what {
+                params.push(("srwhat", what));
+            }
 
-        for (char_count, ch) in extract.chars().enumerate() {
-            if char_count >= MAX_SNIPPET_LENGTH - 3 {
+            let response = self.get_response(&url, &params).await?;
+
+            let search_response: WikipediaSearchResponse = response.json().await?;
+
+            articles = search_response
+                .query
+                .search
+                .into_iter()
+                .map(|mut item| {
+                    item.snippet = clean_snippet(&item.snippet, true);
+                    item
+                })
+                .collect();
+
+            if !articles.is_empty() || attempt + 1 == attempts.len() {
                 break;
             }
-            result.push(ch);
+            tracing::debug!("🔎 По '{}' нет точных совпадений, ищем по тексту", query);
         }
 
-        if let Some(last_space) = result.rfind(' ') {
-            result.truncate(last_space);
-        }
-
-        result.push_str("...");
-        result
+        Ok(articles)
     }
 
-    async fn get_batch_search_snippets(
+    async fn get_batch_info_internal(
         &self,
-        titles: &[String],
+        pageids: Vec<u64>,
         language: SupportedLanguage,
-    ) -> WikiResult<std::collections::HashMap<String, String>> {
-        if titles.is_empty() {
-            return Ok(std::collections::HashMap::new());
+    ) -> WikiResult<HashMap<u64, ArticleBatchInfo>> {
+        if pageids.is_empty() {
+            return Ok(HashMap::new());
         }
 
-        let url = format!("https://{}.wikipedia.org/w/api.php", language.code());
-        let search_query = titles.join(" OR ");
-
-        let params = [
-            ("action", "query"),
-            ("list", "search"),
-            ("srsearch", &search_query),
-            ("format", "json"),
-            ("srlimit", &std::cmp::min(titles.len() * 2, 50).to_string()),
-            ("srprop", "snippet"),
-        ];
-
-        let response = self.client.get(&url).query(&params).send().await?;
+        let pageids_str = pageids
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+            .join("|");
 
-        if !response.status().is_success() {
-            return Err(WikiError::Network(response.error_for_status().unwrap_err()));
-        }
+        let batch_response = self
+            .fetch_batch_pages(&[("pageids", &pageids_str)], language)
+            .await?;
 
-        let search_response: WikipediaSearchResponse = response.json().await?;
-        let mut result = std::collections::HashMap::new();
+        let mut result = HashMap::new();
 
-        for title in titles {
-            if let Some(article) = search_response
-                .query
-                .search
-                .iter()
-                .find(|a| a.title.to_lowercase() == title.to_lowercase())
-            {
-                let cleaned_snippet = clean_html(&article.snippet);
-                if !cleaned_snippet.trim().is_empty() {
-                    result.insert(title.clone(), cleaned_snippet);
-                    tracing::debug!(
-                        "🔄 Найден snippet для '{}': {} символов",
-                        title,
-                        result[title].len()
-                    );
-                }
+        for (page_id_str, page_info) in batch_response.query.pages {
+            if let Ok(page_id) = page_id_str.parse::<u64>() {
+                result.insert(page_id, self.batch_info_from_page(page_info, language));
             }
         }
 
-        tracing::info!(
-            "🔄 Batch search получил {} snippet'ов из {} запрошенных",
-            result.len(),
-            titles.len()
-        );
         Ok(result)
     }
-}
 
-#[async_trait]
-impl WikipediaApi for WikipediaService {
-    async fn search(
+    async fn get_batch_info_by_titles_internal(
         &self,
-        query: &str,
+        titles: Vec<String>,
         language: SupportedLanguage,
-    ) -> WikiResult<Vec<WikipediaSearchItem>> {
-        if query.trim().is_empty() {
-            return Err(WikiError::NoResults {
-                query: query.to_string(),
-            });
+    ) -> WikiResult<HashMap<String, ArticleBatchInfo>> {
+        if titles.is_empty() {
+            return Ok(HashMap::new());
         }
 
-        let cache_key = self.search_cache_key(query, language);
+        let titles_str = titles.join("|");
+        let mut query = self
+            .fetch_batch_pages(&[("titles", &titles_str), ("redirects", "1")], language)
+            .await?
+            .query;
+
+        let pages: HashMap<String, ArticleBatchInfo> = std::mem::take(&mut query.pages)
+            .into_values()
+            .map(|page_info| {
+                (
+                    page_info.title.clone(),
+                    self.batch_info_from_page(page_info, language),
+                )
+            })
+            .collect();
 
-        if let Some(cached_result) = self.search_cache.get(&cache_key).await {
-            return Ok(cached_result);
+        // Несуществующие страницы (`missing`) отброшены в fetch_batch_pages,
+        // и их заголовков в результате нет.
+        Ok(titles
+            .into_iter()
+            .filter_map(|title| {
+                let batch_info = pages.get(&query.resolve_title(&title))?.clone();
+                Some((title, batch_info))
+            })
+            .collect())
+    }
+
+    /// Запрос `prop=extracts|pageimages|…` для страниц, выбранных `selector`
+    /// (`pageids=` или `titles=`), с догрузкой обрезанных extract'ов и миниатюр.
+    async fn fetch_batch_pages(
+        &self,
+        selector: &[(&str, &str)],
+        language: SupportedLanguage,
+    ) -> WikiResult<WikipediaBatchResponse> {
+        let url = self.api_url(language);
+
+        let mut params = vec![("action", "query"), ("format", "json")];
+        params.extend_from_slice(selector);
+        params.extend_from_slice(&[
+            ("prop", self.page_props(language)),
+            ("exlimit", "max"),
+            ("piprop", "thumbnail|original"),
+            ("pithumbsize", THUMBNAIL_SIZE),
+            ("pilimit", "max"),
+            ("coprop", "lat|lon"),
+            ("ppprop", "wikibase_item|wikibase-shortdesc"),
+            ("cllimit", "10"),
+            ("clshow", "!hidden"),
+            ("clprop", "hidden"),
+        ]);
+        params.extend_from_slice(self.extract_params());
+        // Без `extract_sentences` карточке нужно всё вступление, поэтому
+        // `exchars` здесь не отправляется; заданное число предложений
+        // соблюдается и в первом запросе, и при догрузке через continue
+        let sentences = self.config.extract_sentences.map(|n| n.to_string());
+        if let Some(sentences) = &sentences {
+            params.push(("exsentences", sentences));
+        }
+        if self.requests_assessments(language) {
+            params.push(("palimit", "max"));
         }
 
-        let articles = self.search_internal(query, language).await?;
+        let response = self.get_response(&url, &params).await?;
 
-        self.search_cache.insert(cache_key, articles.clone()).await;
+        let mut batch_response: WikipediaBatchResponse = response.json().await?;
+        batch_response.query.pages.retain(|key, page| {
+            if page.missing {
+                tracing::debug!("🕳️ Страница {} ('{}') не существует", key, page.title);
+            }
+            !page.missing
+        });
 
-        Ok(articles)
+        self.merge_continued_pages(
+            &mut batch_response.query.pages,
+            batch_response.continuation.as_ref(),
+            language,
+            sentences.as_deref().map(|n| ("exsentences", n)),
+        )
+        .await;
+
+        Ok(batch_response)
     }
 
-    async fn get_batch_info(
+    fn batch_info_from_page(
         &self,
-        pageids: Vec<u64>,
+        page_info: WikipediaPageInfo,
         language: SupportedLanguage,
-    ) -> WikiResult<HashMap<u64, ArticleBatchInfo>> {
-        if pageids.is_empty() {
-            return Ok(HashMap::new());
-        }
-
-        let cache_key = self.batch_cache_key(&pageids, language);
+    ) -> ArticleBatchInfo {
+        let thumbnail = page_thumbnail(page_info.thumbnail, page_info.original.as_ref());
+        let image_url = thumbnail.as_ref().map(|thumb| thumb.source.clone());
+        let image_width = thumbnail.as_ref().map(|thumb| thumb.width);
+        let image_height = thumbnail.as_ref().map(|thumb| thumb.height);
+
+        let coordinates = page_info
+            .coordinates
+            .as_ref()
+            .and_then(|coords| coords.first())
+            .map(|coord| Coordinates {
+                lat: coord.lat,
+                lon: coord.lon,
+            });
 
-        if let Some(cached_result) = self.batch_cache.get(&cache_key).await {
-            return Ok(cached_result);
+        let categories = Self::topic_categories(page_info.categories, language);
+
+        let wikidata_id = page_info
+            .pageprops
+            .as_ref()
+            .and_then(|props| props.wikibase_item.clone());
+        let short_description = page_info
+            .pageprops
+            .as_ref()
+            .and_then(|props| props.short_description.clone())
+            .filter(|description| !description.trim().is_empty());
+
+        let extract_html = self.extract_html(page_info.extract.as_deref());
+        let quality = quality_from_assessments(page_info.pageassessments.as_ref());
+        ArticleBatchInfo {
+            image_url,
+            image_width,
+            image_height,
+            extract: self.clean_extract(page_info.extract),
+            extract_html,
+            short_description,
+            wikidata_id,
+            coordinates,
+            categories,
+            quality,
+            sentence_bounded: self.config.extract_sentences.is_some(),
+            photo: page_photo(page_info.original.as_ref()),
         }
+    }
 
-        let batch_info = self.get_batch_info_internal(pageids, language).await?;
+    async fn search_and_get_info_unified(
+        &self,
+        query: &str,
+        language: SupportedLanguage,
+    ) -> WikiResult<CachedArticles> {
+        let query = self.prepare_query(query)?;
+
+        let max_results = self.config.max_search_results.to_string();
+        let attempts = self.search_what_attempts();
+        let mut cached = CachedArticles::new(Vec::new());
+        for (attempt, what) in attempts.iter().enumerate() {
+            let mut generator_params = vec![
+                ("generator", "search"),
+                ("gsrsearch", query.as_str()),
+                ("gsrlimit", max_results.as_str()),
+                ("gsrprop", "snippet|titlesnippet|size|wordcount|timestamp"),
+                ("gsrinfo", "totalhits"),
+                ("gsrinterwiki", "1"),
+                ("iwurl", "1"),
+            ];
+            if let Some(profile) = &self.config.search_profile {
+                generator_params.push(("gsrqiprofile", profile.as_str()));
+            }
+            if let Some(what) = what {
+                generator_params.push(("gsrwhat", what));
+            }
 
-        self.batch_cache.insert(cache_key, batch_info.clone()).await;
+            tracing::info!("📡 Unified API запрос для '{}'", query);
+            cached = self
+                .fetch_generator_articles(&generator_params, language)
+                .await?;
+            if !cached.articles.is_empty() || attempt + 1 == attempts.len() {
+                break;
+            }
+            tracing::debug!("🔎 По '{}' нет точных совпадений, ищем по тексту", query);
+        }
 
-        Ok(batch_info)
+        Ok(cached)
     }
 
-    async fn get_enriched_articles(
+    /// Статьи, заголовок которых начинается с `prefix`, сразу с extract'ами,
+    /// миниатюрами и метаданными (`generator=prefixsearch`). Для коротких
+    /// запросов совпадение по началу заголовка часто точнее полнотекстового
+    /// поиска. Порядок — порядок prefixsearch (`index`).
+    pub async fn prefix_search_enriched(
         &self,
-        query: &str,
+        prefix: &str,
         language: SupportedLanguage,
     ) -> WikiResult<Vec<EnrichedArticle>> {
-        let articles = self.search(query, language).await?;
-
-        if articles.is_empty() {
-            return Err(WikiError::NoResults {
-                query: query.to_string(),
-            });
+        if let Some(fixtures) = &self.fixtures {
+            return fixtures
+                .get_enriched_articles_optimized(prefix, language)
+                .await;
         }
 
-        let pageids: Vec<u64> = articles
-            .iter()
-            .filter_map(|article| article.pageid)
-            .collect();
+        let prefix = self.prepare_query(prefix)?;
+        let cache_key = format!(
+            "prefixsearch:{}:{}:{}",
+            self.settings_hash(),
+            language.code(),
+            normalize_cache_query(&prefix)
+        );
 
-        let batch_info = if !pageids.is_empty() {
-            self.get_batch_info(pageids, language).await?
-        } else {
-            HashMap::new()
+        self.unified_cache
+            .try_get_with(cache_key, async {
+                let max_results = self.config.max_search_results.to_string();
+                let generator_params = [
+                    ("generator", "prefixsearch"),
+                    ("gpssearch", prefix.as_str()),
+                    ("gpslimit", max_results.as_str()),
+                ];
+
+                tracing::info!("📡 Prefixsearch запрос для '{}'", prefix);
+                self.fetch_generator_articles(&generator_params, language)
+                    .await
+            })
+            .await
+            .map(|cached| cached.articles)
+            .map_err(WikiError::from_shared)
+    }
+
+    /// Один запрос `action=query` с генератором страниц (`generator_params`) и
+    /// всеми нужными боту свойствами страниц; догружает обрезанные лимитами
+    /// extract'ы и собирает статьи в порядке генератора.
+    async fn fetch_generator_articles(
+        &self,
+        generator_params: &[(&str, &str)],
+        language: SupportedLanguage,
+    ) -> WikiResult<CachedArticles> {
+        let url = self.api_url(language);
+
+        let extract_length = self.extract_length_param();
+        let mut params = vec![("action", "query"), ("format", "json")];
+        params.extend_from_slice(generator_params);
+        params.extend_from_slice(&[
+            ("prop", self.page_props(language)),
+            (extract_length.0, &extract_length.1),
+            ("exlimit", "max"),
+            ("piprop", "thumbnail|original"),
+            ("pithumbsize", THUMBNAIL_SIZE),
+            ("pilimit", "max"),
+            ("coprop", "lat|lon"),
+            ("ppprop", "wikibase_item|wikibase-shortdesc"),
+            ("cllimit", "10"),
+            ("clshow", "!hidden"),
+            ("clprop", "hidden"),
+        ]);
+        params.extend_from_slice(self.extract_params());
+        if self.requests_assessments(language) {
+            params.push(("palimit", "max"));
+        }
+
+        tracing::debug!("📡 Запрос страниц генератора: {}", url);
+
+        let mut unified_response: UnifiedWikipediaResponse = self.get_json(&url, &params).await?;
+        unified_response.query.pages.retain(|_, page| !page.missing);
+
+        // exlimit/pilimit ограничены (20 extract'ов с exintro), остальное
+        // MediaWiki отдаёт через continue — догружаем, а не уходим в fallback
+        self.merge_continued_pages(
+            &mut unified_response.query.pages,
+            unified_response.continuation.as_ref(),
+            language,
+            Some((extract_length.0, &extract_length.1)),
+        )
+        .await;
+
+        tracing::info!(
+            "📊 Получено {} страниц от unified API",
+            unified_response.query.pages.len()
+        );
+
+        let total_hits = unified_response
+            .query
+            .searchinfo
+            .as_ref()
+            .and_then(|info| info.totalhits);
+        let interwiki = std::mem::take(&mut unified_response.query.interwiki);
+        let mut enriched_articles = Vec::new();
+        let mut titles_without_extract = Vec::new();
+
+        // Сначала собираем все статьи и определяем какие нуждаются в fallback
+        let mut temp_articles = Vec::new();
+
+        for (page_id, mut page_info) in unified_response.query.pages {
+            let extract_html = self.extract_html(page_info.extract.as_deref());
+            page_info.extract = self.clean_extract(page_info.extract);
+            tracing::debug!(
+                "🔍 Обрабатываю страницу: '{}' (ID: {})",
+                page_info.title,
+                page_id
+            );
+
+            let has_extract = page_info
+                .extract
+                .as_ref()
+                .is_some_and(|e| !e.trim().is_empty());
+
+            if !has_extract {
+                titles_without_extract.push(page_info.title.clone());
+                tracing::debug!(
+                    "❌ Extract отсутствует для '{}', добавляем в fallback",
+                    page_info.title
+                );
+            } else {
+                tracing::debug!(
+                    "✅ Extract найден для '{}': {} символов",
+                    page_info.title,
+                    page_info.extract.as_ref().unwrap().len()
+                );
+            }
+
+            temp_articles.push((page_id, page_info, extract_html));
+        }
+
+        // Batch fallback для всех статей без extract
+        let fallback_snippets = if !titles_without_extract.is_empty() {
+            tracing::info!(
+                "🔄 Batch fallback для {} статей без extract",
+                titles_without_extract.len()
+            );
+            self.get_batch_search_snippets(&titles_without_extract, language)
+                .await
+                .unwrap_or_default()
+        } else {
+            std::collections::HashMap::new()
+        };
+
+        // Теперь создаем enriched articles
+        for (_page_id, page_info, extract_html) in temp_articles {
+            let thumbnail = page_thumbnail(page_info.thumbnail, page_info.original.as_ref());
+            let image_url = thumbnail.as_ref().map(|thumb| thumb.source.clone());
+            let image_width = thumbnail.as_ref().map(|thumb| thumb.width);
+            let image_height = thumbnail.as_ref().map(|thumb| thumb.height);
+
+            let coordinates = page_info
+                .coordinates
+                .as_ref()
+                .and_then(|coords| coords.first())
+                .map(|coord| Coordinates {
+                    lat: coord.lat,
+                    lon: coord.lon,
+                });
+
+            let categories = Self::topic_categories(page_info.categories, language);
+
+            let wikidata_id = page_info
+                .pageprops
+                .as_ref()
+                .and_then(|props| props.wikibase_item.clone());
+            let short_description = page_info
+                .pageprops
+                .as_ref()
+                .and_then(|props| props.short_description.clone())
+                .filter(|description| !description.trim().is_empty());
+
+            let batch_info = ArticleBatchInfo {
+                image_url,
+                image_width,
+                image_height,
+                extract: page_info.extract.clone(),
+                extract_html,
+                short_description,
+                wikidata_id,
+                coordinates,
+                categories,
+                quality: quality_from_assessments(page_info.pageassessments.as_ref()),
+                sentence_bounded: self.config.extract_sentences.is_some(),
+                photo: page_photo(page_info.original.as_ref()),
+            };
+
+            let snippet = if let Some(ref extract) = page_info.extract {
+                if !extract.trim().is_empty() {
+                    Self::create_snippet_from_extract(extract, language)
+                } else {
+                    fallback_snippets
+                        .get(&page_info.title)
+                        .cloned()
+                        .unwrap_or_else(|| page_info.title.clone())
+                }
+            } else {
+                fallback_snippets
+                    .get(&page_info.title)
+                    .cloned()
+                    .unwrap_or_else(|| page_info.title.clone())
+            };
+
+            let basic_info = WikipediaSearchItem {
+                title: page_info.title.clone(),
+                snippet: snippet.clone(),
+                pageid: Some(page_info.pageid),
+                size: None,
+                wordcount: None,
+                timestamp: None,
+            };
+
+            let article_url = self.get_article_url(&page_info.title, language);
+
+            let enriched_article =
+                EnrichedArticle::new(basic_info, Some(batch_info), None, article_url)
+                    .with_relevance_index(page_info.index);
+
+            enriched_articles.push(enriched_article);
+        }
+
+        tracing::info!("✅ Создано {} обогащенных статей", enriched_articles.len());
+
+        enriched_articles.sort_by(|a, b| match (a.relevance_index, b.relevance_index) {
+            (Some(idx_a), Some(idx_b)) => idx_a.cmp(&idx_b),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => {
+                let quality_weight = self.config.ranking.quality;
+                let score_a = Self::calculate_article_score(a, quality_weight);
+                let score_b = Self::calculate_article_score(b, quality_weight);
+                score_b
+                    .partial_cmp(&score_a)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            }
+        });
+
+        Ok(CachedArticles {
+            total_hits,
+            interwiki,
+            ..CachedArticles::new(enriched_articles)
+        })
+    }
+
+    /// Дописывает в `pages` extract'ы и миниатюры, которые MediaWiki не отдала
+    /// из-за лимитов и предложила догрузить через `continuation`. Ошибка
+    /// догрузки не фатальна: страницы остаются как есть.
+    async fn merge_continued_pages<P: ContinuablePage>(
+        &self,
+        pages: &mut HashMap<String, P>,
+        continuation: Option<&HashMap<String, serde_json::Value>>,
+        language: SupportedLanguage,
+        extract_length: Option<(&str, &str)>,
+    ) {
+        let Some(continuation) =
+            continuation.filter(|continuation| needs_prop_continuation(Some(continuation)))
+        else {
+            return;
+        };
+
+        let extracts_cut = continuation.contains_key("excontinue");
+        let images_cut = continuation.contains_key("picontinue");
+        let mut missing: Vec<u64> = pages
+            .values_mut()
+            .filter_map(|page| {
+                let pageid = page.pageid();
+                let fields = page.continued_fields();
+                ((extracts_cut && fields.extract.is_none())
+                    || (images_cut && fields.thumbnail.is_none()))
+                .then_some(pageid)
+            })
+            .collect();
+        missing.sort_unstable();
+        let mut continued = self
+            .fetch_continued_page_data(&missing, language, extract_length)
+            .await
+            .unwrap_or_else(|e| {
+                tracing::warn!("⚠️ Не удалось догрузить extract'ы: {}", e);
+                HashMap::new()
+            });
+
+        for page in pages.values_mut() {
+            if let Some(data) = continued.remove(&page.pageid()) {
+                let fields = page.continued_fields();
+                *fields.extract = fields.extract.take().or(data.extract);
+                *fields.thumbnail = fields.thumbnail.take().or(data.thumbnail);
+                *fields.original = fields.original.take().or(data.original);
+            }
+        }
+    }
+
+    /// Догружает extract'ы и миниатюры страниц, которые не поместились в лимиты
+    /// `exlimit`/`pilimit`, следуя за `continue` до конца.
+    async fn fetch_continued_page_data(
+        &self,
+        pageids: &[u64],
+        language: SupportedLanguage,
+        extract_length: Option<(&str, &str)>,
+    ) -> WikiResult<HashMap<u64, ContinuedPageData>> {
+        const MAX_CONTINUE_REQUESTS: usize = 10;
+
+        let mut result: HashMap<u64, ContinuedPageData> = HashMap::new();
+        if pageids.is_empty() {
+            return Ok(result);
+        }
+
+        let url = self.api_url(language);
+        let pageids_str = pageids
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+            .join("|");
+        let prop = match self.project {
+            WikiProject::Wiktionary => "extracts",
+            WikiProject::Wikipedia | WikiProject::Wikivoyage => "extracts|pageimages",
+        };
+
+        let mut continuation = HashMap::from([("continue".to_string(), String::new())]);
+
+        for _ in 0..MAX_CONTINUE_REQUESTS {
+            let mut params: Vec<(&str, &str)> = vec![
+                ("action", "query"),
+                ("format", "json"),
+                ("pageids", &pageids_str),
+                ("prop", prop),
+                ("exlimit", "max"),
+                ("piprop", "thumbnail|original"),
+                ("pithumbsize", THUMBNAIL_SIZE),
+                ("pilimit", "max"),
+            ];
+            params.extend_from_slice(self.extract_params());
+            if let Some(extract_length) = extract_length {
+                params.push(extract_length);
+            }
+            params.extend(
+                continuation
+                    .iter()
+                    .map(|(key, value)| (key.as_str(), value.as_str())),
+            );
+
+            tracing::debug!("🔁 Догружаем extract'ы для {} страниц", pageids.len());
+            let response = self.get_response(&url, &params).await?;
+
+            let batch_response: WikipediaBatchResponse = response.json().await?;
+
+            for page in batch_response.query.pages.into_values() {
+                let entry = result.entry(page.pageid).or_default();
+                entry.extract = entry.extract.take().or(page.extract);
+                entry.thumbnail = entry.thumbnail.take().or(page.thumbnail);
+                entry.original = entry.original.take().or(page.original);
+            }
+
+            match batch_response.continuation {
+                Some(next) if needs_prop_continuation(Some(&next)) => {
+                    continuation = next
+                        .into_iter()
+                        .map(|(key, value)| {
+                            let value = match value {
+                                serde_json::Value::String(value) => value,
+                                other => other.to_string(),
+                            };
+                            (key, value)
+                        })
+                        .collect();
+                }
+                _ => break,
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Оставляет только видимые категории и убирает из названий префикс пространства имён.
+    fn topic_categories(
+        categories: Option<Vec<WikipediaCategory>>,
+        language: SupportedLanguage,
+    ) -> Vec<String> {
+        categories
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|category| !category.is_hidden())
+            .map(|category| {
+                strip_namespace_prefix(&category.title, language)
+                    .trim()
+                    .to_string()
+            })
+            .filter(|title| !title.is_empty())
+            .collect()
+    }
+
+    /// Оценка статьи для упорядочивания результатов без позиции в поиске.
+    /// `quality_weight` — прибавка за класс качества (`RankingWeights::quality`).
+    fn calculate_article_score(article: &EnrichedArticle, quality_weight: f64) -> f64 {
+        let mut score = 0.0;
+
+        if let Some(batch_info) = &article.batch_info {
+            if let Some(quality) = batch_info.quality {
+                score += quality.score() * quality_weight;
+            }
+
+            if batch_info.image_url.is_some() {
+                score += 10.0;
+            }
+
+            if let Some(extract) = &batch_info.extract {
+                score += (extract.len() as f64 / 100.0).min(20.0);
+            }
+
+            if batch_info.wikidata_id.is_some() {
+                score += 15.0;
+            }
+
+            if batch_info.coordinates.is_some() {
+                score += 5.0;
+            }
+
+            score += batch_info.categories.len() as f64;
+        }
+
+        if let Some(wordcount) = article.basic_info.wordcount {
+            score += (wordcount as f64 / 1000.0).min(30.0);
+        }
+
+        score
+    }
+
+    /// Запрашивать ли оценки качества статей для этого раздела.
+    fn requests_assessments(&self, language: SupportedLanguage) -> bool {
+        self.project == WikiProject::Wikipedia
+            && self.config.ranking.quality > 0.0
+            && PAGE_ASSESSMENT_LANGUAGES.contains(&language)
+    }
+
+    /// `prop` для запроса сведений о статьях; оценки качества добавляются
+    /// только там, где они поддерживаются.
+    fn page_props(&self, language: SupportedLanguage) -> &'static str {
+        if self.requests_assessments(language) {
+            PAGE_PROPS_WITH_ASSESSMENTS
+        } else {
+            self.project.page_props()
+        }
+    }
+
+    /// Ограничение длины extract'а в поиске: `exsentences`, если заданы
+    /// предложения, иначе `exchars`. Оба параметра вместе не отправляются.
+    fn extract_length_param(&self) -> (&'static str, String) {
+        match self.config.extract_sentences {
+            Some(sentences) => ("exsentences", sentences.to_string()),
+            None => ("exchars", EXTRACT_CHARS.to_string()),
+        }
+    }
+
+    /// Параметры `prop=extracts`: вводная часть статьи, простым текстом или
+    /// HTML в зависимости от `extract_format`.
+    fn extract_params(&self) -> &'static [(&'static str, &'static str)] {
+        match self.config.extract_format {
+            ExtractFormat::Plain => &[("exintro", "1"), ("explaintext", "1")],
+            ExtractFormat::Html => &[("exintro", "1")],
+        }
+    }
+
+    /// Исходный HTML текста статьи, если он запрашивался.
+    fn extract_html(&self, extract: Option<&str>) -> Option<String> {
+        match self.config.extract_format {
+            ExtractFormat::Plain => None,
+            ExtractFormat::Html => extract.map(str::to_string),
+        }
+    }
+
+    /// Текстовая версия extract'а: из HTML убираются теги, затем остатки
+    /// шаблонов и при `trim_parentheticals` — вводная скобка.
+    fn clean_extract(&self, extract: Option<String>) -> Option<String> {
+        let extract = match self.config.extract_format {
+            ExtractFormat::Plain => extract,
+            ExtractFormat::Html => extract.map(|html| html_to_plain_text(&html)),
+        }
+        .map(|extract| strip_extract_noise(&extract));
+        if !self.config.trim_parentheticals {
+            return extract;
+        }
+        extract.map(|extract| strip_leading_parenthetical(&extract))
+    }
+
+    fn create_snippet_from_extract(extract: &str, language: SupportedLanguage) -> String {
+        const MAX_SNIPPET_LENGTH: usize = 200;
+        /// Насколько раньше лимита ещё можно обрезать по концу предложения.
+        const SENTENCE_LOOKBACK: usize = 60;
+
+        if extract.len() <= MAX_SNIPPET_LENGTH {
+            return extract.to_string();
+        }
+
+        let mut result = String::with_capacity(MAX_SNIPPET_LENGTH);
+
+        for (char_count, ch) in extract.chars().enumerate() {
+            if char_count >= MAX_SNIPPET_LENGTH - 3 {
+                break;
+            }
+            result.push(ch);
+        }
+
+        if language.uses_word_spaces() {
+            if let Some(last_space) = result.rfind(' ') {
+                result.truncate(last_space);
+            }
+        } else {
+            let min_sentence_chars = result.chars().count().saturating_sub(SENTENCE_LOOKBACK);
+            let sentence_end = result
+                .char_indices()
+                .enumerate()
+                .filter(|(char_idx, (_, ch))| {
+                    *char_idx >= min_sentence_chars
+                        && matches!(ch, '。' | '！' | '？' | '.' | '!' | '?')
+                })
+                .last()
+                .map(|(_, (byte_idx, ch))| byte_idx + ch.len_utf8());
+
+            // Целое предложение не нуждается в многоточии
+            if let Some(end) = sentence_end {
+                result.truncate(end);
+                return result;
+            }
+        }
+
+        result.push_str("...");
+        result
+    }
+
+    /// Оглавление статьи. Для несуществующей страницы возвращает `NoResults`,
+    /// для статьи без разделов — пустой список.
+    pub async fn get_sections(
+        &self,
+        title: &str,
+        language: SupportedLanguage,
+    ) -> WikiResult<Vec<Section>> {
+        let parse = self.parse_page(title, language, "sections").await?;
+
+        Ok(parse
+            .sections
+            .into_iter()
+            .map(|section| Section {
+                level: section.toclevel,
+                title: clean_html(&section.line),
+                anchor: section.anchor,
+            })
+            .collect())
+    }
+
+    /// Параметры первой карточки статьи (`{{Infobox …}}`) в виде плоского
+    /// словаря; ограничения разбора описаны у [`parse_infobox`]. Для статьи
+    /// без карточки — пустой словарь, для несуществующей — `NoResults`.
+    pub async fn get_infobox(
+        &self,
+        title: &str,
+        language: SupportedLanguage,
+    ) -> WikiResult<HashMap<String, String>> {
+        let parse = self.parse_page(title, language, "wikitext").await?;
+
+        Ok(parse
+            .wikitext
+            .as_deref()
+            .map(parse_infobox)
+            .unwrap_or_default())
+    }
+
+    /// `action=parse` для страницы `title` с указанными `prop`.
+    async fn parse_page(
+        &self,
+        title: &str,
+        language: SupportedLanguage,
+        prop: &str,
+    ) -> WikiResult<WikipediaParse> {
+        let title = title.trim();
+        if title.is_empty() || self.fixtures.is_some() {
+            return Err(WikiError::NoResults {
+                query: title.to_string(),
+            });
+        }
+
+        let url = self.api_url(language);
+        let params = [
+            ("action", "parse"),
+            ("format", "json"),
+            ("formatversion", "2"),
+            ("page", title),
+            ("prop", prop),
+            ("redirects", "1"),
+        ];
+
+        let response = self.get_response(&url, &params).await?;
+
+        let parse_response: WikipediaParseResponse = response.json().await?;
+
+        if let Some(error) = parse_response.error {
+            if matches!(error.code.as_str(), "missingtitle" | "invalidtitle") {
+                return Err(WikiError::NoResults {
+                    query: title.to_string(),
+                });
+            }
+            tracing::warn!(
+                "⚠️ Ошибка parse API для '{}': {} {:?}",
+                title,
+                error.code,
+                error.info
+            );
+            return Err(WikiError::UnexpectedApiResponse);
+        }
+
+        parse_response.parse.ok_or(WikiError::UnexpectedApiResponse)
+    }
+
+    /// Вариант исправления запроса от MediaWiki ("Did you mean"), если он есть.
+    pub async fn get_search_suggestion(
+        &self,
+        query: &str,
+        language: SupportedLanguage,
+    ) -> WikiResult<Option<String>> {
+        if self.fixtures.is_some() {
+            return Ok(None);
+        }
+
+        let query = self.prepare_query(query)?;
+        let url = self.api_url(language);
+
+        let params = [
+            ("action", "query"),
+            ("list", "search"),
+            ("srsearch", query.as_str()),
+            ("format", "json"),
+            ("srlimit", "1"),
+            ("srinfo", "suggestion"),
+            ("srprop", ""),
+        ];
+
+        let response = self.get_response(&url, &params).await?;
+
+        let search_response: WikipediaSearchResponse = response.json().await?;
+
+        Ok(Self::suggestion_from(search_response))
+    }
+
+    fn suggestion_from(response: WikipediaSearchResponse) -> Option<String> {
+        response
+            .query
+            .searchinfo
+            .and_then(|info| info.suggestion)
+            .map(|suggestion| suggestion.trim().to_string())
+            .filter(|suggestion| !suggestion.is_empty())
+    }
+
+    async fn get_batch_search_snippets(
+        &self,
+        titles: &[String],
+        language: SupportedLanguage,
+    ) -> WikiResult<std::collections::HashMap<String, String>> {
+        if titles.is_empty() {
+            return Ok(std::collections::HashMap::new());
+        }
+
+        let url = self.api_url(language);
+        let search_query = titles.join(" OR ");
+
+        let params = [
+            ("action", "query"),
+            ("list", "search"),
+            ("srsearch", &search_query),
+            ("format", "json"),
+            ("srlimit", &std::cmp::min(titles.len() * 2, 50).to_string()),
+            ("srprop", "snippet"),
+        ];
+
+        let response = self.get_response(&url, &params).await?;
+
+        let search_response: WikipediaSearchResponse = response.json().await?;
+        let mut result = std::collections::HashMap::new();
+
+        for title in titles {
+            if let Some(article) = search_response
+                .query
+                .search
+                .iter()
+                .find(|a| a.title.to_lowercase() == title.to_lowercase())
+            {
+                let cleaned_snippet = clean_snippet(&article.snippet, true);
+                if !cleaned_snippet.trim().is_empty() {
+                    result.insert(title.clone(), cleaned_snippet);
+                    tracing::debug!(
+                        "🔄 Найден snippet для '{}': {} символов",
+                        title,
+                        result[title].len()
+                    );
+                }
+            }
+        }
+
+        tracing::info!(
+            "🔄 Batch search получил {} snippet'ов из {} запрошенных",
+            result.len(),
+            titles.len()
+        );
+        Ok(result)
+    }
+
+    /// Ищет одновременно во всех популярных языковых разделах и возвращает первый
+    /// «сильный» результат (у лучшей статьи есть и изображение, и extract).
+    /// Остальные запросы при этом отменяются. Если сильного результата нет,
+    /// берётся первый непустой раздел в порядке `popular_languages()`.
+    pub async fn search_best_effort(
+        &self,
+        query: &str,
+    ) -> WikiResult<(SupportedLanguage, Vec<EnrichedArticle>)> {
+        use futures::stream::{FuturesUnordered, StreamExt};
+
+        let languages = SupportedLanguage::popular_languages();
+
+        let mut in_flight: FuturesUnordered<_> = languages
+            .iter()
+            .map(|&language| async move {
+                let result = self.search_single_language(query, language).await;
+                (language, result)
+            })
+            .collect();
+
+        let mut weak_results = HashMap::new();
+
+        while let Some((language, result)) = in_flight.next().await {
+            match result {
+                Ok(articles) if Self::is_strong_result(&articles) => {
+                    tracing::info!(
+                        "🌐 Авто-режим выбрал язык {} для '{}'",
+                        language.code(),
+                        query
+                    );
+                    return Ok((language, articles));
+                }
+                Ok(articles) if !articles.is_empty() => {
+                    weak_results.insert(language, articles);
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::debug!("Авто-режим: ошибка для языка {}: {}", language.code(), e);
+                }
+            }
+        }
+
+        languages
+            .iter()
+            .find_map(|language| {
+                weak_results
+                    .remove(language)
+                    .map(|articles| (*language, articles))
+            })
+            .ok_or_else(|| WikiError::NoResults {
+                query: query.to_string(),
+            })
+    }
+
+    fn is_strong_result(articles: &[EnrichedArticle]) -> bool {
+        articles.first().is_some_and(|article| {
+            article.image_url().is_some()
+                && article
+                    .batch_info
+                    .as_ref()
+                    .and_then(|info| info.extract.as_deref())
+                    .is_some_and(|extract| !extract.trim().is_empty())
+        })
+    }
+}
+
+#[async_trait]
+impl WikipediaApi for WikipediaService {
+    async fn search(
+        &self,
+        query: &str,
+        language: SupportedLanguage,
+    ) -> WikiResult<Vec<WikipediaSearchItem>> {
+        if let Some(fixtures) = &self.fixtures {
+            return fixtures.search(query, language).await;
+        }
+
+        if query.trim().is_empty() {
+            return Err(WikiError::NoResults {
+                query: query.to_string(),
+            });
+        }
+
+        let cache_key = self.search_cache_key(query, language);
+
+        if let Some(cached_result) = self.search_cache.get(&cache_key).await {
+            return Ok(cached_result);
+        }
+
+        let articles = self.search_internal(query, language).await?;
+
+        self.search_cache.insert(cache_key, articles.clone()).await;
+
+        Ok(articles)
+    }
+
+    async fn get_batch_info(
+        &self,
+        pageids: Vec<u64>,
+        language: SupportedLanguage,
+    ) -> WikiResult<HashMap<u64, ArticleBatchInfo>> {
+        if let Some(fixtures) = &self.fixtures {
+            return fixtures.get_batch_info(pageids, language).await;
+        }
+
+        if pageids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let cache_key = self.batch_cache_key(&pageids, language);
+
+        if let Some(cached_result) = self.batch_cache.get(&cache_key).await {
+            return Ok(cached_result);
+        }
+
+        let batch_info = self.get_batch_info_internal(pageids, language).await?;
+
+        self.batch_cache.insert(cache_key, batch_info.clone()).await;
+
+        Ok(batch_info)
+    }
+
+    async fn get_batch_info_by_titles(
+        &self,
+        titles: Vec<String>,
+        language: SupportedLanguage,
+    ) -> WikiResult<HashMap<String, ArticleBatchInfo>> {
+        if let Some(fixtures) = &self.fixtures {
+            return fixtures.get_batch_info_by_titles(titles, language).await;
+        }
+
+        if titles.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let cache_key = self.title_batch_cache_key(&titles, language);
+
+        if let Some(cached_result) = self.title_batch_cache.get(&cache_key).await {
+            return Ok(cached_result);
+        }
+
+        let batch_info = self
+            .get_batch_info_by_titles_internal(titles, language)
+            .await?;
+
+        self.title_batch_cache
+            .insert(cache_key, batch_info.clone())
+            .await;
+
+        Ok(batch_info)
+    }
+
+    async fn get_enriched_articles(
+        &self,
+        query: &str,
+        language: SupportedLanguage,
+    ) -> WikiResult<Vec<EnrichedArticle>> {
+        if let Some(fixtures) = &self.fixtures {
+            return fixtures.get_enriched_articles(query, language).await;
+        }
+
+        let articles = self.search(query, language).await?;
+
+        if articles.is_empty() {
+            return Err(WikiError::NoResults {
+                query: query.to_string(),
+            });
+        }
+
+        let pageids: Vec<u64> = articles
+            .iter()
+            .filter_map(|article| article.pageid)
+            .collect();
+
+        let batch_info = if !pageids.is_empty() {
+            self.get_batch_info(pageids, language).await?
+        } else {
+            HashMap::new()
+        };
+
+        let enriched_articles = articles
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, article)| {
+                if let Some(pageid) = article.pageid {
+                    let article_url = self.get_article_url(&article.title, language);
+                    let batch_data = batch_info.get(&pageid).cloned();
+
+                    let enriched_article =
+                        EnrichedArticle::new(article, batch_data, None, article_url)
+                            .with_relevance_index(Some(index as i32));
+
+                    Some(enriched_article)
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        Ok(enriched_articles)
+    }
+
+    /// Ищет в запрошенном разделе, а если там пусто — по очереди в разделах
+    /// из `fallback_languages`. Статьи помечаются языком раздела, где нашлись.
+    async fn get_enriched_articles_optimized(
+        &self,
+        query: &str,
+        language: SupportedLanguage,
+    ) -> WikiResult<Vec<EnrichedArticle>> {
+        let primary = match self.search_single_language(query, language).await {
+            Ok(articles) if !articles.is_empty() => {
+                return Ok(tag_source_language(articles, language));
+            }
+            Ok(articles) => Ok(articles),
+            Err(e @ WikiError::NoResults { .. }) => Err(e),
+            Err(e) => return Err(e),
+        };
+
+        for fallback in self.fallback_chain(language) {
+            tracing::info!(
+                "↪️ Пусто в {} для '{}', пробуем {}",
+                language.code(),
+                query,
+                fallback.code()
+            );
+            match self.search_single_language(query, fallback).await {
+                Ok(articles) if !articles.is_empty() => {
+                    return Ok(tag_source_language(articles, fallback));
+                }
+                Ok(_) | Err(WikiError::NoResults { .. }) => {}
+                Err(e) => {
+                    tracing::warn!("⚠️ Ошибка поиска в {}: {}", fallback.code(), e);
+                }
+            }
+        }
+
+        primary
+    }
+
+    fn get_article_url(&self, title: &str, language: SupportedLanguage) -> String {
+        format!(
+            "https://{}.{}/wiki/{}",
+            language.code(),
+            self.project.domain(),
+            urlencoding::encode(title)
+        )
+    }
+}
+
+fn tag_source_language(
+    mut articles: Vec<EnrichedArticle>,
+    language: SupportedLanguage,
+) -> Vec<EnrichedArticle> {
+    for article in &mut articles {
+        article.source_language = Some(language);
+    }
+    articles
+}
+
+fn normalize_cache_query(query: &str) -> String {
+    normalize_whitespace(query).to_lowercase()
+}
+
+/// Стоит ли повторять запрос обычным поиском после ошибки unified-пути: только
+/// если unified-ответ сломан (не разобрался, 5xx) или не пришёл (таймаут, нет
+/// соединения), а не если результатов нет или запрос пуст — обычный поиск
+/// вернул бы то же самое. Каждый такой сбой ставит unified-путь на паузу.
+fn should_fallback_to_legacy(error: &WikiError) -> bool {
+    match error {
+        WikiError::Parse(_)
+        | WikiError::TruncatedResponse(_)
+        | WikiError::UnexpectedApiResponse
+        | WikiError::Timeout
+        | WikiError::Connect(_) => true,
+        WikiError::Network(e) => {
+            e.is_decode() || e.status().is_some_and(|status| status.is_server_error())
+        }
+        _ => false,
+    }
+}
+
+/// Extract и миниатюра страницы, полученные через продолжение запроса.
+#[derive(Default)]
+struct ContinuedPageData {
+    extract: Option<String>,
+    thumbnail: Option<WikipediaThumbnail>,
+    original: Option<WikipediaThumbnail>,
+}
+
+/// Поля страницы, которые дописываются из [`ContinuedPageData`].
+struct ContinuedFields<'a> {
+    extract: &'a mut Option<String>,
+    thumbnail: &'a mut Option<WikipediaThumbnail>,
+    original: &'a mut Option<WikipediaThumbnail>,
+}
+
+/// Страница ответа, в которую дописываются [`ContinuedPageData`].
+trait ContinuablePage {
+    fn pageid(&self) -> u64;
+
+    fn continued_fields(&mut self) -> ContinuedFields<'_>;
+}
+
+impl ContinuablePage for WikipediaPageInfo {
+    fn pageid(&self) -> u64 {
+        self.pageid
+    }
+
+    fn continued_fields(&mut self) -> ContinuedFields<'_> {
+        ContinuedFields {
+            extract: &mut self.extract,
+            thumbnail: &mut self.thumbnail,
+            original: &mut self.original,
+        }
+    }
+}
+
+impl ContinuablePage for UnifiedWikipediaPage {
+    fn pageid(&self) -> u64 {
+        self.pageid
+    }
+
+    fn continued_fields(&mut self) -> ContinuedFields<'_> {
+        ContinuedFields {
+            extract: &mut self.extract,
+            thumbnail: &mut self.thumbnail,
+            original: &mut self.original,
+        }
+    }
+}
+
+/// Миниатюра страницы, а если `pageimages` её не вернул — уменьшенное
+/// исходное изображение.
+fn page_thumbnail(
+    thumbnail: Option<WikipediaThumbnail>,
+    original: Option<&WikipediaThumbnail>,
+) -> Option<WikipediaThumbnail> {
+    thumbnail.or_else(|| {
+        original.and_then(|original| WikipediaThumbnail::from_original(original, THUMBNAIL_WIDTH))
+    })
+}
+
+/// Исходное изображение страницы, уменьшенное до [`PHOTO_WIDTH`], — для
+/// фото-результатов.
+fn page_photo(original: Option<&WikipediaThumbnail>) -> Option<ArticlePhoto> {
+    let photo = WikipediaThumbnail::from_original(original?, PHOTO_WIDTH)?;
+    Some(ArticlePhoto {
+        url: photo.source,
+        width: photo.width,
+        height: photo.height,
+    })
+}
+
+/// Вернул ли MediaWiki extract'ы или миниатюры не для всех страниц.
+fn needs_prop_continuation(continuation: Option<&HashMap<String, serde_json::Value>>) -> bool {
+    continuation.is_some_and(|continuation| {
+        continuation.contains_key("excontinue") || continuation.contains_key("picontinue")
+    })
+}
+
+pub fn parse_query_with_language(query: &str) -> (SupportedLanguage, String) {
+    crate::config::languages::parse_query_with_language(query)
+}
+
+pub fn parse_query_with_default_language(
+    query: &str,
+    default_language: SupportedLanguage,
+) -> (SupportedLanguage, String) {
+    crate::config::languages::parse_query_with_default_language(query, default_language)
+}
+
+/// Раскрывает синтаксис `cat:Название` в поисковый оператор `incategory:"Название"`.
+pub fn expand_category_filter(query: &str) -> String {
+    match query.strip_prefix("cat:") {
+        Some(category) if !category.trim().is_empty() => {
+            format!("incategory:\"{}\"", category.trim().replace('"', ""))
+        }
+        _ => query.to_string(),
+    }
+}
+
+pub fn get_article_url_lang(title: &str, language: &WikipediaLanguage) -> String {
+    format!(
+        "https://{}.wikipedia.org/wiki/{}",
+        language.code(),
+        urlencoding::encode(title)
+    )
+}
+
+pub async fn search_wikipedia_lang(
+    query: &str,
+    language: &WikipediaLanguage,
+) -> WikiResult<Vec<WikipediaSearchItem>> {
+    let config = crate::config::AppConfig::from_env()?;
+    let service = WikipediaService::new(config)?;
+
+    service.search(query, language.inner()).await
+}
+
+pub async fn get_articles_batch_info_lang(
+    pageids: Vec<u64>,
+    language: &WikipediaLanguage,
+) -> WikiResult<HashMap<u64, ArticleBatchInfo>> {
+    let config = crate::config::AppConfig::from_env()?;
+    let service = WikipediaService::new(config)?;
+
+    service.get_batch_info(pageids, language.inner()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::UserFriendlyError;
+    use crate::models::QualityClass;
+
+    #[tokio::test]
+    async fn test_cache_key_generation() {
+        let config = AppConfig::for_tests();
+        let service = WikipediaService::new(config).unwrap();
+
+        let key1 = service.search_cache_key("test", SupportedLanguage::English);
+        let key2 = service.search_cache_key("Test", SupportedLanguage::English);
+
+        assert_eq!(key1, key2);
+
+        let key3 = service.search_cache_key("test", SupportedLanguage::Russian);
+        assert_ne!(key1, key3);
+    }
+
+    #[test]
+    fn test_cache_keys_depend_on_result_settings() {
+        let mut config = AppConfig::for_tests();
+        config.wikipedia.max_search_results = 10;
+        let service = WikipediaService::new(config.clone()).unwrap();
+        let same = WikipediaService::new(config.clone()).unwrap();
+        config.wikipedia.max_search_results = 20;
+        let changed = WikipediaService::new(config).unwrap();
+        let language = SupportedLanguage::English;
+
+        assert_eq!(
+            service.unified_cache_key("Einstein", language),
+            same.unified_cache_key("Einstein", language)
+        );
+        assert_ne!(
+            service.unified_cache_key("Einstein", language),
+            changed.unified_cache_key("Einstein", language)
+        );
+        assert_ne!(
+            service.search_cache_key("Einstein", language),
+            changed.search_cache_key("Einstein", language)
+        );
+        assert_ne!(
+            service.batch_cache_key(&[1, 2], language),
+            changed.batch_cache_key(&[1, 2], language)
+        );
+
+        // Предложения вместо символов меняют и сами extract'ы, и их пометку
+        let mut config = AppConfig::for_tests();
+        config.wikipedia.max_search_results = 10;
+        config.wikipedia.extract_sentences = Some(2);
+        let sentences = WikipediaService::new(config).unwrap();
+        assert_ne!(
+            service.unified_cache_key("Einstein", language),
+            sentences.unified_cache_key("Einstein", language)
+        );
+        assert_ne!(
+            service.batch_cache_key(&[1, 2], language),
+            sentences.batch_cache_key(&[1, 2], language)
+        );
+    }
+
+    #[test]
+    fn test_cache_keys_ignore_extra_whitespace() {
+        let config = AppConfig::for_tests();
+        let service = WikipediaService::new(config).unwrap();
+        let language = SupportedLanguage::English;
+
+        for variant in [" Einstein ", "einstein", "EINSTEIN\t", "\n einstein"] {
+            assert_eq!(
+                service.search_cache_key(variant, language),
+                service.search_cache_key("Einstein", language)
+            );
+            assert_eq!(
+                service.unified_cache_key(variant, language),
+                service.unified_cache_key("Einstein", language)
+            );
+        }
+
+        assert_eq!(
+            service.unified_cache_key("Albert   Einstein", language),
+            service.unified_cache_key("albert einstein", language)
+        );
+        assert_ne!(
+            service.unified_cache_key("Albert Einstein", language),
+            service.unified_cache_key("AlbertEinstein", language)
+        );
+        assert_ne!(
+            service.search_cache_key("Einstein", language),
+            service.search_cache_key("Einstein ring", language)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cache_capacities() {
+        let mut config = AppConfig::for_tests();
+        config.cache.max_capacity = 800;
+        config.cache.search_capacity = None;
+        config.cache.batch_capacity = None;
+        config.cache.unified_capacity = None;
+
+        let stats = WikipediaService::new(config.clone())
+            .unwrap()
+            .cache_stats()
+            .await;
+        assert_eq!(stats.search.capacity, Some(800));
+        assert_eq!(stats.batch.capacity, Some(400));
+        assert_eq!(stats.unified.capacity, Some(200));
+
+        config.cache.search_capacity = Some(10);
+        config.cache.batch_capacity = Some(20);
+        config.cache.unified_capacity = Some(30);
+
+        let service = WikipediaService::new(config).unwrap();
+        service
+            .search_cache
+            .insert("search:en:test".to_string(), vec![])
+            .await;
+
+        let stats = service.cache_stats().await;
+        assert_eq!(
+            stats.search,
+            CacheUsage {
+                capacity: Some(10),
+                entries: 1
+            }
+        );
+        assert_eq!(
+            stats.batch,
+            CacheUsage {
+                capacity: Some(20),
+                entries: 0
+            }
+        );
+        assert_eq!(
+            stats.unified,
+            CacheUsage {
+                capacity: Some(30),
+                entries: 0
+            }
+        );
+    }
+
+    #[test]
+    fn test_get_article_url() {
+        let config = AppConfig::for_tests();
+        let service = WikipediaService::new(config).unwrap();
+
+        let url = service.get_article_url("Test Article", SupportedLanguage::English);
+        assert_eq!(url, "https://en.wikipedia.org/wiki/Test%20Article");
+
+        let url_ru = service.get_article_url("Тест", SupportedLanguage::Russian);
+        assert_eq!(
+            url_ru,
+            "https://ru.wikipedia.org/wiki/%D0%A2%D0%B5%D1%81%D1%82"
+        );
+    }
+
+    #[test]
+    fn test_prepare_query_normalizes_whitespace() {
+        let config = AppConfig::for_tests();
+        let service = WikipediaService::new(config).unwrap();
+
+        assert_eq!(
+            service.prepare_query("  Albert \t\n  Einstein  ").unwrap(),
+            "Albert Einstein"
+        );
+        assert_eq!(
+            service.prepare_query("\"теория относительности\"").unwrap(),
+            "\"теория относительности\""
+        );
+        assert!(matches!(
+            service.prepare_query(" \n\t "),
+            Err(WikiError::NoResults { .. })
+        ));
+    }
+
+    #[test]
+    fn test_prepare_query_caps_length() {
+        let mut config = AppConfig::for_tests();
+        config.wikipedia.max_query_length = 10;
+        let service = WikipediaService::new(config).unwrap();
+
+        assert_eq!(service.prepare_query("Москва").unwrap(), "Москва");
+        assert_eq!(
+            service.prepare_query("Санкт-Петербург").unwrap(),
+            "Санкт-Пете"
+        );
+        assert_eq!(
+            service.prepare_query("one two three").unwrap(),
+            "one two th"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_soft_expired_result_is_served_while_refreshing() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "generator".into(),
+                "search".into(),
+            ))
+            .with_chunked_body(move |writer| {
+                let title = if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                    "Old"
+                } else {
+                    std::thread::sleep(Duration::from_millis(300));
+                    "New"
+                };
+                writer.write_all(
+                    format!(r#"{{"query": {{"pages": {{"1": {{"pageid": 1, "title": "{title}", "index": 1}}}}}}}}"#)
+                        .as_bytes(),
+                )
+            })
+            .create_async()
+            .await;
+
+        let mut config = AppConfig::for_tests();
+        config.cache.soft_ttl_secs = Some(0);
+        let service = WikipediaService::new(config)
+            .unwrap()
+            .with_api_base(server.url());
+
+        let first = service
+            .get_enriched_articles_optimized("query", SupportedLanguage::English)
+            .await
+            .unwrap();
+        assert_eq!(first[0].basic_info.title, "Old");
+
+        // Запись уже устарела по мягкому TTL, но отдаётся сразу, без ожидания API
+        let started = Instant::now();
+        let stale = service
+            .get_enriched_articles_optimized("query", SupportedLanguage::English)
+            .await
+            .unwrap();
+        assert_eq!(stale[0].basic_info.title, "Old");
+        assert!(started.elapsed() < Duration::from_millis(300));
+
+        let mut refreshed = stale;
+        for _ in 0..50 {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            refreshed = service
+                .get_enriched_articles_optimized("query", SupportedLanguage::English)
+                .await
+                .unwrap();
+            if refreshed[0].basic_info.title == "New" {
+                break;
+            }
+        }
+        assert_eq!(refreshed[0].basic_info.title, "New");
+    }
+
+    #[tokio::test]
+    async fn test_stale_refresh_skips_paused_unified_path() {
+        let mut server = mockito::Server::new_async().await;
+        let unified = server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "generator".into(),
+                "search".into(),
+            ))
+            .with_body(r#"{"query": {"pages": {"1": {"pageid": 1, "title": "Old", "index": 1}}}}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let legacy_search = server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded("list".into(), "search".into()))
+            .with_body(r#"{"query": {"search": [{"title": "New", "snippet": "", "pageid": 2}]}}"#)
+            .expect_at_least(1)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded("pageids".into(), "2".into()))
+            .with_body(r#"{"query": {"pages": {"2": {"pageid": 2, "title": "New"}}}}"#)
+            .create_async()
+            .await;
+
+        let mut config = AppConfig::for_tests();
+        config.cache.soft_ttl_secs = Some(0);
+        let service = WikipediaService::new(config)
+            .unwrap()
+            .with_api_base(server.url());
+        let language = SupportedLanguage::English;
+
+        let first = service
+            .get_enriched_articles_optimized("query", language)
+            .await
+            .unwrap();
+        assert_eq!(first[0].basic_info.title, "Old");
+
+        // Пока unified-путь на паузе, фоновое обновление идёт обычным поиском
+        service.path_health.record_failure(language).await;
+        let mut refreshed = first;
+        for _ in 0..50 {
+            refreshed = service
+                .get_enriched_articles_optimized("query", language)
+                .await
+                .unwrap();
+            if refreshed[0].basic_info.title == "New" {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(refreshed[0].basic_info.title, "New");
+
+        unified.assert_async().await;
+        legacy_search.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_nearmatch_without_results_falls_back_to_text() {
+        let mut server = mockito::Server::new_async().await;
+        let nearmatch = server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "srwhat".into(),
+                "nearmatch".into(),
+            ))
+            .with_body(r#"{"query": {"search": []}}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let text = server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded("srwhat".into(), "text".into()))
+            .with_body(
+                r#"{"query": {"search": [
+                    {"title": "Albert Einstein", "snippet": "physicist", "pageid": 736}
+                ]}}"#,
+            )
+            .expect(1)
+            .create_async()
+            .await;
+
+        let mut config = AppConfig::for_tests();
+        config.wikipedia.search_what = Some("nearmatch".to_string());
+        let service = WikipediaService::new(config)
+            .unwrap()
+            .with_api_base(server.url());
+
+        let articles = service
+            .search("einstien physics", SupportedLanguage::English)
+            .await
+            .unwrap();
+        assert_eq!(articles[0].title, "Albert Einstein");
+        nearmatch.assert_async().await;
+        text.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_search_profile_is_sent_only_when_configured() {
+        let mut server = mockito::Server::new_async().await;
+        let with_profile = server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("srsearch".into(), "profiled".into()),
+                mockito::Matcher::UrlEncoded("srqiprofile".into(), "popular_inclinks".into()),
+            ]))
+            .with_body(r#"{"query": {"search": []}}"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let without_profile = server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("srsearch".into(), "plain".into()),
+                mockito::Matcher::UrlEncoded(
+                    "srprop".into(),
+                    "snippet|titlesnippet|size|wordcount|timestamp".into(),
+                ),
+            ]))
+            .match_request(|request| !request.path_and_query().contains("srqiprofile="))
+            .with_body(r#"{"query": {"search": []}}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let mut config = AppConfig::for_tests();
+        let plain_service = WikipediaService::new(config.clone())
+            .unwrap()
+            .with_api_base(server.url());
+        config.wikipedia.search_profile = Some("popular_inclinks".to_string());
+        let profiled_service = WikipediaService::new(config)
+            .unwrap()
+            .with_api_base(server.url());
+
+        let _ = profiled_service
+            .search("profiled", SupportedLanguage::English)
+            .await;
+        let _ = plain_service
+            .search("plain", SupportedLanguage::English)
+            .await;
+
+        with_profile.assert_async().await;
+        without_profile.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_slow_response_maps_to_timeout() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        // Принимаем соединение, но не отвечаем дольше таймаута клиента
+        let server = tokio::spawn(async move {
+            let (_socket, _) = listener.accept().await.unwrap();
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        });
+
+        let mut config = AppConfig::for_tests();
+        config.wikipedia.request_timeout_secs = 1;
+        let service = WikipediaService::new(config)
+            .unwrap()
+            .with_api_base(format!("http://{address}"));
+
+        let result = service.search("Einstein", SupportedLanguage::English).await;
+        assert!(matches!(result, Err(WikiError::Timeout)), "{result:?}");
+        assert!(result.unwrap_err().user_message().starts_with("⏱️"));
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_refused_connection_maps_to_connect_error() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        drop(listener);
+
+        let config = AppConfig::for_tests();
+        let service = WikipediaService::new(config)
+            .unwrap()
+            .with_api_base(format!("http://{address}"));
+
+        let result = service.search("Einstein", SupportedLanguage::English).await;
+        assert!(matches!(result, Err(WikiError::Connect(_))), "{result:?}");
+    }
+
+    #[test]
+    fn test_prepare_query_applies_synonyms() {
+        let mut config = AppConfig::for_tests();
+        config.wikipedia.query_synonyms =
+            HashMap::from([("covid".to_string(), "COVID-19".to_string())]);
+        let service = WikipediaService::new(config).unwrap();
+
+        assert_eq!(service.prepare_query("covid").unwrap(), "COVID-19");
+        assert_eq!(service.prepare_query("  CoViD ").unwrap(), "COVID-19");
+        assert_eq!(
+            service.prepare_query("covid vaccine").unwrap(),
+            "covid vaccine"
+        );
+        assert_eq!(service.prepare_query("Москва").unwrap(), "Москва");
+    }
+
+    #[test]
+    fn test_prepare_query_sanitize_is_opt_in() {
+        let mut config = AppConfig::for_tests();
+        let service = WikipediaService::new(config.clone()).unwrap();
+        assert_eq!(service.prepare_query("C++ (язык)").unwrap(), "C++ (язык)");
+
+        config.wikipedia.sanitize_queries = true;
+        let service = WikipediaService::new(config).unwrap();
+        assert_eq!(service.prepare_query("C++ (язык)").unwrap(), "C язык");
+    }
+
+    #[test]
+    fn test_extract_sentences_replace_exchars() {
+        let mut config = AppConfig::for_tests();
+
+        let service = WikipediaService::new(config.clone()).unwrap();
+        assert_eq!(
+            service.extract_length_param(),
+            ("exchars", EXTRACT_CHARS.to_string())
+        );
+
+        config.wikipedia.extract_sentences = Some(2);
+        let service = WikipediaService::new(config).unwrap();
+        assert_eq!(
+            service.extract_length_param(),
+            ("exsentences", "2".to_string())
+        );
+        let batch_info = service.batch_info_from_page(
+            serde_json::from_str(r#"{"pageid": 1, "title": "Test", "extract": "One. Two."}"#)
+                .unwrap(),
+            SupportedLanguage::English,
+        );
+        assert!(batch_info.sentence_bounded);
+    }
+
+    #[test]
+    fn test_original_image_used_when_thumbnail_missing() {
+        let service = WikipediaService::new(AppConfig::for_tests()).unwrap();
+
+        let batch_info = service.batch_info_from_page(
+            serde_json::from_str(
+                r#"{"pageid": 1, "title": "Ulm", "original": {
+                    "source": "https://upload.wikimedia.org/wikipedia/commons/a/ab/Ulm_M%C3%BCnster.jpg",
+                    "width": 3000, "height": 2000}}"#,
+            )
+            .unwrap(),
+            SupportedLanguage::English,
+        );
+        assert_eq!(
+            batch_info.image_url.as_deref(),
+            Some("https://commons.wikimedia.org/wiki/Special:FilePath/Ulm_M%C3%BCnster.jpg?width=300")
+        );
+        assert_eq!(
+            (batch_info.image_width, batch_info.image_height),
+            (Some(300), Some(200))
+        );
+
+        // Миниатюра важнее оригинала, чужие ссылки не переделываются
+        let batch_info = service.batch_info_from_page(
+            serde_json::from_str(
+                r#"{"pageid": 2, "title": "Test",
+                    "thumbnail": {"source": "https://upload.wikimedia.org/t.jpg", "width": 300, "height": 300},
+                    "original": {"source": "https://upload.wikimedia.org/wikipedia/ru/1/12/O.png", "width": 600, "height": 600}}"#,
+            )
+            .unwrap(),
+            SupportedLanguage::English,
+        );
+        assert_eq!(
+            batch_info.image_url.as_deref(),
+            Some("https://upload.wikimedia.org/t.jpg")
+        );
+        let original = WikipediaThumbnail {
+            source: "https://example.org/wikipedia/ru/1/12/O.png".to_string(),
+            width: 600,
+            height: 600,
         };
+        assert!(WikipediaThumbnail::from_original(&original, 300).is_none());
+    }
 
-        let enriched_articles = articles
-            .into_iter()
-            .enumerate()
-            .filter_map(|(index, article)| {
-                if let Some(pageid) = article.pageid {
-                    let article_url = self.get_article_url(&article.title, language);
-                    let batch_data = batch_info.get(&pageid).cloned();
+    #[test]
+    fn test_clean_extract_trim_parentheticals_is_opt_in() {
+        let mut config = AppConfig::for_tests();
+        let intro = "Albert Einstein (14 March 1879 – 18 April 1955) was a physicist.";
 
-                    let enriched_article =
-                        EnrichedArticle::new(article, batch_data, None, article_url)
-                            .with_relevance_index(Some(index as i32));
+        let service = WikipediaService::new(config.clone()).unwrap();
+        assert_eq!(
+            service.clean_extract(Some(intro.to_string())).as_deref(),
+            Some(intro)
+        );
 
-                    Some(enriched_article)
-                } else {
-                    None
-                }
-            })
+        config.wikipedia.trim_parentheticals = true;
+        let service = WikipediaService::new(config).unwrap();
+        assert_eq!(
+            service.clean_extract(Some(intro.to_string())).as_deref(),
+            Some("Albert Einstein was a physicist.")
+        );
+        assert_eq!(service.clean_extract(None), None);
+    }
+
+    #[test]
+    fn test_topic_categories_skip_hidden() {
+        let categories: Vec<WikipediaCategory> = serde_json::from_str(
+            r#"[
+                {"ns": 14, "title": "Категория:Физики Германии"},
+                {"ns": 14, "title": "Категория:Википедия:Статьи с мёртвыми ссылками", "hidden": ""},
+                {"ns": 14, "title": "Category:Articles with dead external links", "hidden": true},
+                {"ns": 14, "title": "Category:Nobel laureates in Physics"}
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            WikipediaService::topic_categories(Some(categories), SupportedLanguage::Russian),
+            vec![
+                "Физики Германии".to_string(),
+                "Nobel laureates in Physics".to_string()
+            ]
+        );
+        assert!(WikipediaService::topic_categories(None, SupportedLanguage::Russian).is_empty());
+    }
+
+    #[test]
+    fn test_expand_category_filter() {
+        assert_eq!(
+            expand_category_filter("cat:Физики Германии"),
+            "incategory:\"Физики Германии\""
+        );
+        assert_eq!(expand_category_filter("cat:"), "cat:");
+        assert_eq!(expand_category_filter("Эйнштейн"), "Эйнштейн");
+    }
+
+    #[test]
+    fn test_api_url_uses_base_override() {
+        let config = AppConfig::for_tests();
+        let service = WikipediaService::new(config.clone()).unwrap();
+        assert_eq!(
+            service.api_url(SupportedLanguage::German),
+            "https://de.wikipedia.org/w/api.php"
+        );
+
+        let service = WikipediaService::new(config.clone())
+            .unwrap()
+            .with_api_base("https://mirror.example/{lang}/");
+        assert_eq!(
+            service.api_url(SupportedLanguage::German),
+            "https://mirror.example/de/w/api.php"
+        );
+
+        let service = WikipediaService::new(config)
+            .unwrap()
+            .with_api_base("http://127.0.0.1:8080");
+        assert_eq!(
+            service.api_url(SupportedLanguage::German),
+            "http://127.0.0.1:8080/w/api.php"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unified_search_against_mock_server() {
+        let mut server = mockito::Server::new_async().await;
+
+        let unified_mock = server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "generator".into(),
+                "search".into(),
+            ))
+            .with_body(
+                r#"{
+                    "batchcomplete": "",
+                    "query": {
+                        "pages": {
+                            "10": {
+                                "pageid": 10,
+                                "title": "Second",
+                                "index": 2,
+                                "extract": "Second article extract.",
+                                "thumbnail": {"source": "https://upload.wikimedia.org/second.jpg", "width": 300, "height": 200},
+                                "pageprops": {"wikibase_item": "Q10"}
+                            },
+                            "20": {
+                                "pageid": 20,
+                                "title": "First",
+                                "index": 1
+                            }
+                        }
+                    }
+                }"#,
+            )
+            .expect(1)
+            .create_async()
+            .await;
+
+        let fallback_mock = server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("list".into(), "search".into()),
+                mockito::Matcher::UrlEncoded("srsearch".into(), "First".into()),
+            ]))
+            .with_body(
+                r#"{
+                    "query": {
+                        "search": [
+                            {"title": "First", "snippet": "<span class=\"searchmatch\">First</span> snippet", "pageid": 20}
+                        ]
+                    }
+                }"#,
+            )
+            .expect(1)
+            .create_async()
+            .await;
+
+        let config = AppConfig::for_tests();
+        let service = WikipediaService::new(config)
+            .unwrap()
+            .with_api_base(server.url());
+
+        let articles = service
+            .search_and_get_info_unified("query", SupportedLanguage::English)
+            .await
+            .unwrap()
+            .articles;
+
+        unified_mock.assert_async().await;
+        fallback_mock.assert_async().await;
+
+        let titles: Vec<&str> = articles
+            .iter()
+            .map(|article| article.basic_info.title.as_str())
             .collect();
+        assert_eq!(titles, vec!["First", "Second"]);
 
-        Ok(enriched_articles)
+        assert_eq!(articles[0].basic_info.snippet, "First snippet");
+        assert_eq!(articles[1].basic_info.snippet, "Second article extract.");
+        assert_eq!(articles[1].image_dimensions(), Some((300, 200)));
+        assert_eq!(
+            articles[1]
+                .batch_info
+                .as_ref()
+                .and_then(|info| info.wikidata_id.as_deref()),
+            Some("Q10")
+        );
     }
 
-    async fn get_enriched_articles_optimized(
-        &self,
-        query: &str,
-        language: SupportedLanguage,
-    ) -> WikiResult<Vec<EnrichedArticle>> {
-        let cache_key = format!("unified:{}:{}", language.code(), query.to_lowercase());
+    #[tokio::test]
+    async fn test_unified_search_follows_extract_continuation() {
+        let mut server = mockito::Server::new_async().await;
+
+        server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "generator".into(),
+                "search".into(),
+            ))
+            .with_body(
+                r#"{
+                    "continue": {"excontinue": 1, "continue": "gsroffset||"},
+                    "query": {"pages": {
+                        "1": {"pageid": 1, "title": "First", "index": 1, "extract": "First extract."},
+                        "2": {"pageid": 2, "title": "Second", "index": 2},
+                        "3": {"pageid": 3, "title": "Third", "index": 3}
+                    }}
+                }"#,
+            )
+            .create_async()
+            .await;
+        let first_chunk = server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("pageids".into(), "2|3".into()),
+                mockito::Matcher::UrlEncoded("continue".into(), "".into()),
+            ]))
+            .with_body(
+                r#"{
+                    "continue": {"excontinue": 1, "continue": "||"},
+                    "query": {"pages": {
+                        "2": {"pageid": 2, "title": "Second", "extract": "Second extract."},
+                        "3": {"pageid": 3, "title": "Third"}
+                    }}
+                }"#,
+            )
+            .create_async()
+            .await;
+        let second_chunk = server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("pageids".into(), "2|3".into()),
+                mockito::Matcher::UrlEncoded("excontinue".into(), "1".into()),
+            ]))
+            .with_body(
+                r#"{"query": {"pages": {
+                    "2": {"pageid": 2, "title": "Second"},
+                    "3": {"pageid": 3, "title": "Third", "extract": "Third extract."}
+                }}}"#,
+            )
+            .create_async()
+            .await;
+        let snippet_fallback = server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded("list".into(), "search".into()))
+            .expect(0)
+            .create_async()
+            .await;
+
+        let config = AppConfig::for_tests();
+        let service = WikipediaService::new(config)
+            .unwrap()
+            .with_api_base(server.url());
+
+        let articles = service
+            .search_and_get_info_unified("query", SupportedLanguage::English)
+            .await
+            .unwrap()
+            .articles;
+
+        first_chunk.assert_async().await;
+        second_chunk.assert_async().await;
+        snippet_fallback.assert_async().await;
+        let extracts: Vec<_> = articles
+            .iter()
+            .map(|article| article.batch_info.as_ref().unwrap().extract.as_deref())
+            .collect();
+        assert_eq!(
+            extracts,
+            vec![
+                Some("First extract."),
+                Some("Second extract."),
+                Some("Third extract.")
+            ]
+        );
+    }
 
-        if let Some(cached_result) = self.unified_cache.get(&cache_key).await {
-            return Ok(cached_result);
-        }
+    #[tokio::test]
+    async fn test_batch_continuation_keeps_sentence_limit() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("pageids".into(), "1|2".into()),
+                mockito::Matcher::UrlEncoded("exsentences".into(), "2".into()),
+            ]))
+            .with_body(
+                r#"{
+                    "continue": {"excontinue": 1, "continue": "||"},
+                    "query": {"pages": {
+                        "1": {"pageid": 1, "title": "First", "extract": "One. Two."},
+                        "2": {"pageid": 2, "title": "Second"}
+                    }}
+                }"#,
+            )
+            .create_async()
+            .await;
+        let continued = server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("pageids".into(), "2".into()),
+                mockito::Matcher::UrlEncoded("exsentences".into(), "2".into()),
+            ]))
+            .with_body(
+                r#"{"query": {"pages": {"2": {"pageid": 2, "title": "Second", "extract": "Three. Four."}}}}"#,
+            )
+            .create_async()
+            .await;
+
+        let mut config = AppConfig::for_tests();
+        config.wikipedia.extract_sentences = Some(2);
+        let service = WikipediaService::new(config)
+            .unwrap()
+            .with_api_base(server.url());
+
+        let batch = service
+            .get_batch_info_internal(vec![1, 2], SupportedLanguage::English)
+            .await
+            .unwrap();
+
+        continued.assert_async().await;
+        assert_eq!(batch[&2].extract.as_deref(), Some("Three. Four."));
+        assert!(batch[&2].sentence_bounded);
+    }
+
+    #[tokio::test]
+    async fn test_get_sections() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "page".into(),
+                "Albert Einstein".into(),
+            ))
+            .with_body(
+                r#"{"parse": {"title": "Albert Einstein", "sections": [
+                    {"toclevel": 1, "level": "2", "line": "Early life", "anchor": "Early_life"},
+                    {"toclevel": 2, "level": "3", "line": "<i>Annus mirabilis</i>", "anchor": "Annus_mirabilis"}
+                ]}}"#,
+            )
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded("page".into(), "Stub".into()))
+            .with_body(r#"{"parse": {"title": "Stub", "sections": []}}"#)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded("page".into(), "Missing".into()))
+            .with_body(
+                r#"{"error": {"code": "missingtitle", "info": "The page you specified doesn't exist."}}"#,
+            )
+            .create_async()
+            .await;
+
+        let config = AppConfig::for_tests();
+        let service = WikipediaService::new(config)
+            .unwrap()
+            .with_api_base(server.url());
+
+        let sections = service
+            .get_sections("Albert Einstein", SupportedLanguage::English)
+            .await
+            .unwrap();
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[1].level, 2);
+        assert_eq!(sections[1].title, "Annus mirabilis");
+
+        let sections = service
+            .get_sections("Stub", SupportedLanguage::English)
+            .await
+            .unwrap();
+        assert!(sections.is_empty());
+
+        let missing = service
+            .get_sections("Missing", SupportedLanguage::English)
+            .await;
+        assert!(matches!(missing, Err(WikiError::NoResults { .. })));
+    }
 
-        let result = self.search_and_get_info_unified(query, language).await;
+    #[tokio::test]
+    async fn test_optimized_search_does_not_fall_back_on_no_results() {
+        let mut server = mockito::Server::new_async().await;
+        let any_request = server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::Any)
+            .expect(0)
+            .create_async()
+            .await;
+
+        let config = AppConfig::for_tests();
+        let service = WikipediaService::new(config)
+            .unwrap()
+            .with_api_base(server.url());
+
+        let result = service
+            .get_enriched_articles_optimized("   ", SupportedLanguage::English)
+            .await;
+
+        assert!(matches!(result, Err(WikiError::NoResults { .. })));
+        any_request.assert_async().await;
+    }
 
-        match &result {
-            Ok(enriched_articles) => {
-                self.unified_cache
-                    .insert(cache_key, enriched_articles.clone())
-                    .await;
-            }
-            Err(_) => {
-                return self.get_enriched_articles(query, language).await;
-            }
+    #[tokio::test]
+    async fn test_optimized_search_falls_back_on_server_error_and_remembers_it() {
+        let mut server = mockito::Server::new_async().await;
+        let unified = server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "generator".into(),
+                "search".into(),
+            ))
+            .with_status(503)
+            .expect(2)
+            .create_async()
+            .await;
+        let legacy_search = server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded("list".into(), "search".into()))
+            .with_body(
+                r#"{"query": {"search": [{"title": "Fallback", "snippet": "Snippet", "pageid": 7}]}}"#,
+            )
+            .expect(2)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded("pageids".into(), "7".into()))
+            .with_body(r#"{"query": {"pages": {"7": {"pageid": 7, "title": "Fallback"}}}}"#)
+            .create_async()
+            .await;
+
+        let mut config = AppConfig::for_tests();
+        config.cache.enabled = false;
+        config.retry.max_attempts = 2;
+        config.retry.base_delay_ms = 1;
+        let service = WikipediaService::new(config)
+            .unwrap()
+            .with_api_base(server.url());
+
+        // 503 повторяется один раз, после чего unified API на паузе
+        for query in ["first", "second"] {
+            let articles = service
+                .get_enriched_articles_optimized(query, SupportedLanguage::English)
+                .await
+                .unwrap();
+            assert_eq!(articles[0].basic_info.title, "Fallback");
         }
 
-        result
+        // Второй запрос не трогает unified API, пока действует пауза после сбоя
+        unified.assert_async().await;
+        legacy_search.assert_async().await;
+        assert_eq!(
+            service.preferred_path(SupportedLanguage::English).await,
+            SearchPath::Legacy
+        );
+        let paths = service.cache_stats().await.paths;
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].consecutive_failures, 1);
     }
 
-    fn get_article_url(&self, title: &str, language: SupportedLanguage) -> String {
-        format!(
-            "https://{}.wikipedia.org/wiki/{}",
-            language.code(),
-            urlencoding::encode(title)
-        )
+    #[tokio::test]
+    async fn test_repeated_unified_failures_grow_the_pause() {
+        let mut server = mockito::Server::new_async().await;
+        // Сломанный ответ — не 5xx, но тоже уводит на обычный поиск
+        let unified = server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "generator".into(),
+                "search".into(),
+            ))
+            .with_body("<html>not json</html>")
+            .expect(2)
+            .create_async()
+            .await;
+        let legacy_search = server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded("list".into(), "search".into()))
+            .with_body(
+                r#"{"query": {"search": [{"title": "Fallback", "snippet": "Snippet", "pageid": 7}]}}"#,
+            )
+            .expect(3)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded("pageids".into(), "7".into()))
+            .with_body(r#"{"query": {"pages": {"7": {"pageid": 7, "title": "Fallback"}}}}"#)
+            .create_async()
+            .await;
+
+        let mut config = AppConfig::for_tests();
+        config.cache.enabled = false;
+        config.retry.max_attempts = 1;
+        let service = WikipediaService::new(config)
+            .unwrap()
+            .with_api_base(server.url())
+            .with_unified_cooldown(Duration::from_millis(100), Duration::from_secs(10));
+        let language = SupportedLanguage::English;
+        let search = |query| service.get_enriched_articles_optimized(query, language);
+
+        search("first").await.unwrap();
+        let paths = service.cache_stats().await.paths;
+        assert_eq!(paths[0].preferred, SearchPath::Legacy);
+        assert_eq!(paths[0].consecutive_failures, 1);
+        assert!(paths[0].retry_in.unwrap() <= Duration::from_millis(100));
+
+        // Во время паузы unified API не запрашивается
+        search("second").await.unwrap();
+        assert_eq!(service.cache_stats().await.paths[0].consecutive_failures, 1);
+
+        // После паузы — пробная попытка; её сбой удваивает паузу
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert_eq!(service.preferred_path(language).await, SearchPath::Unified);
+        search("third").await.unwrap();
+        let paths = service.cache_stats().await.paths;
+        assert_eq!(paths[0].preferred, SearchPath::Legacy);
+        assert_eq!(paths[0].consecutive_failures, 2);
+        assert!(paths[0].retry_in.unwrap() > Duration::from_millis(100));
+
+        unified.assert_async().await;
+        legacy_search.assert_async().await;
     }
-}
 
-pub fn parse_query_with_language(query: &str) -> (SupportedLanguage, String) {
-    crate::config::languages::parse_query_with_language(query)
-}
+    #[tokio::test]
+    async fn test_optimized_search_falls_back_on_unified_timeout() {
+        let mut server = mockito::Server::new_async().await;
+        let unified = server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "generator".into(),
+                "search".into(),
+            ))
+            .with_chunked_body(|writer| {
+                std::thread::sleep(Duration::from_millis(1500));
+                writer.write_all(br#"{"query": {"pages": {}}}"#)
+            })
+            .expect(1)
+            .create_async()
+            .await;
+        let legacy_search = server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded("list".into(), "search".into()))
+            .with_body(
+                r#"{"query": {"search": [{"title": "Fallback", "snippet": "Snippet", "pageid": 7}]}}"#,
+            )
+            .expect(1)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded("pageids".into(), "7".into()))
+            .with_body(r#"{"query": {"pages": {"7": {"pageid": 7, "title": "Fallback"}}}}"#)
+            .create_async()
+            .await;
+
+        let mut config = AppConfig::for_tests();
+        config.wikipedia.request_timeout_secs = 1;
+        config.retry.max_attempts = 1;
+        let service = WikipediaService::new(config)
+            .unwrap()
+            .with_api_base(server.url());
+
+        let articles = service
+            .get_enriched_articles_optimized("slow", SupportedLanguage::English)
+            .await
+            .unwrap();
+        assert_eq!(articles[0].basic_info.title, "Fallback");
+        unified.assert_async().await;
+        legacy_search.assert_async().await;
+    }
 
-pub fn get_article_url_lang(title: &str, language: &WikipediaLanguage) -> String {
-    format!(
-        "https://{}.wikipedia.org/wiki/{}",
-        language.code(),
-        urlencoding::encode(title)
-    )
-}
+    #[tokio::test]
+    async fn test_optimized_search_walks_fallback_languages() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/ru/w/api.php")
+            .match_query(mockito::Matcher::Any)
+            .with_body(r#"{"query": {"search": [], "pages": {}}}"#)
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/en/w/api.php")
+            .match_query(mockito::Matcher::Any)
+            .with_body(unified_page_body("Found in English", false))
+            .create_async()
+            .await;
+
+        let mut config = AppConfig::for_tests();
+        config.wikipedia.fallback_languages = ["ru", "en", "ru"].map(String::from).to_vec();
+        let service = WikipediaService::new(config)
+            .unwrap()
+            .with_api_base(format!("{}/{{lang}}", server.url()));
+
+        let articles = service
+            .get_enriched_articles_optimized("query", SupportedLanguage::Russian)
+            .await
+            .unwrap();
+        assert_eq!(articles[0].basic_info.title, "Found in English");
+        assert_eq!(
+            articles[0].source_language,
+            Some(SupportedLanguage::English)
+        );
+        assert!(articles[0]
+            .article_url
+            .starts_with("https://en.wikipedia.org/wiki/"));
+    }
 
-pub async fn search_wikipedia_lang(
-    query: &str,
-    language: &WikipediaLanguage,
-) -> WikiResult<Vec<WikipediaSearchItem>> {
-    let config = crate::config::AppConfig::from_env()?;
-    let service = WikipediaService::new(config)?;
+    #[tokio::test]
+    async fn test_concurrent_identical_queries_share_one_request() {
+        let mut server = mockito::Server::new_async().await;
+        let unified = server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::Any)
+            .with_chunked_body(|writer| {
+                std::thread::sleep(std::time::Duration::from_millis(300));
+                writer.write_all(unified_page_body("Trending", true).as_bytes())
+            })
+            .expect(1)
+            .create_async()
+            .await;
+
+        let config = AppConfig::for_tests();
+        let service = WikipediaService::new(config)
+            .unwrap()
+            .with_api_base(server.url());
+
+        let results = futures::future::join_all((0..10).map(|i| {
+            let service = service.clone();
+            // Запросы отличаются только регистром и пробелами — ключ кэша общий
+            let query = if i % 2 == 0 { "trending" } else { " Trending " };
+            async move {
+                service
+                    .get_enriched_articles_optimized(query, SupportedLanguage::English)
+                    .await
+            }
+        }))
+        .await;
 
-    service.search(query, language.inner()).await
-}
+        for result in results {
+            assert_eq!(result.unwrap()[0].basic_info.title, "Trending");
+        }
+        unified.assert_async().await;
+    }
 
-pub async fn get_articles_batch_info_lang(
-    pageids: Vec<u64>,
-    language: &WikipediaLanguage,
-) -> WikiResult<HashMap<u64, ArticleBatchInfo>> {
-    let config = crate::config::AppConfig::from_env()?;
-    let service = WikipediaService::new(config)?;
+    #[tokio::test]
+    async fn test_slow_search_serves_cached_prefix_results() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "gsrsearch".into(),
+                "einst".into(),
+            ))
+            .with_body(unified_page_body("Einstein (prefix)", false))
+            .create_async()
+            .await;
+        let slow = server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "gsrsearch".into(),
+                "einstein".into(),
+            ))
+            .with_chunked_body(|writer| {
+                std::thread::sleep(std::time::Duration::from_millis(800));
+                writer.write_all(unified_page_body("Albert Einstein", true).as_bytes())
+            })
+            .expect(1)
+            .create_async()
+            .await;
+
+        let mut config = AppConfig::for_tests();
+        config.wikipedia.cached_fallback_deadline_ms = Some(100);
+        let service = WikipediaService::new(config)
+            .unwrap()
+            .with_api_base(server.url());
+
+        service
+            .get_enriched_articles_optimized("einst", SupportedLanguage::English)
+            .await
+            .unwrap();
+
+        let started = std::time::Instant::now();
+        let articles = service
+            .get_enriched_articles_optimized("einstein", SupportedLanguage::English)
+            .await
+            .unwrap();
+        assert_eq!(articles[0].basic_info.title, "Einstein (prefix)");
+        assert!(started.elapsed() < std::time::Duration::from_millis(600));
+
+        // Медленный запрос дозаполняет кэш в фоне
+        tokio::time::sleep(std::time::Duration::from_millis(1200)).await;
+        let articles = service
+            .get_enriched_articles_optimized("einstein", SupportedLanguage::English)
+            .await
+            .unwrap();
+        assert_eq!(articles[0].basic_info.title, "Albert Einstein");
+        slow.assert_async().await;
+    }
 
-    service.get_batch_info(pageids, language.inner()).await
-}
+    #[tokio::test]
+    async fn test_prefix_search_enriched_keeps_prefixsearch_order() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("generator".into(), "prefixsearch".into()),
+                mockito::Matcher::UrlEncoded("gpssearch".into(), "Einst".into()),
+                mockito::Matcher::UrlEncoded("prop".into(), PAGE_PROPS_WITH_ASSESSMENTS.into()),
+            ]))
+            .with_body(
+                r#"{"query": {"pages": {
+                    "10": {"pageid": 10, "title": "Einsteinium", "index": 2, "extract": "Element."},
+                    "20": {"pageid": 20, "title": "Einstein", "index": 1, "extract": "Physicist.",
+                           "thumbnail": {"source": "https://upload.wikimedia.org/e.jpg", "width": 300, "height": 400}},
+                    "30": {"pageid": 30, "title": "Einstein ring", "extract": "Lensing."}
+                }}}"#,
+            )
+            .expect(1)
+            .create_async()
+            .await;
+
+        let config = AppConfig::for_tests();
+        let service = WikipediaService::new(config)
+            .unwrap()
+            .with_api_base(server.url());
+
+        let articles = service
+            .prefix_search_enriched("Einst", SupportedLanguage::English)
+            .await
+            .unwrap();
+        let titles: Vec<&str> = articles
+            .iter()
+            .map(|article| article.basic_info.title.as_str())
+            .collect();
+        // Страница без index идёт после упорядоченных
+        assert_eq!(titles, ["Einstein", "Einsteinium", "Einstein ring"]);
+        assert!(articles[0].valid_image_url().is_some());
+
+        // Повторный запрос берётся из кэша
+        service
+            .prefix_search_enriched("einst ", SupportedLanguage::English)
+            .await
+            .unwrap();
+        mock.assert_async().await;
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    fn unified_page_body(title: &str, with_image: bool) -> String {
+        let thumbnail = if with_image {
+            r#", "thumbnail": {"source": "https://upload.wikimedia.org/a.jpg", "width": 300, "height": 300}"#
+        } else {
+            ""
+        };
+        format!(
+            r#"{{"query": {{"pages": {{"1": {{"pageid": 1, "title": "{title}", "index": 1, "extract": "Extract."{thumbnail}}}}}}}}}"#
+        )
+    }
 
     #[tokio::test]
-    async fn test_cache_key_generation() {
-        std::env::set_var("BOT_TOKEN", "test_token_123");
-        let config = AppConfig::from_env().unwrap();
-        let service = WikipediaService::new(config).unwrap();
+    async fn test_search_best_effort_picks_strong_language() {
+        let mut server = mockito::Server::new_async().await;
+
+        server
+            .mock("GET", "/ru/w/api.php")
+            .match_query(mockito::Matcher::Any)
+            .with_body(unified_page_body("Слабая", false))
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/en/w/api.php")
+            .match_query(mockito::Matcher::Any)
+            .with_body(unified_page_body("Strong", true))
+            .create_async()
+            .await;
+        server
+            .mock(
+                "GET",
+                mockito::Matcher::Regex(r"^/(uk|de|fr|es)/w/api\.php".to_string()),
+            )
+            .match_query(mockito::Matcher::Any)
+            .with_chunked_body(|writer| {
+                std::thread::sleep(std::time::Duration::from_secs(3));
+                writer.write_all(unified_page_body("Slow", true).as_bytes())
+            })
+            .create_async()
+            .await;
 
-        let key1 = service.search_cache_key("test", SupportedLanguage::English);
-        let key2 = service.search_cache_key("Test", SupportedLanguage::English);
+        let config = AppConfig::for_tests();
+        let service = WikipediaService::new(config)
+            .unwrap()
+            .with_api_base(format!("{}/{{lang}}", server.url()));
 
-        assert_eq!(key1, key2);
+        let started = std::time::Instant::now();
+        let (language, articles) = service.search_best_effort("query").await.unwrap();
 
-        let key3 = service.search_cache_key("test", SupportedLanguage::Russian);
-        assert_ne!(key1, key3);
+        assert_eq!(language, SupportedLanguage::English);
+        assert_eq!(articles[0].basic_info.title, "Strong");
+        assert!(started.elapsed() < std::time::Duration::from_secs(2));
     }
 
-    #[test]
-    fn test_get_article_url() {
-        std::env::set_var("BOT_TOKEN", "test_token_123");
-        let config = AppConfig::from_env().unwrap();
-        let service = WikipediaService::new(config).unwrap();
+    #[tokio::test]
+    async fn test_search_best_effort_falls_back_to_weak_result() {
+        let mut server = mockito::Server::new_async().await;
+
+        server
+            .mock("GET", "/uk/w/api.php")
+            .match_query(mockito::Matcher::Any)
+            .with_body(unified_page_body("Слабка", false))
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/de/w/api.php")
+            .match_query(mockito::Matcher::Any)
+            .with_body(unified_page_body("Schwach", false))
+            .create_async()
+            .await;
+        server
+            .mock(
+                "GET",
+                mockito::Matcher::Regex(r"^/(ru|en|fr|es)/w/api\.php".to_string()),
+            )
+            .match_query(mockito::Matcher::Any)
+            .with_body(r#"{"query": {"search": []}}"#)
+            .create_async()
+            .await;
+
+        let config = AppConfig::for_tests();
+        let service = WikipediaService::new(config)
+            .unwrap()
+            .with_api_base(format!("{}/{{lang}}", server.url()));
+
+        let (language, articles) = service.search_best_effort("query").await.unwrap();
+
+        assert_eq!(language, SupportedLanguage::Ukrainian);
+        assert_eq!(articles[0].basic_info.title, "Слабка");
+    }
 
-        let url = service.get_article_url("Test Article", SupportedLanguage::English);
-        assert_eq!(url, "https://en.wikipedia.org/wiki/Test%20Article");
+    #[test]
+    fn test_suggestion_from_empty_search_response() {
+        let response: WikipediaSearchResponse = serde_json::from_str(
+            r#"{
+                "batchcomplete": "",
+                "query": {
+                    "searchinfo": {"totalhits": 0, "suggestion": "albert einstein", "suggestionsnippet": "albert einstein"},
+                    "search": []
+                }
+            }"#,
+        )
+        .unwrap();
 
-        let url_ru = service.get_article_url("Тест", SupportedLanguage::Russian);
+        assert!(response.query.search.is_empty());
         assert_eq!(
-            url_ru,
-            "https://ru.wikipedia.org/wiki/%D0%A2%D0%B5%D1%81%D1%82"
+            WikipediaService::suggestion_from(response),
+            Some("albert einstein".to_string())
         );
+
+        let response: WikipediaSearchResponse =
+            serde_json::from_str(r#"{"query": {"search": []}}"#).unwrap();
+        assert_eq!(WikipediaService::suggestion_from(response), None);
     }
 
     #[test]
     fn test_create_snippet_from_extract() {
         let short_extract = "Короткий текст.";
-        let snippet = WikipediaService::create_snippet_from_extract(short_extract);
+        let snippet = WikipediaService::create_snippet_from_extract(
+            short_extract,
+            SupportedLanguage::Russian,
+        );
         assert_eq!(snippet, "Короткий текст.");
 
         let simple_long = "A".repeat(250);
-        let snippet = WikipediaService::create_snippet_from_extract(&simple_long);
+        let snippet =
+            WikipediaService::create_snippet_from_extract(&simple_long, SupportedLanguage::English);
         println!("Simple long snippet length: {}", snippet.len());
         assert!(snippet.len() <= 200);
         assert!(snippet.ends_with("..."));
 
         let text_with_spaces = "word ".repeat(50);
-        let snippet = WikipediaService::create_snippet_from_extract(&text_with_spaces);
+        let snippet = WikipediaService::create_snippet_from_extract(
+            &text_with_spaces,
+            SupportedLanguage::English,
+        );
         println!("Spaces text snippet length: {}", snippet.len());
         assert!(snippet.len() <= 200);
         assert!(snippet.ends_with("..."));
     }
+
+    #[test]
+    fn test_create_snippet_from_cjk_extract_prefers_sentence_end() {
+        // 150 символов в первом предложении, затем длинное второе без точки
+        let japanese = format!("{}。{}", "東".repeat(150), "京".repeat(100));
+        let snippet =
+            WikipediaService::create_snippet_from_extract(&japanese, SupportedLanguage::Japanese);
+        assert_eq!(snippet, format!("{}。", "東".repeat(150)));
+
+        let chinese = format!("{}！{}", "北".repeat(180), "京".repeat(100));
+        let snippet =
+            WikipediaService::create_snippet_from_extract(&chinese, SupportedLanguage::Chinese);
+        assert_eq!(snippet, format!("{}！", "北".repeat(180)));
+    }
+
+    #[test]
+    fn test_create_snippet_from_cjk_extract_without_nearby_sentence_end() {
+        // Точка слишком далеко от лимита — режем по границе символа
+        let japanese = format!("{}。{}", "東".repeat(20), "京".repeat(300));
+        let snippet =
+            WikipediaService::create_snippet_from_extract(&japanese, SupportedLanguage::Japanese);
+        assert!(snippet.ends_with("京..."));
+        assert_eq!(snippet.chars().count(), 200);
+
+        let chinese = "中".repeat(300);
+        let snippet =
+            WikipediaService::create_snippet_from_extract(&chinese, SupportedLanguage::Chinese);
+        assert_eq!(snippet, format!("{}...", "中".repeat(197)));
+    }
+
+    #[tokio::test]
+    async fn test_probe_reports_unreachable_api() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "meta".into(),
+                "siteinfo".into(),
+            ))
+            .with_body(r#"{"batchcomplete": "", "query": {"general": {}}}"#)
+            .create_async()
+            .await;
+
+        let config = AppConfig::for_tests();
+        let service = WikipediaService::new(config.clone())
+            .unwrap()
+            .with_api_base(server.url());
+        assert!(service.probe(SupportedLanguage::English).await.is_ok());
+
+        let broken = WikipediaService::new(config)
+            .unwrap()
+            .with_api_base(format!("{}/missing", server.url()));
+        assert!(broken.probe(SupportedLanguage::English).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_too_many_requests_maps_to_rate_limited() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::Any)
+            .with_status(429)
+            .with_header("Retry-After", "12")
+            .create_async()
+            .await;
+
+        let service = WikipediaService::new(AppConfig::for_tests())
+            .unwrap()
+            .with_api_base(server.url());
+
+        let error = service
+            .get_search_suggestion("einstein", SupportedLanguage::English)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            WikiError::RateLimited {
+                retry_after: Some(retry_after)
+            } if retry_after == Duration::from_secs(12)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_extract_format_controls_explaintext() {
+        use crate::config::Branding;
+        use crate::models::SUMMARY_LENGTH;
+
+        let page = |extract: &str| {
+            serde_json::json!({
+                "query": {"pages": {"1": {
+                    "pageid": 1, "title": "Albert Einstein", "index": 1, "extract": extract
+                }}}
+            })
+            .to_string()
+        };
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::Any)
+            .match_request(|request| request.path_and_query().contains("explaintext=1"))
+            .with_body(page("Albert Einstein was a physicist."))
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::Any)
+            .match_request(|request| !request.path_and_query().contains("explaintext"))
+            .with_body(page(
+                "<p class=\"intro\"><b>Albert Einstein</b> was a <i>physicist</i>.</p>",
+            ))
+            .create_async()
+            .await;
+
+        let mut config = AppConfig::for_tests();
+        let search = |config: AppConfig| {
+            let server_url = server.url();
+            async move {
+                WikipediaService::new(config)
+                    .unwrap()
+                    .with_api_base(server_url)
+                    .get_enriched_articles_optimized("einstein", SupportedLanguage::English)
+                    .await
+                    .unwrap()
+                    .remove(0)
+            }
+        };
+
+        config.wikipedia.extract_format = ExtractFormat::Plain;
+        let plain = search(config.clone()).await;
+        assert_eq!(
+            plain.best_description(SUMMARY_LENGTH, Default::default()),
+            "Albert Einstein was a physicist."
+        );
+        assert!(plain.batch_info.as_ref().unwrap().extract_html.is_none());
+        assert!(!plain.to_html(300, &Branding::default()).contains("<i>"));
+
+        config.wikipedia.extract_format = ExtractFormat::Html;
+        let html = search(config).await;
+        assert_eq!(
+            html.best_description(SUMMARY_LENGTH, Default::default()),
+            "Albert Einstein was a physicist."
+        );
+        assert!(html
+            .to_markdown(300, &Branding::default())
+            .contains("Albert Einstein was"));
+        assert!(html
+            .to_html(300, &Branding::default())
+            .contains("<b>Albert Einstein</b> was a <i>physicist</i>."));
+    }
+
+    #[tokio::test]
+    async fn test_get_infobox() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("prop".into(), "wikitext".into()),
+                mockito::Matcher::UrlEncoded("page".into(), "Berlin".into()),
+            ]))
+            .with_body(
+                serde_json::json!({"parse": {
+                    "title": "Berlin",
+                    "wikitext": "{{Infobox German state\n| name = Berlin\n| country = [[Germany]]\n\
+                                 | population_total = 3,878,100\n}}\n'''Berlin''' is the capital."
+                }})
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let service = WikipediaService::new(AppConfig::for_tests())
+            .unwrap()
+            .with_api_base(server.url());
+
+        let infobox = service
+            .get_infobox("Berlin", SupportedLanguage::English)
+            .await
+            .unwrap();
+        assert_eq!(infobox["name"], "Berlin");
+
+        let facts = crate::models::InfoboxFacts::from_infobox(&infobox);
+        assert_eq!(facts.country.as_deref(), Some("Germany"));
+        assert_eq!(facts.population.as_deref(), Some("3,878,100"));
+    }
+
+    #[test]
+    fn test_article_score_prefers_assessed_quality() {
+        let article = |quality| {
+            EnrichedArticle::new(
+                WikipediaSearchItem {
+                    title: "Test".to_string(),
+                    snippet: String::new(),
+                    pageid: Some(1),
+                    size: None,
+                    wordcount: Some(5000),
+                    timestamp: None,
+                },
+                Some(ArticleBatchInfo {
+                    extract: Some("Extract.".to_string()),
+                    quality,
+                    ..Default::default()
+                }),
+                None,
+                String::new(),
+            )
+        };
+        let featured = article(Some(QualityClass::Featured));
+        let stub = article(Some(QualityClass::Stub));
+        let unassessed = article(None);
+
+        let score = WikipediaService::calculate_article_score;
+        assert!(score(&featured, 20.0) > score(&stub, 20.0));
+        assert!(score(&featured, 20.0) > score(&unassessed, 20.0));
+        assert_eq!(score(&featured, 0.0), score(&stub, 0.0));
+    }
+
+    #[tokio::test]
+    async fn test_batch_info_skips_missing_pages() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "pageids".into(),
+                "736|999999".into(),
+            ))
+            .with_body(
+                r#"{"batchcomplete": "", "query": {"pages": {
+                    "736": {"pageid": 736, "title": "Albert Einstein", "extract": "Physicist."},
+                    "999999": {"pageid": 999999, "missing": ""}
+                }}}"#,
+            )
+            .expect(1)
+            .create_async()
+            .await;
+
+        let service = WikipediaService::new(AppConfig::for_tests())
+            .unwrap()
+            .with_api_base(server.url());
+
+        let batch = service
+            .get_batch_info(vec![736, 999999], SupportedLanguage::English)
+            .await
+            .unwrap();
+        assert_eq!(batch.len(), 1);
+        assert_eq!(batch[&736].extract.as_deref(), Some("Physicist."));
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_unified_batchcomplete_only_is_empty_without_fallback() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "generator".into(),
+                "search".into(),
+            ))
+            .with_body(r#"{"batchcomplete": ""}"#)
+            .create_async()
+            .await;
+        let legacy_search = server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded("list".into(), "search".into()))
+            .expect(0)
+            .create_async()
+            .await;
+
+        let service = WikipediaService::new(AppConfig::for_tests())
+            .unwrap()
+            .with_api_base(server.url());
+
+        let articles = service
+            .get_enriched_articles_optimized("Nothing here", SupportedLanguage::English)
+            .await
+            .unwrap();
+        assert!(articles.is_empty());
+        legacy_search.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_batch_info_by_titles_maps_normalized_and_redirected_titles() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded(
+                    "titles".into(),
+                    "einstein|Berlin|No such page".into(),
+                ),
+                mockito::Matcher::UrlEncoded("redirects".into(), "1".into()),
+            ]))
+            .with_body(
+                r#"{"batchcomplete": "", "query": {
+                    "normalized": [{"from": "einstein", "to": "Einstein"}],
+                    "redirects": [{"from": "Einstein", "to": "Albert Einstein"}],
+                    "pages": {
+                        "736": {"pageid": 736, "title": "Albert Einstein", "extract": "Physicist.",
+                                "pageprops": {"wikibase_item": "Q937"}},
+                        "3354": {"pageid": 3354, "title": "Berlin", "extract": "Capital."},
+                        "-1": {"title": "No such page", "missing": ""}
+                    }
+                }}"#,
+            )
+            .expect(1)
+            .create_async()
+            .await;
+
+        let service = WikipediaService::new(AppConfig::for_tests())
+            .unwrap()
+            .with_api_base(server.url());
+
+        let titles = vec![
+            "einstein".to_string(),
+            "Berlin".to_string(),
+            "No such page".to_string(),
+        ];
+        let batch = service
+            .get_batch_info_by_titles(titles.clone(), SupportedLanguage::English)
+            .await
+            .unwrap();
+        assert_eq!(batch.len(), 2);
+        assert_eq!(batch["einstein"].wikidata_id.as_deref(), Some("Q937"));
+        assert_eq!(batch["Berlin"].extract.as_deref(), Some("Capital."));
+
+        // Повторный запрос тех же заголовков в другом порядке берётся из кэша
+        let mut reordered = titles;
+        reordered.reverse();
+        service
+            .get_batch_info_by_titles(reordered, SupportedLanguage::English)
+            .await
+            .unwrap();
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_unified_search_remembers_total_hits() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("generator".into(), "search".into()),
+                mockito::Matcher::UrlEncoded("gsrinfo".into(), "totalhits".into()),
+            ]))
+            .with_body(
+                r#"{"batchcomplete": "", "query": {
+                    "searchinfo": {"totalhits": 48213},
+                    "pages": {
+                        "736": {"pageid": 736, "title": "Albert Einstein", "index": 1, "extract": "Physicist."}
+                    }
+                }}"#,
+            )
+            .create_async()
+            .await;
+
+        let service = WikipediaService::new(AppConfig::for_tests())
+            .unwrap()
+            .with_api_base(server.url());
+
+        assert_eq!(
+            service
+                .total_hits("einstein", SupportedLanguage::English)
+                .await,
+            None
+        );
+        service
+            .get_enriched_articles_optimized("einstein", SupportedLanguage::English)
+            .await
+            .unwrap();
+        assert_eq!(
+            service
+                .total_hits("einstein", SupportedLanguage::English)
+                .await,
+            Some(48213)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_warm_cache_populates_search_results() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "generator".into(),
+                "search".into(),
+            ))
+            .with_body(
+                r#"{"batchcomplete": "", "query": {"pages": {
+                    "1": {"pageid": 1, "title": "Moscow", "index": 1, "extract": "Capital."}
+                }}}"#,
+            )
+            .expect(2)
+            .create_async()
+            .await;
+
+        let mut config = AppConfig::for_tests();
+        config.wikipedia.warm_queries = vec!["Москва".to_string(), "en:Moscow".to_string()];
+        let service = WikipediaService::new(config)
+            .unwrap()
+            .with_api_base(server.url());
+
+        assert_eq!(service.warm_cache().await, 2);
+
+        // После прогрева запросы отвечаются из кэша
+        service
+            .get_enriched_articles_optimized("Москва", SupportedLanguage::Russian)
+            .await
+            .unwrap();
+        service
+            .get_enriched_articles_optimized("Moscow", SupportedLanguage::English)
+            .await
+            .unwrap();
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_unified_search_remembers_interwiki_hits() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("generator".into(), "search".into()),
+                mockito::Matcher::UrlEncoded("gsrinterwiki".into(), "1".into()),
+            ]))
+            .with_body(
+                r#"{"batchcomplete": "", "query": {
+                    "interwiki": [{"title": "wikt:serendipity", "iw": "wikt"}],
+                    "pages": {
+                        "1": {"pageid": 1, "title": "Serendipity", "index": 1, "extract": "Luck."}
+                    }
+                }}"#,
+            )
+            .create_async()
+            .await;
+
+        let service = WikipediaService::new(AppConfig::for_tests())
+            .unwrap()
+            .with_api_base(server.url());
+
+        service
+            .get_enriched_articles_optimized("serendipity", SupportedLanguage::English)
+            .await
+            .unwrap();
+        let hits = service
+            .interwiki_hits("serendipity", SupportedLanguage::English)
+            .await;
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].display_title(), "serendipity");
+    }
+
+    #[tokio::test]
+    async fn test_get_definition_from_wiktionary() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/wiktionary.org/en/w/api.php")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("titles".into(), "Serendipity|serendipity".into()),
+                mockito::Matcher::UrlEncoded("exsectionformat".into(), "wiki".into()),
+            ]))
+            .with_body(
+                r#"{"batchcomplete": "", "query": {
+                    "pages": {
+                        "-1": {"ns": 0, "title": "Serendipity", "missing": ""},
+                        "7": {"pageid": 7, "ns": 0, "title": "serendipity",
+                              "extract": "== English ==\n=== Etymology ===\nCoined by Horace Walpole.\n=== Noun ===\nserendipity (uncountable)\nAn unsought, unintended, and/or unexpected discovery."}
+                    }
+                }}"#,
+            )
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/wiktionary.org/en/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "titles".into(),
+                "qwzx".into(),
+            ))
+            .with_body(
+                r#"{"batchcomplete": "", "query": {"pages": {"-1": {"ns": 0, "title": "Qwzx", "missing": ""}}}}"#,
+            )
+            .create_async()
+            .await;
+
+        let service = WikipediaService::new(AppConfig::for_tests())
+            .unwrap()
+            .with_api_base(format!("{}/{{project}}/{{lang}}", server.url()))
+            .with_project(WikiProject::Wiktionary);
+
+        let definition = service
+            .get_definition("Serendipity", SupportedLanguage::English)
+            .await
+            .unwrap()
+            .expect("definition is found");
+        assert_eq!(definition.word, "serendipity");
+        assert_eq!(
+            definition.text,
+            "An unsought, unintended, and/or unexpected discovery."
+        );
+        assert_eq!(definition.url, "https://en.wiktionary.org/wiki/serendipity");
+
+        assert_eq!(
+            service
+                .get_definition("qwzx", SupportedLanguage::English)
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unified_search_retries_truncated_body() {
+        let mut server = mockito::Server::new_async().await;
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        let mock = server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "generator".into(),
+                "search".into(),
+            ))
+            .with_body_from_request(move |_| {
+                let body = if counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst) == 0 {
+                    r#"{"batchcomplete": "", "query": {"pages": {"736": {"pageid": 7"#
+                } else {
+                    r#"{"batchcomplete": "", "query": {"pages": {
+                        "736": {"pageid": 736, "title": "Albert Einstein", "index": 1, "extract": "Physicist."}
+                    }}}"#
+                };
+                body.into()
+            })
+            .expect(2)
+            .create_async()
+            .await;
+
+        let mut config = AppConfig::for_tests();
+        config.retry.base_delay_ms = 1;
+        let service = WikipediaService::new(config)
+            .unwrap()
+            .with_api_base(server.url());
+
+        let cached = service
+            .search_and_get_info_unified("einstein", SupportedLanguage::English)
+            .await
+            .unwrap();
+        assert_eq!(cached.articles.len(), 1);
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_unified_search_does_not_retry_schema_mismatch() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/w/api.php")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "generator".into(),
+                "search".into(),
+            ))
+            .with_body(r#"{"batchcomplete": "", "query": {"searchinfo": {"totalhits": 0}}}"#)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let mut config = AppConfig::for_tests();
+        config.retry.base_delay_ms = 1;
+        let service = WikipediaService::new(config)
+            .unwrap()
+            .with_api_base(server.url());
+
+        let result = service
+            .search_and_get_info_unified("einstein", SupportedLanguage::English)
+            .await;
+        assert!(matches!(result, Err(WikiError::Parse(_))));
+        mock.assert_async().await;
+    }
 }
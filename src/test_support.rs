@@ -0,0 +1,45 @@
+//! Заготовки статей, общие для тестов разных модулей.
+
+use crate::models::{ArticleBatchInfo, EnrichedArticle, WikipediaSearchItem};
+
+/// Статья только с заголовком: без сведений пакетного запроса и Wikidata.
+pub fn article(title: &str) -> EnrichedArticle {
+    EnrichedArticle::new(
+        WikipediaSearchItem {
+            title: title.to_string(),
+            snippet: String::new(),
+            pageid: None,
+            size: None,
+            wordcount: None,
+            timestamp: None,
+        },
+        None,
+        None,
+        format!("https://ru.wikipedia.org/wiki/{title}"),
+    )
+}
+
+/// Статья со сведениями пакетного запроса `batch_info`.
+pub fn article_with(title: &str, batch_info: ArticleBatchInfo) -> EnrichedArticle {
+    let mut article = article(title);
+    article.batch_info = Some(batch_info);
+    article
+}
+
+/// Статьи с заголовками `titles` в том же порядке.
+pub fn titled(titles: &[&str]) -> Vec<EnrichedArticle> {
+    titles.iter().map(|title| article(title)).collect()
+}
+
+/// `count` статей «Статья N» с описанием и ссылкой `…/wiki/N`.
+pub fn articles(count: usize) -> Vec<EnrichedArticle> {
+    (0..count)
+        .map(|idx| {
+            let mut article = article(&format!("Статья {idx}"));
+            article.basic_info.snippet = "Описание".to_string();
+            article.basic_info.pageid = Some(idx as u64);
+            article.article_url = format!("https://ru.wikipedia.org/wiki/{idx}");
+            article
+        })
+        .collect()
+}
@@ -0,0 +1,288 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use super::decode_html_entities;
+use crate::config::Branding;
+
+static HTML_TAG_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"<(/?)([a-zA-Z][a-zA-Z0-9]*)[^>]*>").expect("Failed to compile HTML tag regex")
+});
+
+/// Экранирует текст для `ParseMode::Html`: Telegram требует заменять только
+/// `<`, `>` и `&` (и кавычки внутри атрибутов).
+pub fn escape_html(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '<' => "&lt;".to_string(),
+            '>' => "&gt;".to_string(),
+            '&' => "&amp;".to_string(),
+            '"' => "&quot;".to_string(),
+            _ => c.to_string(),
+        })
+        .collect()
+}
+
+/// HTML-аналог `format_article_description`.
+pub fn format_article_description_html(title: &str, description: &str, url: &str) -> String {
+    format_article_description_html_branded(title, description, url, &Branding::default())
+}
+
+/// HTML-аналог `format_article_description_branded`.
+pub fn format_article_description_html_branded(
+    title: &str,
+    description: &str,
+    url: &str,
+    branding: &Branding,
+) -> String {
+    format_article_markup_html_branded(title, &escape_html(description), url, branding)
+}
+
+/// То же, но описание уже размечено HTML из подмножества Telegram
+/// (см. [`sanitize_telegram_html`]) и не экранируется повторно.
+pub fn format_article_markup_html_branded(
+    title: &str,
+    description_html: &str,
+    url: &str,
+    branding: &Branding,
+) -> String {
+    format_article_markup_html_subtitled(title, None, description_html, url, branding)
+}
+
+/// То же, но с подзаголовком курсивом отдельной строкой под заголовком.
+pub fn format_article_markup_html_subtitled(
+    title: &str,
+    subtitle: Option<&str>,
+    description_html: &str,
+    url: &str,
+    branding: &Branding,
+) -> String {
+    let separator = escape_html(&branding.separator);
+    let subtitle = subtitle
+        .map(|subtitle| format!("\n<i>{}</i>", escape_html(subtitle)))
+        .unwrap_or_default();
+    format!(
+        "{}{subtitle}{separator}{description_html}{separator}{}",
+        with_emoji(
+            &branding.title_emoji,
+            &format!("<b>{}</b>", escape_html(title))
+        ),
+        with_emoji(
+            &branding.link_emoji,
+            &format!(
+                "<a href=\"{}\">{}</a>",
+                escape_html(url),
+                escape_html(&branding.link_label)
+            )
+        )
+    )
+}
+
+/// Приводит HTML из `prop=extracts` к тегам, которые понимает Telegram
+/// (`b`, `i`, `u`, `s`, `code`): синонимы вроде `strong` и `em` переименовываются,
+/// остальные теги отбрасываются, абзацы и `<br>` становятся переводами строки.
+/// Видимый текст обрезается до `max_chars` символов с «...», незакрытые теги
+/// закрываются, поэтому результат всегда корректен для `ParseMode::Html`.
+pub fn sanitize_telegram_html(html: &str, max_chars: usize) -> String {
+    let mut renderer = HtmlRenderer::new(true, Some(max_chars));
+    renderer.render(html);
+    renderer.finish()
+}
+
+/// Простой текст из HTML `prop=extracts`: все теги убираются, абзацы
+/// разделяются переводом строки, как в ответе с `explaintext`.
+pub fn html_to_plain_text(html: &str) -> String {
+    let mut renderer = HtmlRenderer::new(false, None);
+    renderer.render(html);
+    renderer.finish()
+}
+
+struct HtmlRenderer {
+    out: String,
+    keep_tags: bool,
+    remaining: Option<usize>,
+    open_tags: Vec<&'static str>,
+    at_line_start: bool,
+    pending_space: bool,
+    truncated: bool,
+}
+
+impl HtmlRenderer {
+    fn new(keep_tags: bool, max_chars: Option<usize>) -> Self {
+        Self {
+            out: String::new(),
+            keep_tags,
+            remaining: max_chars,
+            open_tags: Vec::new(),
+            at_line_start: true,
+            pending_space: false,
+            truncated: false,
+        }
+    }
+
+    fn render(&mut self, html: &str) {
+        let mut last = 0;
+        for captures in HTML_TAG_REGEX.captures_iter(html) {
+            let tag = captures.get(0).expect("whole match");
+            self.push_text(&html[last..tag.start()]);
+            if self.truncated {
+                return;
+            }
+            self.push_tag(!captures[1].is_empty(), &captures[2].to_lowercase());
+            last = tag.end();
+        }
+        self.push_text(&html[last..]);
+    }
+
+    fn push_text(&mut self, text: &str) {
+        // Пробелы и переводы строк внутри HTML-текста — обычные пробелы
+        for ch in decode_html_entities(text).chars() {
+            if ch.is_whitespace() {
+                self.pending_space = !self.at_line_start;
+                continue;
+            }
+            if self.pending_space {
+                self.push_char(' ');
+            }
+            self.push_char(ch);
+        }
+    }
+
+    fn push_char(&mut self, ch: char) {
+        if self.truncated {
+            return;
+        }
+        if let Some(remaining) = self.remaining.as_mut() {
+            if *remaining == 0 {
+                self.truncated = true;
+                return;
+            }
+            *remaining -= 1;
+        }
+
+        if self.keep_tags {
+            self.out.push_str(&escape_html(&ch.to_string()));
+        } else {
+            self.out.push(ch);
+        }
+        self.at_line_start = false;
+        self.pending_space = false;
+    }
+
+    fn push_tag(&mut self, closing: bool, name: &str) {
+        let line_break = match name {
+            "br" => true,
+            "p" | "div" | "li" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => closing,
+            _ => false,
+        };
+        if line_break {
+            if !self.at_line_start {
+                self.out.push('\n');
+                self.at_line_start = true;
+            }
+            self.pending_space = false;
+            return;
+        }
+
+        if !self.keep_tags {
+            return;
+        }
+        let Some(tag) = telegram_tag(name) else {
+            return;
+        };
+        if !closing {
+            if self.pending_space {
+                self.push_char(' ');
+            }
+            self.out.push_str(&format!("<{tag}>"));
+            self.open_tags.push(tag);
+        } else if self.open_tags.last() == Some(&tag) {
+            self.out.push_str(&format!("</{tag}>"));
+            self.open_tags.pop();
+        }
+    }
+
+    fn finish(mut self) -> String {
+        let trimmed = self.out.trim_end().len();
+        self.out.truncate(trimmed);
+        if self.truncated {
+            self.out.push_str("...");
+        }
+        while let Some(tag) = self.open_tags.pop() {
+            self.out.push_str(&format!("</{tag}>"));
+        }
+        self.out
+    }
+}
+
+/// Имя тега Telegram для тега HTML или `None`, если тег не поддерживается.
+fn telegram_tag(name: &str) -> Option<&'static str> {
+    match name {
+        "b" | "strong" => Some("b"),
+        "i" | "em" => Some("i"),
+        "u" | "ins" => Some("u"),
+        "s" | "strike" | "del" => Some("s"),
+        "code" => Some("code"),
+        _ => None,
+    }
+}
+
+fn with_emoji(emoji: &str, text: &str) -> String {
+    if emoji.is_empty() {
+        text.to_string()
+    } else {
+        format!("{} {text}", escape_html(emoji))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(
+            escape_html("a < b && c > d"),
+            "a &lt; b &amp;&amp; c &gt; d"
+        );
+        assert_eq!(escape_html("\"quoted\""), "&quot;quoted&quot;");
+        assert_eq!(escape_html("Пушкин (поэт)"), "Пушкин (поэт)");
+    }
+
+    #[test]
+    fn test_format_article_description_html() {
+        let message =
+            format_article_description_html("AT&T", "<script>", "https://example.com/?a=1&b=2");
+        assert_eq!(
+            message,
+            "📖 <b>AT&amp;T</b>\n\n&lt;script&gt;\n\n🔗 <a href=\"https://example.com/?a=1&amp;b=2\">Читать полностью</a>"
+        );
+    }
+
+    #[test]
+    fn test_sanitize_telegram_html_keeps_allowed_subset() {
+        let html = "<p class=\"mw-empty-elt\">\n</p>\n<p><b>Альберт Эйнштейн</b> (<span>нем.</span> <i lang=\"de\">Albert Einstein</i>) — физик &amp; <strong>философ</strong>.</p>\n<p>Второй <em>абзац</em><sup>2</sup></p>";
+        assert_eq!(
+            sanitize_telegram_html(html, 500),
+            "<b>Альберт Эйнштейн</b> (нем. <i>Albert Einstein</i>) — физик &amp; <b>философ</b>.\nВторой <i>абзац</i>2"
+        );
+    }
+
+    #[test]
+    fn test_sanitize_telegram_html_truncates_and_closes_tags() {
+        assert_eq!(
+            sanitize_telegram_html("<p><b>one two three</b> four</p>", 7),
+            "<b>one two...</b>"
+        );
+        assert_eq!(sanitize_telegram_html("a <b>b</i> c", 10), "a <b>b c</b>");
+    }
+
+    #[test]
+    fn test_html_to_plain_text_strips_tags() {
+        assert_eq!(
+            html_to_plain_text(
+                "<p><b>Эйнштейн</b> — физик &lt;теоретик&gt;.</p>\n<p>Второй абзац</p>"
+            ),
+            "Эйнштейн — физик <теоретик>.\nВторой абзац"
+        );
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+use crate::config::RetryConfig;
+use crate::errors::{WikiError, WikiResult};
+
+/// Политика повторов HTTP-запросов, общая для Wikipedia и Wikidata:
+/// экспоненциальная пауза со случайным разбросом, ограничение числа попыток
+/// и классификация ошибок на временные и окончательные.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub fn new(max_attempts: u32, base_delay: Duration, max_delay: Duration) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            base_delay,
+            max_delay,
+        }
+    }
+
+    pub fn from_config(config: &RetryConfig) -> Self {
+        Self::new(
+            config.max_attempts,
+            Duration::from_millis(config.base_delay_ms),
+            Duration::from_millis(config.max_delay_ms),
+        )
+    }
+
+    /// Политика без повторов.
+    pub fn none() -> Self {
+        Self::new(1, Duration::ZERO, Duration::ZERO)
+    }
+
+    /// Выполняет `f`, повторяя его после временных ошибок, пока не кончатся
+    /// попытки. Возвращается результат последней попытки.
+    pub async fn execute<F, Fut, T>(&self, mut f: F) -> WikiResult<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = WikiResult<T>>,
+    {
+        let mut attempt = 1;
+        loop {
+            let error = match f().await {
+                Ok(value) => return Ok(value),
+                Err(error) => error,
+            };
+
+            if attempt >= self.max_attempts {
+                return Err(error);
+            }
+            let Some(delay) = self.retry_delay(&error, attempt) else {
+                return Err(error);
+            };
+
+            tracing::debug!(
+                "🔁 Попытка {}/{} не удалась ({}), повтор через {:?}",
+                attempt,
+                self.max_attempts,
+                error,
+                delay
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// Пауза перед повтором после `attempt`-й попытки или `None`, если ошибку
+    /// повторять бессмысленно.
+    fn retry_delay(&self, error: &WikiError, attempt: u32) -> Option<Duration> {
+        if let WikiError::RateLimited {
+            retry_after: Some(retry_after),
+        } = error
+        {
+            return (*retry_after <= self.max_delay).then_some(*retry_after);
+        }
+
+        is_retryable(error).then(|| self.backoff(attempt))
+    }
+
+    /// `base_delay · 2^(attempt-1)`, не больше `max_delay`, со случайным
+    /// разбросом в нижнюю половину, чтобы повторы разных запросов не совпадали.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exponential = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt - 1))
+            .min(self.max_delay);
+        let half = exponential / 2;
+        let jitter_nanos = half.as_nanos() as u64;
+        let jitter = if jitter_nanos == 0 {
+            Duration::ZERO
+        } else {
+            Duration::from_nanos(random_u64() % (jitter_nanos + 1))
+        };
+        half + jitter
+    }
+}
+
+/// Временная ли ошибка: таймаут, сбой соединения, оборванное тело ответа,
+/// 429 или 500/502/503/504.
+pub fn is_retryable(error: &WikiError) -> bool {
+    match error {
+        WikiError::Timeout
+        | WikiError::Connect(_)
+        | WikiError::TruncatedResponse(_)
+        | WikiError::RateLimited { .. } => true,
+        WikiError::Network(error) => error
+            .status()
+            .is_none_or(|status| matches!(status.as_u16(), 500 | 502 | 503 | 504)),
+        _ => false,
+    }
+}
+
+fn random_u64() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy::new(
+            max_attempts,
+            Duration::from_millis(1),
+            Duration::from_millis(5),
+        )
+    }
+
+    /// Замыкание, которое падает `failures` раз с `error`, а потом отвечает успехом.
+    async fn run(
+        policy: &RetryPolicy,
+        failures: u32,
+        error: fn() -> WikiError,
+    ) -> (WikiResult<u32>, u32) {
+        let calls = AtomicU32::new(0);
+        let result = policy
+            .execute(|| async {
+                let call = calls.fetch_add(1, Ordering::SeqCst) + 1;
+                if call <= failures {
+                    Err(error())
+                } else {
+                    Ok(call)
+                }
+            })
+            .await;
+        (result, calls.load(Ordering::SeqCst))
+    }
+
+    #[tokio::test]
+    async fn test_retries_transient_errors_until_success() {
+        let (result, calls) = run(&policy(3), 2, || WikiError::Timeout).await;
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(calls, 3);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_attempts() {
+        let (result, calls) = run(&policy(3), 5, || WikiError::Timeout).await;
+        assert!(matches!(result, Err(WikiError::Timeout)));
+        assert_eq!(calls, 3);
+
+        let (result, calls) = run(&RetryPolicy::none(), 5, || WikiError::Timeout).await;
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+
+    #[tokio::test]
+    async fn test_permanent_errors_are_not_retried() {
+        let (result, calls) = run(&policy(3), 1, || WikiError::UnexpectedApiResponse).await;
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+
+    #[tokio::test]
+    async fn test_long_retry_after_is_not_waited() {
+        let long = || WikiError::RateLimited {
+            retry_after: Some(Duration::from_secs(60)),
+        };
+        let (result, calls) = run(&policy(3), 1, long).await;
+        assert!(matches!(result, Err(WikiError::RateLimited { .. })));
+        assert_eq!(calls, 1);
+
+        let short = || WikiError::RateLimited {
+            retry_after: Some(Duration::from_millis(2)),
+        };
+        let (result, calls) = run(&policy(3), 1, short).await;
+        assert_eq!(result.unwrap(), 2);
+        assert_eq!(calls, 2);
+    }
+
+    #[test]
+    fn test_backoff_is_capped() {
+        let policy = RetryPolicy::new(10, Duration::from_millis(100), Duration::from_millis(300));
+        for attempt in 1..10 {
+            let delay = policy.backoff(attempt);
+            assert!(delay <= Duration::from_millis(300), "{attempt}: {delay:?}");
+        }
+        assert!(policy.backoff(1) >= Duration::from_millis(50));
+    }
+}
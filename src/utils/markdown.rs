@@ -1,3 +1,12 @@
+use crate::config::Branding;
+use crate::models::Section;
+
+/// Максимальная длина текстового сообщения в Telegram.
+pub const TELEGRAM_MESSAGE_LIMIT: usize = 4096;
+
+/// Максимальная длина подписи к медиа в Telegram.
+pub const TELEGRAM_CAPTION_LIMIT: usize = 1024;
+
 pub fn escape_markdown(text: &str) -> String {
     text.chars()
         .map(|c| match c {
@@ -19,6 +28,46 @@ pub fn escape_markdown_url(url: &str) -> String {
         .collect()
 }
 
+/// Превращает MarkdownV2 в обычный текст: снимает экранирование и символы
+/// разметки, ссылки `[текст](url)` заменяет на `текст (url)`. Нужен, чтобы
+/// отправить сообщение без `parse_mode`, если Telegram не разобрал разметку.
+pub fn strip_markdown_v2(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let mut in_url = false;
+    let mut line_start = true;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some(escaped) = chars.next() {
+                    plain.push(escaped);
+                }
+            }
+            ']' if chars.peek() == Some(&'(') => {
+                chars.next();
+                plain.push_str(" (");
+                in_url = true;
+            }
+            ')' if in_url => {
+                plain.push(')');
+                in_url = false;
+            }
+            _ if in_url => plain.push(c),
+            '*' | '_' | '~' | '`' | '|' | '[' => {}
+            '>' if line_start => {
+                if chars.peek() == Some(&' ') {
+                    chars.next();
+                }
+            }
+            _ => plain.push(c),
+        }
+        line_start = c == '\n';
+    }
+
+    plain
+}
+
 pub fn bold(text: &str) -> String {
     format!("*{}*", escape_markdown(text))
 }
@@ -60,14 +109,137 @@ pub fn emoji_header(emoji: &str, text: &str) -> String {
 }
 
 pub fn format_article_description(title: &str, description: &str, url: &str) -> String {
+    format_article_description_branded(title, description, url, &Branding::default())
+}
+
+/// Карточка статьи с оформлением из [`Branding`]. Все части оформления
+/// экранируются так же, как текст статьи.
+pub fn format_article_description_branded(
+    title: &str,
+    description: &str,
+    url: &str,
+    branding: &Branding,
+) -> String {
+    format_article_description_subtitled(title, None, description, url, branding)
+}
+
+/// То же, но с подзаголовком курсивом отдельной строкой под заголовком.
+pub fn format_article_description_subtitled(
+    title: &str,
+    subtitle: Option<&str>,
+    description: &str,
+    url: &str,
+    branding: &Branding,
+) -> String {
+    let separator = escape_markdown(&branding.separator);
+    let subtitle = subtitle
+        .map(|subtitle| format!("\n_{}_", escape_markdown(subtitle)))
+        .unwrap_or_default();
     format!(
-        "📖 *{}*\n\n{}\n\n🔗 [Читать полностью]({})",
-        escape_markdown(title),
+        "{}{subtitle}{separator}{}{separator}{}",
+        with_emoji(
+            &branding.title_emoji,
+            &format!("*{}*", escape_markdown(title))
+        ),
         escape_markdown(description),
-        escape_markdown_url(url)
+        with_emoji(
+            &branding.link_emoji,
+            &format!(
+                "[{}]({})",
+                escape_markdown(&branding.link_label),
+                escape_markdown_url(url)
+            )
+        )
     )
 }
 
+/// Добавляет к уже отформатированному `text` экранированный эмодзи; пустой
+/// эмодзи не оставляет лишнего пробела.
+fn with_emoji(emoji: &str, text: &str) -> String {
+    if emoji.is_empty() {
+        text.to_string()
+    } else {
+        format!("{} {text}", escape_markdown(emoji))
+    }
+}
+
+/// Обрезает MarkdownV2-сообщение до `limit` символов, сохраняя завершающую
+/// строку со ссылкой ("🔗 Читать полностью") вместе с отделяющими её переводами
+/// строк и не разрывая escape-последовательности.
+pub fn truncate_for_telegram(text: &str, limit: usize) -> String {
+    const ELLIPSIS: &str = "…";
+
+    if text.chars().count() <= limit {
+        return text.to_string();
+    }
+
+    let (body, footer) = text.split_at(footer_start(text));
+
+    let budget = limit.saturating_sub(footer.chars().count() + ELLIPSIS.chars().count());
+    let mut truncated: String = body.chars().take(budget).collect();
+
+    if let Some(last_space) = truncated.rfind(' ') {
+        truncated.truncate(last_space);
+    }
+
+    let trailing_backslashes = truncated.chars().rev().take_while(|&c| c == '\\').count();
+    if trailing_backslashes % 2 == 1 {
+        truncated.pop();
+    }
+
+    format!("{}{ELLIPSIS}{footer}", truncated.trim_end())
+}
+
+/// Начало завершающей строки со ссылкой (включая переводы строк перед ней)
+/// или конец текста, если последняя строка — не ссылка.
+fn footer_start(text: &str) -> usize {
+    let Some(last_newline) = text.rfind('\n') else {
+        return text.len();
+    };
+
+    let last_line = &text[last_newline + 1..];
+    if !(last_line.contains("](") && last_line.ends_with(')')) {
+        return text.len();
+    }
+
+    text[..last_newline].trim_end_matches('\n').len()
+}
+
+/// Оглавление статьи со ссылками на разделы. Разделы, не помещающиеся в
+/// сообщение Telegram, отбрасываются целиком, чтобы не разрывать разметку ссылок.
+pub fn format_table_of_contents(title: &str, article_url: &str, sections: &[Section]) -> String {
+    let header = format!("📑 *Содержание: {}*\n\n", escape_markdown(title));
+    let footer = format!(
+        "\n\n🔗 [Читать полностью]({})",
+        escape_markdown_url(article_url)
+    );
+
+    if sections.is_empty() {
+        return format!("{header}В статье нет разделов{footer}");
+    }
+
+    let budget = TELEGRAM_MESSAGE_LIMIT - header.chars().count() - footer.chars().count() - 2;
+    let mut lines: Vec<String> = Vec::new();
+    let mut used = 0;
+
+    for section in sections {
+        let line = format!(
+            "{}• {}",
+            "    ".repeat(section.level.saturating_sub(1) as usize),
+            link(&section.title, &section.url(article_url))
+        );
+        let line_len = line.chars().count() + 1;
+        if used + line_len > budget {
+            lines.push("…".to_string());
+            break;
+        }
+        used += line_len;
+        lines.push(line);
+    }
+
+    format!("{header}{}{footer}", lines.join("\n"))
+}
+
 pub fn format_error_message(error: &str) -> String {
     format!("⚠️ *Ошибка*\n\n{}", escape_markdown(error))
 }
@@ -80,6 +252,13 @@ pub fn format_no_results_message(query: &str, language: &str) -> String {
     )
 }
 
+pub fn format_suggestion_message(suggestion: &str) -> String {
+    format!(
+        "🤔 *Возможно, вы имели в виду:* {}",
+        escape_markdown(suggestion)
+    )
+}
+
 pub fn format_welcome_message() -> String {
     r#"🌍 *Добро пожаловать в Wikipedia Search Bot\!*
 
@@ -121,6 +300,66 @@ pub fn format_welcome_message() -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_strip_markdown_v2() {
+        let markdown = format!(
+            "{}\n\n{}\n{}",
+            bold("Albert Einstein (1879–1955)"),
+            quote("E = mc²!"),
+            link("Читать [полностью]", "https://en.wikipedia.org/wiki/A_(b)"),
+        );
+        assert_eq!(
+            strip_markdown_v2(&markdown),
+            "Albert Einstein (1879–1955)\n\nE = mc²!\n\
+             Читать [полностью] (https://en.wikipedia.org/wiki/A_(b))"
+        );
+        assert_eq!(
+            strip_markdown_v2("__italic__ ||spoiler|| `code`"),
+            "italic spoiler code"
+        );
+    }
+
+    #[test]
+    fn test_format_table_of_contents() {
+        let sections = vec![
+            Section {
+                level: 1,
+                title: "Early life".to_string(),
+                anchor: "Early_life".to_string(),
+            },
+            Section {
+                level: 2,
+                title: "Family (1879)".to_string(),
+                anchor: "Family_(1879)".to_string(),
+            },
+        ];
+        let message = format_table_of_contents(
+            "Albert Einstein",
+            "https://en.wikipedia.org/wiki/Albert%20Einstein",
+            &sections,
+        );
+        assert!(message.contains(
+            "• [Early life](https://en.wikipedia.org/wiki/Albert%20Einstein#Early_life)"
+        ));
+        assert!(message.contains(
+            "    • [Family \\(1879\\)](https://en.wikipedia.org/wiki/Albert%20Einstein#Family_.281879.29)"
+        ));
+
+        let empty = format_table_of_contents("Stub", "https://en.wikipedia.org/wiki/Stub", &[]);
+        assert!(empty.contains("В статье нет разделов"));
+
+        let many: Vec<Section> = (0..500)
+            .map(|idx| Section {
+                level: 1,
+                title: format!("Section {idx}"),
+                anchor: format!("Section_{idx}"),
+            })
+            .collect();
+        let long = format_table_of_contents("Long", "https://en.wikipedia.org/wiki/Long", &many);
+        assert!(long.chars().count() <= TELEGRAM_MESSAGE_LIMIT);
+        assert!(long.contains("\n…\n"));
+    }
+
     #[test]
     fn test_escape_markdown() {
         assert_eq!(escape_markdown("Hello_world"), "Hello\\_world");
@@ -166,4 +405,79 @@ mod tests {
         assert!(result.contains("Test description"));
         assert!(result.contains("🔗 [Читать полностью](https://example.com)"));
     }
+
+    #[test]
+    fn test_format_article_description_with_custom_branding() {
+        let branding = Branding {
+            title_emoji: String::new(),
+            link_emoji: "→".to_string(),
+            link_label: "Open (wiki)".to_string(),
+            separator: "\n-\n".to_string(),
+            ..Branding::default()
+        };
+        assert_eq!(
+            format_article_description_branded("Title", "Text.", "https://example.com", &branding),
+            "*Title*\n\\-\nText\\.\n\\-\n→ [Open \\(wiki\\)](https://example.com)"
+        );
+    }
+
+    #[test]
+    fn test_truncate_for_telegram_keeps_custom_footer() {
+        let branding = Branding {
+            link_emoji: String::new(),
+            link_label: "More".to_string(),
+            ..Branding::default()
+        };
+        let message = format_article_description_branded(
+            "Title",
+            &"word ".repeat(100),
+            "https://example.com",
+            &branding,
+        );
+
+        let truncated = truncate_for_telegram(&message, 100);
+        assert!(truncated.chars().count() <= 100);
+        assert!(truncated.ends_with("…\n\n[More](https://example.com)"));
+    }
+
+    #[test]
+    fn test_truncate_for_telegram_keeps_short_message() {
+        let message = format_article_description("Title", "Short.", "https://example.com");
+        assert_eq!(
+            truncate_for_telegram(&message, TELEGRAM_MESSAGE_LIMIT),
+            message
+        );
+    }
+
+    #[test]
+    fn test_truncate_for_telegram_over_limit() {
+        let description = "Предложение с точкой. ".repeat(400);
+        let message =
+            format_article_description("Длинная статья", &description, "https://example.com");
+        assert!(message.chars().count() > TELEGRAM_MESSAGE_LIMIT);
+
+        let truncated = truncate_for_telegram(&message, TELEGRAM_MESSAGE_LIMIT);
+
+        assert!(truncated.chars().count() <= TELEGRAM_MESSAGE_LIMIT);
+        assert!(truncated.starts_with("📖 *Длинная статья*"));
+        assert!(truncated.ends_with("…\n\n🔗 [Читать полностью](https://example.com)"));
+    }
+
+    #[test]
+    fn test_truncate_for_telegram_does_not_split_escape() {
+        let message = format!(
+            "{}\n\n🔗 [Читать полностью](https://example.com)",
+            "a\\.".repeat(50)
+        );
+        let footer_len = "\n\n🔗 [Читать полностью](https://example.com)"
+            .chars()
+            .count();
+
+        for limit in footer_len + 2..footer_len + 20 {
+            let truncated = truncate_for_telegram(&message, limit);
+            let body = truncated.split('…').next().unwrap();
+            let trailing = body.chars().rev().take_while(|&c| c == '\\').count();
+            assert_eq!(trailing % 2, 0, "dangling escape at limit {limit}");
+        }
+    }
 }
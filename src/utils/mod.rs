@@ -1,5 +1,15 @@
+pub mod html;
+pub mod http;
 pub mod markdown;
+pub mod tasks;
 pub mod text;
+pub mod time;
+pub mod translit;
 
+pub use html::*;
+pub use http::*;
 pub use markdown::*;
+pub use tasks::*;
 pub use text::*;
+pub use time::*;
+pub use translit::*;
@@ -0,0 +1,103 @@
+use std::future::Future;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+
+/// Реестр фоновых задач сервисов: обновление кэша, поиск, продолжающийся
+/// после дедлайна, запись аналитики. При остановке бота `shutdown` отменяет
+/// их и дожидается завершения, чтобы ни одна задача не писала в файл или кэш
+/// во время разрушения runtime. Клонирование дешёвое: копии общие.
+#[derive(Clone, Default)]
+pub struct BackgroundTasks {
+    token: CancellationToken,
+    tracker: TaskTracker,
+}
+
+impl BackgroundTasks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Запускает задачу, которая при `shutdown` прерывается в ближайшей
+    /// точке ожидания. `None` в результате — задача отменена.
+    pub fn spawn<F>(&self, task: F) -> JoinHandle<Option<F::Output>>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let token = self.token.clone();
+        self.tracker.spawn(async move {
+            tokio::select! {
+                _ = token.cancelled() => None,
+                output = task => Some(output),
+            }
+        })
+    }
+
+    /// Запускает задачу, которая сама следит за токеном отмены и завершается
+    /// аккуратно (например, дописывает накопленное).
+    pub fn spawn_with_shutdown<F, Fut>(&self, task: F) -> JoinHandle<Fut::Output>
+    where
+        F: FnOnce(CancellationToken) -> Fut,
+        Fut: Future + Send + 'static,
+        Fut::Output: Send + 'static,
+    {
+        self.tracker.spawn(task(self.token.clone()))
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    /// Отменяет все задачи и ждёт, пока они завершатся. Задачи, запущенные
+    /// после вызова, отменяются сразу.
+    pub async fn shutdown(&self) {
+        self.tracker.close();
+        self.token.cancel();
+        self.tracker.wait().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_shutdown_stops_spawned_loop() {
+        let tasks = BackgroundTasks::new();
+        let ticks = Arc::new(AtomicUsize::new(0));
+
+        let counter = Arc::clone(&ticks);
+        let handle = tasks.spawn(async move {
+            loop {
+                counter.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        tasks.shutdown().await;
+        assert!(tasks.is_shutting_down());
+        assert_eq!(handle.await.unwrap(), None);
+
+        let stopped_at = ticks.load(Ordering::SeqCst);
+        assert!(stopped_at > 0);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(ticks.load(Ordering::SeqCst), stopped_at);
+    }
+
+    #[tokio::test]
+    async fn test_graceful_task_finishes_its_work() {
+        let tasks = BackgroundTasks::new();
+        let handle = tasks.spawn_with_shutdown(|shutdown| async move {
+            shutdown.cancelled().await;
+            "flushed"
+        });
+
+        tasks.shutdown().await;
+        assert_eq!(handle.await.unwrap(), "flushed");
+    }
+}
@@ -1,6 +1,8 @@
 use once_cell::sync::Lazy;
 use regex::Regex;
 
+use crate::config::languages::SupportedLanguage;
+
 static HTML_TAG_REGEX: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"<[^>]*>").expect("Failed to compile HTML tag regex"));
 
@@ -14,6 +16,37 @@ pub fn clean_html(text: &str) -> String {
     text.trim().to_string()
 }
 
+/// Сноски вида «[1]», «[12]», «[note 3]», «[прим. 2]».
+static REFERENCE_MARKER_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"[ \t]*\[\s*(?:(?i:note|nb|прим\.?)\s*)?\d{1,3}\s*\]")
+        .expect("Failed to compile reference marker regex")
+});
+
+/// Ссылки на правку раздела: «[edit]», «[править | править код]».
+static EDIT_LINK_REGEX: Lazy<Regex> = Lazy::new(|| {
+    let label =
+        r"(?:edit(?:\s+source)?|править(?:\s+код)?|редактировать|редагувати|bearbeiten|modifier)";
+    Regex::new(&format!(r"(?i)[ \t]*\[\s*{label}(?:\s*\|\s*{label})*\s*\]"))
+        .expect("Failed to compile edit link regex")
+});
+
+/// [`clean_html`] для snippet'ов поиска: дополнительно убирает ссылки
+/// «[edit]» и, если `strip_references`, сноски «[1]». Прочее содержимое
+/// квадратных скобок («[sic]») остаётся.
+pub fn clean_snippet(text: &str, strip_references: bool) -> String {
+    let text = clean_html(text);
+    let text = EDIT_LINK_REGEX.replace_all(&text, "");
+    let text = if strip_references {
+        REFERENCE_MARKER_REGEX.replace_all(&text, "")
+    } else {
+        text
+    };
+    MULTIPLE_SPACES_REGEX
+        .replace_all(&text, " ")
+        .trim()
+        .to_string()
+}
+
 pub fn decode_html_entities(text: &str) -> String {
     text.replace("&amp;", "&")
         .replace("&lt;", "<")
@@ -26,6 +59,85 @@ pub fn decode_html_entities(text: &str) -> String {
         .replace("&hellip;", "…")
 }
 
+/// Строки координат в начале extract'а, оставшиеся от шаблонов `{{coord}}`.
+static LEADING_COORDINATES_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^\s*(?i:coordinates|координаты|координати|koordinaten|coordonnées|coordenadas)\s*:[^\n]*\n",
+    )
+    .expect("Failed to compile leading coordinates regex")
+});
+
+/// Отметки аудио с произношением: «( listen)», «(слушать)», «ⓘ».
+static LISTEN_MARKER_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"[ \t]*(?:\(\s*(?i:listen|слушать|слухати|anhören|écouter|escuchar)\s*\)|ⓘ)")
+        .expect("Failed to compile listen marker regex")
+});
+
+/// Скобки, от содержимого которых после удаления шаблонов остались только
+/// пробелы и знаки препинания: «( )», «(; )», «[ ]».
+static EMPTY_BRACKETS_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"[ \t]*(?:\([\s,;]*\)|\[[\s,;]*\])")
+        .expect("Failed to compile empty brackets regex")
+});
+
+/// Убирает из extract'а остатки шаблонов: строки координат в начале,
+/// отметки произношения и опустевшие скобки. Обычный текст не меняется.
+pub fn strip_extract_noise(text: &str) -> String {
+    let mut text = text;
+    while let Some(found) = LEADING_COORDINATES_REGEX.find(text) {
+        text = &text[found.end()..];
+    }
+    let text = LISTEN_MARKER_REGEX.replace_all(text, "");
+    let text = EMPTY_BRACKETS_REGEX.replace_all(&text, "");
+    text.trim_start().to_string()
+}
+
+/// Насколько далеко от начала текста может стоять открывающая скобка,
+/// чтобы считаться вводной (произношение, даты жизни).
+const LEADING_PARENTHETICAL_MAX_OFFSET: usize = 100;
+
+/// Удаляет первую сбалансированную скобку `(...)` в начале текста, например
+/// «Albert Einstein (/ˈaɪnstaɪn/; 14 March 1879 – 18 April 1955) was…».
+/// Скобки дальше от начала и несбалансированные скобки не трогаются.
+pub fn strip_leading_parenthetical(text: &str) -> String {
+    let Some(open) = text.find('(') else {
+        return text.to_string();
+    };
+    if text[..open].chars().count() > LEADING_PARENTHETICAL_MAX_OFFSET {
+        return text.to_string();
+    }
+
+    let mut depth = 0usize;
+    let mut close = None;
+    for (offset, ch) in text[open..].char_indices() {
+        match ch {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    close = Some(open + offset);
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let Some(close) = close else {
+        return text.to_string();
+    };
+
+    let before = text[..open].trim_end();
+    let after = text[close + 1..].trim_start();
+    if before.is_empty() {
+        return after.to_string();
+    }
+    if after.is_empty() || after.starts_with([',', '.', ';', ':']) {
+        return format!("{before}{after}");
+    }
+    format!("{before} {after}")
+}
+
 pub fn truncate_string(text: &str, max_chars: usize) -> String {
     if text.len() <= max_chars {
         return text.to_string();
@@ -88,6 +200,132 @@ pub fn sanitize_search_query(query: &str) -> String {
         .to_string()
 }
 
+/// Локализованные названия пространств имён MediaWiki по языкам: категории,
+/// файлы, шаблоны, служебные страницы. Английские названия действуют в любом
+/// разделе, поэтому перечислены отдельно. Для остальных разделов известно
+/// только название категорий — в японском и китайском оно английское.
+const NAMESPACE_PREFIXES: &[(&str, &[&str])] = &[
+    (
+        "en",
+        &[
+            "Category",
+            "File",
+            "Image",
+            "Template",
+            "Wikipedia",
+            "Portal",
+            "Help",
+        ],
+    ),
+    (
+        "ru",
+        &[
+            "Категория",
+            "Файл",
+            "Изображение",
+            "Шаблон",
+            "Википедия",
+            "Портал",
+            "Справка",
+        ],
+    ),
+    (
+        "uk",
+        &[
+            "Категорія",
+            "Файл",
+            "Шаблон",
+            "Вікіпедія",
+            "Портал",
+            "Довідка",
+        ],
+    ),
+    (
+        "de",
+        &[
+            "Kategorie",
+            "Datei",
+            "Bild",
+            "Vorlage",
+            "Wikipedia",
+            "Portal",
+            "Hilfe",
+        ],
+    ),
+    (
+        "fr",
+        &[
+            "Catégorie",
+            "Fichier",
+            "Modèle",
+            "Wikipédia",
+            "Portail",
+            "Aide",
+        ],
+    ),
+    (
+        "es",
+        &[
+            "Categoría",
+            "Archivo",
+            "Plantilla",
+            "Wikipedia",
+            "Portal",
+            "Ayuda",
+        ],
+    ),
+    ("it", &["Categoria"]),
+    ("pt", &["Categoria"]),
+    ("pl", &["Kategoria"]),
+    ("ko", &["분류"]),
+    ("ar", &["تصنيف"]),
+    ("he", &["קטגוריה"]),
+    ("tr", &["Kategori"]),
+    ("nl", &["Categorie"]),
+    ("sv", &["Kategori"]),
+    ("no", &["Kategori"]),
+    ("da", &["Kategori"]),
+    ("fi", &["Luokka"]),
+    ("cs", &["Kategorie"]),
+    ("bg", &["Категория"]),
+    ("hr", &["Kategorija"]),
+    ("sr", &["Категорија"]),
+    ("sk", &["Kategória"]),
+    ("sl", &["Kategorija"]),
+    ("hu", &["Kategória"]),
+    ("ro", &["Categorie"]),
+    ("el", &["Κατηγορία"]),
+    ("lv", &["Kategorija"]),
+    ("lt", &["Kategorija"]),
+    ("et", &["Kategooria"]),
+    ("ca", &["Categoria"]),
+    ("eu", &["Kategoria"]),
+    ("gl", &["Categoría"]),
+];
+
+/// Убирает префикс пространства имён ("Category:", "Категория:",
+/// "Catégorie:", "File:", "Файл:" …) раздела `language` или английский без
+/// учёта регистра. Двоеточие в обычном заголовке ("Star Wars: Episode I") не
+/// трогается.
+pub fn strip_namespace_prefix(title: &str, language: SupportedLanguage) -> &str {
+    let Some((prefix, rest)) = title.split_once(':') else {
+        return title;
+    };
+
+    let prefix = prefix.trim().to_lowercase();
+    let is_namespace = NAMESPACE_PREFIXES
+        .iter()
+        .filter(|(code, _)| *code == "en" || *code == language.code())
+        .flat_map(|(_, prefixes)| prefixes.iter())
+        .any(|namespace| namespace.to_lowercase() == prefix);
+
+    if is_namespace {
+        rest.trim_start()
+    } else {
+        title
+    }
+}
+
 pub fn is_empty_or_whitespace(text: &str) -> bool {
     text.trim().is_empty()
 }
@@ -100,10 +338,137 @@ pub fn capitalize_first_letter(text: &str) -> String {
     }
 }
 
+/// Подсказка «~12 345 результатов» / «~12,345 results» с числом найденных
+/// статей (`totalhits`). Русский текст — для русской или неизвестной локали.
+pub fn format_total_hits(total_hits: u64, locale: Option<&str>) -> String {
+    let russian = locale.is_none_or(|code| code.to_lowercase().starts_with("ru"));
+
+    if russian {
+        let forms = ["результат", "результата", "результатов"];
+        let form = forms[super::time::russian_plural_form(total_hits as i64)];
+        format!("~{} {form}", group_thousands(total_hits, ' '))
+    } else {
+        let plural = if total_hits == 1 { "" } else { "s" };
+        format!("~{} result{plural}", group_thousands(total_hits, ','))
+    }
+}
+
+/// Число с разделителем разрядов: `12 345` или `12,345`.
+pub fn group_thousands(number: u64, separator: char) -> String {
+    let digits = number.to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (idx, digit) in digits.chars().enumerate() {
+        if idx > 0 && (digits.len() - idx).is_multiple_of(3) {
+            grouped.push(separator);
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_format_total_hits() {
+        assert_eq!(format_total_hits(1, None), "~1 результат");
+        assert_eq!(format_total_hits(342, Some("ru")), "~342 результата");
+        assert_eq!(format_total_hits(12_345, Some("ru")), "~12 345 результатов");
+        assert_eq!(
+            format_total_hits(1_234_567, Some("en-US")),
+            "~1,234,567 results"
+        );
+        assert_eq!(format_total_hits(1, Some("de")), "~1 result");
+        assert_eq!(format_total_hits(999, Some("en")), "~999 results");
+    }
+
+    #[test]
+    fn test_strip_extract_noise() {
+        assert_eq!(
+            strip_extract_noise(
+                "Coordinates: 48°51′24″N 2°21′08″E\nParis (French pronunciation: [paʁi] ( listen)) is the capital of France."
+            ),
+            "Paris (French pronunciation: [paʁi]) is the capital of France."
+        );
+        assert_eq!(
+            strip_extract_noise(
+                "Координаты: 55°45′ с. ш. 37°37′ в. д.\nМосква́ ( ; ) — столица России."
+            ),
+            "Москва́ — столица России."
+        );
+        assert_eq!(
+            strip_extract_noise(
+                "Albert Einstein ⓘ (14 March 1879 – 18 April 1955) was a physicist []."
+            ),
+            "Albert Einstein (14 March 1879 – 18 April 1955) was a physicist."
+        );
+    }
+
+    #[test]
+    fn test_strip_extract_noise_keeps_clean_prose() {
+        for text in [
+            "Москва — столица России.\n\nГород федерального значения.",
+            "Coordinate geometry (also analytic geometry) is the study of geometry.",
+            "The band released Listen (2004), their second album.",
+            "Python (programming language) is popular; see also [1].",
+        ] {
+            assert_eq!(strip_extract_noise(text), text);
+        }
+    }
+
+    #[test]
+    fn test_strip_leading_parenthetical() {
+        assert_eq!(
+            strip_leading_parenthetical(
+                "Albert Einstein (/ˈaɪnstaɪn/; 14 March 1879 – 18 April 1955) was a physicist."
+            ),
+            "Albert Einstein was a physicist."
+        );
+        assert_eq!(
+            strip_leading_parenthetical(
+                "Пушкин (26 мая [6 июня] 1799, Москва (Россия) — 1837) — русский поэт."
+            ),
+            "Пушкин — русский поэт."
+        );
+        // Без скобок текст не меняется
+        assert_eq!(
+            strip_leading_parenthetical("Москва — столица России."),
+            "Москва — столица России."
+        );
+        // Несбалансированная скобка — оставляем как есть
+        assert_eq!(
+            strip_leading_parenthetical("Ошибка (без закрытия"),
+            "Ошибка (без закрытия"
+        );
+        // Скобки далеко от начала не считаются вводными
+        let late = format!("{} (пояснение) конец", "слово ".repeat(30));
+        assert_eq!(strip_leading_parenthetical(&late), late);
+    }
+
+    #[test]
+    fn test_clean_snippet_strips_references_and_edit_links() {
+        assert_eq!(
+            clean_snippet("Einstein was a physicist.[1] He won[12] a prize", true),
+            "Einstein was a physicist. He won a prize"
+        );
+        assert_eq!(
+            clean_snippet("Biography [edit] Early life [править | править код]", true),
+            "Biography Early life"
+        );
+        assert_eq!(
+            clean_snippet("Text<sup>[note 3]</sup> end", true),
+            "Text end"
+        );
+
+        // Сноски можно оставить, ссылки на правку убираются всегда
+        assert_eq!(clean_snippet("Sentence.[1] [Edit]", false), "Sentence.[1]");
+
+        let sic = "He wrote \"their [sic] house\" [2]";
+        assert_eq!(clean_snippet(sic, true), "He wrote \"their [sic] house\"");
+        assert_eq!(clean_snippet(sic, false), sic);
+    }
+
     #[test]
     fn test_clean_html() {
         assert_eq!(clean_html("<p>Hello <b>world</b>!</p>"), "Hello world!");
@@ -154,6 +519,43 @@ mod tests {
         assert_eq!(sanitize_search_query("  spaced  query  "), "spaced query");
     }
 
+    #[test]
+    fn test_strip_namespace_prefix() {
+        use SupportedLanguage::{English, French, Russian};
+
+        assert_eq!(
+            strip_namespace_prefix("Категория:Физика", Russian),
+            "Физика"
+        );
+        assert_eq!(
+            strip_namespace_prefix("Category:Physics", English),
+            "Physics"
+        );
+        assert_eq!(
+            strip_namespace_prefix("catégorie: Physique", French),
+            "Physique"
+        );
+        assert_eq!(
+            strip_namespace_prefix("Файл:Pushkin.jpg", Russian),
+            "Pushkin.jpg"
+        );
+        assert_eq!(strip_namespace_prefix("Физика", Russian), "Физика");
+        assert_eq!(
+            strip_namespace_prefix("Star Wars: Episode I", English),
+            "Star Wars: Episode I"
+        );
+        // Английские названия действуют в любом разделе, чужие — нет
+        assert_eq!(strip_namespace_prefix("Category:Физика", Russian), "Физика");
+        assert_eq!(
+            strip_namespace_prefix("Категория:Physics", English),
+            "Категория:Physics"
+        );
+        assert_eq!(
+            strip_namespace_prefix("Kategorie:Physik", SupportedLanguage::German),
+            "Physik"
+        );
+    }
+
     #[test]
     fn test_capitalize_first_letter() {
         assert_eq!(capitalize_first_letter("hello"), "Hello");
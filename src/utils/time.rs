@@ -0,0 +1,133 @@
+use chrono::{DateTime, Utc};
+
+/// Строка «обновлено 3 дня назад» / «updated 3 days ago» для времени
+/// последней правки статьи (`timestamp` поиска, ISO 8601). Русский текст —
+/// для русской или неизвестной локали, иначе английский. `None`, если время
+/// не разобралось.
+pub fn format_last_edited(
+    timestamp: &str,
+    now: DateTime<Utc>,
+    locale: Option<&str>,
+) -> Option<String> {
+    let edited = DateTime::parse_from_rfc3339(timestamp).ok()?;
+    let seconds = (now - edited.with_timezone(&Utc)).num_seconds().max(0);
+    let russian = locale.is_none_or(|code| code.to_lowercase().starts_with("ru"));
+
+    const MINUTE: i64 = 60;
+    const HOUR: i64 = 60 * MINUTE;
+    const DAY: i64 = 24 * HOUR;
+    const MONTH: i64 = 30 * DAY;
+    const YEAR: i64 = 365 * DAY;
+
+    if seconds < MINUTE {
+        let text = if russian {
+            "обновлено только что"
+        } else {
+            "updated just now"
+        };
+        return Some(text.to_string());
+    }
+
+    let (count, unit) = match seconds {
+        s if s < HOUR => (s / MINUTE, Unit::Minute),
+        s if s < DAY => (s / HOUR, Unit::Hour),
+        s if s < MONTH => (s / DAY, Unit::Day),
+        s if s < YEAR => (s / MONTH, Unit::Month),
+        s => (s / YEAR, Unit::Year),
+    };
+
+    Some(if russian {
+        format!("обновлено {count} {} назад", unit.russian(count))
+    } else {
+        let plural = if count == 1 { "" } else { "s" };
+        format!("updated {count} {}{plural} ago", unit.english())
+    })
+}
+
+#[derive(Clone, Copy)]
+enum Unit {
+    Minute,
+    Hour,
+    Day,
+    Month,
+    Year,
+}
+
+impl Unit {
+    fn english(self) -> &'static str {
+        match self {
+            Self::Minute => "minute",
+            Self::Hour => "hour",
+            Self::Day => "day",
+            Self::Month => "month",
+            Self::Year => "year",
+        }
+    }
+
+    fn russian(self, count: i64) -> &'static str {
+        let forms = match self {
+            Self::Minute => ["минуту", "минуты", "минут"],
+            Self::Hour => ["час", "часа", "часов"],
+            Self::Day => ["день", "дня", "дней"],
+            Self::Month => ["месяц", "месяца", "месяцев"],
+            Self::Year => ["год", "года", "лет"],
+        };
+        forms[russian_plural_form(count)]
+    }
+}
+
+/// Индекс формы русского существительного при числе: 1 минуту, 2 минуты, 5 минут.
+pub(crate) fn russian_plural_form(count: i64) -> usize {
+    match (count % 10, count % 100) {
+        (1, n) if n != 11 => 0,
+        (2..=4, n) if !(12..=14).contains(&n) => 1,
+        _ => 2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2024-05-20T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_format_last_edited_russian() {
+        let cases = [
+            ("2024-05-20T11:59:30Z", "обновлено только что"),
+            ("2024-05-20T11:39:00Z", "обновлено 21 минуту назад"),
+            ("2024-05-20T09:00:00Z", "обновлено 3 часа назад"),
+            ("2024-05-09T12:00:00Z", "обновлено 11 дней назад"),
+            ("2024-02-20T12:00:00Z", "обновлено 3 месяца назад"),
+            ("2019-05-20T12:00:00Z", "обновлено 5 лет назад"),
+        ];
+        for (timestamp, expected) in cases {
+            assert_eq!(
+                format_last_edited(timestamp, now(), Some("ru")).as_deref(),
+                Some(expected),
+                "{timestamp}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_format_last_edited_english() {
+        assert_eq!(
+            format_last_edited("2024-05-19T12:00:00Z", now(), Some("en-US")).as_deref(),
+            Some("updated 1 day ago")
+        );
+        assert_eq!(
+            format_last_edited("2022-05-20T12:00:00Z", now(), Some("de")).as_deref(),
+            Some("updated 2 years ago")
+        );
+    }
+
+    #[test]
+    fn test_format_last_edited_rejects_garbage() {
+        assert_eq!(format_last_edited("yesterday", now(), None), None);
+    }
+}
@@ -0,0 +1,224 @@
+/// Многобуквенные сочетания проверяются раньше одиночных букв.
+const LATIN_TO_CYRILLIC: &[(&str, &str)] = &[
+    ("shch", "щ"),
+    ("sch", "щ"),
+    ("zh", "ж"),
+    ("kh", "х"),
+    ("ts", "ц"),
+    ("ch", "ч"),
+    ("sh", "ш"),
+    ("yu", "ю"),
+    ("ya", "я"),
+    ("yo", "ё"),
+    ("ye", "е"),
+    ("a", "а"),
+    ("b", "б"),
+    ("c", "к"),
+    ("d", "д"),
+    ("e", "е"),
+    ("f", "ф"),
+    ("g", "г"),
+    ("h", "х"),
+    ("i", "и"),
+    ("j", "дж"),
+    ("k", "к"),
+    ("l", "л"),
+    ("m", "м"),
+    ("n", "н"),
+    ("o", "о"),
+    ("p", "п"),
+    ("q", "к"),
+    ("r", "р"),
+    ("s", "с"),
+    ("t", "т"),
+    ("u", "у"),
+    ("v", "в"),
+    ("w", "в"),
+    ("x", "кс"),
+    ("z", "з"),
+];
+
+const CYRILLIC_TO_LATIN: &[(char, &str)] = &[
+    ('а', "a"),
+    ('б', "b"),
+    ('в', "v"),
+    ('г', "g"),
+    ('д', "d"),
+    ('е', "e"),
+    ('ё', "yo"),
+    ('ж', "zh"),
+    ('з', "z"),
+    ('и', "i"),
+    ('й', "y"),
+    ('к', "k"),
+    ('л', "l"),
+    ('м', "m"),
+    ('н', "n"),
+    ('о', "o"),
+    ('п', "p"),
+    ('р', "r"),
+    ('с', "s"),
+    ('т', "t"),
+    ('у', "u"),
+    ('ф', "f"),
+    ('х', "kh"),
+    ('ц', "ts"),
+    ('ч', "ch"),
+    ('ш', "sh"),
+    ('щ', "shch"),
+    ('ъ', ""),
+    ('ы', "y"),
+    ('ь', ""),
+    ('э', "e"),
+    ('ю', "yu"),
+    ('я', "ya"),
+    ('і', "i"),
+    ('ї', "yi"),
+    ('є', "ye"),
+    ('ґ', "g"),
+];
+
+fn is_latin_vowel(c: char) -> bool {
+    matches!(c.to_ascii_lowercase(), 'a' | 'e' | 'i' | 'o' | 'u' | 'y')
+}
+
+fn apply_case(cyrillic: &str, uppercase: bool) -> String {
+    if !uppercase {
+        return cyrillic.to_string();
+    }
+    let mut chars = cyrillic.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
+        None => String::new(),
+    }
+}
+
+/// Латиница → кириллица по упрощённой практической транскрипции ("Pushkin" → "Пушкин").
+/// Символы, не являющиеся латинскими буквами, переносятся как есть.
+pub fn latin_to_cyrillic(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut result = String::with_capacity(text.len() * 2);
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if !c.is_ascii_alphabetic() {
+            result.push(c);
+            i += 1;
+            continue;
+        }
+
+        let uppercase = c.is_ascii_uppercase();
+
+        // "y" — й после гласной, "ий" в конце слова после согласной, иначе ы
+        if c.eq_ignore_ascii_case(&'y')
+            && !chars.get(i + 1).is_some_and(|n| "aouAOUeE".contains(*n))
+        {
+            let prev = i.checked_sub(1).map(|p| chars[p]);
+            let next_is_letter = chars.get(i + 1).is_some_and(|n| n.is_ascii_alphabetic());
+            let cyrillic = match prev {
+                Some(p) if is_latin_vowel(p) => "й",
+                Some(p) if p.is_ascii_alphabetic() && !next_is_letter => "ий",
+                Some(p) if p.is_ascii_alphabetic() => "ы",
+                _ => "й",
+            };
+            result.push_str(&apply_case(cyrillic, uppercase));
+            i += 1;
+            continue;
+        }
+
+        // Конечное "i" после "r"/"l" — мужские имена на -ий (Yuri, Dmitri, Anatoli)
+        if c.eq_ignore_ascii_case(&'i')
+            && i.checked_sub(1).is_some_and(|p| "rlRL".contains(chars[p]))
+            && !chars.get(i + 1).is_some_and(|n| n.is_ascii_alphabetic())
+        {
+            result.push_str(&apply_case("ий", uppercase));
+            i += 1;
+            continue;
+        }
+
+        let rest: String = chars[i..]
+            .iter()
+            .take(4)
+            .collect::<String>()
+            .to_ascii_lowercase();
+        let (latin, cyrillic) = LATIN_TO_CYRILLIC
+            .iter()
+            .find(|(latin, _)| rest.starts_with(latin))
+            .copied()
+            .unwrap_or(("", ""));
+
+        if latin.is_empty() {
+            result.push(c);
+            i += 1;
+        } else {
+            result.push_str(&apply_case(cyrillic, uppercase));
+            i += latin.chars().count();
+        }
+    }
+
+    result
+}
+
+/// Кириллица → латиница ("Пушкин" → "Pushkin").
+pub fn cyrillic_to_latin(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            let lower = c.to_lowercase().next().unwrap_or(c);
+            match CYRILLIC_TO_LATIN.iter().find(|(cyr, _)| *cyr == lower) {
+                Some((_, latin)) => apply_case(latin, c != lower),
+                None => c.to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Есть латинские буквы и нет букв других алфавитов.
+pub fn is_latin_only(text: &str) -> bool {
+    text.chars().any(|c| c.is_ascii_alphabetic())
+        && !text
+            .chars()
+            .any(|c| c.is_alphabetic() && !c.is_ascii_alphabetic())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latin_to_cyrillic_common_names() {
+        assert_eq!(latin_to_cyrillic("Pushkin"), "Пушкин");
+        assert_eq!(latin_to_cyrillic("Chekhov"), "Чехов");
+        assert_eq!(latin_to_cyrillic("Tolstoy"), "Толстой");
+        assert_eq!(latin_to_cyrillic("Dostoevsky"), "Достоевский");
+        assert_eq!(latin_to_cyrillic("Moskva"), "Москва");
+        assert_eq!(latin_to_cyrillic("Yuri Gagarin"), "Юрий Гагарин");
+        assert_eq!(latin_to_cyrillic("Dmitri Mendeleev"), "Дмитрий Менделеев");
+        assert_eq!(latin_to_cyrillic("Zhukov"), "Жуков");
+        assert_eq!(latin_to_cyrillic("Khrushchev"), "Хрущев");
+    }
+
+    #[test]
+    fn test_cyrillic_to_latin_common_names() {
+        assert_eq!(cyrillic_to_latin("Пушкин"), "Pushkin");
+        assert_eq!(cyrillic_to_latin("Чехов"), "Chekhov");
+        assert_eq!(cyrillic_to_latin("Жуков"), "Zhukov");
+        assert_eq!(cyrillic_to_latin("Москва"), "Moskva");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for name in ["Pushkin", "Chekhov", "Moskva", "Gagarin", "Zhukov"] {
+            assert_eq!(cyrillic_to_latin(&latin_to_cyrillic(name)), name);
+        }
+    }
+
+    #[test]
+    fn test_is_latin_only() {
+        assert!(is_latin_only("Pushkin 1799"));
+        assert!(!is_latin_only("Пушкин"));
+        assert!(!is_latin_only("Pushkin Пушкин"));
+        assert!(!is_latin_only("1799"));
+    }
+}